@@ -0,0 +1,89 @@
+//! Timing of `Simulation::tick` at several ball counts, resolving collisions
+//! sequentially and in parallel. Uses a plain harness since no benchmarking
+//! crate is a dependency. Run with `cargo bench`
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use ggez::graphics::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use balls::{Ball, Scene, Simulation};
+
+const WIDTH: f32 = 1600.0;
+const HEIGHT: f32 = 1200.0;
+/// Time spent measuring each case, once warmed up
+const MEASURE_TIME: Duration = Duration::from_secs(1);
+const WARMUP_TICKS: u32 = 3;
+
+/// Random balls filling the box, so collisions dominate
+fn dense(count: usize) -> Scene {
+    let mut rng = StdRng::seed_from_u64(1);
+    let radius = (WIDTH * HEIGHT / count as f32).sqrt() / 3.0;
+    scene((0..count).map(|_| {
+        let x = rng.gen_range(radius..WIDTH - radius);
+        let y = rng.gen_range(radius..HEIGHT - radius);
+        Ball::new(x, y, radius, Color::WHITE)
+    }))
+}
+
+/// Tiny balls spread apart without gravity, so integration and pair tests dominate
+fn sparse(count: usize) -> Scene {
+    let mut rng = StdRng::seed_from_u64(1);
+    let mut scene = scene((0..count).map(|_| {
+        let mut ball = Ball::new(
+            rng.gen_range(1.0..WIDTH - 1.0),
+            rng.gen_range(1.0..HEIGHT - 1.0),
+            0.01,
+            Color::WHITE,
+        );
+        ball.velocity.x = rng.gen_range(-1.0..1.0);
+        ball.velocity.y = rng.gen_range(-1.0..1.0);
+        ball
+    }));
+    scene.gravity = 0.0;
+    scene
+}
+
+fn scene(balls: impl Iterator<Item = Ball>) -> Scene {
+    Scene::new(balls.collect(), 0.5)
+}
+
+/// Mean time of a tick, over as many ticks as fit in the measuring time
+fn measure(scene: Scene, parallel: bool) -> (Duration, u32) {
+    let mut sim = Simulation::from_scene(scene, WIDTH, HEIGHT);
+    sim.params.parallel = parallel;
+    for _ in 0..WARMUP_TICKS {
+        black_box(sim.tick());
+    }
+    let start = Instant::now();
+    let mut ticks = 0;
+    while ticks == 0 || start.elapsed() < MEASURE_TIME {
+        black_box(sim.tick());
+        ticks += 1;
+    }
+    (start.elapsed() / ticks, ticks)
+}
+
+fn main() {
+    println!(
+        "{:<8} {:<10} {:>7} {:>12} {:>7}",
+        "Scene", "Collisions", "Balls", "Per tick", "Ticks"
+    );
+    for count in [100, 1_000, 10_000] {
+        for (name, scene) in [("dense", dense(count)), ("sparse", sparse(count))] {
+            for parallel in [false, true] {
+                let (per_tick, ticks) = measure(scene.clone(), parallel);
+                println!(
+                    "{:<8} {:<10} {:>7} {:>12} {:>7}",
+                    name,
+                    if parallel { "parallel" } else { "sequential" },
+                    count,
+                    format!("{:.2?}", per_tick),
+                    ticks
+                );
+            }
+        }
+    }
+}
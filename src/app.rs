@@ -1,289 +1,1362 @@
-use ggez::event::EventHandler;
-use ggez::graphics::{Color, DrawMode, DrawParam, Mesh};
+mod control;
+mod input;
+mod keyboard;
+mod menus;
+mod render;
+mod window;
+
+use std::fs;
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+use ggez::event::{EventHandler, MouseButton};
+use ggez::graphics::Mesh;
+use ggez::input::keyboard::{KeyInput, KeyMods};
 use ggez::mint::{Point2, Vector2};
-use ggez::winit::event::VirtualKeyCode;
-use ggez::{graphics, Context};
-use rand::Rng;
-
-#[derive(Clone)]
-struct Ball {
-    point: Point2<f32>,
-    radius: f32,
-    velocity: Vector2<f32>,
-    color: Color,
+use ggez::{Context, GameResult};
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use self::input::{BallAction, Tool};
+use self::keyboard::{Arrows, Slots};
+use self::menus::MenuAction;
+use self::render::PatternMeshes;
+use crate::art::Exposure;
+use crate::audio::{BeatDetector, Effects, Music};
+use crate::ball::{Ball, BallId};
+use crate::capture::{self, ClipRecorder, Screenshot};
+use crate::config::{AppBuilder, AppConfig};
+use crate::config_file::{self, ConfigWatcher};
+use crate::control::{Reply, Requests};
+use crate::crash::{Crash, CRASH_FILE};
+use crate::events::Event;
+use crate::graph::EnergyGraph;
+use crate::highlight::{Highlight, History};
+use crate::hover::Hover;
+use crate::inspector::Inspector;
+use crate::keys::Keybindings;
+#[cfg(feature = "lan")]
+use crate::lan::Session;
+use crate::menu::Menu;
+use crate::mode::challenge::{Challenge, Timed};
+use crate::mode::{GameMode, ModeContext, Sandbox};
+use crate::particles::Particles;
+use crate::prediction::Prediction;
+use crate::profiler::Profiler;
+use crate::protractor::Protractor;
+use crate::quality::{AdaptiveQuality, Quality};
+use crate::replay::{Input, Recording, Replay};
+use crate::rewind::Rewind;
+use crate::ruler::Ruler;
+use crate::scene::{self, Scene, SceneSource};
+use crate::score::Score;
+use crate::settings::{Channel, Settings, WindowGeometry};
+use crate::simulation::{Params, Simulation, TICK};
+use crate::spawn::SpawnDialog;
+use crate::stats::{Stats, StepStats};
+use crate::stress::{self, StressTest, Verdict};
+use crate::text::TextStyle;
+use crate::toast::Toasts;
+use crate::trajectory::{TrajectoryLog, LOGS_DIR};
+use crate::walls::Walls;
+
+#[derive(Clone, Copy, PartialEq)]
+enum State {
+    Menu,
+    Running,
+    Paused,
+}
+
+/// Upwards speed given to balls by an average beat
+const BEAT_IMPULSE: f32 = 4.0;
+/// Limit on relative beat strength, so loud transients don't launch balls off screen
+const MAX_BEAT_STRENGTH: f32 = 3.0;
+/// Seconds between autosaves of a running scene
+const AUTOSAVE_INTERVAL: f32 = 60.0;
+/// Fastest balls may move with reduced motion on, in pixels per tick
+const REDUCED_MOTION_SPEED: f32 = 12.0;
+
+pub struct App {
+    state: State,
+    menu: Menu<MenuAction>,
+    source: Option<SceneSource>,
+    sim: Simulation,
+    /// Rules of the game being played over the simulation
+    mode: Box<dyn GameMode>,
+    /// Points scored in the current game
+    score: Score,
+    /// Offset from the held ball's centre to the cursor
+    grab_offset: Point2<f32>,
+    /// Ball picked with the keyboard, which the arrow, pin and throw keys act on
+    selected: Option<BallId>,
+    /// Arrow keys held down, aiming a throw of the selected ball
+    arrows: Arrows,
+    /// Whether the selected ball is held still by the keyboard while a throw charges
+    aiming: bool,
+    /// Seconds a throw of the selected ball has charged, while the throw key is held
+    charge: Option<f32>,
+    /// Direction the selected ball was last aimed in, which it is thrown in
+    aim: Vector2<f32>,
+    tool: Tool,
+    inspector: Option<Inspector>,
+    /// Distance being measured with the measure tool, until dismissed
+    ruler: Option<Ruler>,
+    /// Angle being measured with the protractor tool, until dismissed
+    protractor: Option<Protractor>,
+    /// Where the mouse was last seen
+    cursor: Point2<f32>,
+    /// Whether Ctrl is held, snapping placement to the grid
+    snap: bool,
+    /// Whether Shift is held, for finer nudges and cycling the selection backwards
+    shift: bool,
+    /// Readout of the ball under the cursor, while inspecting
+    hover: Option<Hover>,
+    /// How the path of a held or aimed ball is predicted
+    prediction: Prediction,
+    /// Last few seconds of balls, to replay from
+    history: History,
+    /// Slow-motion replay being shown instead of the simulation
+    highlight: Option<Highlight>,
+    /// Last few seconds of the simulation, to rewind through
+    rewind: Rewind,
+    /// Whether the rewind key is held, going back through time instead of ticking
+    rewinding: bool,
+    /// Snapshots stored with Ctrl and a number key
+    slots: Slots,
+    /// Right-click menu, with the ball it acts on
+    context_menu: Option<(BallId, Menu<BallAction>)>,
+    spawn_dialog: Option<SpawnDialog>,
+    keys: Keybindings,
+    show_help: bool,
+    toasts: Toasts,
+    graph: EnergyGraph,
+    show_graph: bool,
+    stats: Stats,
+    show_stats: bool,
+    profiler: Profiler,
+    show_profiler: bool,
+    /// Stress test adding balls until the frame rate drops, if running
+    stress: Option<StressTest>,
+    music: Option<Music>,
+    effects: Effects,
+    /// Specks thrown out by popped balls
+    particles: Particles,
+    /// Circle of radius 1, scaled to draw balls and particles without a new mesh for each
+    circle: Mesh,
+    /// Patterns drawn over balls, scaled like `circle`
+    patterns: PatternMeshes,
+    /// Grid that placement snaps to, with the grid size and drawable size it was built for
+    grid: Option<(f32, (f32, f32), Mesh)>,
+    /// Detail to draw at, lowered while frames take too long
+    quality: AdaptiveQuality,
+    /// Size of text and menus, from the config until changed with Ctrl+= or Ctrl+-
+    text_style: TextStyle,
+    /// When the current frame started being updated
+    frame_start: Instant,
+    /// Input listener for audio-reactive mode, if enabled
+    beats: Option<BeatDetector>,
+    settings: Settings,
+    /// Size and place of the window, saved when the app closes
+    window: WindowGeometry,
+    /// Directory settings are saved to
+    config_dir: PathBuf,
+    /// Directory the scene is autosaved to
+    data_dir: PathBuf,
+    autosave_timer: f32,
+    config: AppConfig,
+    /// Watches config files for changes, if enabled
+    config_watcher: Option<ConfigWatcher>,
+    /// Seed of `rng`, shown so a run can be repeated
+    seed: u64,
+    rng: StdRng,
+    /// Ticks run since launch, which recorded input is timed against
+    ticks: u64,
+    /// Input so far and the file to save it to, if recording
+    recording: Option<(PathBuf, Recording)>,
+    replay: Option<Replay>,
+    screenshot: Option<Screenshot>,
+    /// Whether anything may look different from the last frame drawn
+    redraw: bool,
+    /// Size of the window when the last frame was drawn
+    drawn_size: (f32, f32),
+    /// Frames of the GIF clip being recorded, if any
+    clip: Option<ClipRecorder>,
+    /// Long-exposure picture being taken, if any
+    art: Option<Exposure>,
+    /// Clips and pictures being encoded in the background, with what each one is
+    saving: Vec<(&'static str, JoinHandle<Result<PathBuf, String>>)>,
+    trajectory: Option<TrajectoryLog>,
+    /// Ticks to run while the next frame is drawn, if physics overlaps drawing
+    queued_ticks: u32,
+    /// Stats of the ticks run while the last frame was drawn
+    overlapped: Option<StepStats>,
+    /// Copy of the simulation last drawn while physics overlapped, kept to copy
+    /// the next frame into without allocating a new one
+    spare: Option<Simulation>,
+    /// Commands from outside the app, if taking any
+    requests: Option<Requests>,
+    /// Clients to send collisions to
+    subscribers: Vec<Sender<Reply>>,
+    /// Clients to send the state to after every frame
+    watchers: Vec<Sender<Reply>>,
+    /// Sandbox shared with other instances, if hosting or joined
+    #[cfg(feature = "lan")]
+    lan: Option<Session>,
 }
 
-impl Ball {
-    pub fn new(x: f32, y: f32, radius: f32, color: Color) -> Self {
-        Self {
-            point: Point2 { x, y },
-            radius,
-            velocity: Vector2 { x: 0.0, y: 0.0 },
-            color,
+impl App {
+    pub fn new(ctx: &mut Context) -> Self {
+        Self::builder().build(ctx)
+    }
+
+    pub fn builder() -> AppBuilder {
+        AppBuilder::default()
+    }
+
+    pub(crate) fn with_config(ctx: &mut Context, mut config: AppConfig) -> Self {
+        let settings = Settings::load(ctx.fs.user_config_dir());
+        let decorated = window::decorate(ctx, &config);
+        let window = window::restore(ctx, &config, settings.window.as_ref());
+        let (width, height) = ctx.gfx.drawable_size();
+
+        // A replay starts the same way its recording did
+        let replay = config.replay.as_deref().map(Recording::load);
+        if let Some(Ok(recording)) = &replay {
+            config.seed = Some(recording.seed);
+            config.balls = recording.balls;
+            config.scene = recording.scene.clone();
+        }
+        let seed = config.seed_or_random();
+        let rng = StdRng::seed_from_u64(seed);
+        let recording = config.record.clone().map(|path| {
+            let recording = Recording {
+                seed,
+                window_size: (width, height),
+                balls: config.balls,
+                scene: config.scene.clone(),
+                inputs: Vec::new(),
+            };
+            (path, recording)
+        });
+        let mut sim = Simulation::new(Params {
+            gravity: config.gravity,
+            width,
+            height,
+            parallel: false,
+            heat: false,
+            walls: Walls::default(),
+        });
+        if let Some(forces) = config.forces.take() {
+            sim.forces = forces;
+        }
+        let requests = config.requests.take();
+        #[cfg(feature = "lan")]
+        let mut lan = config.lan.take();
+        #[cfg(feature = "lan")]
+        if let Some(Session::Host(host)) = &mut lan {
+            host.limit_radius(config.radius.0, config.radius.1);
+        }
+
+        let mut app = Self {
+            state: State::Menu,
+            menu: Menu::new("", Point2 { x: 0.0, y: 0.0 }),
+            source: None,
+            sim,
+            mode: Box::new(Sandbox),
+            score: Score::default(),
+            grab_offset: Point2 { x: 0.0, y: 0.0 },
+            selected: None,
+            arrows: Arrows::default(),
+            aiming: false,
+            charge: None,
+            aim: Vector2 { x: 0.0, y: -1.0 },
+            tool: Tool::Grab,
+            inspector: None,
+            ruler: None,
+            protractor: None,
+            cursor: Point2 { x: 0.0, y: 0.0 },
+            snap: false,
+            shift: false,
+            hover: None,
+            prediction: Prediction::Alone,
+            history: History::default(),
+            highlight: None,
+            rewind: Rewind::new(config.rewind_seconds),
+            rewinding: false,
+            slots: Default::default(),
+            context_menu: None,
+            spawn_dialog: None,
+            keys: config.keys.clone(),
+            show_help: false,
+            toasts: Toasts::default(),
+            graph: EnergyGraph::default(),
+            show_graph: false,
+            stats: Stats::default(),
+            show_stats: false,
+            profiler: Profiler::default(),
+            show_profiler: false,
+            stress: None,
+            music: None,
+            effects: Effects::load(ctx),
+            particles: Particles::default(),
+            circle: render::unit_circle(ctx, Quality::default()),
+            patterns: PatternMeshes::new(ctx, Quality::default()),
+            grid: None,
+            quality: AdaptiveQuality::default(),
+            text_style: TextStyle::new(config.ui_scale),
+            frame_start: Instant::now(),
+            beats: None,
+            settings,
+            window,
+            config_dir: ctx.fs.user_config_dir().to_path_buf(),
+            data_dir: ctx.fs.user_data_dir().to_path_buf(),
+            autosave_timer: 0.0,
+            config_watcher: config.watch_config.then(ConfigWatcher::new),
+            config,
+            seed,
+            rng,
+            ticks: 0,
+            recording,
+            replay: None,
+            screenshot: None,
+            redraw: true,
+            drawn_size: (0.0, 0.0),
+            clip: None,
+            art: None,
+            saving: Vec::new(),
+            trajectory: None,
+            queued_ticks: 0,
+            overlapped: None,
+            spare: None,
+            requests,
+            subscribers: Vec::new(),
+            watchers: Vec::new(),
+            #[cfg(feature = "lan")]
+            lan,
+        };
+        match replay {
+            Some(Ok(recording)) => {
+                if recording.window_size != (width, height) {
+                    app.toasts
+                        .push("Replay was recorded at another window size, and may differ");
+                }
+                app.replay = Some(Replay::new(recording));
+            }
+            Some(Err(err)) => app.toasts.push(format!("Failed to load replay: {}", err)),
+            None => (),
+        }
+        if let Err(err) = decorated {
+            app.toasts.push(format!("Failed to load icon: {}", err));
+        }
+        app.sim.params.parallel = app.parallel();
+        if let Some(path) = app.config.log_csv.clone() {
+            app.toggle_log(Some(path));
+        }
+        if app.crash_path().exists() {
+            app.toasts
+                .push("The last session crashed, its scene can be restored from the menu");
+        }
+        // Stay on the start screen if there is nothing to load, or it fails
+        app.open_menu();
+        if app.is_guest() {
+            // Filled in by the host's first frame
+            let scene = app.config.random_scene(&mut app.rng, 0, width, height);
+            app.load_scene(scene, SceneSource::Random(0));
+        } else if let Some(path) = app.config.scene.clone() {
+            app.load_source(ctx, SceneSource::File(path));
+        } else if app.config.balls > 0 || app.config.stress {
+            app.load_source(ctx, SceneSource::Random(app.config.balls));
+        }
+        if app.config.stress {
+            app.stress = Some(StressTest::default());
+        }
+        app
+    }
+
+    /// Play looping music from a path, or the default track in the resources directory
+    pub fn load_music(&mut self, ctx: &mut Context, path: Option<&Path>) {
+        match Music::load(ctx, path, self.settings.audio.music_volume()) {
+            Ok(music) => self.music = music,
+            Err(err) => self.toasts.push(format!("Failed to load music: {}", err)),
         }
     }
 
-    pub fn new_random(rng: &mut impl Rng, width: f32, height: f32) -> Self {
-        let radius = rng.gen_range(10.0..50.0);
-        let x = rng.gen_range(radius..width - radius);
-        let y = rng.gen_range(radius..height - radius);
-        Ball::new(x, y, radius, color!(?rng))
+    pub fn reset(&mut self, ctx: &mut Context) {
+        if let Some(source) = self.source.clone() {
+            self.load_source(ctx, source);
+        }
     }
 
-    pub fn collides(&self, other: &Self) -> bool {
-        let dx = self.point.x - other.point.x;
-        let dy = self.point.y - other.point.y;
-        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
-        dist <= self.radius + other.radius
+    fn load_scene(&mut self, scene: Scene, source: SceneSource) {
+        let Params { width, height, .. } = self.sim.params;
+        // Keep forces the user has toggled
+        let forces = mem::take(&mut self.sim.forces);
+        let heat = self.sim.params.heat;
+        self.sim = Simulation::from_scene(scene, width, height);
+        self.sim.forces = forces;
+        self.sim.params.heat = heat;
+        self.sim.params.parallel = self.parallel();
+        self.sim.cap = self.config.cap();
+        self.sim.lifetime = self.config.lifetime;
+        self.sim.max_speed = self.speed_limit();
+        self.inspector = None;
+        self.context_menu = None;
+        self.score = Score::default();
+        self.particles.clear();
+        self.graph.clear();
+        self.history.clear();
+        self.highlight = None;
+        self.rewind.clear();
+        self.rewinding = false;
+        if let Some(log) = &mut self.trajectory {
+            log.balls.clear();
+        }
+        self.source = Some(source);
+        self.state = State::Running;
     }
 
-    pub fn collides_point(&self, point: Point2<f32>) -> bool {
-        let dx = self.point.x - point.x;
-        let dy = self.point.y - point.y;
-        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
-        dist <= self.radius
+    fn load_source(&mut self, ctx: &mut Context, source: SceneSource) {
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut mode: Box<dyn GameMode> = Box::new(Sandbox);
+        let scene = match &source {
+            SceneSource::Demo(demo) => demo.build(&mut self.rng, width, height),
+            SceneSource::Mode(kind) => {
+                let (game, scene) = kind.start(width, height);
+                mode = game;
+                scene
+            }
+            SceneSource::Challenge(path) => match Challenge::load(path) {
+                Ok(challenge) => {
+                    let (game, scene) = Timed::new(challenge, width, height);
+                    mode = Box::new(game);
+                    scene
+                }
+                Err(err) => {
+                    self.toasts.push(format!(
+                        "Failed to load {}: {}",
+                        scene::file_name(path),
+                        err
+                    ));
+                    return;
+                }
+            },
+            SceneSource::Random(count) => {
+                self.config
+                    .random_scene(&mut self.rng, *count, width, height)
+            }
+            SceneSource::Crash => match Crash::load(&self.crash_path()) {
+                Ok(crash) => {
+                    // Carry on with the same randomness as before the crash
+                    self.seed = crash.seed;
+                    self.rng = StdRng::seed_from_u64(crash.seed);
+                    crash.scene
+                }
+                Err(err) => {
+                    self.toasts
+                        .push(format!("Failed to restore scene from crash: {}", err));
+                    return;
+                }
+            },
+            SceneSource::File(path) => match scene::load(path) {
+                Ok(scene) => scene,
+                Err(err) => {
+                    self.toasts.push(format!(
+                        "Failed to load {}: {}",
+                        scene::file_name(path),
+                        err
+                    ));
+                    return;
+                }
+            },
+        };
+        self.toasts.push(format!("Loaded {}", source.name()));
+        log::info!("Loaded {}", source.name());
+        if let SceneSource::Mode(kind) = source {
+            self.toasts.push(kind.description());
+        }
+        self.mode = mode;
+        self.load_scene(scene, source);
+        self.with_mode(|mode, game| mode.start(game));
     }
 
-    pub fn move_from(&mut self, other: &Self) {
-        let bounce = 0.05;
-        let jump = 0.6;
+    /// Run a mode hook with what it can change
+    fn with_mode<T>(&mut self, f: impl FnOnce(&mut dyn GameMode, &mut ModeContext) -> T) -> T {
+        let mut game = ModeContext {
+            sim: &mut self.sim,
+            toasts: &mut self.toasts,
+            rng: &mut self.rng,
+        };
+        f(self.mode.as_mut(), &mut game)
+    }
 
-        let dx = self.point.x - other.point.x;
-        let dy = self.point.y - other.point.y;
-        let angle = dy.atan2(dx);
+    /// Apply changed values from the config files to the running app
+    fn reload_config(&mut self) {
+        let mut config = AppConfig::default();
+        if let Err(err) = config_file::load(&mut config) {
+            self.toasts
+                .push(format!("Failed to reload config: {}", err));
+            return;
+        }
 
-        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
-        let force = self.radius + other.radius - dist;
+        // Only overwrite values that changed, to keep changes made in the app
+        if config.gravity != self.config.gravity {
+            self.sim.params.gravity = config.gravity;
+        }
+        if config.restitution != self.config.restitution {
+            if let Some(restitution) = config.restitution {
+                self.sim.update_balls(|ball| ball.restitution = restitution);
+            }
+        }
+        if let Some(forces) = config.forces.take() {
+            self.sim.forces = forces;
+            self.sim.wake();
+        }
+        if config.cap() != self.config.cap() {
+            self.sim.cap = config.cap();
+        }
+        self.sim.lifetime = config.lifetime;
+        self.keys = config.keys.clone();
 
-        let x = angle.cos() * force;
-        let y = angle.sin() * force;
-        self.velocity.x += x * bounce * self.get_bounce_amount();
-        self.velocity.y += y * bounce * self.get_bounce_amount();
-        self.point.x += x * jump;
-        self.point.y += y * jump;
+        self.config.gravity = config.gravity;
+        self.config.units = config.units;
+        self.config.grid_size = config.grid_size;
+        self.config.skip_static_frames = config.skip_static_frames;
+        self.config.adaptive_quality = config.adaptive_quality;
+        self.config.reduced_motion = config.reduced_motion;
+        self.sim.max_speed = self.speed_limit();
+        if config.ui_scale != self.config.ui_scale {
+            self.config.ui_scale = config.ui_scale;
+            self.text_style = TextStyle::new(config.ui_scale);
+        }
+        self.config.restitution = config.restitution;
+        self.config.radius = config.radius;
+        self.config.palette = config.palette;
+        self.config.max_balls = config.max_balls;
+        self.config.cap_policy = config.cap_policy;
+        self.config.lifetime = config.lifetime;
+        self.config.keys = config.keys;
+        self.toasts.push("Config reloaded");
     }
 
-    pub fn get_bounce_amount(&self) -> f32 {
-        let bounce_mass_falloff = 0.05;
-        1.0 / (self.radius * bounce_mass_falloff).max(1.0)
+    /// Change a volume setting, saving and applying it
+    fn adjust_volume(&mut self, channel: Channel, direction: f32) {
+        self.settings.audio.adjust(channel, direction);
+        self.apply_volume();
     }
-}
 
-pub struct App {
-    balls: Vec<Ball>,
-    active_ball: Option<(usize, Point2<f32>)>,
-}
+    fn set_volume(&mut self, channel: Channel, volume: f32) {
+        self.settings.audio.set(channel, volume);
+        self.apply_volume();
+    }
 
-impl App {
-    pub fn new(ctx: &mut Context) -> Self {
-        let (width, height) = ctx.gfx.drawable_size();
+    /// Play music at the changed volume and save it
+    fn apply_volume(&mut self) {
+        if let Some(music) = &mut self.music {
+            music.set_volume(self.settings.audio.music_volume());
+        }
+        if let Err(err) = self.settings.save(&self.config_dir) {
+            self.toasts
+                .push(format!("Failed to save settings: {}", err));
+        }
+    }
+
+    /// Resize text and menus
+    fn set_text_style(&mut self, style: TextStyle) {
+        self.text_style = style;
+        self.toasts
+            .push(format!("UI scale: {:.0}%", style.scale() * 100.0));
+    }
+
+    /// Whether effects that move on their own are left out, by the config or settings
+    fn reduced_motion(&self) -> bool {
+        self.config.reduced_motion || self.settings.reduced_motion
+    }
+
+    /// Speed limit to give simulations, slowing the fastest balls with reduced motion on
+    fn speed_limit(&self) -> Option<f32> {
+        self.reduced_motion().then_some(REDUCED_MOTION_SPEED)
+    }
 
-        let mut balls = Vec::new();
-        let mut rng = rand::thread_rng();
-        for _ in 0..10 {
-            balls.push(Ball::new_random(&mut rng, width, height));
+    /// Switch reduced motion in the settings, saving and applying it
+    fn toggle_reduced_motion(&mut self) {
+        self.settings.reduced_motion = !self.settings.reduced_motion;
+        if let Err(err) = self.settings.save(&self.config_dir) {
+            self.toasts
+                .push(format!("Failed to save settings: {}", err));
+        }
+        if self.config.reduced_motion && !self.settings.reduced_motion {
+            self.toasts
+                .push("Reduced motion stays on, as the config file turns it on");
         }
-        sort_balls_by_size(&mut balls);
+        self.sim.max_speed = self.speed_limit();
+        if self.reduced_motion() {
+            self.particles.clear();
+        }
+    }
 
-        Self {
-            balls,
-            active_ball: None,
+    fn save_scene(&mut self) {
+        let Some(source) = &self.source else {
+            return;
+        };
+        match scene::save(&self.sim.scene(), &source.file_stem()) {
+            Ok(path) => self
+                .toasts
+                .push(format!("Scene saved to {}", scene::file_name(&path))),
+            Err(err) => self.toasts.push(format!("Failed to save scene: {}", err)),
         }
     }
 
-    pub fn reset(&mut self, ctx: &mut Context) {
-        *self = Self::new(ctx);
+    /// Whether to resolve collisions in parallel, which replays must not do
+    /// so they stay identical to their recording
+    fn parallel(&self) -> bool {
+        self.config.parallel && self.recording.is_none() && self.replay.is_none()
     }
 
-    fn move_active_ball(&mut self, x: f32, y: f32, vx: f32, vy: f32) {
-        if let Some((i, offset)) = self.active_ball {
-            let x = x - offset.x;
-            let y = y - offset.y;
-            let ball = &mut self.balls[i];
-            ball.point = Point2 { x, y };
-            ball.velocity = Vector2 { x: vx, y: vy };
+    /// Whether to run physics while drawing, which needs ticks to run in whole
+    /// frames, so not while replaying or logging between them
+    fn pipelined(&self) -> bool {
+        self.config.pipeline
+            && !self.is_guest()
+            && self.recording.is_none()
+            && self.replay.is_none()
+            && self.trajectory.is_none()
+    }
+
+    /// Run the ticks queued by the last update on another thread, while drawing
+    /// from a copy of the simulation as it was before them
+    fn overlap_physics(&mut self, draw: impl FnOnce(&Self) -> GameResult) -> GameResult {
+        let ticks = mem::take(&mut self.queued_ticks);
+        if ticks == 0 {
+            return draw(self);
         }
+        let mut front = self
+            .spare
+            .take()
+            .unwrap_or_else(|| Simulation::new(self.sim.params));
+        front.fork_from(&self.sim);
+        front.held = self.sim.held;
+        let mut back = mem::replace(&mut self.sim, front);
+        back.clear_events();
+
+        let app = &*self;
+        let (result, stats) = thread::scope(|scope| {
+            let worker = scope.spawn(|| back.run(ticks));
+            let result = draw(app);
+            let stats = worker
+                .join()
+                .unwrap_or_else(|panic| std::panic::resume_unwind(panic));
+            (result, stats)
+        });
+        self.spare = Some(mem::replace(&mut self.sim, back));
+        self.ticks += ticks as u64;
+        self.overlapped = stats;
+        result
     }
 
-    fn is_active_ball(&self, index: usize) -> bool {
-        if let Some((i, _)) = self.active_ball {
-            if i == index {
-                return true;
+    /// Run physics ticks, stopping between them to play back input recorded there
+    fn run_ticks(&mut self, ctx: &mut Context, mut ticks: u32) -> GameResult<Option<StepStats>> {
+        self.sim.clear_events();
+        let mut stats: Option<StepStats> = None;
+        while ticks > 0 && self.state == State::Running {
+            let until_input = self
+                .replay
+                .as_ref()
+                .and_then(|replay| replay.ticks_until_next(self.ticks));
+            let until_sample = self
+                .trajectory
+                .as_ref()
+                .map(|log| log.ticks_until_sample(self.ticks));
+            let count = match until_input.into_iter().chain(until_sample).min() {
+                Some(until) => until.clamp(1, ticks as u64) as u32,
+                None => ticks,
+            };
+            if let Some(step) = self.sim.run(count) {
+                stats = Some(match stats {
+                    Some(stats) => stats.merge(step),
+                    None => step,
+                });
             }
+            self.ticks += count as u64;
+            ticks -= count;
+            self.record_trajectory();
+            self.play_inputs(ctx)?;
         }
-        false
+        Ok(stats)
     }
 
-    fn add_ball(&mut self, ball: Ball) {
-        self.balls.push(ball);
-        sort_balls_by_size(&mut self.balls);
+    /// Play back recorded input due by the current tick
+    fn play_inputs(&mut self, ctx: &mut Context) -> GameResult {
+        while let Some(input) = self
+            .replay
+            .as_mut()
+            .and_then(|replay| replay.next_input(self.ticks))
+        {
+            self.apply_input(ctx, input)?;
+        }
+        if self.replay.as_ref().is_some_and(Replay::is_finished) {
+            self.replay = None;
+            self.toasts.push("Replay finished");
+        }
+        Ok(())
     }
-}
 
-/// Sort list of balls largest to smallest
-fn sort_balls_by_size(balls: &mut Vec<Ball>) {
-    balls.sort_by(|a, b| b.radius.partial_cmp(&a.radius).unwrap());
-}
+    /// Handle input from the user, recording it if enabled. Ignored during a replay
+    fn user_input(&mut self, ctx: &mut Context, input: Input) -> GameResult {
+        if self.replay.is_some() {
+            return Ok(());
+        }
+        if let Some((_, recording)) = &mut self.recording {
+            recording.inputs.push((self.ticks, input));
+        }
+        self.apply_input(ctx, input)
+    }
 
-impl EventHandler for App {
-    fn update(&mut self, ctx: &mut Context) -> Result<(), ggez::GameError> {
-        let (width, height) = ctx.gfx.drawable_size();
+    fn apply_input(&mut self, ctx: &mut Context, input: Input) -> GameResult {
+        self.redraw = true;
+        match input {
+            Input::MouseMotion { x, y, dx, dy } => self.mouse_motion(ctx, x, y, dx, dy),
+            Input::MouseDown { button, x, y } => self.mouse_down(ctx, button, x, y),
+            Input::MouseUp { button, x, y } => self.mouse_up(ctx, button, x, y),
+            Input::KeyDown { key, ctrl } => self.key_down(ctx, key, ctrl),
+            Input::KeyUp { key } => self.key_up(ctx, key),
+        }
+    }
 
-        let bounce_amount = 0.5;
+    /// Start recording a GIF clip, or stop and save it
+    fn toggle_clip(&mut self) {
+        match self.clip.take() {
+            Some(clip) if clip.is_empty() => (),
+            Some(clip) => {
+                self.saving.push(("clip", clip.save()));
+                self.toasts.push("Saving clip...");
+            }
+            None => {
+                self.clip = Some(ClipRecorder::new());
+                self.toasts.push("Recording clip");
+            }
+        }
+    }
 
-        for i in 0..self.balls.len() {
-            if self.is_active_ball(i) {
-                continue;
+    /// Start taking a long-exposure picture of the balls, or stop and save it
+    fn toggle_art(&mut self) {
+        match self.art.take() {
+            Some(art) if art.is_empty() => (),
+            Some(art) => {
+                self.saving.push(("picture", art.save()));
+                self.toasts.push("Saving picture...");
             }
-            let ball = &mut self.balls[i];
-            if ball.point.y + ball.radius < height {
-                ball.velocity.y += 0.5
+            None => {
+                self.art = Some(Exposure::new(self.sim.params.width, self.sim.params.height));
+                self.toasts.push("Taking a long exposure");
             }
         }
+    }
 
-        for i in 0..self.balls.len() {
-            if self.is_active_ball(i) {
-                continue;
+    /// Start logging trajectories to a file, or a new one in the logs directory,
+    /// or stop if already logging
+    fn toggle_log(&mut self, path: Option<PathBuf>) {
+        if let Some(mut log) = self.trajectory.take() {
+            match log.flush() {
+                Ok(()) => self.toasts.push("Stopped logging trajectories"),
+                Err(err) => self.toasts.push(format!("Failed to save log: {}", err)),
+            }
+            return;
+        }
+        let log = match path {
+            Some(path) => Ok(path),
+            None => capture::timestamped(Path::new(LOGS_DIR), "trajectory", "csv"),
+        }
+        .and_then(|path| {
+            let log = TrajectoryLog::create(&path, self.config.log_every)?;
+            Ok((path, log))
+        });
+        match log {
+            Ok((path, log)) => {
+                self.trajectory = Some(log);
+                self.record_trajectory();
+                self.toasts
+                    .push(format!("Logging trajectories to {}", path.display()));
             }
-            let ball = &mut self.balls[i];
-            ball.point.x += ball.velocity.x;
-            ball.point.y += ball.velocity.y;
+            Err(err) => self.toasts.push(format!("Failed to start log: {}", err)),
+        }
+    }
+
+    fn record_trajectory(&mut self) {
+        let Some(log) = &mut self.trajectory else {
+            return;
+        };
+        if let Err(err) = log.record(self.ticks, &self.sim) {
+            self.trajectory = None;
+            self.toasts
+                .push(format!("Failed to log trajectories: {}", err));
+        }
+    }
+
+    /// Report clips and pictures that finished encoding
+    fn check_saved_files(&mut self) {
+        let (done, saving): (Vec<_>, _) = mem::take(&mut self.saving)
+            .into_iter()
+            .partition(|(_, handle)| handle.is_finished());
+        self.saving = saving;
+        for (what, handle) in done {
+            let message = match handle.join() {
+                Ok(Ok(path)) => format!("Saved {} to {}", what, path.display()),
+                Ok(Err(err)) => format!("Failed to save {}: {}", what, err),
+                Err(_) => format!("Failed to save {}", what),
+            };
+            self.toasts.push(message);
+        }
+    }
+
+    /// Save the current scene to restore in the next session
+    fn autosave(&mut self) {
+        self.autosave_timer = 0.0;
+        if self.source.is_none() {
+            return;
+        }
+        if let Err(err) = scene::write(&self.sim.scene(), &self.autosave_path()) {
+            self.toasts.push(format!("Failed to autosave: {}", err));
+        }
+    }
+
+    fn autosave_path(&self) -> PathBuf {
+        self.data_dir.join(scene::AUTOSAVE_FILE)
+    }
+
+    fn crash_path(&self) -> PathBuf {
+        self.data_dir.join(CRASH_FILE)
+    }
+
+    /// Save the scene and seed, to offer restoring them after a crash
+    pub(crate) fn save_crash(&self) -> Result<PathBuf, String> {
+        if self.source.is_none() {
+            return Err("No scene is open".to_string());
+        }
+        let crash = Crash {
+            seed: self.seed,
+            scene: self.sim.scene(),
+        };
+        let path = self.crash_path();
+        crash.save(&path)?;
+        Ok(path)
+    }
+
+    /// Whether the next frame would look the same as the last, with the scene at
+    /// rest or paused and nothing on screen animating
+    fn is_still(&self) -> bool {
+        (self.state != State::Running || self.sim.is_resting())
+            && !self.mode.is_animated()
+            && self.highlight.is_none()
+            && !self.aiming
+            && self.stress.is_none()
+            && self.clip.is_none()
+            && self.screenshot.is_none()
+            && !self.show_stats
+            && !self.show_profiler
+            && !self.show_graph
+            && self.toasts.is_empty()
+            && self.particles.is_empty()
+            && !self.score.is_animating()
+    }
+
+    /// Replay the last big collision in slow motion, or stop a replay being shown
+    fn toggle_highlight(&mut self) {
+        if self.highlight.take().is_some() {
+            return;
+        }
+        self.highlight = self.history.replay();
+        if self.highlight.is_none() {
+            self.toasts.push("Nothing to replay yet");
+        }
+    }
+
+    /// Start going back through time, which replays and LAN guests can't do as
+    /// it depends on how long the key is held rather than on ticks
+    fn start_rewind(&mut self) {
+        if !self.rewind.is_enabled() {
+            self.toasts.push("Rewinding is turned off");
+        } else if self.recording.is_some() || self.replay.is_some() || self.is_guest() {
+            self.toasts
+                .push("Can't rewind while recording or replaying");
+        } else {
+            self.rewinding = true;
+            self.charge = None;
+            self.sim.stop_quake();
+        }
+    }
+
+    /// Follow the ball under the cursor while inspecting
+    fn update_hover(&mut self, ticks: u32) {
+        if self.tool != Tool::Inspect {
+            self.hover = None;
+            return;
         }
+        let Some((id, ball)) = self
+            .sim
+            .ball_at(self.cursor)
+            .and_then(|id| Some((id, self.sim.ball(id)?)))
+        else {
+            self.hover = None;
+            return;
+        };
+        match &mut self.hover {
+            Some(hover) if hover.ball == id => hover.update(ball.velocity, ticks),
+            _ => self.hover = Some(Hover::new(id, ball.velocity)),
+        }
+    }
 
-        for i in 0..self.balls.len() {
-            if self.is_active_ball(i) {
-                continue;
+    /// Burst and sound for every ball popped in the last step
+    fn pop_effects(&mut self, ctx: &mut Context) {
+        let volume = self.settings.audio.effects_volume();
+        let bursts = self.quality.quality.effects() && !self.reduced_motion();
+        for event in self.sim.events() {
+            if let Event::BallPopped {
+                point,
+                radius,
+                color,
+            } = *event
+            {
+                // Not from the app's generator, so effects can't change a replay
+                if bursts {
+                    self.particles
+                        .burst(&mut rand::thread_rng(), point, radius, color);
+                }
+                self.effects.pop(ctx, volume, radius);
             }
-            for j in 0..self.balls.len() {
-                if i == j {
-                    continue;
+            if let Event::Exploded { point, radius } = *event {
+                if bursts {
+                    self.particles.burst(
+                        &mut rand::thread_rng(),
+                        point,
+                        radius / 4.0,
+                        color!(0xffb347),
+                    );
                 }
-                let ball = &self.balls[i];
-                let other = self.balls[j].clone();
+                self.effects.pop(ctx, volume, radius);
+            }
+        }
+    }
+
+    fn move_active_ball(&mut self, x: f32, y: f32, vx: f32, vy: f32) {
+        if let Some(i) = self.sim.held {
+            let Point2 { x, y } = self.snap_point(Point2 {
+                x: x - self.grab_offset.x,
+                y: y - self.grab_offset.y,
+            });
+            let Some(mut ball) = self.sim.ball_mut(i) else {
+                return;
+            };
+            ball.point = Point2 { x, y };
+            ball.velocity = Vector2 { x: vx, y: vy };
+        }
+    }
+
+    /// Add a ball, returning whether the cap allowed it
+    fn add_ball(&mut self, ball: Ball) -> bool {
+        let added = self.sim.add_ball(ball).is_some();
+        if !added {
+            self.cap_reached();
+        }
+        added
+    }
+
+    fn spawn_balls(&mut self, balls: Vec<Ball>) {
+        let count = self.sim.add_balls(balls);
+        if count == 0 {
+            self.cap_reached();
+            return;
+        }
+        self.toasts.push(format!(
+            "{} ball{} added ({} total)",
+            count,
+            if count == 1 { "" } else { "s" },
+            self.sim.ball_count()
+        ));
+    }
+
+    fn cap_reached(&mut self) {
+        if let Some(cap) = self.sim.cap {
+            self.toasts
+                .push(format!("Ball limit reached ({} balls)", cap.max));
+        }
+    }
+
+    fn remove_ball(&mut self, id: BallId) {
+        self.sim.remove_ball(id);
+        if self
+            .inspector
+            .as_ref()
+            .is_some_and(|inspector| inspector.ball == id)
+        {
+            self.inspector = None;
+        }
+        if self
+            .context_menu
+            .as_ref()
+            .is_some_and(|(ball, _)| *ball == id)
+        {
+            self.context_menu = None;
+        }
+    }
+
+    /// Launch every loose ball upwards, scaled by beat strength
+    fn kick_balls(&mut self, strength: f32) {
+        let impulse = BEAT_IMPULSE * strength.min(MAX_BEAT_STRENGTH);
+        self.sim.kick(impulse, &mut self.rng);
+    }
+
+    fn toggle_stress_test(&mut self) {
+        if self.stress.take().is_some() {
+            self.toasts.push("Stress test stopped");
+            return;
+        }
+        self.stress = Some(StressTest::default());
+        self.toasts.push(format!(
+            "Stress test started, adding {} balls a second while above {} FPS",
+            stress::STEP_BALLS,
+            stress::MIN_FPS
+        ));
+    }
 
-                if ball.collides(&other) {
-                    let ball = &mut self.balls[i];
-                    ball.move_from(&other);
+    /// Add balls for a running stress test while the frame rate keeps up, and
+    /// report the most it kept up with once it can't
+    fn update_stress_test(&mut self, ctx: &mut Context, dt: f32) {
+        let Some(stress) = &mut self.stress else {
+            return;
+        };
+        let limit = match stress.update(dt, self.sim.ball_count()) {
+            Verdict::Measuring => return,
+            Verdict::KeptUp => {
+                let (width, height) = (self.sim.params.width, self.sim.params.height);
+                let scene =
+                    self.config
+                        .random_scene(&mut self.rng, stress::STEP_BALLS, width, height);
+                if self.sim.add_balls(scene.balls) == stress::STEP_BALLS {
+                    return;
                 }
+                "the ball limit"
+            }
+            Verdict::TooSlow => "the frame rate",
+        };
+        let Some(stress) = self.stress.take() else {
+            return;
+        };
+        let mut options = Vec::new();
+        if self.parallel() {
+            options.push("parallel collisions");
+        }
+        if self.pipelined() {
+            options.push("pipelined physics");
+        }
+        let report = format!(
+            "Sustained {} balls above {} FPS, stopped by {}{}",
+            stress.sustained,
+            stress::MIN_FPS,
+            limit,
+            if options.is_empty() {
+                String::new()
+            } else {
+                format!(", with {}", options.join(" and "))
+            }
+        );
+        log::info!("{}", report);
+        if self.config.stress {
+            println!("{}", report);
+            ctx.request_quit();
+        } else {
+            self.toasts.push(report);
+        }
+    }
+
+    /// Judge the time spent on the frame just drawn, changing the detail drawn
+    /// if frames have been too slow or fast for a while
+    fn adapt_quality(&mut self, ctx: &Context) {
+        if !self.config.adaptive_quality {
+            return;
+        }
+        let dt = ctx.time.delta().as_secs_f32();
+        let before = self.quality.quality;
+        let Some(quality) = self.quality.record(self.frame_start.elapsed(), dt) else {
+            return;
+        };
+        self.circle = render::unit_circle(ctx, quality);
+        self.patterns = PatternMeshes::new(ctx, quality);
+        if !quality.effects() {
+            self.particles.clear();
+        }
+        log::info!("Drawing quality set to {}", quality.name());
+        self.toasts.push(format!(
+            "Drawing quality {} to {}",
+            if quality < before {
+                "lowered"
+            } else {
+                "raised"
+            },
+            quality.name()
+        ));
+    }
+
+    fn toggle_heat(&mut self) {
+        self.sim.params.heat = !self.sim.params.heat;
+        if !self.sim.params.heat {
+            self.sim.components.temperature.clear();
+        }
+        self.toasts.push(format!(
+            "Temperature {}",
+            if self.sim.params.heat { "ON" } else { "OFF" }
+        ));
+    }
+
+    fn toggle_beats(&mut self) {
+        if self.beats.take().is_some() {
+            self.toasts.push("Audio-reactive mode OFF");
+            return;
+        }
+        match BeatDetector::new() {
+            Ok(beats) => {
+                self.beats = Some(beats);
+                self.toasts.push("Audio-reactive mode ON");
+            }
+            Err(err) => self
+                .toasts
+                .push(format!("Audio-reactive mode unavailable: {}", err)),
+        }
+    }
+
+    /// Sleep until a frame has lasted as long as the FPS cap allows. Physics
+    /// runs in whole ticks whatever the frame rate, so it keeps the same pace
+    fn pace_frame(&mut self) {
+        if let Some(fps) = self.config.fps_cap {
+            let frame = Duration::from_secs_f32(1.0 / fps as f32);
+            if let Some(left) = frame.checked_sub(self.frame_start.elapsed()) {
+                thread::sleep(left);
             }
         }
+        self.frame_start = Instant::now();
+    }
+}
+
+impl EventHandler for App {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.pace_frame();
+        let dt = ctx.time.delta().as_secs_f32();
+        if !self.is_still() {
+            self.redraw = true;
+        }
+        self.toasts.update(dt);
 
-        for ball in &mut self.balls {
-            if ball.point.x - ball.radius < 0.0 {
-                ball.point.x = ball.radius;
-                ball.velocity.x *= -bounce_amount * ball.get_bounce_amount();
+        if let Some(watcher) = &mut self.config_watcher {
+            if watcher.update(dt) {
+                self.reload_config();
             }
-            if ball.point.x + ball.radius >= width {
-                ball.point.x = width - ball.radius;
-                ball.velocity.x *= -bounce_amount * ball.get_bounce_amount();
+        }
+
+        self.check_saved_files();
+        self.play_inputs(ctx)?;
+        self.handle_requests();
+        #[cfg(feature = "lan")]
+        self.receive_lan();
+        if self.state != State::Running {
+            return Ok(());
+        }
+
+        let (width, height) = ctx.gfx.drawable_size();
+        self.sim.params.width = width;
+        self.sim.params.height = height;
+
+        self.autosave_timer += dt;
+        if self.autosave_timer >= AUTOSAVE_INTERVAL {
+            self.autosave();
+        }
+
+        if let Some(beats) = &mut self.beats {
+            if let Some(strength) = beats.update(dt) {
+                self.kick_balls(strength);
             }
+        }
 
-            if ball.point.y + ball.radius >= height {
-                ball.point.y = height - ball.radius;
-                ball.velocity.y *= -bounce_amount * ball.get_bounce_amount();
+        if let Some(highlight) = &mut self.highlight {
+            if highlight.update(dt) {
+                self.highlight = None;
             }
+            return Ok(());
+        }
+
+        if self.rewinding {
+            self.queued_ticks = 0;
+            if let Some(snapshot) = self.rewind.step_back(dt) {
+                self.sim.restore(snapshot);
+            }
+            self.redraw = true;
+            return Ok(());
+        }
+
+        self.aim_selected(dt);
+        let start = Instant::now();
+        let ticks = if self.is_guest() {
+            0
+        } else {
+            self.sim.ticks_due(dt)
+        };
+        let (ticks, stats) = if self.pipelined() {
+            // Queue these to run while the frame is drawn, and take in the ones
+            // that ran while the last frame was
+            let ran = mem::replace(&mut self.queued_ticks, ticks);
+            (ran, self.overlapped.take())
+        } else {
+            (ticks, self.run_ticks(ctx, ticks)?)
+        };
+        log::trace!("Ran {} ticks in {:?}", ticks, start.elapsed());
+        self.profiler.record("Physics", 0, start.elapsed());
+        let times = stats.map(|stats| stats.times).unwrap_or_default();
+        for (phase, elapsed) in times.phases() {
+            self.profiler.record(phase, 1, elapsed);
+        }
+
+        let start = Instant::now();
+        if ticks > 0 {
+            self.history.record(dt, &self.sim);
+            self.rewind.record(ticks, &self.sim);
+            if let Some(art) = &mut self.art {
+                art.expose(&self.sim);
+            }
+        }
+        self.update_hover(ticks);
+        self.publish_events();
+        self.publish_state(ticks);
+        #[cfg(feature = "lan")]
+        self.send_lan();
+        // Timed by ticks rather than frames, so replays play out the same
+        self.with_mode(|mode, game| mode.update(game, ticks as f32 * TICK));
+        self.score.record(self.sim.events());
+        self.score.update(dt);
+        self.pop_effects(ctx);
+        self.particles.update(dt);
+        if stats.is_some() {
+            self.graph
+                .push(self.sim.kinetic_energy(), self.sim.ball_count());
+        }
+        self.stats.record(stats, dt);
+        self.update_stress_test(ctx, dt);
+        self.profiler.record("Effects", 0, start.elapsed());
+        if !self.is_still() {
+            self.redraw = true;
         }
 
         Ok(())
     }
 
-    fn draw(&mut self, ctx: &mut Context) -> Result<(), ggez::GameError> {
-        let mut canvas = graphics::Canvas::from_frame(ctx, color!(BLACK));
-
-        for ball in &self.balls {
-            let circle = Mesh::new_circle(
-                ctx,
-                DrawMode::fill(),
-                ball.point,
-                ball.radius,
-                0.1,
-                ball.color,
-            )?;
-            canvas.draw(&circle, DrawParam::default());
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        // The last frame stays on screen if nothing is drawn over it
+        let size = ctx.gfx.drawable_size();
+        if self.config.skip_static_frames && !self.redraw && size == self.drawn_size {
+            return self.overlap_physics(|_| Ok(()));
         }
+        self.redraw = false;
+        self.drawn_size = size;
 
-        canvas.finish(ctx)
+        let start = Instant::now();
+        let clean = self.screenshot == Some(Screenshot::Clean);
+        if self.snap {
+            self.build_grid(ctx)?;
+        }
+        self.overlap_physics(|app| app.draw_frame(ctx, clean))?;
+        log::trace!("Drew frame in {:?}", start.elapsed());
+        self.profiler.record("Draw", 0, start.elapsed());
+        self.adapt_quality(ctx);
+        if self.screenshot.take().is_some() {
+            match capture::save_screenshot(ctx) {
+                Ok(path) => self
+                    .toasts
+                    .push(format!("Screenshot saved to {}", path.display())),
+                Err(err) => self
+                    .toasts
+                    .push(format!("Failed to take screenshot: {}", err)),
+            }
+        }
+        if let Some(clip) = &mut self.clip {
+            if let Err(err) = clip.capture(ctx, ctx.time.delta().as_secs_f32()) {
+                self.clip = None;
+                self.toasts.push(format!("Failed to record clip: {}", err));
+            }
+        }
+        Ok(())
     }
 
     fn mouse_motion_event(
         &mut self,
-        _ctx: &mut Context,
+        ctx: &mut Context,
         x: f32,
         y: f32,
         dx: f32,
         dy: f32,
-    ) -> Result<(), ggez::GameError> {
-        self.move_active_ball(x, y, dx, dy);
-        Ok(())
+    ) -> GameResult {
+        self.user_input(ctx, Input::MouseMotion { x, y, dx, dy })
     }
 
     fn mouse_button_down_event(
         &mut self,
-        _ctx: &mut Context,
-        _button: ggez::event::MouseButton,
+        ctx: &mut Context,
+        button: MouseButton,
         x: f32,
         y: f32,
-    ) -> Result<(), ggez::GameError> {
-        if self.active_ball.is_some() {
-            return Ok(());
-        }
-        // Reverse to be sorted smallest to largest
-        for i in (0..self.balls.len()).rev() {
-            let ball = &self.balls[i];
-            if ball.collides_point(Point2 { x, y }) {
-                self.active_ball = Some((
-                    i,
-                    Point2 {
-                        x: x - ball.point.x,
-                        y: y - ball.point.y,
-                    },
-                ));
-                self.move_active_ball(x, y, 0.0, 0.0);
-                break;
-            }
-        }
-
-        Ok(())
+    ) -> GameResult {
+        self.user_input(ctx, Input::MouseDown { button, x, y })
     }
 
     fn mouse_button_up_event(
         &mut self,
-        _ctx: &mut Context,
-        _button: ggez::event::MouseButton,
-        _x: f32,
-        _y: f32,
-    ) -> Result<(), ggez::GameError> {
-        self.active_ball = None;
-        Ok(())
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.user_input(ctx, Input::MouseUp { button, x, y })
     }
 
     fn key_down_event(
         &mut self,
         ctx: &mut Context,
-        input: ggez::input::keyboard::KeyInput,
+        input: KeyInput,
         _repeated: bool,
-    ) -> Result<(), ggez::GameError> {
-        let (width, height) = ctx.gfx.drawable_size();
+    ) -> GameResult {
+        let Some(key) = input.keycode else {
+            return Ok(());
+        };
+        let ctrl = input.mods.contains(KeyMods::CTRL);
+        self.user_input(ctx, Input::KeyDown { key, ctrl })
+    }
 
-        let Some(keycode) = input.keycode else {
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
+        let Some(key) = input.keycode else {
             return Ok(());
         };
+        self.user_input(ctx, Input::KeyUp { key })
+    }
 
-        match keycode {
-            VirtualKeyCode::R => {
-                self.reset(ctx);
-            }
-            VirtualKeyCode::Space => {
-                self.add_ball(Ball::new_random(&mut rand::thread_rng(), width, height))
+    fn resize_event(&mut self, _ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.resized(width, height);
+        Ok(())
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        self.autosave();
+        self.save_window(ctx);
+        // Only offer to restore a crash until the app next closes normally
+        let _ = fs::remove_file(self.crash_path());
+        if let Some(log) = &mut self.trajectory {
+            if let Err(err) = log.flush() {
+                log::error!("Failed to save trajectory log: {}", err);
             }
-            VirtualKeyCode::X => {
-                if let Some((i, _)) = self.active_ball {
-                    self.balls.remove(i);
-                }
+        }
+        if let Some((path, recording)) = &self.recording {
+            if let Err(err) = recording.save(path) {
+                log::error!("Failed to save recording: {}", err);
             }
-            _ => (),
         }
-
-        Ok(())
+        Ok(false)
     }
 }
@@ -0,0 +1,341 @@
+use std::sync::mpsc::TryRecvError;
+
+use ggez::graphics::Color;
+use rand::Rng;
+
+use super::{App, State};
+use crate::ball::Ball;
+use crate::capture::Screenshot;
+use crate::config_file::parse_color;
+use crate::control::{self, BallState, Command, ForceState, Reply, VolumeState, WallState};
+use crate::events::Wall;
+#[cfg(feature = "lan")]
+use crate::lan::{Notice, Session};
+use crate::scene;
+use crate::settings::Channel;
+use crate::simulation::Params;
+use crate::text::TextStyle;
+use crate::walls::WallMaterial;
+
+impl App {
+    /// Run every command waiting from outside the app
+    pub(super) fn handle_requests(&mut self) {
+        loop {
+            let Some(requests) = &self.requests else {
+                return;
+            };
+            let request = match requests.try_recv() {
+                Ok(request) => request,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    self.requests = None;
+                    return;
+                }
+            };
+            if let Command::Subscribe = request.command {
+                if request.reply.send(Reply::Ok).is_ok() {
+                    self.subscribers.push(request.reply);
+                }
+                continue;
+            }
+            if let Command::Watch = request.command {
+                if request.reply.send(self.state_reply()).is_ok() {
+                    self.watchers.push(request.reply);
+                }
+                continue;
+            }
+            let reply = self.run_command(request.command);
+            // The client may have gone already, which is fine
+            let _ = request.reply.send(reply);
+            // Show changes made from a settings panel elsewhere
+            if self.state != State::Running && ["Settings", "Walls"].contains(&self.menu.title()) {
+                self.refresh_settings_menu();
+            }
+        }
+    }
+
+    fn run_command(&mut self, command: Command) -> Reply {
+        if !command.is_finite() {
+            return Reply::error("Numbers must be finite");
+        }
+        if let Some(reply) = self.run_settings_command(&command) {
+            return reply;
+        }
+        if self.state == State::Menu {
+            return Reply::error("No scene is open");
+        }
+        match command {
+            Command::Spawn {
+                x,
+                y,
+                radius,
+                vx,
+                vy,
+                color,
+            } => {
+                let color = match self.color_or_random(color) {
+                    Ok(color) => color,
+                    Err(err) => return Reply::error(err),
+                };
+                let mut ball = Ball::new(x, y, radius.max(1.0), color);
+                ball.velocity.x = vx;
+                ball.velocity.y = vy;
+                match self.sim.add_ball(ball) {
+                    Some(id) => Reply::Spawned {
+                        id: control::id_to_u64(id),
+                    },
+                    None => Reply::error("Ball limit reached"),
+                }
+            }
+            Command::Launch {
+                position,
+                radius,
+                speed,
+                color,
+            } => {
+                let color = match self.color_or_random(color) {
+                    Ok(color) => color,
+                    Err(err) => return Reply::error(err),
+                };
+                let Params { width, height, .. } = self.sim.params;
+                let radius = radius.max(1.0);
+                let x = radius + (width - 2.0 * radius).max(0.0) * position.clamp(0.0, 1.0);
+                let mut ball = Ball::new(x, height - radius, radius, color);
+                ball.velocity.y = -speed;
+                match self.sim.add_ball(ball) {
+                    Some(id) => Reply::Spawned {
+                        id: control::id_to_u64(id),
+                    },
+                    None => Reply::error("Ball limit reached"),
+                }
+            }
+            Command::Delete { id } => {
+                let id = control::id_from_u64(id);
+                if self.sim.ball(id).is_none() {
+                    return Reply::error("No such ball");
+                }
+                self.remove_ball(id);
+                Reply::Ok
+            }
+            Command::Gravity { value } => {
+                self.sim.params.gravity = value;
+                Reply::Ok
+            }
+            Command::Kick { impulse } => {
+                self.sim.kick(impulse, &mut self.rng);
+                Reply::Ok
+            }
+            Command::Save { name } => {
+                let name = match (name, &self.source) {
+                    (Some(name), _) => name,
+                    (None, Some(source)) => source.file_stem(),
+                    (None, None) => return Reply::error("No scene is open"),
+                };
+                // Only a plain name, so clients can't write outside the scenes directory
+                if name.is_empty() || name.contains(['/', '\\', '.']) {
+                    return Reply::error("Invalid scene name");
+                }
+                match scene::save(&self.sim.scene(), &name) {
+                    Ok(path) => Reply::Saved {
+                        path: path.display().to_string(),
+                    },
+                    Err(err) => Reply::error(err),
+                }
+            }
+            Command::Screenshot => {
+                self.screenshot = Some(Screenshot::Full);
+                Reply::Ok
+            }
+            Command::State => self.state_reply(),
+            Command::Subscribe
+            | Command::Watch
+            | Command::Settings
+            | Command::Force { .. }
+            | Command::Wall { .. }
+            | Command::Volume { .. }
+            | Command::ReducedMotion { .. }
+            | Command::UiScale { .. } => Reply::Ok,
+        }
+    }
+
+    /// Run a command changing the settings, which work without a scene open too
+    fn run_settings_command(&mut self, command: &Command) -> Option<Reply> {
+        let reply = match *command {
+            Command::Settings => self.settings_reply(),
+            Command::Force { index, enabled } => match self.sim.forces.get_mut(index) {
+                Some(force) => {
+                    force.enabled = enabled;
+                    Reply::Ok
+                }
+                None => Reply::error("No such force"),
+            },
+            Command::Wall {
+                wall,
+                restitution,
+                friction,
+            } => {
+                let walls = &mut self.sim.params.walls;
+                let material = walls.get(wall);
+                walls.set(
+                    wall,
+                    WallMaterial::new(
+                        restitution.unwrap_or(material.restitution),
+                        friction.unwrap_or(material.friction),
+                    ),
+                );
+                Reply::Ok
+            }
+            Command::Volume { channel, value } => {
+                self.set_volume(channel, value);
+                Reply::Ok
+            }
+            Command::ReducedMotion { value } => {
+                if self.settings.reduced_motion != value {
+                    self.toggle_reduced_motion();
+                }
+                Reply::Ok
+            }
+            Command::UiScale { value } => {
+                self.set_text_style(TextStyle::new(value));
+                Reply::Ok
+            }
+            _ => return None,
+        };
+        Some(reply)
+    }
+
+    /// Everything the settings menu shows
+    fn settings_reply(&self) -> Reply {
+        Reply::Settings {
+            gravity: self.sim.params.gravity,
+            forces: self
+                .sim
+                .forces
+                .iter()
+                .map(|force| ForceState {
+                    name: force.generator.name().to_string(),
+                    enabled: force.enabled,
+                })
+                .collect(),
+            walls: Wall::ALL
+                .into_iter()
+                .map(|wall| WallState {
+                    wall,
+                    name: wall.name().to_string(),
+                    material: self.sim.params.walls.get(wall),
+                })
+                .collect(),
+            volumes: Channel::ALL
+                .into_iter()
+                .map(|channel| VolumeState {
+                    channel,
+                    name: channel.name().to_string(),
+                    value: self.settings.audio.get(channel),
+                })
+                .collect(),
+            reduced_motion: self.reduced_motion(),
+            ui_scale: self.text_style.scale(),
+        }
+    }
+
+    /// Every ball, the gravity and the size of the scene
+    fn state_reply(&self) -> Reply {
+        Reply::State {
+            gravity: self.sim.params.gravity,
+            width: self.sim.params.width,
+            height: self.sim.params.height,
+            balls: self
+                .sim
+                .balls()
+                .map(|(id, ball)| BallState::new(id, &ball))
+                .collect(),
+        }
+    }
+
+    /// Color from a `#rrggbb` code, or a random one
+    fn color_or_random(&mut self, hex: Option<String>) -> Result<Color, String> {
+        match hex {
+            Some(hex) => parse_color(&hex),
+            None => {
+                let rng = &mut self.rng;
+                Ok(color!(?rng))
+            }
+        }
+    }
+
+    /// Whether the balls come from a host, so physics doesn't run here
+    #[cfg(feature = "lan")]
+    pub(super) fn is_guest(&self) -> bool {
+        self.lan.as_ref().is_some_and(Session::is_guest)
+    }
+
+    #[cfg(not(feature = "lan"))]
+    pub(super) fn is_guest(&self) -> bool {
+        false
+    }
+
+    /// Apply what other players did since the last frame, or what the host sent
+    #[cfg(feature = "lan")]
+    pub(super) fn receive_lan(&mut self) {
+        let Some(session) = &mut self.lan else {
+            return;
+        };
+        let notices = match session.receive(&mut self.sim) {
+            Ok(notices) => notices,
+            Err(err) => {
+                // Carry on alone with the balls as they were last sent
+                self.lan = None;
+                self.toasts.push(format!("Disconnected: {}", err));
+                return;
+            }
+        };
+        for notice in notices {
+            self.toasts.push(match notice {
+                Notice::Joined(peer) => format!("Player {} joined", peer),
+                Notice::Spectating(peer) => format!("Player {} is watching", peer),
+                Notice::Left(peer) => format!("Player {} left", peer),
+                Notice::Refused => "Another player is holding that ball".to_string(),
+                Notice::SizeDiffers { width, height } => format!(
+                    "The host's window is {}x{}, so some balls may be out of view",
+                    width, height
+                ),
+            });
+        }
+    }
+
+    /// Send the balls to other players if hosting, or this player's input if joined
+    #[cfg(feature = "lan")]
+    pub(super) fn send_lan(&mut self) {
+        if let Some(session) = &mut self.lan {
+            session.send(&mut self.sim, self.ticks);
+        }
+    }
+
+    /// Send the state to watchers after a frame that changed it, dropping any that left
+    pub(super) fn publish_state(&mut self, ticks: u32) {
+        if self.watchers.is_empty() || (ticks == 0 && !self.is_guest()) {
+            return;
+        }
+        let reply = self.state_reply();
+        self.watchers
+            .retain(|watcher| watcher.send(reply.clone()).is_ok());
+    }
+
+    /// Send collisions from the last step to subscribers, dropping any that left
+    pub(super) fn publish_events(&mut self) {
+        if self.subscribers.is_empty() {
+            return;
+        }
+        let replies: Vec<Reply> = self
+            .sim
+            .events()
+            .iter()
+            .filter_map(Reply::collision)
+            .collect();
+        self.subscribers.retain(|subscriber| {
+            replies
+                .iter()
+                .all(|reply| subscriber.send(reply.clone()).is_ok())
+        });
+    }
+}
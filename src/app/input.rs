@@ -0,0 +1,586 @@
+use ggez::event::MouseButton;
+use ggez::mint::{Point2, Vector2};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::keyboard;
+use super::menus::MenuAction;
+use super::{App, State};
+use crate::ball::BallId;
+use crate::capture::Screenshot;
+use crate::inspector::Inspector;
+use crate::keys::Action;
+use crate::menu::Menu;
+use crate::protractor::Protractor;
+use crate::ruler::Ruler;
+use crate::settings::Channel;
+use crate::spawn::SpawnDialog;
+use crate::svg;
+
+/// What clicking on a ball does
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum Tool {
+    Grab,
+    Inspect,
+    Measure,
+    Protractor,
+}
+
+impl Tool {
+    pub(super) const ALL: [Tool; 4] = [Tool::Grab, Tool::Inspect, Tool::Measure, Tool::Protractor];
+
+    pub(super) fn name(self) -> &'static str {
+        match self {
+            Tool::Grab => "Grab",
+            Tool::Inspect => "Inspect",
+            Tool::Measure => "Measure",
+            Tool::Protractor => "Protractor",
+        }
+    }
+
+    pub(super) fn description(self) -> &'static str {
+        match self {
+            Tool::Grab => "Drag and throw balls, holding Ctrl to snap to the grid",
+            Tool::Inspect => "Hover a ball to see its motion, click to edit it, arrows to adjust",
+            Tool::Measure => "Drag to measure a distance, click to clear it",
+            Tool::Protractor => {
+                "Drag one arm to see its incline, then click to place a second and see the angle"
+            }
+        }
+    }
+}
+
+/// Actions in the right-click menu of a ball
+#[derive(Clone)]
+pub(super) enum BallAction {
+    Delete,
+    Clone,
+    TogglePin,
+    RandomColor,
+    Inspect,
+    ToggleLogged,
+}
+
+impl App {
+    pub(super) fn open_context_menu(&mut self, id: BallId, x: f32, y: f32) {
+        let Some(ball) = self.sim.ball(id) else {
+            return;
+        };
+        let pin = if ball.pinned { "Unpin" } else { "Pin" };
+        let menu = Menu::popup(Point2 { x, y })
+            .item("Delete", BallAction::Delete)
+            .item("Clone", BallAction::Clone)
+            .item(pin, BallAction::TogglePin)
+            .item("Change color", BallAction::RandomColor)
+            .item("Inspect", BallAction::Inspect);
+        let menu = match &self.trajectory {
+            Some(log) if log.balls.contains(&id) => {
+                menu.item("Stop logging trajectory", BallAction::ToggleLogged)
+            }
+            Some(_) => menu.item("Log trajectory", BallAction::ToggleLogged),
+            None => menu,
+        };
+        self.context_menu = Some((id, menu));
+    }
+
+    fn select_ball_action(&mut self, ctx: &mut Context, id: BallId, action: BallAction) {
+        self.context_menu = None;
+        let Some(ball) = self.sim.ball(id) else {
+            return;
+        };
+        match action {
+            BallAction::Delete => {
+                self.remove_ball(id);
+                self.toasts
+                    .push(format!("Ball deleted ({} total)", self.sim.ball_count()));
+            }
+            BallAction::Clone => {
+                let mut ball = ball;
+                ball.point.x += ball.radius;
+                ball.point.y -= ball.radius;
+                if self.add_ball(ball) {
+                    self.toasts
+                        .push(format!("Ball cloned ({} total)", self.sim.ball_count()));
+                }
+            }
+            BallAction::TogglePin => {
+                let point = self.snap_point(ball.point);
+                if let Some(mut ball) = self.sim.ball_mut(id) {
+                    ball.pinned = !ball.pinned;
+                    ball.velocity = Vector2 { x: 0.0, y: 0.0 };
+                    if ball.pinned {
+                        ball.point = point;
+                    }
+                }
+            }
+            BallAction::RandomColor => {
+                let rng = &mut self.rng;
+                if let Some(mut ball) = self.sim.ball_mut(id) {
+                    ball.color = color!(?rng);
+                }
+            }
+            BallAction::Inspect => {
+                let (width, _) = ctx.gfx.drawable_size();
+                self.inspector = Some(Inspector::new(id, width));
+                self.spawn_dialog = None;
+            }
+            BallAction::ToggleLogged => {
+                if let Some(log) = &mut self.trajectory {
+                    if !log.balls.remove(&id) {
+                        log.balls.insert(id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Nearest grid point while Ctrl is held, or the point itself otherwise
+    pub(super) fn snap_point(&self, point: Point2<f32>) -> Point2<f32> {
+        if !self.snap {
+            return point;
+        }
+        let size = self.config.grid_size;
+        Point2 {
+            x: (point.x / size).round() * size,
+            y: (point.y / size).round() * size,
+        }
+    }
+
+    fn toggle_tool(&mut self) {
+        self.tool = match self.tool {
+            Tool::Grab => Tool::Inspect,
+            Tool::Inspect => {
+                self.inspector = None;
+                Tool::Measure
+            }
+            Tool::Measure => {
+                self.ruler = None;
+                Tool::Protractor
+            }
+            Tool::Protractor => {
+                self.protractor = None;
+                Tool::Grab
+            }
+        };
+        self.toasts.push(format!("Tool: {}", self.tool.name()));
+    }
+
+    pub(super) fn mouse_motion(
+        &mut self,
+        _ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        self.cursor = Point2 { x, y };
+        match self.state {
+            State::Menu | State::Paused => self.menu.hover(x, y, self.text_style),
+            State::Running => {
+                if let Some((_, menu)) = &mut self.context_menu {
+                    menu.hover(x, y, self.text_style);
+                }
+                let snapped = self.snap_point(Point2 { x, y });
+                if let Some(ruler) = self.ruler.as_mut().filter(|ruler| ruler.dragging) {
+                    ruler.end = snapped;
+                    return Ok(());
+                }
+                if let Some(protractor) = &mut self.protractor {
+                    protractor.track(snapped);
+                }
+                if self.with_mode(|mode, game| mode.mouse_motion(game, Point2 { x, y })) {
+                    return Ok(());
+                }
+                self.move_active_ball(x, y, dx, dy);
+            }
+        }
+        Ok(())
+    }
+
+    pub(super) fn mouse_down(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        if self.state != State::Running {
+            if let Some(action) = self.menu.click(x, y, self.text_style) {
+                self.select_menu_action(ctx, action);
+            }
+            return Ok(());
+        }
+        if let Some((i, mut menu)) = self.context_menu.take() {
+            // Clicking anywhere else just closes the menu
+            if let Some(action) = menu.click(x, y, self.text_style) {
+                self.select_ball_action(ctx, i, action);
+            }
+            return Ok(());
+        }
+        if button == MouseButton::Right {
+            if let Some(i) = self.sim.ball_at(Point2 { x, y }) {
+                self.open_context_menu(i, x, y);
+            }
+            return Ok(());
+        }
+        if let Some(inspector) = &mut self.inspector {
+            if inspector.contains(x, y, self.text_style) {
+                if let Some(mut ball) = self.sim.ball_mut(inspector.ball) {
+                    inspector.click(&mut ball, x, y, self.text_style);
+                }
+                return Ok(());
+            }
+        }
+        if let Some(dialog) = &mut self.spawn_dialog {
+            if dialog.contains(x, y, self.text_style) {
+                if let Some(balls) = dialog.click(x, y, self.text_style) {
+                    self.spawn_balls(balls);
+                }
+                return Ok(());
+            }
+        }
+        if self.sim.held.is_some() {
+            return Ok(());
+        }
+        let snapped = self.snap_point(Point2 { x, y });
+        if self.tool == Tool::Measure {
+            self.ruler = Some(Ruler::new(snapped));
+            return Ok(());
+        }
+        if self.tool == Tool::Protractor {
+            match &mut self.protractor {
+                Some(protractor) if protractor.is_placing() => {
+                    protractor.place(snapped);
+                }
+                _ => self.protractor = Some(Protractor::new(snapped)),
+            }
+            return Ok(());
+        }
+        if self.with_mode(|mode, game| mode.mouse_down(game, button, Point2 { x, y })) {
+            return Ok(());
+        }
+
+        match self.tool {
+            Tool::Grab => {
+                let hit = self.sim.ball_at(Point2 { x, y });
+                if let Some((id, ball)) = hit.and_then(|id| Some((id, self.sim.ball(id)?))) {
+                    self.grab_offset = Point2 {
+                        x: x - ball.point.x,
+                        y: y - ball.point.y,
+                    };
+                    self.sim.held = Some(id);
+                    self.move_active_ball(x, y, 0.0, 0.0);
+                }
+            }
+            Tool::Inspect => {
+                let (width, _) = ctx.gfx.drawable_size();
+                self.inspector = self
+                    .sim
+                    .ball_at(Point2 { x, y })
+                    .map(|i| Inspector::new(i, width));
+                if self.inspector.is_some() {
+                    self.spawn_dialog = None;
+                }
+            }
+            Tool::Measure | Tool::Protractor => (),
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn mouse_up(
+        &mut self,
+        _ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.sim.release();
+        let snapped = self.snap_point(Point2 { x, y });
+        if let Some(ruler) = &mut self.ruler {
+            ruler.dragging = false;
+            // A click without a drag clears the measurement
+            if ruler.length() < 1.0 {
+                self.ruler = None;
+            }
+        }
+        if let Some(protractor) = self
+            .protractor
+            .as_mut()
+            .filter(|protractor| !protractor.is_placing())
+        {
+            // Letting go of the first arm; a click without a drag clears it
+            if !protractor.place(snapped) {
+                self.protractor = None;
+            }
+        }
+        if self.state == State::Running {
+            self.with_mode(|mode, game| mode.mouse_up(game, button, Point2 { x, y }));
+        }
+        Ok(())
+    }
+
+    pub(super) fn key_down(
+        &mut self,
+        ctx: &mut Context,
+        keycode: VirtualKeyCode,
+        ctrl: bool,
+    ) -> GameResult {
+        let (width, height) = ctx.gfx.drawable_size();
+        if matches!(keycode, VirtualKeyCode::LControl | VirtualKeyCode::RControl) {
+            self.snap = true;
+        }
+        if matches!(keycode, VirtualKeyCode::LShift | VirtualKeyCode::RShift) {
+            self.shift = true;
+        }
+
+        match self.state {
+            State::Menu | State::Paused => match keycode {
+                VirtualKeyCode::Up => self.menu.up(),
+                VirtualKeyCode::Down => self.menu.down(),
+                VirtualKeyCode::Return => {
+                    if let Some(action) = self.menu.selected() {
+                        self.select_menu_action(ctx, action);
+                    }
+                }
+                VirtualKeyCode::Left | VirtualKeyCode::Right => {
+                    if let Some(MenuAction::Volume(channel)) = self.menu.selected() {
+                        let direction = if keycode == VirtualKeyCode::Right {
+                            1.0
+                        } else {
+                            -1.0
+                        };
+                        self.adjust_volume(channel, direction);
+                        self.refresh_settings_menu();
+                    }
+                }
+                VirtualKeyCode::Escape | VirtualKeyCode::P if self.source.is_some() => {
+                    self.state = State::Running;
+                }
+                // Text can be resized from the menus too, so they can be read to start with
+                _ => match self.keys.action(keycode, ctrl) {
+                    Some(Action::UiScaleUp) => self.set_text_style(self.text_style.larger()),
+                    Some(Action::UiScaleDown) => self.set_text_style(self.text_style.smaller()),
+                    _ => (),
+                },
+            },
+            State::Running
+                if self.context_menu.is_some()
+                    && (is_panel_key(keycode) || keycode == VirtualKeyCode::Escape) =>
+            {
+                let Some((i, menu)) = &mut self.context_menu else {
+                    return Ok(());
+                };
+                match keycode {
+                    VirtualKeyCode::Up => menu.up(),
+                    VirtualKeyCode::Down => menu.down(),
+                    VirtualKeyCode::Return => {
+                        let i = *i;
+                        if let Some(action) = menu.selected() {
+                            self.select_ball_action(ctx, i, action);
+                        }
+                    }
+                    VirtualKeyCode::Escape => self.context_menu = None,
+                    _ => (),
+                }
+            }
+            State::Running if self.spawn_dialog.is_some() && is_panel_key(keycode) => {
+                let Some(dialog) = &mut self.spawn_dialog else {
+                    return Ok(());
+                };
+                match keycode {
+                    VirtualKeyCode::Up => dialog.up(),
+                    VirtualKeyCode::Down => dialog.down(),
+                    VirtualKeyCode::Left => dialog.adjust(-1.0),
+                    VirtualKeyCode::Right => dialog.adjust(1.0),
+                    VirtualKeyCode::Return => {
+                        let balls = dialog.balls();
+                        self.spawn_balls(balls);
+                    }
+                    _ => (),
+                }
+            }
+            State::Running if self.inspector.is_some() && is_panel_key(keycode) => {
+                let Some(inspector) = &mut self.inspector else {
+                    return Ok(());
+                };
+                let Some(mut ball) = self.sim.ball_mut(inspector.ball) else {
+                    return Ok(());
+                };
+                match keycode {
+                    VirtualKeyCode::Up => inspector.up(),
+                    VirtualKeyCode::Down => inspector.down(),
+                    VirtualKeyCode::Left => inspector.adjust(&mut ball, -1.0),
+                    VirtualKeyCode::Right => inspector.adjust(&mut ball, 1.0),
+                    _ => (),
+                }
+            }
+            State::Running => {
+                if !ctrl && self.with_mode(|mode, game| mode.key_down(game, keycode)) {
+                    return Ok(());
+                }
+                if self.arrows.set(keycode, true) {
+                    // Arrows aim a charging throw, and otherwise nudge the selected ball
+                    if self.selected.is_none() {
+                        self.cycle_selection(true);
+                    } else if self.charge.is_none() {
+                        self.nudge_selected(keycode);
+                    }
+                    return Ok(());
+                }
+                // Number keys are slots unless bound to something else
+                let slot =
+                    keyboard::slot(keycode).filter(|_| self.keys.action(keycode, ctrl).is_none());
+                if let Some(slot) = slot {
+                    self.use_slot(slot, ctrl);
+                    return Ok(());
+                }
+                match self.keys.action(keycode, ctrl) {
+                    Some(Action::Pause) => self.open_pause_menu(),
+                    Some(Action::SelectMode) => {
+                        self.open_pause_menu();
+                        self.open_modes_menu();
+                    }
+                    Some(Action::ToggleTool) => self.toggle_tool(),
+                    Some(Action::Help) => self.show_help = !self.show_help,
+                    Some(Action::ToggleGraph) => self.show_graph = !self.show_graph,
+                    Some(Action::ToggleStats) => self.show_stats = !self.show_stats,
+                    Some(Action::ToggleAudioReactive) => self.toggle_beats(),
+                    Some(Action::ToggleHeat) => self.toggle_heat(),
+                    Some(Action::InstantReplay) => self.toggle_highlight(),
+                    Some(Action::ToggleProfiler) => self.show_profiler = !self.show_profiler,
+                    Some(Action::StressTest) => self.toggle_stress_test(),
+                    Some(Action::CyclePrediction) => {
+                        self.prediction = self.prediction.next();
+                        self.toasts
+                            .push(format!("Path prediction: {}", self.prediction.name()));
+                    }
+                    Some(Action::ToggleMusic) => {
+                        if let Some(music) = &mut self.music {
+                            let playing = music.toggle();
+                            self.toasts.push(if playing {
+                                "Music playing"
+                            } else {
+                                "Music paused"
+                            });
+                        }
+                    }
+                    Some(action @ (Action::MusicVolumeDown | Action::MusicVolumeUp)) => {
+                        let direction = if action == Action::MusicVolumeUp {
+                            1.0
+                        } else {
+                            -1.0
+                        };
+                        self.adjust_volume(Channel::Music, direction);
+                        self.toasts.push(format!(
+                            "Music volume: {:.0}%",
+                            self.settings.audio.music * 100.0
+                        ));
+                    }
+                    Some(Action::SpawnDialog) => {
+                        self.spawn_dialog = match self.spawn_dialog {
+                            Some(_) => None,
+                            None => Some(SpawnDialog::new(width, height)),
+                        };
+                        self.inspector = None;
+                    }
+                    Some(Action::Reset) => {
+                        self.reset(ctx);
+                    }
+                    Some(Action::SpawnBall) => {
+                        let scene = self.config.random_scene(&mut self.rng, 1, width, height);
+                        if self.sim.add_balls(scene.balls) == 0 {
+                            self.cap_reached();
+                        } else {
+                            self.toasts
+                                .push(format!("Ball added ({} total)", self.sim.ball_count()));
+                        }
+                    }
+                    Some(Action::DeleteHeld) => {
+                        if let Some(i) = self.target_ball() {
+                            self.remove_ball(i);
+                            self.toasts
+                                .push(format!("Ball deleted ({} total)", self.sim.ball_count()));
+                        }
+                    }
+                    Some(Action::CycleKind) => {
+                        if let Some(mut ball) =
+                            self.target_ball().and_then(|i| self.sim.ball_mut(i))
+                        {
+                            let kind = ball.kind.next();
+                            ball.set_kind(kind);
+                            self.toasts.push(format!("Ball kind: {}", kind.name()));
+                        }
+                    }
+                    Some(Action::SaveScene) => self.save_scene(),
+                    Some(Action::ToggleClip) => self.toggle_clip(),
+                    Some(Action::ToggleArt) => self.toggle_art(),
+                    Some(Action::ToggleLog) => self.toggle_log(None),
+                    Some(Action::ExportSvg) => {
+                        match svg::export(&self.sim.scene(), width, height) {
+                            Ok(path) => self
+                                .toasts
+                                .push(format!("Scene exported to {}", path.display())),
+                            Err(err) => self.toasts.push(format!("Failed to export SVG: {}", err)),
+                        }
+                    }
+                    Some(Action::Screenshot) => self.screenshot = Some(Screenshot::Full),
+                    Some(Action::CleanScreenshot) => self.screenshot = Some(Screenshot::Clean),
+                    Some(Action::LoadScene) => {
+                        self.open_pause_menu();
+                        self.open_load_menu();
+                    }
+                    Some(Action::SelectBall) => self.cycle_selection(!self.shift),
+                    Some(Action::TogglePinSelected) => {
+                        if let Some(id) = self.selected {
+                            self.select_ball_action(ctx, id, BallAction::TogglePin);
+                        }
+                    }
+                    Some(Action::ThrowSelected) => self.charge_throw(),
+                    Some(Action::BallMenu) => self.open_selected_menu(),
+                    Some(Action::UiScaleUp) => self.set_text_style(self.text_style.larger()),
+                    Some(Action::UiScaleDown) => self.set_text_style(self.text_style.smaller()),
+                    Some(Action::ToggleFullscreen) => self.toggle_fullscreen(ctx),
+                    Some(Action::Earthquake) => self.sim.start_quake(self.rng.gen()),
+                    Some(Action::Rewind) => self.start_rewind(),
+                    None => (),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub(super) fn key_up(&mut self, _ctx: &mut Context, keycode: VirtualKeyCode) -> GameResult {
+        if matches!(keycode, VirtualKeyCode::LControl | VirtualKeyCode::RControl) {
+            self.snap = false;
+        }
+        if matches!(keycode, VirtualKeyCode::LShift | VirtualKeyCode::RShift) {
+            self.shift = false;
+        }
+        self.arrows.set(keycode, false);
+        match self.keys.action(keycode, false) {
+            Some(Action::ThrowSelected) => self.throw_selected(),
+            Some(Action::Earthquake) => self.sim.stop_quake(),
+            Some(Action::Rewind) => self.rewinding = false,
+            _ => (),
+        }
+        if self.state == State::Running {
+            self.with_mode(|mode, game| mode.key_up(game, keycode));
+        }
+        Ok(())
+    }
+}
+
+/// Keys captured by an open inspector or dialog
+fn is_panel_key(keycode: VirtualKeyCode) -> bool {
+    matches!(
+        keycode,
+        VirtualKeyCode::Up
+            | VirtualKeyCode::Down
+            | VirtualKeyCode::Left
+            | VirtualKeyCode::Right
+            | VirtualKeyCode::Return
+    )
+}
@@ -0,0 +1,275 @@
+use ggez::graphics::{self, DrawMode, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult};
+
+use super::App;
+use crate::ball::BallId;
+use crate::simulation::Snapshot;
+
+/// Pixels the selected ball is moved by each press of an arrow key
+const NUDGE: f32 = 4.0;
+/// Pixels moved by each press with Shift held, for precise placement
+const FINE_NUDGE: f32 = 1.0;
+/// Seconds the throw key is held for a throw at full speed
+const FULL_CHARGE: f32 = 1.0;
+/// Pixels per tick a fully charged throw leaves at
+const THROW_SPEED: f32 = 25.0;
+/// Gap between the selected ball and the ring drawn around it
+const RING_GAP: f32 = 4.0;
+/// Length of the aim line at full charge, beyond the ball's edge
+const AIM_LENGTH: f32 = 60.0;
+
+/// Snapshots stored with Ctrl and a number key, to go back to with the number alone
+pub(super) type Slots = [Option<Snapshot>; 9];
+
+/// Index of the slot a number key stores to and restores from, on the top row
+/// or the number pad
+pub(super) fn slot(keycode: VirtualKeyCode) -> Option<usize> {
+    let slot = match keycode {
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => 0,
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => 1,
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => 2,
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => 3,
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => 4,
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => 5,
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => 6,
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => 7,
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => 8,
+        _ => return None,
+    };
+    Some(slot)
+}
+
+/// Arrow keys held down, aiming a throw of the selected ball
+#[derive(Default)]
+pub(super) struct Arrows {
+    up: bool,
+    down: bool,
+    left: bool,
+    right: bool,
+}
+
+impl Arrows {
+    /// Record an arrow key going down or up, returning whether it was one
+    pub(super) fn set(&mut self, keycode: VirtualKeyCode, down: bool) -> bool {
+        let held = match keycode {
+            VirtualKeyCode::Up => &mut self.up,
+            VirtualKeyCode::Down => &mut self.down,
+            VirtualKeyCode::Left => &mut self.left,
+            VirtualKeyCode::Right => &mut self.right,
+            _ => return false,
+        };
+        *held = down;
+        true
+    }
+
+    /// Unit direction of the keys held, if they don't cancel out
+    pub(super) fn direction(&self) -> Option<Vector2<f32>> {
+        let axis = |minus: bool, plus: bool| plus as i8 as f32 - minus as i8 as f32;
+        let x = axis(self.left, self.right);
+        let y = axis(self.up, self.down);
+        let length = x.hypot(y);
+        (length > 0.0).then(|| Vector2 {
+            x: x / length,
+            y: y / length,
+        })
+    }
+}
+
+impl App {
+    /// Select the ball after the selected one from left to right, or before it
+    /// if not `forward`, passing through none at the ends so the selection can
+    /// be cleared
+    pub(super) fn cycle_selection(&mut self, forward: bool) {
+        let mut balls: Vec<_> = self
+            .sim
+            .balls()
+            .map(|(id, ball)| (id, ball.point.x, ball.point.y))
+            .collect();
+        if balls.is_empty() {
+            self.toasts.push("No balls to select");
+            return;
+        }
+        balls.sort_by(|a, b| a.1.total_cmp(&b.1).then(a.2.total_cmp(&b.2)));
+        let last = balls.len() - 1;
+        let current = self
+            .selected
+            .and_then(|id| balls.iter().position(|&(other, ..)| other == id));
+        let next = match (current, forward) {
+            (None, true) => Some(0),
+            (None, false) => Some(last),
+            (Some(i), true) => (i < last).then_some(i + 1),
+            (Some(i), false) => i.checked_sub(1),
+        };
+        self.charge = None;
+        self.stop_aiming();
+        self.selected = next.map(|i| balls[i].0);
+    }
+
+    /// Move the selected ball a step in the direction of an arrow key, stopping it
+    pub(super) fn nudge_selected(&mut self, keycode: VirtualKeyCode) {
+        let Some(id) = self.selected else {
+            return;
+        };
+        // The mouse has the ball while dragging it
+        if self.sim.held == Some(id) {
+            return;
+        }
+        let step = if self.shift { FINE_NUDGE } else { NUDGE };
+        let (dx, dy) = match keycode {
+            VirtualKeyCode::Up => (0.0, -step),
+            VirtualKeyCode::Down => (0.0, step),
+            VirtualKeyCode::Left => (-step, 0.0),
+            VirtualKeyCode::Right => (step, 0.0),
+            _ => return,
+        };
+        if let Some(mut ball) = self.sim.ball_mut(id) {
+            ball.point.x += dx;
+            ball.point.y += dy;
+            ball.velocity = Vector2 { x: 0.0, y: 0.0 };
+        }
+        self.sim.wake();
+    }
+
+    /// Store the simulation in a slot, or go back to the one stored there,
+    /// keeping the forces and settings as they are now
+    pub(super) fn use_slot(&mut self, slot: usize, store: bool) {
+        if store {
+            self.slots[slot] = Some(self.sim.snapshot());
+            self.toasts.push(format!("Stored slot {}", slot + 1));
+            return;
+        }
+        if self.slots[slot].is_none() {
+            self.toasts.push(format!(
+                "Slot {} is empty, store it with Ctrl+{0}",
+                slot + 1
+            ));
+            return;
+        }
+        self.charge = None;
+        self.stop_aiming();
+        if let Some(snapshot) = &self.slots[slot] {
+            self.sim.restore(snapshot);
+        }
+        self.toasts.push(format!("Restored slot {}", slot + 1));
+    }
+
+    /// Ball keyboard actions apply to: the one held with the mouse, or else the selected one
+    pub(super) fn target_ball(&self) -> Option<BallId> {
+        self.sim.held.or(self.selected)
+    }
+
+    /// Open the context menu of the selected ball, at its centre
+    pub(super) fn open_selected_menu(&mut self) {
+        let Some((id, ball)) = self.selected.and_then(|id| Some((id, self.sim.ball(id)?))) else {
+            return;
+        };
+        self.open_context_menu(id, ball.point.x, ball.point.y);
+    }
+
+    /// Start charging a throw of the selected ball, holding it in place
+    pub(super) fn charge_throw(&mut self) {
+        if self.selected.is_some() && self.charge.is_none() {
+            self.charge = Some(0.0);
+        }
+    }
+
+    /// Throw the selected ball in the direction it was last aimed, faster the
+    /// longer the throw was charged
+    pub(super) fn throw_selected(&mut self) {
+        let Some(velocity) = self.throw_velocity() else {
+            return;
+        };
+        self.charge = None;
+        let Some(id) = self
+            .selected
+            .filter(|&id| self.aiming && self.sim.held == Some(id))
+        else {
+            return;
+        };
+        if let Some(mut ball) = self.sim.ball_mut(id) {
+            ball.velocity = velocity;
+        }
+        self.aiming = false;
+        self.sim.release();
+    }
+
+    /// Velocity the selected ball would be thrown at if the throw key were let go now
+    pub(super) fn throw_velocity(&self) -> Option<Vector2<f32>> {
+        let speed = THROW_SPEED * self.charge? / FULL_CHARGE;
+        Some(Vector2 {
+            x: self.aim.x * speed,
+            y: self.aim.y * speed,
+        })
+    }
+
+    /// Hold the selected ball still while a throw charges, aiming it with the arrow keys
+    pub(super) fn aim_selected(&mut self, dt: f32) {
+        let Some(id) = self.selected.filter(|&id| self.sim.ball(id).is_some()) else {
+            self.selected = None;
+            self.charge = None;
+            self.aiming = false;
+            return;
+        };
+        let Some(charge) = &mut self.charge else {
+            self.stop_aiming();
+            return;
+        };
+        *charge = (*charge + dt).min(FULL_CHARGE);
+        // A ball held with the mouse can't be taken by the keyboard
+        if self.sim.held.is_some_and(|held| held != id) {
+            return;
+        }
+        if let Some(direction) = self.arrows.direction() {
+            self.aim = direction;
+        }
+        self.sim.held = Some(id);
+        self.aiming = true;
+        if let Some(mut ball) = self.sim.ball_mut(id) {
+            ball.velocity = Vector2 { x: 0.0, y: 0.0 };
+        }
+    }
+
+    /// Let go of the ball held still for a throw, without throwing it
+    fn stop_aiming(&mut self) {
+        if !self.aiming {
+            return;
+        }
+        self.aiming = false;
+        if self.sim.held.is_some() && self.sim.held == self.selected {
+            self.sim.release();
+        }
+    }
+
+    /// Ring around the selected ball, with a line showing the aim of a charging throw
+    pub(super) fn draw_selection(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut graphics::Canvas,
+    ) -> GameResult {
+        let Some(ball) = self.selected.and_then(|id| self.sim.ball(id)) else {
+            return Ok(());
+        };
+        let ring = Mesh::new_circle(
+            ctx,
+            DrawMode::stroke(2.0),
+            ball.point,
+            ball.radius + RING_GAP,
+            0.1,
+            color!(WHITE),
+        )?;
+        canvas.draw(&ring, DrawParam::default());
+        if let Some(charge) = self.charge.filter(|&charge| charge > 0.0) {
+            let along = |distance: f32| Point2 {
+                x: ball.point.x + self.aim.x * distance,
+                y: ball.point.y + self.aim.y * distance,
+            };
+            let edge = ball.radius + RING_GAP;
+            let points = [along(edge), along(edge + AIM_LENGTH * charge / FULL_CHARGE)];
+            let line = Mesh::new_line(ctx, &points, 3.0, color!(0xffd166))?;
+            canvas.draw(&line, DrawParam::default());
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,294 @@
+use std::path::PathBuf;
+
+use ggez::mint::Point2;
+use ggez::Context;
+
+use super::{App, State};
+use crate::events::Wall;
+use crate::menu::Menu;
+use crate::mode::challenge::{self, Challenge};
+use crate::mode::ModeKind;
+use crate::scene::{self, Demo, SceneSource};
+use crate::settings::Channel;
+use crate::units::Planet;
+
+#[derive(Clone)]
+pub(super) enum MenuAction {
+    Continue,
+    Demo(Demo),
+    Mode(ModeKind),
+    ModesList,
+    Challenge(PathBuf),
+    ChallengesList,
+    Open(PathBuf),
+    RestoreCrash,
+    Quit,
+    Resume,
+    Reset,
+    Save,
+    LoadList,
+    Settings,
+    CycleGravity,
+    CyclePlanet,
+    /// Switch a force on or off, by index
+    ToggleForce(usize),
+    WallsList,
+    /// Change the material of a side of the box to the next preset
+    CycleWall(Wall),
+    Volume(Channel),
+    ReducedMotion,
+    MainMenu,
+    ConfirmQuit,
+    /// Return to the top of the pause menu
+    Back,
+}
+
+/// Gravity values cycled through from the settings menu
+const GRAVITY_PRESETS: [f32; 4] = [0.5, 1.0, 0.25, 0.0];
+
+impl App {
+    /// Show start screen, keeping the current scene to continue later
+    pub(super) fn open_menu(&mut self) {
+        let mut menu = Menu::new("Balls", Point2 { x: 60.0, y: 60.0 });
+        if let Some(source) = &self.source {
+            menu = menu.item(
+                format!("Continue ({})", source.name()),
+                MenuAction::Continue,
+            );
+        } else {
+            if self.crash_path().exists() {
+                menu = menu.item("Restore scene from crash", MenuAction::RestoreCrash);
+            }
+            if self.autosave_path().exists() {
+                menu = menu.item(
+                    "Restore last session",
+                    MenuAction::Open(self.autosave_path()),
+                );
+            }
+        }
+        for demo in Demo::ALL {
+            menu = menu.item(demo.name(), MenuAction::Demo(demo));
+        }
+        menu = menu.item("Game modes", MenuAction::ModesList);
+        for path in scene::recent(5) {
+            menu = menu.item(
+                format!("Open {}", scene::file_name(&path)),
+                MenuAction::Open(path),
+            );
+        }
+        menu = menu.item("Quit", MenuAction::Quit);
+
+        self.menu = menu;
+        self.sim.held = None;
+        self.state = State::Menu;
+    }
+
+    /// Pause the running scene and show the pause menu over it
+    pub(super) fn open_pause_menu(&mut self) {
+        self.menu = Menu::new("Paused", Point2 { x: 60.0, y: 60.0 })
+            .item("Resume", MenuAction::Resume)
+            .item("Reset", MenuAction::Reset)
+            .item("Save scene", MenuAction::Save)
+            .item("Load scene", MenuAction::LoadList)
+            .item("Game modes", MenuAction::ModesList)
+            .item("Settings", MenuAction::Settings)
+            .item("Main menu", MenuAction::MainMenu)
+            .item("Quit", MenuAction::ConfirmQuit);
+        self.sim.held = None;
+        self.state = State::Paused;
+    }
+
+    pub(super) fn open_load_menu(&mut self) {
+        let mut menu = Menu::new("Load scene", Point2 { x: 60.0, y: 60.0 });
+        for path in scene::recent(10) {
+            menu = menu.item(scene::file_name(&path), MenuAction::Open(path));
+        }
+        self.menu = menu.item("Back", MenuAction::Back);
+    }
+
+    /// List games to play, with the sandbox going back to the scenes on the start screen
+    pub(super) fn open_modes_menu(&mut self) {
+        let mut menu = Menu::new("Game modes", Point2 { x: 60.0, y: 60.0 })
+            .item("Sandbox", MenuAction::MainMenu);
+        for kind in ModeKind::ALL {
+            menu = menu.item(kind.name(), MenuAction::Mode(kind));
+        }
+        self.menu = menu
+            .item("Challenges", MenuAction::ChallengesList)
+            .item("Back", MenuAction::Back);
+    }
+
+    /// List challenge files, by the name inside each one
+    fn open_challenges_menu(&mut self) {
+        let paths = challenge::list();
+        if paths.is_empty() {
+            self.toasts.push(format!(
+                "No challenges found in {}/",
+                challenge::CHALLENGES_DIR
+            ));
+            return;
+        }
+        let mut menu = Menu::new("Challenges", Point2 { x: 60.0, y: 60.0 });
+        for path in paths {
+            let name = Challenge::load(&path)
+                .map_or_else(|_| scene::file_name(&path), |challenge| challenge.name);
+            menu = menu.item(name, MenuAction::Challenge(path));
+        }
+        self.menu = menu.item("Back", MenuAction::Back);
+    }
+
+    fn open_settings_menu(&mut self) {
+        let units = self.config.units;
+        let gravity = self.sim.params.gravity;
+        let planet = Planet::matching(units, gravity).map_or("Custom", Planet::name);
+        let mut menu = Menu::new("Settings", Point2 { x: 60.0, y: 60.0 })
+            .item(
+                format!(
+                    "Gravity: {} ({:.2} m/s²)",
+                    gravity,
+                    units.gravity_in_meters(gravity)
+                ),
+                MenuAction::CycleGravity,
+            )
+            .item(format!("Planet: {}", planet), MenuAction::CyclePlanet);
+        for (i, force) in self.sim.forces.iter().enumerate() {
+            menu = menu.item(
+                format!(
+                    "{}: {}",
+                    force.generator.name(),
+                    if force.enabled { "ON" } else { "OFF" }
+                ),
+                MenuAction::ToggleForce(i),
+            );
+        }
+        menu = menu.item("Walls", MenuAction::WallsList);
+        for channel in Channel::ALL {
+            menu = menu.item(
+                format!(
+                    "{}: {:.0}%",
+                    channel.name(),
+                    self.settings.audio.get(channel) * 100.0
+                ),
+                MenuAction::Volume(channel),
+            );
+        }
+        self.menu = menu
+            .item(
+                format!(
+                    "Reduced motion: {}",
+                    if self.reduced_motion() { "ON" } else { "OFF" }
+                ),
+                MenuAction::ReducedMotion,
+            )
+            .item("Back", MenuAction::Back);
+    }
+
+    /// Rebuild settings menu to show changed values, keeping the selection
+    pub(super) fn refresh_settings_menu(&mut self) {
+        let selected = self.menu.selected_index();
+        match self.menu.title() {
+            "Walls" => self.open_walls_menu(),
+            _ => self.open_settings_menu(),
+        }
+        self.menu.select(selected);
+    }
+
+    fn open_walls_menu(&mut self) {
+        let mut menu = Menu::new("Walls", Point2 { x: 60.0, y: 60.0 });
+        for wall in Wall::ALL {
+            let material = self.sim.params.walls.get(wall);
+            menu = menu.item(
+                format!("{}: {}", wall.name(), material.name().unwrap_or("Custom")),
+                MenuAction::CycleWall(wall),
+            );
+        }
+        self.menu = menu.item("Back", MenuAction::Settings);
+    }
+
+    fn open_quit_menu(&mut self) {
+        self.menu = Menu::new("Really quit?", Point2 { x: 60.0, y: 60.0 })
+            .item("No", MenuAction::Back)
+            .item("Yes", MenuAction::Quit);
+    }
+
+    pub(super) fn select_menu_action(&mut self, ctx: &mut Context, action: MenuAction) {
+        match action {
+            MenuAction::Continue | MenuAction::Resume => self.state = State::Running,
+            MenuAction::Demo(demo) => self.load_source(ctx, SceneSource::Demo(demo)),
+            MenuAction::Mode(kind) => self.load_source(ctx, SceneSource::Mode(kind)),
+            MenuAction::Challenge(path) => self.load_source(ctx, SceneSource::Challenge(path)),
+            MenuAction::Open(path) => self.load_source(ctx, SceneSource::File(path)),
+            MenuAction::RestoreCrash => self.load_source(ctx, SceneSource::Crash),
+            MenuAction::Quit => ctx.request_quit(),
+            MenuAction::Reset => self.reset(ctx),
+            MenuAction::Save => {
+                self.save_scene();
+                self.state = State::Running;
+            }
+            MenuAction::LoadList => self.open_load_menu(),
+            MenuAction::ModesList => self.open_modes_menu(),
+            MenuAction::ChallengesList => self.open_challenges_menu(),
+            MenuAction::Settings => self.open_settings_menu(),
+            MenuAction::CycleGravity => {
+                let next = GRAVITY_PRESETS
+                    .iter()
+                    .position(|&gravity| gravity == self.sim.params.gravity)
+                    .map_or(0, |i| (i + 1) % GRAVITY_PRESETS.len());
+                self.sim.params.gravity = GRAVITY_PRESETS[next];
+                self.toasts.push(if self.sim.params.gravity == 0.0 {
+                    "Physics: zero-G ON".to_string()
+                } else {
+                    format!("Physics: gravity {}", self.sim.params.gravity)
+                });
+                self.refresh_settings_menu();
+            }
+            MenuAction::CyclePlanet => {
+                let units = self.config.units;
+                let planet = match Planet::matching(units, self.sim.params.gravity) {
+                    Some(planet) => {
+                        let i = Planet::ALL.iter().position(|&other| other == planet);
+                        Planet::ALL[i.map_or(0, |i| (i + 1) % Planet::ALL.len())]
+                    }
+                    None => Planet::Earth,
+                };
+                self.sim.params.gravity = units.gravity(planet.gravity());
+                self.toasts.push(format!(
+                    "Physics: {} gravity, {} m/s²",
+                    planet.name(),
+                    planet.gravity()
+                ));
+                self.refresh_settings_menu();
+            }
+            MenuAction::ToggleForce(i) => {
+                if let Some(force) = self.sim.forces.get_mut(i) {
+                    force.enabled = !force.enabled;
+                }
+                self.refresh_settings_menu();
+            }
+            MenuAction::WallsList => self.open_walls_menu(),
+            MenuAction::CycleWall(wall) => {
+                let material = self.sim.params.walls.get(wall).next();
+                self.sim.params.walls.set(wall, material);
+                self.refresh_settings_menu();
+            }
+            MenuAction::Volume(channel) => {
+                // Cycle round, from full back to silent
+                let direction = if self.settings.audio.get(channel) >= 1.0 {
+                    -10.0
+                } else {
+                    1.0
+                };
+                self.adjust_volume(channel, direction);
+                self.refresh_settings_menu();
+            }
+            MenuAction::ReducedMotion => {
+                self.toggle_reduced_motion();
+                self.refresh_settings_menu();
+            }
+            MenuAction::MainMenu => self.open_menu(),
+            MenuAction::ConfirmQuit => self.open_quit_menu(),
+            MenuAction::Back if self.state == State::Menu => self.open_menu(),
+            MenuAction::Back => self.open_pause_menu(),
+        }
+    }
+}
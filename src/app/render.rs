@@ -0,0 +1,515 @@
+use std::f32::consts::{FRAC_PI_2, PI};
+
+use ggez::graphics::{self, Color, DrawMode, DrawParam, Mesh, MeshBuilder, Quad, Rect};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::input::Tool;
+use super::{App, State};
+use crate::ball::{BallKind, Pattern};
+use crate::help;
+use crate::highlight;
+use crate::menu;
+use crate::prediction::{self, Prediction};
+use crate::profiler;
+use crate::quality::Quality;
+use crate::zone::Filter;
+
+/// Color hot balls are tinted towards
+const GLOW: Color = color!(0xffb347);
+/// Directions of the cracks drawn on damaged balls, in the order they appear
+const CRACKS: [f32; 3] = [0.7, 2.9, 4.8];
+/// Farthest the scene is drawn from its place during an earthquake at full strength
+const CAMERA_SHAKE: f32 = 8.0;
+/// Half the width of the band on a striped ball, relative to its radius
+const STRIPE: f32 = 0.5;
+/// Points along each curved edge of a stripe
+const STRIPE_POINTS: usize = 12;
+
+/// White pattern over a ball of radius 1, for each pattern but plain, to be
+/// turned, scaled and faded when drawn
+pub(super) struct PatternMeshes {
+    stripe: Mesh,
+    dots: Mesh,
+    rings: Mesh,
+}
+
+impl PatternMeshes {
+    /// Patterns with the tessellation of a quality
+    pub(super) fn new(ctx: &Context, quality: Quality) -> Self {
+        let build = |pattern| {
+            let mut mesh = MeshBuilder::new();
+            unit_pattern(&mut mesh, pattern, quality.circle_tolerance())
+                .expect("patterns always make valid meshes");
+            Mesh::from_data(ctx, mesh.build())
+        };
+        Self {
+            stripe: build(Pattern::Stripe),
+            dots: build(Pattern::Dots),
+            rings: build(Pattern::Rings),
+        }
+    }
+
+    fn get(&self, pattern: Pattern) -> Option<&Mesh> {
+        match pattern {
+            Pattern::Plain => None,
+            Pattern::Stripe => Some(&self.stripe),
+            Pattern::Dots => Some(&self.dots),
+            Pattern::Rings => Some(&self.rings),
+        }
+    }
+}
+
+/// Add a pattern over a ball of radius 1 at the origin
+fn unit_pattern(mesh: &mut MeshBuilder, pattern: Pattern, tolerance: f32) -> GameResult {
+    let origin = Point2 { x: 0.0, y: 0.0 };
+    let at = |distance: f32, angle: f32| Point2 {
+        x: angle.cos() * distance,
+        y: angle.sin() * distance,
+    };
+    match pattern {
+        Pattern::Plain => {}
+        Pattern::Stripe => {
+            // White caps above and below a band of the ball's own color
+            let edge = STRIPE.asin();
+            for start in [edge, PI + edge] {
+                let sweep = PI - 2.0 * edge;
+                let cap: Vec<_> = (0..=STRIPE_POINTS)
+                    .map(|i| at(1.0, start + sweep * i as f32 / STRIPE_POINTS as f32))
+                    .collect();
+                mesh.polygon(DrawMode::fill(), &cap, color!(WHITE))?;
+            }
+        }
+        Pattern::Dots => {
+            mesh.circle(DrawMode::fill(), origin, 0.16, tolerance, color!(WHITE))?;
+            for i in 0..4 {
+                let centre = at(0.58, i as f32 * FRAC_PI_2);
+                mesh.circle(DrawMode::fill(), centre, 0.16, tolerance, color!(WHITE))?;
+            }
+        }
+        Pattern::Rings => {
+            mesh.circle(
+                DrawMode::stroke(0.14),
+                origin,
+                0.72,
+                tolerance,
+                color!(WHITE),
+            )?;
+            mesh.circle(DrawMode::fill(), origin, 0.28, tolerance, color!(WHITE))?;
+        }
+    }
+    Ok(())
+}
+
+/// Straight line, drawn as a turned quad so no mesh is made each frame
+fn draw_line(
+    canvas: &mut graphics::Canvas,
+    from: Point2<f32>,
+    to: Point2<f32>,
+    width: f32,
+    color: Color,
+) {
+    let (dx, dy) = (to.x - from.x, to.y - from.y);
+    canvas.draw(
+        &Quad,
+        DrawParam::from(from)
+            .rotation(dy.atan2(dx))
+            .scale([dx.hypot(dy), width])
+            .offset([0.0, 0.5])
+            .color(color),
+    );
+}
+
+/// Filled white circle of radius 1, to be scaled and tinted when drawn, with
+/// the tessellation of a quality
+pub(super) fn unit_circle(ctx: &Context, quality: Quality) -> Mesh {
+    let origin = Point2 { x: 0.0, y: 0.0 };
+    Mesh::new_circle(
+        ctx,
+        DrawMode::fill(),
+        origin,
+        1.0,
+        quality.circle_tolerance(),
+        color!(WHITE),
+    )
+    .expect("a circle always makes a valid mesh")
+}
+
+impl App {
+    /// Draw everything, or just the balls if `clean`
+    pub(super) fn draw_frame(&self, ctx: &mut Context, clean: bool) -> GameResult {
+        let (width, height) = ctx.gfx.drawable_size();
+        let mut canvas = graphics::Canvas::from_frame(ctx, color!(BLACK));
+
+        match self.state {
+            State::Menu => {
+                self.menu.draw(&mut canvas, self.text_style);
+            }
+            State::Running if clean => {
+                self.draw_balls(&mut canvas);
+            }
+            State::Running if self.highlight.is_some() => {
+                self.draw_highlight(&mut canvas);
+            }
+            State::Running => {
+                // The scene shakes during an earthquake, but the interface stays put
+                let (dx, dy) = self.camera_shake();
+                canvas.set_screen_coordinates(Rect::new(dx, dy, width, height));
+                if let Some((_, _, grid)) = self.grid.as_ref().filter(|_| self.snap) {
+                    canvas.draw(grid, DrawParam::default());
+                }
+                self.draw_zones(ctx, &mut canvas)?;
+                self.draw_balls(&mut canvas);
+                self.draw_selection(ctx, &mut canvas)?;
+                self.particles.draw(&mut canvas, &self.circle);
+                self.draw_prediction(ctx, &mut canvas)?;
+                self.mode
+                    .draw(ctx, &mut canvas, &self.sim, self.text_style)?;
+                canvas.set_screen_coordinates(Rect::new(0.0, 0.0, width, height));
+                self.score.draw(
+                    ctx,
+                    &mut canvas,
+                    width,
+                    self.reduced_motion(),
+                    self.text_style,
+                );
+                self.draw_spawners(ctx, &mut canvas)?;
+                self.draw_inspector(ctx, &mut canvas)?;
+                if let Some(ruler) = &self.ruler {
+                    ruler.draw(ctx, &mut canvas, self.config.units, self.text_style)?;
+                }
+                if let Some(protractor) = &self.protractor {
+                    protractor.draw(ctx, &mut canvas, self.text_style)?;
+                }
+                if let Some(hover) = &self.hover {
+                    if let Some(ball) = self.sim.ball(hover.ball) {
+                        hover.draw(ctx, &mut canvas, &ball, self.text_style)?;
+                    }
+                }
+                if let Some(dialog) = &self.spawn_dialog {
+                    dialog.draw(&mut canvas, self.text_style);
+                }
+                if let Some((_, menu)) = &self.context_menu {
+                    menu.draw(&mut canvas, self.text_style);
+                }
+                self.draw_hud(&mut canvas, height);
+                if self.show_graph {
+                    let (x, y) = (10.0, height - self.text_style.px(36.0));
+                    self.graph.draw(ctx, &mut canvas, x, y, self.text_style)?;
+                }
+                if self.show_stats {
+                    self.stats.draw(&mut canvas, 10.0, 10.0, self.text_style);
+                }
+                if self.show_profiler {
+                    let x = width - self.text_style.px(profiler::WIDTH) - 10.0;
+                    self.profiler.draw(&mut canvas, x, 10.0, self.text_style);
+                }
+                if let Some(stress) = &self.stress {
+                    stress.draw(&mut canvas, self.sim.ball_count(), self.text_style);
+                }
+                if self.show_help {
+                    let tools = Tool::ALL
+                        .iter()
+                        .map(|tool| (tool.name(), tool.description()));
+                    help::draw(
+                        &mut canvas,
+                        &self.keys,
+                        tools,
+                        width,
+                        height,
+                        self.text_style,
+                    );
+                }
+            }
+            State::Paused => {
+                self.draw_zones(ctx, &mut canvas)?;
+                self.draw_balls(&mut canvas);
+                self.particles.draw(&mut canvas, &self.circle);
+                self.mode
+                    .draw(ctx, &mut canvas, &self.sim, self.text_style)?;
+                menu::draw_shade(&mut canvas, width, height, color!(0, 0, 0, 150));
+                self.menu.draw(&mut canvas, self.text_style);
+            }
+        }
+
+        if !clean {
+            self.toasts
+                .draw(ctx, &mut canvas, width, height, self.text_style);
+        }
+
+        canvas.finish(ctx)
+    }
+
+    fn draw_balls(&self, canvas: &mut graphics::Canvas) {
+        for obstacle in &self.sim.obstacles {
+            canvas.draw(
+                &Quad,
+                DrawParam::default()
+                    .dest_rect(obstacle.rect())
+                    .color(color!(WHITE)),
+            );
+        }
+        for (id, ball) in self.sim.balls() {
+            // Balloons pop when their time is up, instead of fading out
+            let fade = match self.sim.components.lifetime.get(id) {
+                Some(lifetime) if ball.kind != BallKind::Balloon => lifetime.fade(),
+                _ => 1.0,
+            };
+            // Colors from `color!` can have an alpha above 1, which would never fade
+            let mut color = ball.color;
+            if let Some(&heat) = self.sim.components.temperature.get(id) {
+                color.r += (GLOW.r - color.r) * heat;
+                color.g += (GLOW.g - color.g) * heat;
+                color.b += (GLOW.b - color.b) * heat;
+            }
+            color.a = color.a.min(1.0) * fade;
+            if ball.kind == BallKind::Ghost {
+                color.a *= 0.4;
+            }
+            let outline = match ball.kind {
+                BallKind::Heavy => Some((4.0, color!(60, 60, 60))),
+                BallKind::Bouncy => Some((2.0, color!(WHITE))),
+                BallKind::Sticky => Some((3.0, color!(0x9acd32))),
+                BallKind::Bomb => Some((3.0, color!(0xe63946))),
+                BallKind::Normal | BallKind::Ghost | BallKind::Balloon => None,
+            };
+            let mut radius = (ball.radius * fade).max(1.0);
+            // The outline is a disc under a smaller one, so no ring mesh is made each frame
+            if let Some((width, mut outline)) = outline {
+                outline.a = outline.a.min(1.0) * fade;
+                canvas.draw(
+                    &self.circle,
+                    DrawParam::from(ball.point)
+                        .scale([radius, radius])
+                        .color(outline),
+                );
+                radius = (radius - width).max(1.0);
+            }
+            canvas.draw(
+                &self.circle,
+                DrawParam::from(ball.point)
+                    .scale([radius, radius])
+                    .color(color),
+            );
+            if let Some(pattern) = self.patterns.get(ball.pattern) {
+                let angle = self
+                    .sim
+                    .components
+                    .spin
+                    .get(id)
+                    .map_or(0.0, |spin| spin.angle);
+                canvas.draw(
+                    pattern,
+                    DrawParam::from(ball.point)
+                        .rotation(angle)
+                        .scale([radius, radius])
+                        .color(Color::new(1.0, 1.0, 1.0, color.a * 0.9)),
+                );
+            }
+
+            if let Some(health) = self.sim.components.health.get(id) {
+                // One more crack for each quarter of the hit points lost
+                let count = ((health.damage() * 4.0) as usize).min(CRACKS.len());
+                for &angle in &CRACKS[..count] {
+                    let point = |distance: f32, bend: f32| Point2 {
+                        x: ball.point.x + (angle + bend).cos() * radius * distance,
+                        y: ball.point.y + (angle + bend).sin() * radius * distance,
+                    };
+                    let crack = color!(0, 0, 0, 180);
+                    draw_line(canvas, point(0.1, 0.0), point(0.5, 0.3), 1.5, crack);
+                    draw_line(canvas, point(0.5, 0.3), point(0.95, 0.1), 1.5, crack);
+                }
+            }
+            if ball.kind == BallKind::Balloon {
+                let knot = Point2 {
+                    x: ball.point.x,
+                    y: ball.point.y + radius,
+                };
+                let end = Point2 {
+                    x: knot.x,
+                    y: knot.y + radius * 0.8,
+                };
+                draw_line(canvas, knot, end, 1.5, color!(200, 200, 200));
+            }
+        }
+    }
+
+    /// Offset to draw the scene at, jumping about during an earthquake unless
+    /// motion is reduced. Random apart from the simulation, so replays don't change
+    fn camera_shake(&self) -> (f32, f32) {
+        let strength = self.sim.quake_strength();
+        if strength == 0.0 || self.reduced_motion() {
+            return (0.0, 0.0);
+        }
+        let reach = CAMERA_SHAKE * strength;
+        let mut rng = rand::thread_rng();
+        (rng.gen_range(-reach..=reach), rng.gen_range(-reach..=reach))
+    }
+
+    /// Build the faint grid that placement snaps to, unless it was already
+    /// built for this grid size and drawable size
+    pub(super) fn build_grid(&mut self, ctx: &Context) -> GameResult {
+        let size = self.config.grid_size;
+        let (width, height) = ctx.gfx.drawable_size();
+        let built = self
+            .grid
+            .as_ref()
+            .map(|&(size, drawable, _)| (size, drawable));
+        if built == Some((size, (width, height))) {
+            return Ok(());
+        }
+        let mut builder = MeshBuilder::new();
+        for i in 0..=(width / size) as usize {
+            let x = i as f32 * size;
+            builder.line(&[[x, 0.0], [x, height]], 1.0, color!(40, 40, 40))?;
+        }
+        for i in 0..=(height / size) as usize {
+            let y = i as f32 * size;
+            builder.line(&[[0.0, y], [width, y]], 1.0, color!(40, 40, 40))?;
+        }
+        self.grid = Some((size, (width, height), Mesh::from_data(ctx, builder.build())));
+        Ok(())
+    }
+
+    /// Zones behind the balls, tinted by what they count, with their tallies
+    fn draw_zones(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        for zone in &self.sim.zones {
+            let rect = Rect::new(
+                zone.min.x,
+                zone.min.y,
+                zone.max.x - zone.min.x,
+                zone.max.y - zone.min.y,
+            );
+            let mut tint = match zone.filter {
+                Filter::Color(color) => color,
+                _ => color!(128, 128, 128),
+            };
+            tint.a = 0.15;
+            let fill = Mesh::new_rectangle(ctx, DrawMode::fill(), rect, tint)?;
+            canvas.draw(&fill, DrawParam::default());
+            let outline = if zone.is_complete() {
+                color!(GREEN)
+            } else {
+                color!(128, 128, 128)
+            };
+            let border = Mesh::new_rectangle(ctx, DrawMode::stroke(2.0), rect, outline)?;
+            canvas.draw(&border, DrawParam::default());
+
+            let tally = format!("{}/{}", zone.count, zone.total);
+            let text = self.text_style.text(
+                if zone.label.is_empty() {
+                    tally
+                } else {
+                    format!("{}: {}", zone.label, tally)
+                },
+                18.0,
+            );
+            let margin = self.text_style.px(6.0);
+            canvas.draw(&text, DrawParam::from([rect.x + margin, rect.y + margin]));
+        }
+        Ok(())
+    }
+
+    /// Balls of the replay being shown, marked as a replay
+    fn draw_highlight(&self, canvas: &mut graphics::Canvas) {
+        let Some(highlight) = &self.highlight else {
+            return;
+        };
+        for ball in highlight.balls() {
+            canvas.draw(
+                &self.circle,
+                DrawParam::from(ball.point)
+                    .scale([ball.radius, ball.radius])
+                    .color(ball.color),
+            );
+        }
+        let text = self
+            .text_style
+            .text(format!("REPLAY  x{}", highlight::SPEED), 24.0);
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]).color(color!(0xe63946)));
+    }
+
+    /// Path the held ball, or the ball the mode is aiming or a throw is charging, would take if let go now
+    fn draw_prediction(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        if self.prediction == Prediction::Off {
+            return Ok(());
+        }
+        let aim = self.mode.aim().or_else(|| {
+            let id = self.sim.held?;
+            if let Some(velocity) = self.throw_velocity().filter(|_| self.aiming) {
+                return Some((id, velocity));
+            }
+            Some((id, self.sim.ball(id)?.velocity))
+        });
+        let Some((id, velocity)) = aim else {
+            return Ok(());
+        };
+        // Running every ball ahead is the most expensive prediction
+        let others = self.prediction == Prediction::WithOthers && self.quality.quality.effects();
+        let path = self.sim.predict(id, velocity, prediction::TICKS, others);
+        prediction::draw_path(ctx, canvas, &path)
+    }
+
+    fn draw_spawners(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> GameResult {
+        for spawner in &self.sim.spawners {
+            let ring = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(2.0),
+                spawner.point,
+                8.0,
+                0.1,
+                color!(128, 128, 128),
+            )?;
+            canvas.draw(&ring, DrawParam::default());
+        }
+        Ok(())
+    }
+
+    fn draw_inspector(&self, ctx: &mut Context, canvas: &mut graphics::Canvas) -> ggez::GameResult {
+        let Some(inspector) = &self.inspector else {
+            return Ok(());
+        };
+        let Some(ball) = self.sim.ball(inspector.ball) else {
+            return Ok(());
+        };
+        let ring = Mesh::new_circle(
+            ctx,
+            DrawMode::stroke(2.0),
+            ball.point,
+            ball.radius + 3.0,
+            0.1,
+            color!(WHITE),
+        )?;
+        canvas.draw(&ring, DrawParam::default());
+        inspector.draw(canvas, &ball, self.config.units, self.text_style);
+        Ok(())
+    }
+
+    fn draw_hud(&self, canvas: &mut graphics::Canvas, height: f32) {
+        let text = self.text_style.text(
+            format!(
+                "Mode: {}    Tool: {}    Seed: {}{}{}{}",
+                self.mode.name(),
+                self.tool.name(),
+                self.seed,
+                if self.clip.is_some() { "    REC" } else { "" },
+                if self.art.is_some() {
+                    "    EXPOSING"
+                } else {
+                    ""
+                },
+                if self.rewinding {
+                    format!("    REWIND ({:.1}s left)", self.rewind.seconds_left())
+                } else {
+                    String::new()
+                }
+            ),
+            18.0,
+        );
+        canvas.draw(
+            &text,
+            DrawParam::from([10.0, height - self.text_style.px(28.0)]),
+        );
+    }
+}
@@ -0,0 +1,209 @@
+use ggez::winit::dpi::PhysicalPosition;
+use std::path::Path;
+
+use ggez::winit::monitor::MonitorHandle;
+use ggez::winit::window::Icon;
+use ggez::Context;
+
+use super::App;
+use crate::config::AppConfig;
+use crate::settings::WindowGeometry;
+
+/// Width and height of the drawn ball icon
+const ICON_SIZE: u32 = 64;
+
+/// Give the window its title and icon, drawing a ball for the icon if none is
+/// set or it fails to load
+pub(super) fn decorate(ctx: &mut Context, config: &AppConfig) -> Result<(), String> {
+    ctx.gfx.set_window_title(&config.title);
+    let (icon, result) = match config.icon.as_deref().map(load_icon) {
+        Some(Ok(icon)) => (icon, Ok(())),
+        Some(Err(err)) => (ball_icon(), Err(err)),
+        None => (ball_icon(), Ok(())),
+    };
+    ctx.gfx.window().set_window_icon(Some(icon));
+    result
+}
+
+fn load_icon(path: &Path) -> Result<Icon, String> {
+    let image = image::open(path)
+        .map_err(|err| format!("{}: {}", path.display(), err))?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(|err| err.to_string())
+}
+
+/// Shaded ball lit from the top left, with smoothed edges
+fn ball_icon() -> Icon {
+    let centre = ICON_SIZE as f32 / 2.0;
+    let radius = centre - 2.0;
+    let (light_x, light_y) = (centre - radius * 0.4, centre - radius * 0.4);
+    let [r, g, b] = [0x48, 0xca, 0xe4].map(|channel: u8| channel as f32);
+    let mut pixels = Vec::with_capacity((ICON_SIZE * ICON_SIZE * 4) as usize);
+    for y in 0..ICON_SIZE {
+        for x in 0..ICON_SIZE {
+            let (x, y) = (x as f32 + 0.5, y as f32 + 0.5);
+            let alpha = (radius - (x - centre).hypot(y - centre) + 0.5).clamp(0.0, 1.0);
+            // Brightest at the light, darkening towards the far edge
+            let shade = 1.3 - (x - light_x).hypot(y - light_y) / (radius * 2.0);
+            let channel = |base: f32| (base * shade).clamp(0.0, 255.0) as u8;
+            pixels.extend([channel(r), channel(g), channel(b), (alpha * 255.0) as u8]);
+        }
+    }
+    Icon::from_rgba(pixels, ICON_SIZE, ICON_SIZE).expect("icon pixels match its size")
+}
+
+/// Move and resize the window to where it was last closed, unless its size or
+/// fullscreen was set or a replay needs the size it was recorded at, then show it.
+/// Returns the geometry the window now has
+pub(super) fn restore(
+    ctx: &mut Context,
+    config: &AppConfig,
+    saved: Option<&WindowGeometry>,
+) -> WindowGeometry {
+    let geometry = match saved {
+        Some(saved) if config.restore_window && config.replay.is_none() => {
+            place(ctx, saved);
+            saved.clone()
+        }
+        _ => open_on_monitor(ctx, config),
+    };
+    ctx.gfx.window().set_visible(true);
+    geometry
+}
+
+/// Geometry from the config, on the monitor asked for and covering it if borderless
+fn open_on_monitor(ctx: &mut Context, config: &AppConfig) -> WindowGeometry {
+    let mut geometry = config.window_geometry();
+    let monitor = match &config.monitor {
+        Some(choice) => find_monitor(ctx, choice),
+        None if config.borderless => ctx.gfx.window().current_monitor(),
+        None => None,
+    };
+    let Some(monitor) = monitor else {
+        return geometry;
+    };
+    let (position, size) = (monitor.position(), monitor.size());
+    let scale = monitor.scale_factor() as f32;
+    if config.borderless {
+        geometry.width = size.width as f32 / scale;
+        geometry.height = size.height as f32 / scale;
+        geometry.position = Some((position.x, position.y));
+    } else {
+        // Centred, where a window manager would most likely put it
+        let x = position.x + (size.width as i32 - (geometry.width * scale) as i32).max(0) / 2;
+        let y = position.y + (size.height as i32 - (geometry.height * scale) as i32).max(0) / 2;
+        geometry.position = Some((x, y));
+    }
+    geometry.monitor = monitor.name();
+    place(ctx, &geometry);
+    geometry
+}
+
+/// Monitor by number from 1, or the first with the text in its name
+fn find_monitor(ctx: &Context, choice: &str) -> Option<MonitorHandle> {
+    let mut monitors = ctx.gfx.window().available_monitors();
+    let monitor = match choice.parse::<usize>() {
+        Ok(number) => monitors.nth(number.checked_sub(1)?),
+        Err(_) => {
+            let choice = choice.to_lowercase();
+            monitors.find(|monitor| {
+                monitor
+                    .name()
+                    .is_some_and(|name| name.to_lowercase().contains(&choice))
+            })
+        }
+    };
+    if monitor.is_none() {
+        let names: Vec<_> = ctx
+            .gfx
+            .window()
+            .available_monitors()
+            .map(|monitor| monitor.name().unwrap_or_else(|| "unnamed".to_string()))
+            .collect();
+        log::warn!(
+            "No monitor `{}`, opening on the default one. Monitors: {}",
+            choice,
+            names.join(", ")
+        );
+    }
+    monitor
+}
+
+/// Apply saved geometry, going back to its monitor only if that is still connected
+fn place(ctx: &mut Context, geometry: &WindowGeometry) {
+    let monitor = geometry.monitor.as_ref().and_then(|name| {
+        ctx.gfx
+            .window()
+            .available_monitors()
+            .find(|monitor| monitor.name().as_ref() == Some(name))
+    });
+    if let Some(monitor) = monitor {
+        // The monitors may have been rearranged, leaving the old position off screen
+        let (x, y) = geometry
+            .position
+            .filter(|&(x, y)| contains(&monitor, x, y))
+            .unwrap_or((monitor.position().x, monitor.position().y));
+        if let Err(err) = ctx.gfx.set_window_position(PhysicalPosition::new(x, y)) {
+            log::warn!("Failed to move window: {}", err);
+        }
+    }
+    if let Err(err) = ctx.gfx.set_mode(geometry.window_mode()) {
+        log::warn!("Failed to restore window size: {}", err);
+    }
+}
+
+fn contains(monitor: &MonitorHandle, x: i32, y: i32) -> bool {
+    let position = monitor.position();
+    let size = monitor.size();
+    (position.x..position.x + size.width as i32).contains(&x)
+        && (position.y..position.y + size.height as i32).contains(&y)
+}
+
+impl App {
+    /// Remember the size of the window, unless it is only fullscreen
+    pub(super) fn resized(&mut self, width: f32, height: f32) {
+        if !self.window.fullscreen {
+            self.window.width = width;
+            self.window.height = height;
+        }
+    }
+
+    pub(super) fn toggle_fullscreen(&mut self, ctx: &mut Context) {
+        if !self.window.fullscreen {
+            // To go back to once the window leaves fullscreen
+            self.window.position = window_position(ctx);
+        }
+        self.window.fullscreen = !self.window.fullscreen;
+        if let Err(err) = ctx.gfx.set_mode(self.window.window_mode()) {
+            self.window.fullscreen = !self.window.fullscreen;
+            self.toasts
+                .push(format!("Failed to change fullscreen: {}", err));
+            return;
+        }
+        if let Some((x, y)) = self.window.position.filter(|_| !self.window.fullscreen) {
+            let _ = ctx.gfx.set_window_position(PhysicalPosition::new(x, y));
+        }
+    }
+
+    /// Save where the window is, to open it there next time
+    pub(super) fn save_window(&mut self, ctx: &Context) {
+        if !self.window.fullscreen {
+            self.window.position = window_position(ctx).or(self.window.position);
+        }
+        self.window.monitor = ctx
+            .gfx
+            .window()
+            .current_monitor()
+            .and_then(|monitor| monitor.name());
+        self.settings.window = Some(self.window.clone());
+        if let Err(err) = self.settings.save(&self.config_dir) {
+            log::warn!("Failed to save window position: {}", err);
+        }
+    }
+}
+
+fn window_position(ctx: &Context) -> Option<(i32, i32)> {
+    let position = ctx.gfx.window_position().ok()?;
+    Some((position.x, position.y))
+}
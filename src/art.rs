@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+
+use ggez::mint::Point2;
+use image::RgbaImage;
+
+use crate::ball::BallId;
+use crate::capture;
+use crate::simulation::Simulation;
+
+/// Directory that long-exposure pictures are written to
+pub const ART_DIR: &str = "art";
+
+/// Pixels of the picture per pixel of the window
+const SCALE: f32 = 2.0;
+/// Longest side of the picture, with larger windows given fewer pixels each
+const MAX_SIDE: f32 = 4096.0;
+/// Light a ball leaves on the pixels it covers for a whole frame
+const EXPOSURE: f32 = 0.04;
+/// Width of the soft edge of each ball, in pixels of the picture
+const FEATHER: f32 = 1.5;
+
+/// Light gathered from every ball as it moves, like a camera with its shutter
+/// left open, to save as a picture
+pub struct Exposure {
+    width: usize,
+    height: usize,
+    /// Pixels of the picture per pixel of the window
+    scale: f32,
+    light: Vec<[f32; 3]>,
+    /// Where each ball was when last exposed, to streak it from there
+    last: HashMap<BallId, Point2<f32>>,
+    frames: u32,
+}
+
+impl Exposure {
+    /// Empty picture of a window of this size
+    pub fn new(width: f32, height: f32) -> Self {
+        let scale = SCALE.min(MAX_SIDE / width.max(height).max(1.0));
+        let (width, height) = ((width * scale) as usize, (height * scale) as usize);
+        Self {
+            width,
+            height,
+            scale,
+            light: vec![[0.0; 3]; width * height],
+            last: HashMap::new(),
+            frames: 0,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames == 0
+    }
+
+    /// Add a frame of the balls, spreading the light of those that moved along
+    /// their path, so faster balls leave fainter streaks
+    pub fn expose(&mut self, sim: &Simulation) {
+        let mut last = HashMap::with_capacity(self.last.len());
+        for (id, ball) in sim.balls() {
+            let to = Point2 {
+                x: ball.point.x * self.scale,
+                y: ball.point.y * self.scale,
+            };
+            let from = self.last.get(&id).copied().unwrap_or(to);
+            let radius = ball.radius * self.scale;
+            let color = [ball.color.r, ball.color.g, ball.color.b];
+            let length = (to.x - from.x).hypot(to.y - from.y);
+            let stamps = (length / (radius * 0.5).max(1.0)).ceil().max(1.0) as usize;
+            for i in 1..=stamps {
+                let t = i as f32 / stamps as f32;
+                let centre = Point2 {
+                    x: from.x + (to.x - from.x) * t,
+                    y: from.y + (to.y - from.y) * t,
+                };
+                self.stamp(centre, radius, color, EXPOSURE / stamps as f32);
+            }
+            last.insert(id, to);
+        }
+        self.last = last;
+        self.frames += 1;
+    }
+
+    /// Add light in a soft-edged circle
+    fn stamp(&mut self, centre: Point2<f32>, radius: f32, color: [f32; 3], amount: f32) {
+        let left = (centre.x - radius).floor().max(0.0) as usize;
+        let top = (centre.y - radius).floor().max(0.0) as usize;
+        let right = ((centre.x + radius).ceil().max(0.0) as usize).min(self.width);
+        let bottom = ((centre.y + radius).ceil().max(0.0) as usize).min(self.height);
+        for y in top..bottom {
+            let row = y * self.width;
+            let dy = y as f32 + 0.5 - centre.y;
+            for x in left..right {
+                let distance = (x as f32 + 0.5 - centre.x).hypot(dy);
+                let coverage = ((radius - distance) / FEATHER).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    let pixel = &mut self.light[row + x];
+                    for (channel, base) in pixel.iter_mut().zip(color) {
+                        *channel += base * amount * coverage;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Develop the picture and write it to a new PNG file on another thread,
+    /// which returns its path
+    pub fn save(self) -> JoinHandle<Result<PathBuf, String>> {
+        thread::spawn(move || {
+            let mut image = RgbaImage::new(self.width as u32, self.height as u32);
+            for (pixel, light) in image.pixels_mut().zip(&self.light) {
+                // Bright areas fade to white gradually rather than clipping
+                let develop = |light: f32| ((1.0 - (-light).exp()) * 255.0) as u8;
+                pixel.0 = [develop(light[0]), develop(light[1]), develop(light[2]), 255];
+            }
+            let path = capture::timestamped(Path::new(ART_DIR), "art", "png")?;
+            image.save(&path).map_err(|err| err.to_string())?;
+            Ok(path)
+        })
+    }
+}
@@ -0,0 +1,217 @@
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+use cpal::{FromSample, SampleFormat, SizedSample, StreamConfig};
+use ggez::audio::{SoundData, SoundSource, Source};
+use ggez::{Context, GameResult};
+
+/// Track played from the resources directory when no path is given
+const DEFAULT_TRACK: &str = "/music.ogg";
+
+/// Looping background music
+pub struct Music {
+    source: Source,
+}
+
+impl Music {
+    /// Start playing a track from a path, or the default track if it exists
+    pub fn load(ctx: &mut Context, path: Option<&Path>, volume: f32) -> GameResult<Option<Self>> {
+        let mut source = match path {
+            Some(path) => {
+                let data = SoundData::from_bytes(&fs::read(path)?);
+                Source::from_data(ctx, data)?
+            }
+            None if ctx.fs.exists(DEFAULT_TRACK) => Source::new(ctx, DEFAULT_TRACK)?,
+            None => return Ok(None),
+        };
+
+        source.set_repeat(true);
+        source.set_volume(volume);
+        source.play(ctx)?;
+
+        Ok(Some(Self { source }))
+    }
+
+    /// Pause or resume, returning whether it is now playing
+    pub fn toggle(&mut self) -> bool {
+        if self.source.paused() {
+            self.source.resume();
+            true
+        } else {
+            self.source.pause();
+            false
+        }
+    }
+
+    pub fn set_volume(&mut self, volume: f32) {
+        self.source.set_volume(volume);
+    }
+}
+
+/// Sample rate of generated sounds
+const SAMPLE_RATE: u32 = 22050;
+/// Sound played when a ball pops, from the resources directory if it exists
+const POP_SOUND: &str = "/pop.ogg";
+
+/// Short sounds played over the music
+pub struct Effects {
+    pop: SoundData,
+}
+
+impl Effects {
+    /// Load sounds from the resources directory, generating any that are missing
+    pub fn load(ctx: &mut Context) -> Self {
+        let pop = if ctx.fs.exists(POP_SOUND) {
+            SoundData::new(ctx, POP_SOUND).unwrap_or_else(|err| {
+                log::warn!("Failed to load {}: {}", POP_SOUND, err);
+                SoundData::from_bytes(&pop_wav())
+            })
+        } else {
+            SoundData::from_bytes(&pop_wav())
+        };
+        Self { pop }
+    }
+
+    /// Play a pop, quieter for smaller balls
+    pub fn pop(&self, ctx: &mut Context, volume: f32, radius: f32) {
+        let played = Source::from_data(ctx, self.pop.clone()).and_then(|mut source| {
+            source.set_volume(volume * (radius / 30.0).clamp(0.3, 1.0));
+            source.set_pitch((30.0 / radius).clamp(0.7, 2.0));
+            source.play_detached(ctx)
+        });
+        if let Err(err) = played {
+            log::debug!("Failed to play sound: {}", err);
+        }
+    }
+}
+
+/// A falling blip with a fast decay, as a 16-bit mono WAV file
+fn pop_wav() -> Vec<u8> {
+    let samples = SAMPLE_RATE as usize / 12;
+    let mut phase: f32 = 0.0;
+    let data: Vec<i16> = (0..samples)
+        .map(|i| {
+            let t = i as f32 / samples as f32;
+            phase += std::f32::consts::TAU * (900.0 - 600.0 * t) / SAMPLE_RATE as f32;
+            let envelope = (1.0 - t).powi(3);
+            (phase.sin() * envelope * i16::MAX as f32 * 0.6) as i16
+        })
+        .collect();
+
+    let size = (data.len() * 2) as u32;
+    let mut wav = Vec::with_capacity(44 + size as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + size).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&size.to_le_bytes());
+    for sample in data {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+/// Smoothing factor for the running average input level
+const AVERAGE_SMOOTHING: f32 = 0.05;
+/// How far above the average level counts as a beat
+const BEAT_THRESHOLD: f32 = 1.5;
+/// Quietest level that can count as a beat, to ignore background noise
+const MIN_BEAT_LEVEL: f32 = 0.01;
+/// Seconds to wait after a beat before detecting another
+const BEAT_COOLDOWN: f32 = 0.15;
+
+/// Detects beats in the default audio input device
+pub struct BeatDetector {
+    // Kept alive so the stream keeps recording
+    _stream: cpal::Stream,
+    /// Latest RMS level, stored as `f32` bits
+    level: Arc<AtomicU32>,
+    average: f32,
+    cooldown: f32,
+}
+
+impl BeatDetector {
+    /// Start listening to the default input device
+    pub fn new() -> Result<Self, String> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or("No audio input device")?;
+        let config = device
+            .default_input_config()
+            .map_err(|err| err.to_string())?;
+
+        let level = Arc::new(AtomicU32::new(0));
+        let stream = match config.sample_format() {
+            SampleFormat::F32 => listen::<f32>(&device, &config.into(), level.clone()),
+            SampleFormat::I16 => listen::<i16>(&device, &config.into(), level.clone()),
+            SampleFormat::U16 => listen::<u16>(&device, &config.into(), level.clone()),
+            format => return Err(format!("Unsupported sample format {}", format)),
+        }?;
+        stream.play().map_err(|err| err.to_string())?;
+
+        Ok(Self {
+            _stream: stream,
+            level,
+            average: 0.0,
+            cooldown: 0.0,
+        })
+    }
+
+    /// Returns strength of a beat, relative to the average level, if one occurred
+    pub fn update(&mut self, dt: f32) -> Option<f32> {
+        let level = f32::from_bits(self.level.load(Ordering::Relaxed));
+        self.cooldown = (self.cooldown - dt).max(0.0);
+
+        let is_beat =
+            self.cooldown <= 0.0 && level > MIN_BEAT_LEVEL && level > self.average * BEAT_THRESHOLD;
+        let strength = level / self.average.max(MIN_BEAT_LEVEL);
+
+        self.average += (level - self.average) * AVERAGE_SMOOTHING;
+
+        if !is_beat {
+            return None;
+        }
+        self.cooldown = BEAT_COOLDOWN;
+        Some(strength)
+    }
+}
+
+fn listen<T>(
+    device: &cpal::Device,
+    config: &StreamConfig,
+    level: Arc<AtomicU32>,
+) -> Result<cpal::Stream, String>
+where
+    T: SizedSample,
+    f32: FromSample<T>,
+{
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _| {
+                if data.is_empty() {
+                    return;
+                }
+                let sum: f32 = data
+                    .iter()
+                    .map(|&sample| sample.to_sample::<f32>().powi(2))
+                    .sum();
+                let rms = (sum / data.len() as f32).sqrt();
+                level.store(rms.to_bits(), Ordering::Relaxed);
+            },
+            |_| (),
+            None,
+        )
+        .map_err(|err| err.to_string())
+}
@@ -0,0 +1,419 @@
+use ggez::graphics::Color;
+use ggez::mint::{Point2, Vector2};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use slotmap::new_key_type;
+
+/// Speed below which a ball counts as sleeping
+const SLEEP_SPEED: f32 = 0.1;
+/// Fraction of the difference in velocity a sticky ball and what it touches lose on each contact
+const STICKINESS: f32 = 0.5;
+/// Collision layer balls are on unless given another
+pub const DEFAULT_LAYER: u32 = 1;
+/// Mask of a ball that collides with balls on every layer
+pub const ALL_LAYERS: u32 = u32::MAX;
+/// Layers that can be picked from the inspector
+pub const LAYERS: u32 = 8;
+
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Ball {
+    pub point: Point2<f32>,
+    pub radius: f32,
+    pub velocity: Vector2<f32>,
+    pub color: Color,
+    /// How much speed is kept when bouncing off a wall
+    #[serde(default = "default_restitution")]
+    pub restitution: f32,
+    /// Pinned balls are never moved by physics
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub kind: BallKind,
+    /// Collision layers the ball is on, one per bit
+    #[serde(default = "default_layer")]
+    pub layer: u32,
+    /// Layers of the balls this one collides with. Balls hit the walls whatever
+    /// their layers
+    #[serde(default = "default_mask")]
+    pub mask: u32,
+    #[serde(default)]
+    pub pattern: Pattern,
+}
+
+fn default_restitution() -> f32 {
+    0.5
+}
+
+fn default_layer() -> u32 {
+    DEFAULT_LAYER
+}
+
+fn default_mask() -> u32 {
+    ALL_LAYERS
+}
+
+/// Whether balls on two layers collide, which each must allow of the other
+pub fn layers_meet(layer: u32, mask: u32, other_layer: u32, other_mask: u32) -> bool {
+    layer & other_mask != 0 && other_layer & mask != 0
+}
+
+/// Layers set in a bitfield as numbers from 1, such as for the inspector
+pub fn layer_names(bits: u32) -> String {
+    let list = |bits: u32| {
+        let numbers: Vec<_> = (0..u32::BITS)
+            .filter(|bit| bits & (1 << bit) != 0)
+            .map(|bit| (bit + 1).to_string())
+            .collect();
+        numbers.join(", ")
+    };
+    match bits {
+        ALL_LAYERS => "all".to_string(),
+        0 => "none".to_string(),
+        // Listing the layers left out is shorter
+        _ if bits.count_ones() > u32::BITS / 2 => format!("all but {}", list(!bits)),
+        _ => list(bits),
+    }
+}
+
+impl Ball {
+    pub fn new(x: f32, y: f32, radius: f32, color: Color) -> Self {
+        Self {
+            point: Point2 { x, y },
+            radius,
+            velocity: Vector2 { x: 0.0, y: 0.0 },
+            color,
+            restitution: default_restitution(),
+            pinned: false,
+            kind: BallKind::Normal,
+            layer: DEFAULT_LAYER,
+            mask: ALL_LAYERS,
+            pattern: Pattern::Plain,
+        }
+    }
+
+    pub fn new_random(rng: &mut impl Rng, width: f32, height: f32) -> Self {
+        let radius = rng.gen_range(10.0..50.0);
+        let x = rng.gen_range(radius..(width - radius).max(radius + 1.0));
+        let y = rng.gen_range(radius..(height - radius).max(radius + 1.0));
+        Ball::new(x, y, radius, color!(?rng))
+    }
+
+    /// How far two balls overlap, negative when apart
+    pub fn penetration(&self, other: &Self) -> f32 {
+        let dx = self.point.x - other.point.x;
+        let dy = self.point.y - other.point.y;
+        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
+        self.radius + other.radius - dist
+    }
+
+    pub fn collides_point(&self, point: Point2<f32>) -> bool {
+        let dx = self.point.x - point.x;
+        let dy = self.point.y - point.y;
+        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
+        dist <= self.radius
+    }
+
+    /// Push this ball out of another, taking only its own side of the push that
+    /// `separate` would give both, so each ball of a pair can be moved on its own
+    pub fn move_from(&mut self, other: &Self, fixed: (bool, bool)) {
+        let bounce = 0.05;
+        let jump = 0.6;
+
+        let Some((share, _)) = self.push_shares(other, fixed) else {
+            return;
+        };
+        // Twice the share, so two equal balls each move as far as they used to
+        let share = 2.0 * share;
+        let bounce = bounce * self.pair_bounce(other);
+
+        let dx = self.point.x - other.point.x;
+        let dy = self.point.y - other.point.y;
+        let angle = dy.atan2(dx);
+
+        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
+        let force = self.radius + other.radius - dist;
+
+        let x = angle.cos() * force * share;
+        let y = angle.sin() * force * share;
+        self.velocity.x += x * bounce;
+        self.velocity.y += y * bounce;
+        self.point.x += x * jump;
+        self.point.y += y * jump;
+
+        if self.kind == BallKind::Sticky || other.kind == BallKind::Sticky {
+            self.velocity.x += (other.velocity.x - self.velocity.x) * STICKINESS * share;
+            self.velocity.y += (other.velocity.y - self.velocity.y) * STICKINESS * share;
+        }
+    }
+
+    /// Push two overlapping balls apart, splitting the push by inverse mass so
+    /// heavier balls give way less. Fixed balls don't move at all
+    pub fn separate(&mut self, other: &mut Self, fixed: (bool, bool)) {
+        let bounce = 0.1;
+        let jump = 0.8;
+
+        let Some((share, other_share)) = self.push_shares(other, fixed) else {
+            return;
+        };
+        let bounce = bounce * self.pair_bounce(other);
+
+        let dx = self.point.x - other.point.x;
+        let dy = self.point.y - other.point.y;
+        let angle = dy.atan2(dx);
+
+        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
+        let force = self.radius + other.radius - dist;
+        let x = angle.cos() * force;
+        let y = angle.sin() * force;
+        self.velocity.x += x * bounce * share;
+        self.velocity.y += y * bounce * share;
+        other.velocity.x -= x * bounce * other_share;
+        other.velocity.y -= y * bounce * other_share;
+        self.point.x += x * jump * share;
+        self.point.y += y * jump * share;
+        other.point.x -= x * jump * other_share;
+        other.point.y -= y * jump * other_share;
+
+        if self.kind == BallKind::Sticky || other.kind == BallKind::Sticky {
+            let dvx = (other.velocity.x - self.velocity.x) * STICKINESS;
+            let dvy = (other.velocity.y - self.velocity.y) * STICKINESS;
+            self.velocity.x += dvx * share;
+            self.velocity.y += dvy * share;
+            other.velocity.x -= dvx * other_share;
+            other.velocity.y -= dvy * other_share;
+        }
+    }
+
+    /// How much of a push between two balls each one takes, by inverse mass, or
+    /// nothing if both are fixed
+    fn push_shares(&self, other: &Self, fixed: (bool, bool)) -> Option<(f32, f32)> {
+        let inverse_mass = |ball: &Self, fixed| {
+            if fixed {
+                0.0
+            } else {
+                1.0 / ball.mass().max(f32::EPSILON)
+            }
+        };
+        let (a, b) = (inverse_mass(self, fixed.0), inverse_mass(other, fixed.1));
+        if a + b == 0.0 {
+            return None;
+        }
+        Some((a / (a + b), b / (a + b)))
+    }
+
+    /// How hard a pair bounces apart, the same for both so the push keeps their momentum
+    fn pair_bounce(&self, other: &Self) -> f32 {
+        (self.get_bounce_amount() + other.get_bounce_amount()) / 2.0
+    }
+
+    /// Whether two balls push each other apart when they overlap
+    pub fn collides_with(&self, other: &Self) -> bool {
+        self.kind != BallKind::Ghost && other.kind != BallKind::Ghost
+    }
+
+    /// Mass, proportional to area and density
+    pub fn mass(&self) -> f32 {
+        self.radius.powi(2) * self.kind.density()
+    }
+
+    pub fn speed(&self) -> f32 {
+        (self.velocity.x.powi(2) + self.velocity.y.powi(2)).sqrt()
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        0.5 * self.mass() * self.speed().powi(2)
+    }
+
+    /// Whether ball has effectively come to rest
+    pub fn is_sleeping(&self) -> bool {
+        self.pinned || self.speed() < SLEEP_SPEED
+    }
+
+    pub fn get_bounce_amount(&self) -> f32 {
+        let bounce_mass_falloff = 0.05;
+        1.0 / (self.radius * bounce_mass_falloff).max(1.0) / self.kind.density()
+    }
+
+    /// Change kind, taking on its restitution
+    pub fn set_kind(&mut self, kind: BallKind) {
+        self.kind = kind;
+        self.restitution = kind.restitution();
+    }
+}
+
+/// Physical preset and look of a ball
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BallKind {
+    #[default]
+    Normal,
+    /// Dense, barely bounces, and shoves lighter balls aside
+    Heavy,
+    /// Keeps nearly all its speed when bouncing
+    Bouncy,
+    /// Takes on the velocity of whatever it touches, so clumps together
+    Sticky,
+    /// Passes through other balls, only bouncing off walls
+    Ghost,
+    /// Light, floats up against the ceiling, and pops when squeezed by heavy balls
+    Balloon,
+    /// Blows up on a hard impact, throwing nearby balls away and setting off other bombs
+    Bomb,
+}
+
+impl BallKind {
+    pub const ALL: [BallKind; 7] = [
+        BallKind::Normal,
+        BallKind::Heavy,
+        BallKind::Bouncy,
+        BallKind::Sticky,
+        BallKind::Ghost,
+        BallKind::Balloon,
+        BallKind::Bomb,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            BallKind::Normal => "Normal",
+            BallKind::Heavy => "Heavy",
+            BallKind::Bouncy => "Bouncy",
+            BallKind::Sticky => "Sticky",
+            BallKind::Ghost => "Ghost",
+            BallKind::Balloon => "Balloon",
+            BallKind::Bomb => "Bomb",
+        }
+    }
+
+    /// Mass per area, relative to a normal ball
+    pub fn density(self) -> f32 {
+        match self {
+            BallKind::Heavy => 4.0,
+            BallKind::Balloon => 0.3,
+            _ => 1.0,
+        }
+    }
+
+    /// Gravity felt, relative to a normal ball, which is negative for balls that float
+    pub fn gravity_scale(self) -> f32 {
+        match self {
+            BallKind::Balloon => -0.4,
+            _ => 1.0,
+        }
+    }
+
+    pub fn restitution(self) -> f32 {
+        match self {
+            BallKind::Heavy => 0.2,
+            BallKind::Bouncy => 0.95,
+            BallKind::Sticky => 0.1,
+            BallKind::Balloon => 0.6,
+            BallKind::Normal | BallKind::Ghost | BallKind::Bomb => default_restitution(),
+        }
+    }
+
+    /// Next kind, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL.iter().position(|&kind| kind == self).unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Previous kind, wrapping around
+    pub fn previous(self) -> Self {
+        let i = Self::ALL.iter().position(|&kind| kind == self).unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+/// Design drawn over a ball, turning as it spins
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pattern {
+    #[default]
+    Plain,
+    /// White band across the middle, like a billiard ball
+    Stripe,
+    /// White spots, one in the middle and four around it
+    Dots,
+    /// Bullseye of white rings
+    Rings,
+}
+
+impl Pattern {
+    pub const ALL: [Pattern; 4] = [
+        Pattern::Plain,
+        Pattern::Stripe,
+        Pattern::Dots,
+        Pattern::Rings,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Pattern::Plain => "Plain",
+            Pattern::Stripe => "Stripe",
+            Pattern::Dots => "Dots",
+            Pattern::Rings => "Rings",
+        }
+    }
+
+    /// Next pattern, wrapping around
+    pub fn next(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&pattern| pattern == self)
+            .unwrap_or(0);
+        Self::ALL[(i + 1) % Self::ALL.len()]
+    }
+
+    /// Previous pattern, wrapping around
+    pub fn previous(self) -> Self {
+        let i = Self::ALL
+            .iter()
+            .position(|&pattern| pattern == self)
+            .unwrap_or(0);
+        Self::ALL[(i + Self::ALL.len() - 1) % Self::ALL.len()]
+    }
+}
+
+new_key_type! {
+    /// Stable handle to a ball, which stays valid until the ball is removed
+    pub struct BallId;
+}
+
+/// Point that pulls every unpinned ball towards it
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Attractor {
+    pub point: Point2<f32>,
+    pub strength: f32,
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::Rng;
+
+    use super::*;
+    use crate::scene::Scene;
+    use crate::simulation::Simulation;
+    use crate::testing::{check, random_scene, TICKS};
+
+    #[test]
+    fn patterns_never_change_how_balls_move() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut patterned = scene.clone();
+            for ball in &mut patterned.balls {
+                ball.pattern = Pattern::ALL[rng.gen_range(0..Pattern::ALL.len())];
+            }
+            let run = |scene: Scene| {
+                let mut sim = Simulation::from_scene(scene, width, height);
+                sim.run(TICKS);
+                sim.balls()
+                    .map(|(_, ball)| (ball.point, ball.velocity))
+                    .collect::<Vec<_>>()
+            };
+            assert!(
+                run(scene) == run(patterned),
+                "seed {seed}: patterned balls moved differently"
+            );
+        });
+    }
+}
@@ -0,0 +1,120 @@
+use std::path::PathBuf;
+use std::process;
+
+use clap::{value_parser, Arg, ArgMatches, Command};
+use log::LevelFilter;
+
+use balls::{AppBuilder, AppConfig};
+
+/// Address to listen on, unless given
+const DEFAULT_ADDR: &str = "0.0.0.0:7878";
+
+fn main() {
+    let matches = command().get_matches();
+
+    let level = matches
+        .get_one::<LevelFilter>("log-level")
+        .copied()
+        .unwrap_or(LevelFilter::Warn);
+    if let Err(err) = balls::init_logging(level, None) {
+        eprintln!("Failed to start logging: {}", err);
+    }
+
+    let mut config = AppConfig::default();
+    if let Err(err) = balls::load_config(&mut config) {
+        log::error!("Failed to load config: {}", err);
+    }
+    let app = app_builder(AppBuilder::from(config), &matches);
+    let addr = matches
+        .get_one::<String>("addr")
+        .map_or(DEFAULT_ADDR, String::as_str);
+    if let Err(err) = balls::run_server(app.into_config(), addr) {
+        eprintln!("{}", err);
+        process::exit(1);
+    }
+}
+
+fn command() -> Command {
+    Command::new("balls-server")
+        .about("Bouncy ball physics without a window, shared with players who join with --join")
+        .arg(
+            Arg::new("addr")
+                .long("addr")
+                .value_name("ADDR")
+                .help("Address to listen for players on [default: 0.0.0.0:7878]"),
+        )
+        .arg(
+            Arg::new("balls")
+                .long("balls")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Start with N random balls instead of the pile demo"),
+        )
+        .arg(
+            Arg::new("scene")
+                .long("scene")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Open a saved scene file"),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .value_name("WxH")
+                .value_parser(parse_size)
+                .help("Size of the scene in pixels, which players' windows should match"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("S")
+                .value_parser(value_parser!(u64))
+                .help("Seed for random generation, to repeat a run"),
+        )
+        .arg(
+            Arg::new("max-balls")
+                .long("max-balls")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Most balls at once, so players can't slow the server to a crawl"),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .value_parser(value_parser!(LevelFilter))
+                .default_value("warn")
+                .help("Most detailed log messages to show: off, error, warn, info, debug or trace"),
+        )
+}
+
+fn app_builder(mut app: AppBuilder, matches: &ArgMatches) -> AppBuilder {
+    if let Some(&count) = matches.get_one::<usize>("balls") {
+        app = app.balls(count);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("scene") {
+        app = app.scene(path);
+    }
+    if let Some(&(width, height)) = matches.get_one::<(f32, f32)>("size") {
+        app = app.window_size(width, height);
+    }
+    if let Some(&seed) = matches.get_one::<u64>("seed") {
+        app = app.seed(seed);
+    }
+    if let Some(&max) = matches.get_one::<usize>("max-balls") {
+        app = app.max_balls(max);
+    }
+    app
+}
+
+/// Parse scene size such as `800x600`
+fn parse_size(value: &str) -> Result<(f32, f32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or("expected size as WIDTHxHEIGHT")?;
+    let parse = |side: &str| match side.trim().parse::<u32>() {
+        Ok(side) if side > 0 => Ok(side as f32),
+        _ => Err(format!("invalid size `{}`", side)),
+    };
+    Ok((parse(width)?, parse(height)?))
+}
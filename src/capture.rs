@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::thread::{self, JoinHandle};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::graphics::ImageFormat;
+use ggez::Context;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::imageops::{self, FilterType};
+use image::{Delay, Frame, RgbaImage};
+
+/// Directory that screenshots are written to
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+/// Directory that GIF clips are written to
+pub const CLIPS_DIR: &str = "clips";
+
+/// Frames per second of GIF clips
+const CLIP_FPS: u32 = 12;
+/// Longest clip kept, with older frames dropped while recording
+const CLIP_SECONDS: u32 = 8;
+/// Widest frame of a clip, with larger windows scaled down
+const CLIP_WIDTH: u32 = 400;
+
+/// Screenshot to take once the next frame is drawn
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Screenshot {
+    /// Everything on screen
+    Full,
+    /// Just the balls, without any text or panels
+    Clean,
+}
+
+/// Read back the last frame drawn
+pub fn frame_pixels(ctx: &Context) -> Result<RgbaImage, String> {
+    let frame = ctx.gfx.frame();
+    let mut pixels = frame.to_pixels(&ctx.gfx).map_err(|err| err.to_string())?;
+    match frame.format() {
+        ImageFormat::Rgba8Unorm | ImageFormat::Rgba8UnormSrgb => (),
+        ImageFormat::Bgra8Unorm | ImageFormat::Bgra8UnormSrgb => {
+            for pixel in pixels.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+        }
+        format => return Err(format!("Unsupported frame format {:?}", format)),
+    }
+    RgbaImage::from_raw(frame.width(), frame.height(), pixels)
+        .ok_or_else(|| "Frame size does not match its pixels".to_string())
+}
+
+/// Write the last frame drawn to a new PNG file, returning its path
+pub fn save_screenshot(ctx: &Context) -> Result<PathBuf, String> {
+    let image = frame_pixels(ctx)?;
+    let path = timestamped(Path::new(SCREENSHOTS_DIR), "screenshot", "png")?;
+    image.save(&path).map_err(|err| err.to_string())?;
+    Ok(path)
+}
+
+/// Recent frames, captured at a low rate and size to keep memory bounded
+pub struct ClipRecorder {
+    frames: VecDeque<RgbaImage>,
+    /// Seconds since the last captured frame
+    timer: f32,
+}
+
+impl ClipRecorder {
+    pub fn new() -> Self {
+        Self {
+            frames: VecDeque::new(),
+            timer: 1.0 / CLIP_FPS as f32,
+        }
+    }
+
+    /// Capture the last frame drawn, if it is time for another
+    pub fn capture(&mut self, ctx: &Context, dt: f32) -> Result<(), String> {
+        self.timer += dt;
+        if self.timer < 1.0 / CLIP_FPS as f32 {
+            return Ok(());
+        }
+        self.timer = 0.0;
+
+        let mut frame = frame_pixels(ctx)?;
+        if frame.width() > CLIP_WIDTH {
+            let height = frame.height() * CLIP_WIDTH / frame.width();
+            frame = imageops::resize(&frame, CLIP_WIDTH, height.max(1), FilterType::Triangle);
+        }
+        if self.frames.len() == (CLIP_FPS * CLIP_SECONDS) as usize {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(frame);
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    /// Encode to a new GIF file on another thread, which returns its path
+    pub fn save(self) -> JoinHandle<Result<PathBuf, String>> {
+        thread::spawn(move || {
+            let path = timestamped(Path::new(CLIPS_DIR), "clip", "gif")?;
+            let file = File::create(&path).map_err(|err| err.to_string())?;
+            let mut encoder = GifEncoder::new(BufWriter::new(file));
+            encoder
+                .set_repeat(Repeat::Infinite)
+                .map_err(|err| err.to_string())?;
+            let delay = Delay::from_numer_denom_ms(1000, CLIP_FPS);
+            let frames = self
+                .frames
+                .into_iter()
+                .map(|image| Frame::from_parts(image, 0, 0, delay));
+            encoder
+                .encode_frames(frames)
+                .map_err(|err| err.to_string())?;
+            Ok(path)
+        })
+    }
+}
+
+/// Path for a new file in a directory, named after the current time
+pub fn timestamped(dir: &Path, prefix: &str, extension: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|err| err.to_string())?
+        .as_millis();
+    Ok(dir.join(format!("{}-{}.{}", prefix, millis, extension)))
+}
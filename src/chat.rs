@@ -0,0 +1,265 @@
+//! Twitch chat commands, read anonymously over IRC so no account is needed.
+//! Viewers can type:
+//!
+//! - `!ball [color] [radius]` to launch a ball
+//! - `!boom` to kick every ball
+//! - `!gravity value`
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpStream;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use rand::Rng;
+
+use crate::control::{Command, Request};
+
+const SERVER: &str = "irc.chat.twitch.tv:6667";
+/// Wait before reconnecting after losing the connection
+const RECONNECT_DELAY: Duration = Duration::from_secs(10);
+/// Least time between commands from anyone, so a busy chat can't flood the app
+const GLOBAL_COOLDOWN: Duration = Duration::from_millis(200);
+const MAX_RADIUS: f32 = 60.0;
+const MIN_RADIUS: f32 = 5.0;
+const LAUNCH_SPEED: f32 = 15.0;
+const BOOM_IMPULSE: f32 = 10.0;
+const MAX_GRAVITY: f32 = 3.0;
+
+/// Who may use chat commands, and how often
+#[derive(Clone, Debug)]
+pub struct ChatOptions {
+    /// Channel to join, without the `#`
+    pub channel: String,
+    /// Only these users may use commands, or anyone if empty
+    pub allowlist: Vec<String>,
+    /// Least time between commands from one user
+    pub cooldown: Duration,
+}
+
+/// Join a channel in the background, sending viewers' commands to the app
+pub fn connect(options: ChatOptions, sender: Sender<Request>) {
+    thread::spawn(move || {
+        let mut limiter = RateLimiter::new(&options);
+        loop {
+            match read_chat(&options, &mut limiter, &sender) {
+                Ok(()) => return,
+                Err(err) => log::warn!("Chat connection lost: {}", err),
+            }
+            thread::sleep(RECONNECT_DELAY);
+        }
+    });
+}
+
+/// Read messages until the app goes away, or fail if the connection does
+fn read_chat(
+    options: &ChatOptions,
+    limiter: &mut RateLimiter,
+    sender: &Sender<Request>,
+) -> Result<(), String> {
+    let mut stream = TcpStream::connect(SERVER).map_err(|err| err.to_string())?;
+    // Anonymous users can read chat but never send to it
+    let nick = format!("justinfan{}", rand::thread_rng().gen_range(10000..100000));
+    write!(
+        stream,
+        "NICK {}\r\nJOIN #{}\r\n",
+        nick,
+        options.channel.to_lowercase()
+    )
+    .map_err(|err| err.to_string())?;
+
+    let (reply, _) = mpsc::channel();
+    let reader = BufReader::new(stream.try_clone().map_err(|err| err.to_string())?);
+    for line in reader.lines() {
+        let line = line.map_err(|err| err.to_string())?;
+        if let Some(server) = line.strip_prefix("PING ") {
+            write!(stream, "PONG {}\r\n", server).map_err(|err| err.to_string())?;
+            continue;
+        }
+        let Some((user, message)) = parse_privmsg(&line) else {
+            continue;
+        };
+        let Some(command) = command(message) else {
+            continue;
+        };
+        if !limiter.allow(user) {
+            continue;
+        }
+        let request = Request {
+            command,
+            reply: reply.clone(),
+        };
+        if sender.send(request).is_err() {
+            return Ok(());
+        }
+    }
+    Err("connection closed".to_string())
+}
+
+/// Sender and text of a chat message, such as
+/// `:name!name@name.tmi.twitch.tv PRIVMSG #channel :hello`
+fn parse_privmsg(line: &str) -> Option<(&str, &str)> {
+    let rest = line.strip_prefix(':')?;
+    let (prefix, rest) = rest.split_once(' ')?;
+    let user = prefix.split('!').next()?;
+    let rest = rest.strip_prefix("PRIVMSG ")?;
+    let (_, message) = rest.split_once(" :")?;
+    Some((user, message))
+}
+
+fn command(message: &str) -> Option<Command> {
+    let mut words = message.split_whitespace();
+    match words.next()? {
+        "!ball" => {
+            let mut color = None;
+            let mut radius = 20.0;
+            for word in words {
+                match word.parse::<f32>() {
+                    Ok(value) if value.is_finite() => radius = value,
+                    _ => color = color_hex(word).or(color),
+                }
+            }
+            Some(Command::Launch {
+                position: rand::thread_rng().gen(),
+                radius: radius.clamp(MIN_RADIUS, MAX_RADIUS),
+                speed: LAUNCH_SPEED,
+                color,
+            })
+        }
+        "!boom" => Some(Command::Kick {
+            impulse: BOOM_IMPULSE,
+        }),
+        "!gravity" => {
+            let value = words.next()?.parse::<f32>().ok()?;
+            value.is_finite().then(|| Command::Gravity {
+                value: value.clamp(-MAX_GRAVITY, MAX_GRAVITY),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Hex code of a color name, or of a hex code itself
+fn color_hex(word: &str) -> Option<String> {
+    let hex = match word.to_lowercase().as_str() {
+        "red" => "#e63946",
+        "orange" => "#f4a261",
+        "yellow" => "#ffd60a",
+        "green" => "#52b788",
+        "cyan" => "#48cae4",
+        "blue" => "#457b9d",
+        "purple" => "#9d4edd",
+        "pink" => "#ff70a6",
+        "white" => "#ffffff",
+        hex if hex.starts_with('#') && hex.len() == 7 => return Some(hex.to_string()),
+        _ => return None,
+    };
+    Some(hex.to_string())
+}
+
+/// Limits how often each user, and chat as a whole, can send commands
+struct RateLimiter {
+    allowlist: Vec<String>,
+    cooldown: Duration,
+    last_by_user: HashMap<String, Instant>,
+    last: Option<Instant>,
+}
+
+impl RateLimiter {
+    fn new(options: &ChatOptions) -> Self {
+        Self {
+            allowlist: options
+                .allowlist
+                .iter()
+                .map(|user| user.to_lowercase())
+                .collect(),
+            cooldown: options.cooldown,
+            last_by_user: HashMap::new(),
+            last: None,
+        }
+    }
+
+    /// Whether a user may send a command now, counting it if so
+    fn allow(&mut self, user: &str) -> bool {
+        let user = user.to_lowercase();
+        if !self.allowlist.is_empty() && !self.allowlist.contains(&user) {
+            return false;
+        }
+        let now = Instant::now();
+        if self.last.is_some_and(|last| now - last < GLOBAL_COOLDOWN) {
+            return false;
+        }
+        if self
+            .last_by_user
+            .get(&user)
+            .is_some_and(|&last| now - last < self.cooldown)
+        {
+            return false;
+        }
+        self.last = Some(now);
+        self.last_by_user.insert(user, now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_chat_messages_are_read() {
+        assert_eq!(
+            parse_privmsg(":viewer!viewer@viewer.tmi.twitch.tv PRIVMSG #channel :!ball red 30"),
+            Some(("viewer", "!ball red 30"))
+        );
+        assert_eq!(parse_privmsg("PING :tmi.twitch.tv"), None);
+        assert_eq!(
+            parse_privmsg(":tmi.twitch.tv 001 justinfan :Welcome, GLHF!"),
+            None
+        );
+    }
+
+    #[test]
+    fn ball_takes_a_color_and_radius_in_any_order() {
+        for message in ["!ball red 30", "!ball 30 red", "!ball 30 #E63946"] {
+            assert!(
+                matches!(
+                    command(message),
+                    Some(Command::Launch { radius, color: Some(color), .. })
+                        if radius == 30.0 && color.eq_ignore_ascii_case("#e63946")
+                ),
+                "`{}` wasn't a red ball of radius 30",
+                message
+            );
+        }
+        assert!(matches!(
+            command("!ball 1e9 nan mauve"),
+            Some(Command::Launch { radius, color: None, .. }) if radius == MAX_RADIUS
+        ));
+    }
+
+    #[test]
+    fn gravity_must_be_a_finite_number() {
+        assert!(matches!(
+            command("!gravity -100"),
+            Some(Command::Gravity { value }) if value == -MAX_GRAVITY
+        ));
+        assert!(command("!gravity inf").is_none());
+        assert!(command("!gravity down").is_none());
+        assert!(command("!gravity").is_none());
+        assert!(command("hello !boom").is_none());
+    }
+
+    #[test]
+    fn only_allowed_users_get_through_and_not_twice_in_a_row() {
+        let mut limiter = RateLimiter::new(&ChatOptions {
+            channel: "channel".to_string(),
+            allowlist: vec!["Mod".to_string()],
+            cooldown: Duration::from_secs(60),
+        });
+        assert!(!limiter.allow("viewer"));
+        assert!(limiter.allow("mod"));
+        assert!(!limiter.allow("MOD"));
+    }
+}
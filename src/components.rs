@@ -0,0 +1,136 @@
+use ggez::mint::Point2;
+use serde::{Deserialize, Serialize};
+use slotmap::SecondaryMap;
+
+use crate::ball::BallId;
+
+/// Ticks over which a ball shrinks and fades out before it expires
+const FADE_TICKS: u32 = 30;
+
+/// Storage for one kind of optional per-ball data
+pub type Component<T> = SecondaryMap<BallId, T>;
+
+/// Per-ball data kept outside of `Ball`, so each ball only carries what applies to it
+#[derive(Clone, Default)]
+pub struct Components {
+    /// Ticks each resting ball has been asleep for
+    pub asleep: Component<u32>,
+    /// Where each ball last came to a stop, even if it still jitters in place
+    pub settled: Component<Settled>,
+    /// Balls thrown by the player and still moving, so hits can be credited to them
+    pub thrown: Component<()>,
+    /// How long balls that expire have left
+    pub lifetime: Component<Lifetime>,
+    /// How fast balls that change size grow or shrink
+    pub growth: Component<Growth>,
+    /// Heat of balls warmed by collisions, from 0 to 1
+    pub temperature: Component<f32>,
+    /// Hit points of breakable balls, which pop when they run out
+    pub health: Component<Health>,
+    /// How far patterned balls have turned, which only shows in how they are drawn
+    pub spin: Component<Spin>,
+}
+
+/// Hit points lost to hard impacts, with the ball popping at zero
+#[derive(Clone, Copy)]
+pub struct Health {
+    pub hp: f32,
+    pub max: f32,
+}
+
+impl Health {
+    pub fn new(max: f32) -> Self {
+        Self { hp: max, max }
+    }
+
+    /// Fraction of the hit points that have been lost, from 0 to 1
+    pub fn damage(&self) -> f32 {
+        (1.0 - self.hp / self.max).clamp(0.0, 1.0)
+    }
+}
+
+/// Angle a ball has turned to, and how fast it turns, in radians per tick
+#[derive(Clone, Copy, Default)]
+pub struct Spin {
+    pub angle: f32,
+    pub velocity: f32,
+}
+
+/// Point a ball has stayed close to, and for how many ticks
+#[derive(Clone, Copy)]
+pub struct Settled {
+    pub anchor: Point2<f32>,
+    pub ticks: u32,
+}
+
+/// Steady change in radius, until the ball bursts or vanishes at a limit
+#[derive(Clone, Copy, Serialize, Deserialize)]
+pub struct Growth {
+    /// Pixels of radius gained each tick, negative to shrink
+    pub rate: f32,
+    /// Radius at which a growing ball bursts, or a shrinking one is removed
+    pub limit: f32,
+}
+
+impl Growth {
+    /// Whether a ball of a radius has reached the limit
+    pub fn is_done(&self, radius: f32) -> bool {
+        if self.rate >= 0.0 {
+            radius >= self.limit
+        } else {
+            radius <= self.limit
+        }
+    }
+}
+
+/// Ticks a ball has lived, and how many it lives for before being removed
+#[derive(Clone, Copy)]
+pub struct Lifetime {
+    pub age: u32,
+    pub ticks: u32,
+}
+
+impl Lifetime {
+    pub fn new(ticks: u32) -> Self {
+        Self { age: 0, ticks }
+    }
+
+    /// Size and opacity to draw at, from 1 until the ball starts fading out to 0
+    pub fn fade(&self) -> f32 {
+        let left = self.ticks.saturating_sub(self.age);
+        (left as f32 / FADE_TICKS.min(self.ticks).max(1) as f32).min(1.0)
+    }
+}
+
+impl Components {
+    /// Drop everything attached to a removed ball
+    pub fn remove(&mut self, id: BallId) {
+        self.asleep.remove(id);
+        self.settled.remove(id);
+        self.lifetime.remove(id);
+        self.thrown.remove(id);
+        self.growth.remove(id);
+        self.temperature.remove(id);
+        self.health.remove(id);
+        self.spin.remove(id);
+    }
+
+    /// Become a copy of other components, reusing this one's buffers
+    pub fn copy_from(&mut self, source: &Self) {
+        copy_component(&mut self.asleep, &source.asleep);
+        copy_component(&mut self.settled, &source.settled);
+        copy_component(&mut self.thrown, &source.thrown);
+        copy_component(&mut self.lifetime, &source.lifetime);
+        copy_component(&mut self.growth, &source.growth);
+        copy_component(&mut self.temperature, &source.temperature);
+        copy_component(&mut self.health, &source.health);
+        copy_component(&mut self.spin, &source.spin);
+    }
+}
+
+/// Replace a component's data with another's, which cloning a secondary map won't
+/// do in place
+fn copy_component<T: Clone>(component: &mut Component<T>, source: &Component<T>) {
+    component.clear();
+    component.extend(source.iter().map(|(id, value)| (id, value.clone())));
+}
@@ -0,0 +1,471 @@
+use std::path::PathBuf;
+
+use ggez::conf::{WindowMode, WindowSetup};
+use ggez::graphics::Color;
+use ggez::winit::event::VirtualKeyCode;
+use ggez::Context;
+use rand::Rng;
+
+use crate::app::App;
+use crate::control::Requests;
+use crate::forces::{Force, ForceGenerator};
+use crate::keys::{Action, Keybindings};
+#[cfg(feature = "lan")]
+use crate::lan::Session;
+use crate::scene::{self, Scene};
+use crate::settings::WindowGeometry;
+use crate::simulation::{Cap, CapPolicy};
+use crate::units::Units;
+
+/// Lowest frame rate cap, below which frames would need more ticks than a step runs
+pub const MIN_FPS_CAP: u32 = 15;
+
+/// Options for creating an `App`
+pub struct AppConfig {
+    /// Random balls to start with, skipping the start screen if there are any
+    pub balls: usize,
+    /// Smallest and largest radius of random balls
+    pub radius: (f32, f32),
+    pub window_size: (f32, f32),
+    pub fullscreen: bool,
+    /// Open the window with the size, place and fullscreen state it was last
+    /// closed with, cleared when the size, fullscreen or monitor is set
+    pub restore_window: bool,
+    /// Monitor to open the window on, by number from 1 or part of its name
+    pub monitor: Option<String>,
+    /// Cover the whole monitor with a window without a frame, instead of
+    /// switching it to fullscreen
+    pub borderless: bool,
+    /// Spacing of the grid that placement snaps to while Ctrl is held
+    pub grid_size: f32,
+    /// Keep showing the last frame instead of drawing the same one again while
+    /// nothing moves
+    pub skip_static_frames: bool,
+    /// Wait for the display before showing each frame, so frames never tear
+    pub vsync: bool,
+    /// Most frames per second, or as many as vsync allows if unset
+    pub fps_cap: Option<u32>,
+    /// Draw less detail while frames take too long, and restore it once they don't
+    pub adaptive_quality: bool,
+    /// Leave out effects that move on their own, and slow the fastest balls, even
+    /// if turned off in the settings menu
+    pub reduced_motion: bool,
+    /// Size of text and menus relative to the default, for large screens and projectors
+    pub ui_scale: f32,
+    pub title: String,
+    /// PNG image for the window icon, instead of a drawn ball
+    pub icon: Option<PathBuf>,
+    /// Colors random balls are picked from, or any color if empty
+    pub palette: Vec<Color>,
+    /// Seed for everything random, or a new one each run if unset
+    pub seed: Option<u64>,
+    /// Gravity of the random starting scene, in pixels per tick squared
+    pub gravity: f32,
+    /// Scale of real-world units, for gravity presets and readouts in meters
+    pub units: Units,
+    /// Forces applied to every scene, replacing the defaults if set
+    pub forces: Option<Vec<Force>>,
+    /// Scene file to open at start, instead of random balls
+    pub scene: Option<PathBuf>,
+    /// Restitution of random balls, instead of the ball default
+    pub restitution: Option<f32>,
+    pub keys: Keybindings,
+    /// Apply changes to config files while running
+    pub watch_config: bool,
+    /// File to record input to, for replaying later
+    pub record: Option<PathBuf>,
+    /// Recording to play back, instead of taking input
+    pub replay: Option<PathBuf>,
+    /// CSV file to log ball trajectories to from the start
+    pub log_csv: Option<PathBuf>,
+    /// Ticks between rows of a trajectory log
+    pub log_every: u32,
+    /// Resolve collisions on every core, except while recording or replaying
+    pub parallel: bool,
+    /// Run physics on another thread while each frame is drawn, a frame behind,
+    /// except while recording, replaying or logging trajectories
+    pub pipeline: bool,
+    /// Seconds of the past kept to rewind through, or none to turn rewinding off
+    pub rewind_seconds: f32,
+    /// Run a stress test from the start, printing the result and quitting once it ends
+    pub stress: bool,
+    /// Most balls at once, or no limit if unset
+    pub max_balls: Option<usize>,
+    /// What to do when adding a ball would go over `max_balls`
+    pub cap_policy: CapPolicy,
+    /// Ticks balls from spawners last before fading away, or forever if unset
+    pub lifetime: Option<u32>,
+    /// Commands from outside the app, such as a remote client
+    pub requests: Option<Requests>,
+    /// Sandbox shared with other instances, hosted here or joined
+    #[cfg(feature = "lan")]
+    pub lan: Option<Session>,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            balls: 0,
+            radius: (10.0, 50.0),
+            window_size: (800.0, 600.0),
+            fullscreen: false,
+            restore_window: true,
+            monitor: None,
+            borderless: false,
+            grid_size: 20.0,
+            skip_static_frames: true,
+            vsync: true,
+            fps_cap: None,
+            adaptive_quality: true,
+            reduced_motion: false,
+            ui_scale: 1.0,
+            title: "Balls".to_string(),
+            icon: None,
+            palette: Vec::new(),
+            seed: None,
+            gravity: 0.5,
+            units: Units::default(),
+            forces: None,
+            scene: None,
+            restitution: None,
+            keys: Keybindings::default(),
+            watch_config: false,
+            record: None,
+            replay: None,
+            log_csv: None,
+            log_every: 1,
+            parallel: false,
+            pipeline: false,
+            rewind_seconds: 10.0,
+            stress: false,
+            max_balls: None,
+            cap_policy: CapPolicy::default(),
+            lifetime: None,
+            requests: None,
+            #[cfg(feature = "lan")]
+            lan: None,
+        }
+    }
+}
+
+impl AppConfig {
+    /// Seed to use, picking a new one if unset
+    pub(crate) fn seed_or_random(&self) -> u64 {
+        self.seed.unwrap_or_else(rand::random)
+    }
+
+    /// Size and fullscreen state to open the window with, before any is restored
+    pub(crate) fn window_geometry(&self) -> WindowGeometry {
+        let (width, height) = self.window_size;
+        WindowGeometry {
+            width,
+            height,
+            position: None,
+            monitor: None,
+            fullscreen: self.fullscreen,
+            borderless: self.borderless,
+        }
+    }
+
+    /// Ball limit to apply to simulations
+    pub(crate) fn cap(&self) -> Option<Cap> {
+        self.max_balls.map(|max| Cap {
+            max,
+            policy: self.cap_policy,
+        })
+    }
+
+    /// Scene of random balls, following the size range, palette and physics options
+    pub(crate) fn random_scene(
+        &self,
+        rng: &mut impl Rng,
+        count: usize,
+        width: f32,
+        height: f32,
+    ) -> Scene {
+        let mut scene = scene::random(rng, count, self.radius, &self.palette, width, height);
+        scene.gravity = self.gravity;
+        if let Some(restitution) = self.restitution {
+            for ball in &mut scene.balls {
+                ball.restitution = restitution;
+            }
+        }
+        scene
+    }
+}
+
+/// Builder for an `App`, for embedding in other programs
+#[derive(Default)]
+pub struct AppBuilder {
+    config: AppConfig,
+}
+
+impl From<AppConfig> for AppBuilder {
+    fn from(config: AppConfig) -> Self {
+        Self { config }
+    }
+}
+
+impl AppBuilder {
+    pub fn balls(mut self, count: usize) -> Self {
+        self.config.balls = count;
+        self
+    }
+
+    pub fn size_range(mut self, min_radius: f32, max_radius: f32) -> Self {
+        self.config.radius = (min_radius, max_radius.max(min_radius));
+        self
+    }
+
+    pub fn window_size(mut self, width: f32, height: f32) -> Self {
+        self.config.window_size = (width, height);
+        self.config.restore_window = false;
+        self
+    }
+
+    pub fn fullscreen(mut self, fullscreen: bool) -> Self {
+        self.config.fullscreen = fullscreen;
+        self.config.restore_window = false;
+        self
+    }
+
+    /// Monitor to open the window on, by number from 1 or part of its name
+    pub fn monitor(mut self, monitor: impl Into<String>) -> Self {
+        self.config.monitor = Some(monitor.into());
+        self.config.restore_window = false;
+        self
+    }
+
+    /// Whether to cover the monitor with a window without a frame, for kiosks
+    /// and projectors
+    pub fn borderless(mut self, borderless: bool) -> Self {
+        self.config.borderless = borderless;
+        self.config.restore_window = false;
+        self
+    }
+
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.config.title = title.into();
+        self
+    }
+
+    /// PNG image to show as the window icon, instead of a drawn ball
+    pub fn icon(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.icon = Some(path.into());
+        self
+    }
+
+    /// Whether to open the window where it was last closed, if its size and
+    /// fullscreen weren't set
+    pub fn restore_window(mut self, restore: bool) -> Self {
+        self.config.restore_window = restore;
+        self
+    }
+
+    /// Spacing of the grid that placement snaps to while Ctrl is held
+    pub fn grid_size(mut self, size: f32) -> Self {
+        self.config.grid_size = size;
+        self
+    }
+
+    /// Whether to skip drawing frames that would look the same as the last
+    pub fn skip_static_frames(mut self, skip: bool) -> Self {
+        self.config.skip_static_frames = skip;
+        self
+    }
+
+    /// Wait for the display before showing each frame
+    pub fn vsync(mut self, vsync: bool) -> Self {
+        self.config.vsync = vsync;
+        self
+    }
+
+    /// Limit the frame rate, saving power on high refresh rate screens. Capped
+    /// at no fewer than `MIN_FPS_CAP`, so physics keeps up
+    pub fn fps_cap(mut self, fps: u32) -> Self {
+        self.config.fps_cap = Some(fps.max(MIN_FPS_CAP));
+        self
+    }
+
+    /// Whether to draw less detail while frames take too long
+    pub fn adaptive_quality(mut self, adaptive: bool) -> Self {
+        self.config.adaptive_quality = adaptive;
+        self
+    }
+
+    /// Whether to leave out effects that move on their own, for motion-sensitive players
+    pub fn reduced_motion(mut self, reduced: bool) -> Self {
+        self.config.reduced_motion = reduced;
+        self
+    }
+
+    /// Size of text and menus relative to the default
+    pub fn ui_scale(mut self, scale: f32) -> Self {
+        self.config.ui_scale = scale;
+        self
+    }
+
+    pub fn palette(mut self, colors: impl IntoIterator<Item = Color>) -> Self {
+        self.config.palette = colors.into_iter().collect();
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.config.seed = Some(seed);
+        self
+    }
+
+    pub fn gravity(mut self, gravity: f32) -> Self {
+        self.config.gravity = gravity;
+        self
+    }
+
+    /// Set gravity in meters per second squared, at the scale set so far
+    pub fn gravity_ms2(mut self, meters_per_second_squared: f32) -> Self {
+        self.config.gravity = self.config.units.gravity(meters_per_second_squared);
+        self
+    }
+
+    /// Pixels per meter, for converting real-world units
+    pub fn scale(mut self, pixels_per_meter: f32) -> Self {
+        self.config.units = Units::new(pixels_per_meter);
+        self
+    }
+
+    /// Add a force, on top of the defaults unless others were added already
+    pub fn force(mut self, generator: impl ForceGenerator + 'static) -> Self {
+        self.config
+            .forces
+            .get_or_insert_with(Force::defaults)
+            .push(Force::new(generator, true));
+        self
+    }
+
+    pub fn scene(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.scene = Some(path.into());
+        self
+    }
+
+    pub fn restitution(mut self, restitution: f32) -> Self {
+        self.config.restitution = Some(restitution);
+        self
+    }
+
+    /// Replace the keys bound to an action
+    pub fn bind(mut self, action: Action, keys: &[VirtualKeyCode]) -> Self {
+        self.config.keys.bind(action, keys);
+        self
+    }
+
+    /// Reload config files when they change
+    pub fn watch_config(mut self, watch: bool) -> Self {
+        self.config.watch_config = watch;
+        self
+    }
+
+    /// Record input to a file on exit
+    pub fn record(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.record = Some(path.into());
+        self
+    }
+
+    /// Play back a recording, starting the way it did
+    pub fn replay(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.replay = Some(path.into());
+        self
+    }
+
+    /// Log ball trajectories to a CSV file from the start
+    pub fn log_csv(mut self, path: impl Into<PathBuf>) -> Self {
+        self.config.log_csv = Some(path.into());
+        self
+    }
+
+    /// Log trajectories every few ticks, instead of every tick
+    pub fn log_every(mut self, ticks: u32) -> Self {
+        self.config.log_every = ticks.max(1);
+        self
+    }
+
+    /// Resolve collisions on every core, for large scenes
+    pub fn parallel(mut self, parallel: bool) -> Self {
+        self.config.parallel = parallel;
+        self
+    }
+
+    /// Run physics alongside drawing, hiding its cost in large scenes
+    pub fn pipeline(mut self, pipeline: bool) -> Self {
+        self.config.pipeline = pipeline;
+        self
+    }
+
+    /// Seconds of the past kept to rewind through, or 0 to turn rewinding off
+    pub fn rewind_seconds(mut self, seconds: f32) -> Self {
+        self.config.rewind_seconds = seconds.max(0.0);
+        self
+    }
+
+    /// Add balls from the start until the frame rate drops, then print the most
+    /// that kept up and quit
+    pub fn stress(mut self, stress: bool) -> Self {
+        self.config.stress = stress;
+        self
+    }
+
+    /// Limit the number of balls, so spawning can't slow the app to a crawl
+    pub fn max_balls(mut self, max: usize) -> Self {
+        self.config.max_balls = Some(max);
+        self
+    }
+
+    /// What to do when adding a ball would go over the limit
+    pub fn cap_policy(mut self, policy: CapPolicy) -> Self {
+        self.config.cap_policy = policy;
+        self
+    }
+
+    /// Remove balls from spawners after a number of ticks, so they can't fill the scene
+    pub fn lifetime(mut self, ticks: u32) -> Self {
+        self.config.lifetime = Some(ticks);
+        self
+    }
+
+    /// Take commands from another thread, answering each on its reply channel
+    pub fn control(mut self, requests: Requests) -> Self {
+        self.config.requests = Some(requests);
+        self
+    }
+
+    /// Share the sandbox with other instances
+    #[cfg(feature = "lan")]
+    pub fn lan(mut self, session: Session) -> Self {
+        self.config.lan = Some(session);
+        self
+    }
+
+    /// Use exactly these forces instead of the defaults
+    pub fn forces(mut self, forces: Vec<Force>) -> Self {
+        self.config.forces = Some(forces);
+        self
+    }
+
+    /// Window mode to create the context with, matching the window size, and
+    /// hidden until the app has moved it to where it was last closed
+    pub fn window_mode(&self) -> WindowMode {
+        self.config.window_geometry().window_mode().visible(false)
+    }
+
+    /// Window setup to create the context with
+    pub fn window_setup(&self) -> WindowSetup {
+        WindowSetup::default().vsync(self.config.vsync)
+    }
+
+    /// Options set so far, to run without a window
+    pub fn into_config(self) -> AppConfig {
+        self.config
+    }
+
+    pub fn build(self, ctx: &mut Context) -> App {
+        App::with_config(ctx, self.config)
+    }
+}
@@ -0,0 +1,482 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use directories::ProjectDirs;
+use ggez::graphics::Color;
+use ggez::mint::Vector2;
+use ggez::winit::event::VirtualKeyCode;
+use serde::Deserialize;
+use toml::Value;
+
+use crate::config::{AppConfig, MIN_FPS_CAP};
+use crate::forces::{Attractors, Drag, Force, Gravity, Magnetism, Wind};
+use crate::keys::Action;
+use crate::simulation::CapPolicy;
+use crate::text;
+use crate::units::Units;
+
+const FILE_NAME: &str = "config.toml";
+/// Config in the working directory, which overrides the user config
+const LOCAL_FILE: &str = "balls.toml";
+
+/// Seconds between checks for changed config files
+const WATCH_INTERVAL: f32 = 0.5;
+
+/// Written to the user config directory on first run
+const DEFAULT_CONFIG: &str = r##"# Balls configuration
+#
+# Uncomment a value to change it. A `balls.toml` in the working directory
+# overrides this file, and command-line flags override both.
+
+[window]
+# The window opens with the size, place and fullscreen state it was last closed
+# with, unless these are set
+# width = 800
+# height = 600
+# fullscreen = false
+# Monitor to open on, by number from 1 or part of its name
+# monitor = "1"
+# Cover the whole monitor with a window without a frame, for kiosks and projectors
+# borderless = false
+# restore = true
+# Title of the window
+# title = "Balls"
+# PNG image for the window icon, instead of a drawn ball
+# icon = "icon.png"
+# Spacing of the grid that placement snaps to while Ctrl is held
+# grid_size = 20.0
+# Skip drawing frames while nothing moves, to save power
+# skip_static_frames = true
+# Wait for the display before showing each frame
+# vsync = true
+# Most frames per second, to save power on high refresh rate screens
+# fps_cap = 60
+# Draw less detail while frames take too long
+# adaptive_quality = true
+# Leave out particles and rising popups, and slow the fastest balls
+# reduced_motion = false
+# Size of text and menus, such as 2.0 for a 4K screen or projector
+# ui_scale = 1.0
+
+[balls]
+# Random balls to start with, instead of the start screen
+# count = 20
+# min_radius = 10.0
+# max_radius = 50.0
+# Hex colors to pick from, instead of any color
+# palette = ["#e63946", "#f1faee", "#a8dadc", "#457b9d"]
+# Fraction of speed kept when bouncing off a wall
+# restitution = 0.5
+# seed = 1234
+# Most balls at once, and what to do when spawning more:
+# "reject", "remove-oldest" or "remove-smallest"
+# max = 2000
+# cap_policy = "reject"
+# Ticks balls from spawners last before fading away, at 60 ticks per second
+# lifetime = 600
+
+[physics]
+# Downwards acceleration, in pixels per tick squared
+# gravity = 0.5
+# Pixels per meter, and gravity in meters per second squared instead of the above
+# scale = 100.0
+# gravity_ms2 = 9.81
+# Forces which are off unless set
+# drag = 0.01
+# wind = 0.08
+# magnetism = 1.0
+# Resolve collisions on every core, for large scenes
+# parallel = false
+# Run physics on another thread while each frame is drawn
+# pipeline = false
+# Seconds of the past kept to rewind through while Backspace is held, or 0 for none
+# rewind_seconds = 10.0
+
+[keys]
+# Keys for an action, replacing its default keys
+# pause = ["Escape", "P"]
+# spawn_ball = ["Space"]
+"##;
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct FileConfig {
+    window: WindowSection,
+    balls: BallsSection,
+    physics: PhysicsSection,
+    keys: HashMap<Action, Vec<VirtualKeyCode>>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct WindowSection {
+    width: Option<f32>,
+    height: Option<f32>,
+    fullscreen: Option<bool>,
+    monitor: Option<String>,
+    borderless: Option<bool>,
+    restore: Option<bool>,
+    title: Option<String>,
+    icon: Option<PathBuf>,
+    grid_size: Option<f32>,
+    skip_static_frames: Option<bool>,
+    vsync: Option<bool>,
+    fps_cap: Option<u32>,
+    adaptive_quality: Option<bool>,
+    reduced_motion: Option<bool>,
+    ui_scale: Option<f32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct BallsSection {
+    count: Option<usize>,
+    min_radius: Option<f32>,
+    max_radius: Option<f32>,
+    palette: Option<Vec<String>>,
+    restitution: Option<f32>,
+    seed: Option<u64>,
+    max: Option<usize>,
+    cap_policy: Option<CapPolicy>,
+    lifetime: Option<u32>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct PhysicsSection {
+    gravity: Option<f32>,
+    scale: Option<f32>,
+    gravity_ms2: Option<f32>,
+    drag: Option<f32>,
+    wind: Option<f32>,
+    magnetism: Option<f32>,
+    parallel: Option<bool>,
+    pipeline: Option<bool>,
+    rewind_seconds: Option<f32>,
+}
+
+/// User config file, then the local one, in order of priority
+pub fn paths() -> Vec<PathBuf> {
+    let mut paths = Vec::new();
+    if let Some(dirs) = ProjectDirs::from("", "darcy", "balls") {
+        paths.push(dirs.config_dir().join(FILE_NAME));
+    }
+    paths.push(PathBuf::from(LOCAL_FILE));
+    paths
+}
+
+/// Write the commented default config, if there is no user config yet
+pub fn write_default() -> Result<(), String> {
+    let Some(path) = paths().into_iter().next() else {
+        return Ok(());
+    };
+    if path.exists() {
+        return Ok(());
+    }
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    fs::write(&path, DEFAULT_CONFIG).map_err(|err| err.to_string())
+}
+
+/// Apply every config file that exists, with later files overriding earlier ones
+pub fn load(config: &mut AppConfig) -> Result<(), String> {
+    let mut merged = Value::Table(Default::default());
+    for path in paths() {
+        if path.exists() {
+            merge(&mut merged, read(&path)?);
+        }
+    }
+    let file: FileConfig = merged.try_into().map_err(|err| err.to_string())?;
+    file.apply(config)
+}
+
+/// Notices when config files are created, changed or deleted
+pub struct ConfigWatcher {
+    modified: Vec<Option<SystemTime>>,
+    timer: f32,
+}
+
+impl ConfigWatcher {
+    pub fn new() -> Self {
+        Self {
+            modified: modified_times(),
+            timer: 0.0,
+        }
+    }
+
+    /// Whether any config file changed since the last check
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.timer += dt;
+        if self.timer < WATCH_INTERVAL {
+            return false;
+        }
+        self.timer = 0.0;
+
+        let modified = modified_times();
+        if modified == self.modified {
+            return false;
+        }
+        self.modified = modified;
+        true
+    }
+}
+
+fn modified_times() -> Vec<Option<SystemTime>> {
+    paths()
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|meta| meta.modified()).ok())
+        .collect()
+}
+
+fn read(path: &Path) -> Result<Value, String> {
+    let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    toml::from_str(&text).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+/// Recursively overlay one table onto another
+fn merge(base: &mut Value, overlay: Value) {
+    match (base, overlay) {
+        (Value::Table(base), Value::Table(overlay)) => {
+            for (key, value) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge(existing, value),
+                    None => {
+                        base.insert(key, value);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+impl FileConfig {
+    fn apply(self, config: &mut AppConfig) -> Result<(), String> {
+        let (width, height) = config.window_size;
+        config.window_size = (
+            self.window.width.unwrap_or(width),
+            self.window.height.unwrap_or(height),
+        );
+        if self.window.width.is_some() || self.window.height.is_some() {
+            config.restore_window = false;
+        }
+        if let Some(fullscreen) = self.window.fullscreen {
+            config.fullscreen = fullscreen;
+            config.restore_window = false;
+        }
+        if let Some(monitor) = self.window.monitor {
+            config.monitor = Some(monitor);
+            config.restore_window = false;
+        }
+        if let Some(borderless) = self.window.borderless {
+            config.borderless = borderless;
+            config.restore_window = false;
+        }
+        if let Some(restore) = self.window.restore {
+            config.restore_window = config.restore_window && restore;
+        }
+        if let Some(title) = self.window.title {
+            config.title = title;
+        }
+        if let Some(icon) = self.window.icon {
+            config.icon = Some(icon);
+        }
+        if let Some(size) = self.window.grid_size {
+            if size <= 0.0 {
+                return Err(format!("Grid size must be above 0, not {}", size));
+            }
+            config.grid_size = size;
+        }
+        if let Some(skip) = self.window.skip_static_frames {
+            config.skip_static_frames = skip;
+        }
+        config.vsync = self.window.vsync.unwrap_or(config.vsync);
+        if let Some(fps) = self.window.fps_cap {
+            if fps < MIN_FPS_CAP {
+                return Err(format!(
+                    "FPS cap must be at least {}, not {}",
+                    MIN_FPS_CAP, fps
+                ));
+            }
+            config.fps_cap = Some(fps);
+        }
+        if let Some(adaptive) = self.window.adaptive_quality {
+            config.adaptive_quality = adaptive;
+        }
+        if let Some(reduced) = self.window.reduced_motion {
+            config.reduced_motion = reduced;
+        }
+        if let Some(scale) = self.window.ui_scale {
+            if !(text::MIN_SCALE..=text::MAX_SCALE).contains(&scale) {
+                return Err(format!(
+                    "UI scale must be from {} to {}, not {}",
+                    text::MIN_SCALE,
+                    text::MAX_SCALE,
+                    scale
+                ));
+            }
+            config.ui_scale = scale;
+        }
+
+        let balls = self.balls;
+        config.balls = balls.count.unwrap_or(config.balls);
+        let (min_radius, max_radius) = (
+            balls.min_radius.unwrap_or(config.radius.0),
+            balls.max_radius.unwrap_or(config.radius.1),
+        );
+        for radius in [min_radius, max_radius] {
+            if !radius.is_finite() || radius <= 0.0 {
+                return Err(format!("Radius must be above 0, not {}", radius));
+            }
+        }
+        if min_radius > max_radius {
+            return Err(format!(
+                "Min radius {} is above max radius {}",
+                min_radius, max_radius
+            ));
+        }
+        config.radius = (min_radius, max_radius);
+        if let Some(palette) = balls.palette {
+            config.palette = palette
+                .iter()
+                .map(|hex| parse_color(hex))
+                .collect::<Result<_, _>>()?;
+        }
+        config.restitution = balls.restitution.or(config.restitution);
+        config.seed = balls.seed.or(config.seed);
+        config.max_balls = balls.max.or(config.max_balls);
+        config.cap_policy = balls.cap_policy.unwrap_or(config.cap_policy);
+        config.lifetime = balls.lifetime.or(config.lifetime);
+
+        let physics = self.physics;
+        if let Some(scale) = physics.scale {
+            if scale <= 0.0 {
+                return Err(format!("Scale must be above 0, not {}", scale));
+            }
+            config.units = Units::new(scale);
+        }
+        config.gravity = physics.gravity.unwrap_or(config.gravity);
+        if let Some(gravity) = physics.gravity_ms2 {
+            config.gravity = config.units.gravity(gravity);
+        }
+        config.parallel = physics.parallel.unwrap_or(config.parallel);
+        config.pipeline = physics.pipeline.unwrap_or(config.pipeline);
+        if let Some(seconds) = physics.rewind_seconds {
+            if seconds < 0.0 {
+                return Err(format!("Rewind seconds can't be negative, not {}", seconds));
+            }
+            config.rewind_seconds = seconds;
+        }
+        if physics.drag.is_some() || physics.wind.is_some() || physics.magnetism.is_some() {
+            config.forces = Some(vec![
+                Force::new(Gravity, true),
+                Force::new(Attractors, true),
+                Force::new(
+                    physics
+                        .drag
+                        .map_or_else(Drag::default, |amount| Drag { amount }),
+                    physics.drag.is_some(),
+                ),
+                Force::new(
+                    physics.wind.map_or_else(Wind::default, |x| Wind {
+                        acceleration: Vector2 { x, y: 0.0 },
+                    }),
+                    physics.wind.is_some(),
+                ),
+                Force::new(
+                    physics
+                        .magnetism
+                        .map_or_else(Magnetism::default, |strength| Magnetism { strength }),
+                    physics.magnetism.is_some(),
+                ),
+            ]);
+        }
+
+        for (action, keys) in self.keys {
+            config.keys.bind(action, &keys);
+        }
+        Ok(())
+    }
+}
+
+/// Parse a color such as `#ff8800`
+pub(crate) fn parse_color(hex: &str) -> Result<Color, String> {
+    let digits = hex.trim_start_matches('#');
+    match u32::from_str_radix(digits, 16) {
+        Ok(value) if digits.len() == 6 => Ok(color!(
+            (value >> 16) & 0xFF,
+            (value >> 8) & 0xFF,
+            value & 0xFF
+        )),
+        _ => Err(format!("Invalid color `{}`, expected #rrggbb", hex)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(text: &str) -> Result<AppConfig, String> {
+        let mut config = AppConfig::default();
+        let file: FileConfig = toml::from_str(text).map_err(|err| err.to_string())?;
+        file.apply(&mut config)?;
+        Ok(config)
+    }
+
+    #[test]
+    fn colors_are_six_hex_digits() {
+        assert_eq!(parse_color("#ff8800").unwrap(), color!(255, 136, 0));
+        assert_eq!(parse_color("0a0b0c").unwrap(), color!(10, 11, 12));
+        for hex in ["#fff", "#ff88001", "#gg0000", "", "#"] {
+            assert!(parse_color(hex).is_err(), "{}", hex);
+        }
+    }
+
+    #[test]
+    fn local_file_overrides_nested_values() {
+        let mut base: Value = toml::from_str("[balls]\ncount = 5\nmin_radius = 2.0").unwrap();
+        let overlay: Value =
+            toml::from_str("[balls]\ncount = 9\n[physics]\ngravity = 1.0").unwrap();
+        merge(&mut base, overlay);
+        assert_eq!(base["balls"]["count"].as_integer(), Some(9));
+        assert_eq!(base["balls"]["min_radius"].as_float(), Some(2.0));
+        assert_eq!(base["physics"]["gravity"].as_float(), Some(1.0));
+    }
+
+    #[test]
+    fn valid_values_are_applied() {
+        let config = apply(
+            "[balls]\ncount = 3\nmin_radius = 4.0\nmax_radius = 8.0\npalette = [\"#010203\"]\n\
+             [physics]\ngravity = 0.25\nrewind_seconds = 2.0",
+        )
+        .unwrap();
+        assert_eq!(config.balls, 3);
+        assert_eq!(config.radius, (4.0, 8.0));
+        assert_eq!(config.palette, vec![color!(1, 2, 3)]);
+        assert_eq!(config.gravity, 0.25);
+        assert_eq!(config.rewind_seconds, 2.0);
+    }
+
+    #[test]
+    fn invalid_values_are_rejected() {
+        for text in [
+            "[balls]\nmin_radius = 0.0",
+            "[balls]\nmax_radius = -1.0",
+            "[balls]\nmax_radius = nan",
+            "[balls]\nmin_radius = inf",
+            "[balls]\nmin_radius = 9.0\nmax_radius = 3.0",
+            "[balls]\npalette = [\"red\"]",
+            "[window]\ngrid_size = 0.0",
+            "[window]\nfps_cap = 1",
+            "[window]\nui_scale = 100.0",
+            "[physics]\nscale = 0.0",
+            "[physics]\nrewind_seconds = -1.0",
+            "[balls]\nunknown = 1",
+        ] {
+            assert!(apply(text).is_err(), "{}", text);
+        }
+    }
+}
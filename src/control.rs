@@ -0,0 +1,351 @@
+use std::fmt;
+use std::sync::mpsc::{Receiver, Sender};
+
+use serde::{Deserialize, Serialize};
+use slotmap::{Key, KeyData};
+
+use crate::ball::{Ball, BallId};
+use crate::events::{Event, Wall};
+use crate::settings::Channel;
+use crate::walls::WallMaterial;
+
+/// Radius of spawned balls, unless given
+pub const DEFAULT_RADIUS: f32 = 20.0;
+
+/// Command from outside the app, such as a remote client
+#[derive(Clone, Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case")]
+pub enum Command {
+    /// Add a ball, of a random color unless one is given as `#rrggbb`
+    Spawn {
+        x: f32,
+        y: f32,
+        #[serde(default = "default_radius")]
+        radius: f32,
+        #[serde(default)]
+        vx: f32,
+        #[serde(default)]
+        vy: f32,
+        #[serde(default)]
+        color: Option<String>,
+    },
+    Delete {
+        id: u64,
+    },
+    Gravity {
+        value: f32,
+    },
+    /// Launch a ball up from the floor, a fraction of the way across
+    Launch {
+        position: f32,
+        #[serde(default = "default_radius")]
+        radius: f32,
+        speed: f32,
+        #[serde(default)]
+        color: Option<String>,
+    },
+    /// Launch every loose ball upwards
+    Kick {
+        impulse: f32,
+    },
+    /// Save the scene to the scenes directory, by name or under the current scene's name
+    Save {
+        #[serde(default)]
+        name: Option<String>,
+    },
+    /// Save a screenshot once the next frame is drawn
+    Screenshot,
+    /// Every ball and the gravity
+    State,
+    /// Everything the settings menu shows
+    Settings,
+    /// Switch a force on or off, by its index in `settings`
+    Force {
+        index: usize,
+        enabled: bool,
+    },
+    /// Change how bouncy and rough a side of the box is, keeping whatever isn't given
+    Wall {
+        wall: Wall,
+        #[serde(default)]
+        restitution: Option<f32>,
+        #[serde(default)]
+        friction: Option<f32>,
+    },
+    /// Set a channel's volume, from 0 to 1
+    Volume {
+        channel: Channel,
+        value: f32,
+    },
+    ReducedMotion {
+        value: bool,
+    },
+    /// Size of text and menus, from 0.5 to 3
+    UiScale {
+        value: f32,
+    },
+    /// Receive collisions as they happen, until the client goes away
+    Subscribe,
+    /// Receive the state after every frame, until the client goes away
+    Watch,
+}
+
+fn default_radius() -> f32 {
+    DEFAULT_RADIUS
+}
+
+impl Command {
+    /// Whether every number given is finite, as a `NaN` would spread through the scene
+    pub fn is_finite(&self) -> bool {
+        match *self {
+            Command::Spawn {
+                x,
+                y,
+                radius,
+                vx,
+                vy,
+                ..
+            } => [x, y, radius, vx, vy].iter().all(|n| n.is_finite()),
+            Command::Launch {
+                position,
+                radius,
+                speed,
+                ..
+            } => [position, radius, speed].iter().all(|n| n.is_finite()),
+            Command::Gravity { value }
+            | Command::Volume { value, .. }
+            | Command::UiScale { value } => value.is_finite(),
+            Command::Kick { impulse } => impulse.is_finite(),
+            Command::Wall {
+                restitution,
+                friction,
+                ..
+            } => [restitution, friction]
+                .iter()
+                .flatten()
+                .all(|n| n.is_finite()),
+            Command::Delete { .. }
+            | Command::Save { .. }
+            | Command::Screenshot
+            | Command::State
+            | Command::Settings
+            | Command::Force { .. }
+            | Command::ReducedMotion { .. }
+            | Command::Subscribe
+            | Command::Watch => true,
+        }
+    }
+}
+
+/// Answer to a command, or a collision sent to subscribers
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Reply {
+    Ok,
+    Spawned {
+        id: u64,
+    },
+    Saved {
+        path: String,
+    },
+    State {
+        gravity: f32,
+        /// Size of the scene, which balls are kept within
+        width: f32,
+        height: f32,
+        balls: Vec<BallState>,
+    },
+    Settings {
+        gravity: f32,
+        forces: Vec<ForceState>,
+        walls: Vec<WallState>,
+        volumes: Vec<VolumeState>,
+        reduced_motion: bool,
+        ui_scale: f32,
+    },
+    Collision {
+        a: u64,
+        /// Other ball, unless it hit a wall
+        b: Option<u64>,
+        wall: Option<String>,
+        speed: f32,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// Ball as reported to clients
+#[derive(Clone, Debug, Serialize)]
+pub struct BallState {
+    pub id: u64,
+    pub x: f32,
+    pub y: f32,
+    pub vx: f32,
+    pub vy: f32,
+    pub radius: f32,
+    pub pinned: bool,
+    /// As `#rrggbb`
+    pub color: String,
+}
+
+impl BallState {
+    pub fn new(id: BallId, ball: &Ball) -> Self {
+        Self {
+            id: id_to_u64(id),
+            x: ball.point.x,
+            y: ball.point.y,
+            vx: ball.velocity.x,
+            vy: ball.velocity.y,
+            radius: ball.radius,
+            pinned: ball.pinned,
+            color: {
+                let (r, g, b) = ball.color.to_rgb();
+                format!("#{:02x}{:02x}{:02x}", r, g, b)
+            },
+        }
+    }
+}
+
+/// Force as reported to clients, in the order it applies
+#[derive(Clone, Debug, Serialize)]
+pub struct ForceState {
+    pub name: String,
+    pub enabled: bool,
+}
+
+/// Material of a side of the box as reported to clients
+#[derive(Clone, Debug, Serialize)]
+pub struct WallState {
+    pub wall: Wall,
+    pub name: String,
+    #[serde(flatten)]
+    pub material: WallMaterial,
+}
+
+/// Volume of a channel as reported to clients
+#[derive(Clone, Debug, Serialize)]
+pub struct VolumeState {
+    pub channel: Channel,
+    pub name: String,
+    pub value: f32,
+}
+
+/// Command with somewhere to send its answer
+pub struct Request {
+    pub command: Command,
+    pub reply: Sender<Reply>,
+}
+
+/// Requests waiting for the app to handle them
+pub type Requests = Receiver<Request>;
+
+impl Reply {
+    pub fn error(message: impl Into<String>) -> Self {
+        Reply::Error {
+            message: message.into(),
+        }
+    }
+
+    /// Collision to tell subscribers about, if the event is one
+    pub fn collision(event: &Event) -> Option<Self> {
+        match *event {
+            Event::BallHitBall { a, b, speed } => Some(Reply::Collision {
+                a: id_to_u64(a),
+                b: Some(id_to_u64(b)),
+                wall: None,
+                speed,
+            }),
+            Event::BallHitWall { ball, wall, speed } => Some(Reply::Collision {
+                a: id_to_u64(ball),
+                b: None,
+                wall: Some(
+                    match wall {
+                        Wall::Left => "left",
+                        Wall::Right => "right",
+                        Wall::Floor => "floor",
+                        Wall::Ceiling => "ceiling",
+                    }
+                    .to_string(),
+                ),
+                speed,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Reply {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Reply::Ok => write!(f, "ok"),
+            Reply::Spawned { id } => write!(f, "spawned {}", id),
+            Reply::Saved { path } => write!(f, "saved {}", path),
+            Reply::State { gravity, balls, .. } => {
+                write!(f, "gravity {}, {} balls", gravity, balls.len())?;
+                for ball in balls {
+                    write!(
+                        f,
+                        "\n{} at ({:.1}, {:.1}) moving ({:.2}, {:.2}) radius {:.1}{}",
+                        ball.id,
+                        ball.x,
+                        ball.y,
+                        ball.vx,
+                        ball.vy,
+                        ball.radius,
+                        if ball.pinned { " pinned" } else { "" }
+                    )?;
+                }
+                Ok(())
+            }
+            Reply::Settings {
+                gravity,
+                forces,
+                walls,
+                volumes,
+                reduced_motion,
+                ui_scale,
+            } => {
+                write!(
+                    f,
+                    "gravity {}, reduced motion {}, UI scale {:.0}%",
+                    gravity,
+                    if *reduced_motion { "on" } else { "off" },
+                    ui_scale * 100.0
+                )?;
+                for (index, force) in forces.iter().enumerate() {
+                    let state = if force.enabled { "on" } else { "off" };
+                    write!(f, "\nforce {} {}: {}", index, force.name, state)?;
+                }
+                for state in walls {
+                    write!(
+                        f,
+                        "\n{}: restitution {}, friction {}",
+                        state.name, state.material.restitution, state.material.friction
+                    )?;
+                }
+                for volume in volumes {
+                    write!(f, "\n{}: {:.0}%", volume.name, volume.value * 100.0)?;
+                }
+                Ok(())
+            }
+            Reply::Collision { a, b, wall, speed } => match (b, wall) {
+                (Some(b), _) => write!(f, "collision {} with {} at {:.2}", a, b, speed),
+                (None, Some(wall)) => {
+                    write!(f, "collision {} with {} wall at {:.2}", a, wall, speed)
+                }
+                (None, None) => write!(f, "collision {} at {:.2}", a, speed),
+            },
+            Reply::Error { message } => write!(f, "error: {}", message),
+        }
+    }
+}
+
+/// Number that identifies a ball to clients
+pub fn id_to_u64(id: BallId) -> u64 {
+    id.data().as_ffi()
+}
+
+pub fn id_from_u64(id: u64) -> BallId {
+    KeyData::from_ffi(id).into()
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::Path;
+
+use ggez::event::{EventHandler, MouseButton};
+use ggez::input::keyboard::KeyInput;
+use ggez::{Context, GameResult};
+use serde::{Deserialize, Serialize};
+
+use crate::app::App;
+use crate::scene::Scene;
+
+/// File in the user data directory that the scene is saved to on a crash
+pub const CRASH_FILE: &str = "crash.ron";
+
+/// Everything needed to pick up where a crashed session left off
+#[derive(Serialize, Deserialize)]
+pub struct Crash {
+    pub seed: u64,
+    pub scene: Scene,
+}
+
+impl Crash {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let crash: Self = ron::from_str(&file).map_err(|err| err.to_string())?;
+        crash.scene.check()?;
+        Ok(crash)
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        }
+        let file =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|err| err.to_string())?;
+        fs::write(path, file).map_err(|err| err.to_string())
+    }
+}
+
+/// Runs an app, saving its scene to the crash file if it panics
+pub struct CrashGuard {
+    app: App,
+}
+
+impl CrashGuard {
+    pub fn new(app: App) -> Self {
+        Self { app }
+    }
+
+    fn guard<T>(&mut self, f: impl FnOnce(&mut App) -> T) -> T {
+        let payload = match panic::catch_unwind(AssertUnwindSafe(|| f(&mut self.app))) {
+            Ok(result) => return result,
+            Err(payload) => payload,
+        };
+        // The app may be too broken to save, which must not hide the first panic
+        match panic::catch_unwind(AssertUnwindSafe(|| self.app.save_crash())) {
+            Ok(Ok(path)) => eprintln!("Scene saved to {} before crashing", path.display()),
+            Ok(Err(err)) => eprintln!("Failed to save scene before crashing: {}", err),
+            Err(_) => eprintln!("Failed to save scene before crashing"),
+        }
+        panic::resume_unwind(payload)
+    }
+}
+
+impl EventHandler for CrashGuard {
+    fn update(&mut self, ctx: &mut Context) -> GameResult {
+        self.guard(|app| app.update(ctx))
+    }
+
+    fn draw(&mut self, ctx: &mut Context) -> GameResult {
+        self.guard(|app| app.draw(ctx))
+    }
+
+    fn mouse_motion_event(
+        &mut self,
+        ctx: &mut Context,
+        x: f32,
+        y: f32,
+        dx: f32,
+        dy: f32,
+    ) -> GameResult {
+        self.guard(|app| app.mouse_motion_event(ctx, x, y, dx, dy))
+    }
+
+    fn mouse_button_down_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.guard(|app| app.mouse_button_down_event(ctx, button, x, y))
+    }
+
+    fn mouse_button_up_event(
+        &mut self,
+        ctx: &mut Context,
+        button: MouseButton,
+        x: f32,
+        y: f32,
+    ) -> GameResult {
+        self.guard(|app| app.mouse_button_up_event(ctx, button, x, y))
+    }
+
+    fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> GameResult {
+        self.guard(|app| app.key_down_event(ctx, input, repeated))
+    }
+
+    fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> GameResult {
+        self.guard(|app| app.key_up_event(ctx, input))
+    }
+
+    fn resize_event(&mut self, ctx: &mut Context, width: f32, height: f32) -> GameResult {
+        self.guard(|app| app.resize_event(ctx, width, height))
+    }
+
+    fn quit_event(&mut self, ctx: &mut Context) -> GameResult<bool> {
+        self.guard(|app| app.quit_event(ctx))
+    }
+}
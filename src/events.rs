@@ -0,0 +1,71 @@
+use ggez::graphics::Color;
+use ggez::mint::Point2;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::BallId;
+
+/// Side of the box a ball can bounce off
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Wall {
+    Left,
+    Right,
+    Floor,
+    /// Top of the box, which only balloons bounce off
+    Ceiling,
+}
+
+impl Wall {
+    pub const ALL: [Wall; 4] = [Wall::Left, Wall::Right, Wall::Floor, Wall::Ceiling];
+
+    /// Label in the settings
+    pub fn name(self) -> &'static str {
+        match self {
+            Wall::Left => "Left wall",
+            Wall::Right => "Right wall",
+            Wall::Floor => "Floor",
+            Wall::Ceiling => "Ceiling (balloons only)",
+        }
+    }
+}
+
+/// Something that happened in the simulation, for effects and scoring to react to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event {
+    /// Two balls touched, with their relative speed
+    BallHitBall {
+        a: BallId,
+        b: BallId,
+        speed: f32,
+    },
+    /// A ball bounced off a wall, with its speed into the wall
+    BallHitWall {
+        ball: BallId,
+        wall: Wall,
+        speed: f32,
+    },
+    /// A ball bounced off an obstacle, by its index, with its speed into it
+    BallHitObstacle {
+        ball: BallId,
+        obstacle: usize,
+        speed: f32,
+    },
+    BallSpawned(BallId),
+    BallRemoved(BallId),
+    /// A ball burst, after being removed, for effects to show
+    BallPopped {
+        point: Point2<f32>,
+        radius: f32,
+        color: Color,
+    },
+    /// A bomb went off, throwing balls away within a radius
+    Exploded {
+        point: Point2<f32>,
+        radius: f32,
+    },
+    /// A game awarded points for something at a point
+    Scored {
+        points: u32,
+        point: Point2<f32>,
+    },
+}
@@ -0,0 +1,218 @@
+use ggez::mint::Vector2;
+
+use crate::ball::{Attractor, Ball, BallKind};
+use crate::obstacle::Obstacle;
+use crate::simulation::Params;
+
+/// Fraction of speed balloons keep each tick
+const BALLOON_DRAG: f32 = 0.97;
+
+/// State of the simulation that forces may read
+pub struct Env<'a> {
+    pub params: &'a Params,
+    pub attractors: &'a [Attractor],
+    pub obstacles: &'a [Obstacle],
+}
+
+/// Something that accelerates balls every tick. Sent along with the simulation
+/// when physics runs on another thread
+pub trait ForceGenerator: Send {
+    fn name(&self) -> &str;
+
+    /// Change the velocity of loose balls for one tick
+    fn apply(&mut self, balls: &mut [Ball], env: &Env);
+
+    /// Copy to run ahead in a forked simulation, or `None` to leave it out
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        None
+    }
+}
+
+/// Force generator that can be switched on and off
+pub struct Force {
+    pub generator: Box<dyn ForceGenerator>,
+    pub enabled: bool,
+}
+
+impl Force {
+    pub fn new(generator: impl ForceGenerator + 'static, enabled: bool) -> Self {
+        Self {
+            generator: Box::new(generator),
+            enabled,
+        }
+    }
+
+    /// Copy of the force, if its generator can be copied
+    pub fn fork(&self) -> Option<Force> {
+        Some(Force {
+            generator: self.generator.fork()?,
+            enabled: self.enabled,
+        })
+    }
+
+    /// Forces every simulation starts with
+    pub fn defaults() -> Vec<Force> {
+        vec![
+            Force::new(Gravity, true),
+            Force::new(Attractors, true),
+            Force::new(Drag::default(), false),
+            Force::new(Wind::default(), false),
+            Force::new(Magnetism::default(), false),
+        ]
+    }
+}
+
+/// Constant downwards pull, for balls that are off the floor
+#[derive(Clone)]
+pub struct Gravity;
+
+impl ForceGenerator for Gravity {
+    fn name(&self) -> &str {
+        "Gravity"
+    }
+
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn apply(&mut self, balls: &mut [Ball], env: &Env) {
+        for ball in balls {
+            if ball.kind == BallKind::Balloon {
+                // Air holds balloons back, so they drift up instead of shooting off
+                ball.velocity.x *= BALLOON_DRAG;
+                ball.velocity.y *= BALLOON_DRAG;
+            }
+            if ball.point.y + ball.radius < env.params.height {
+                ball.velocity.y += env.params.gravity * ball.kind.gravity_scale();
+            }
+        }
+    }
+}
+
+/// Pull towards each attractor point, weaker with distance
+#[derive(Clone)]
+pub struct Attractors;
+
+impl ForceGenerator for Attractors {
+    fn name(&self) -> &str {
+        "Attractors"
+    }
+
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn apply(&mut self, balls: &mut [Ball], env: &Env) {
+        for ball in balls {
+            for attractor in env.attractors {
+                let dx = attractor.point.x - ball.point.x;
+                let dy = attractor.point.y - ball.point.y;
+                let dist = (dx.powi(2) + dy.powi(2)).sqrt().max(1.0);
+                ball.velocity.x += dx / dist.powi(2) * attractor.strength;
+                ball.velocity.y += dy / dist.powi(2) * attractor.strength;
+            }
+        }
+    }
+}
+
+/// Air resistance, removing a fraction of speed every tick
+#[derive(Clone)]
+pub struct Drag {
+    pub amount: f32,
+}
+
+impl Default for Drag {
+    fn default() -> Self {
+        Self { amount: 0.01 }
+    }
+}
+
+impl ForceGenerator for Drag {
+    fn name(&self) -> &str {
+        "Drag"
+    }
+
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn apply(&mut self, balls: &mut [Ball], _env: &Env) {
+        for ball in balls {
+            ball.velocity.x *= 1.0 - self.amount;
+            ball.velocity.y *= 1.0 - self.amount;
+        }
+    }
+}
+
+/// Steady push in one direction, which moves small balls more
+#[derive(Clone)]
+pub struct Wind {
+    pub acceleration: Vector2<f32>,
+}
+
+impl Default for Wind {
+    fn default() -> Self {
+        Self {
+            acceleration: Vector2 { x: 0.08, y: 0.0 },
+        }
+    }
+}
+
+impl ForceGenerator for Wind {
+    fn name(&self) -> &str {
+        "Wind"
+    }
+
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn apply(&mut self, balls: &mut [Ball], _env: &Env) {
+        for ball in balls {
+            ball.velocity.x += self.acceleration.x * ball.get_bounce_amount();
+            ball.velocity.y += self.acceleration.y * ball.get_bounce_amount();
+        }
+    }
+}
+
+/// Every ball pulls on every other, in proportion to its mass
+#[derive(Clone)]
+pub struct Magnetism {
+    pub strength: f32,
+}
+
+impl Default for Magnetism {
+    fn default() -> Self {
+        Self { strength: 1.0 }
+    }
+}
+
+impl ForceGenerator for Magnetism {
+    fn name(&self) -> &str {
+        "Magnetism"
+    }
+
+    fn fork(&self) -> Option<Box<dyn ForceGenerator>> {
+        Some(Box::new(self.clone()))
+    }
+
+    fn apply(&mut self, balls: &mut [Ball], _env: &Env) {
+        for i in 0..balls.len() {
+            for j in 0..balls.len() {
+                if i == j {
+                    continue;
+                }
+                let other = &balls[j];
+                let dx = other.point.x - balls[i].point.x;
+                let dy = other.point.y - balls[i].point.y;
+                // Stop pulling once touching, so balls don't crush together
+                let dist = (dx.powi(2) + dy.powi(2))
+                    .sqrt()
+                    .max(other.radius + balls[i].radius);
+                let pull = self.strength * other.mass() / dist.powi(3);
+                balls[i].velocity.x += dx * pull;
+                balls[i].velocity.y += dy * pull;
+            }
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::VecDeque;
+
+use ggez::graphics::{Canvas, DrawParam, Mesh, Quad, Rect};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+
+use crate::text::TextStyle;
+
+/// Number of samples kept, one per update
+const CAPACITY: usize = 600;
+const WIDTH: f32 = 300.0;
+const HEIGHT: f32 = 100.0;
+const TEXT_SIZE: f32 = 16.0;
+
+struct Sample {
+    energy: f32,
+    balls: usize,
+}
+
+/// Rolling plot of total kinetic energy and ball count
+#[derive(Default)]
+pub struct EnergyGraph {
+    samples: VecDeque<Sample>,
+}
+
+impl EnergyGraph {
+    pub fn push(&mut self, energy: f32, balls: usize) {
+        self.samples.push_back(Sample { energy, balls });
+        if self.samples.len() > CAPACITY {
+            self.samples.pop_front();
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.samples.clear();
+    }
+
+    /// Draw graph with its bottom-left corner at the given point
+    pub fn draw(
+        &self,
+        ctx: &Context,
+        canvas: &mut Canvas,
+        x: f32,
+        y: f32,
+        style: TextStyle,
+    ) -> GameResult {
+        let (width, height) = (style.px(WIDTH), style.px(HEIGHT));
+        let rect = Rect::new(x, y - height, width, height);
+        canvas.draw(
+            &Quad,
+            DrawParam::default()
+                .dest_rect(rect)
+                .color(color!(30, 30, 30, 200)),
+        );
+
+        let max_energy = self
+            .samples
+            .iter()
+            .map(|sample| sample.energy)
+            .fold(1.0, f32::max);
+        let max_balls = self
+            .samples
+            .iter()
+            .map(|sample| sample.balls)
+            .max()
+            .unwrap_or(1)
+            .max(1);
+
+        let plot = |value: f32, i: usize| Point2 {
+            x: rect.x + i as f32 / CAPACITY as f32 * width,
+            y: rect.y + height - value * height,
+        };
+
+        if self.samples.len() >= 2 {
+            let balls: Vec<_> = self
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| plot(sample.balls as f32 / max_balls as f32, i))
+                .collect();
+            let line = Mesh::new_line(ctx, &balls, 1.0, color!(80, 80, 160))?;
+            canvas.draw(&line, DrawParam::default());
+
+            let energy: Vec<_> = self
+                .samples
+                .iter()
+                .enumerate()
+                .map(|(i, sample)| plot(sample.energy / max_energy, i))
+                .collect();
+            let line = Mesh::new_line(ctx, &energy, 2.0, color!(YELLOW))?;
+            canvas.draw(&line, DrawParam::default());
+        }
+
+        let latest = self.samples.back();
+        let text = style.text(
+            format!(
+                "Energy: {:.0}  Balls: {}",
+                latest.map_or(0.0, |sample| sample.energy),
+                latest.map_or(0, |sample| sample.balls),
+            ),
+            TEXT_SIZE,
+        );
+        canvas.draw(
+            &text,
+            DrawParam::from([rect.x + style.px(4.0), rect.y + style.px(4.0)]),
+        );
+
+        Ok(())
+    }
+}
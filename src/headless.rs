@@ -0,0 +1,166 @@
+#[cfg(feature = "lan")]
+use std::thread;
+#[cfg(feature = "lan")]
+use std::time::Duration;
+use std::time::Instant;
+
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::config::AppConfig;
+#[cfg(feature = "lan")]
+use crate::lan::{Host, Notice, Session};
+use crate::scene::{self, Demo};
+use crate::simulation::{Simulation, TICK};
+use crate::stats::StepStats;
+use crate::trajectory::TrajectoryLog;
+
+/// Ball counts benchmarked when none is given
+const BENCH_COUNTS: [usize; 5] = [10, 100, 250, 500, 1000];
+
+/// Simulation of the scene file, random balls or demo to start with, and the seed used
+fn start(config: &mut AppConfig) -> Result<(Simulation, u64), String> {
+    let seed = config.seed_or_random();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let (width, height) = config.window_size;
+
+    let scene = match &config.scene {
+        Some(path) => scene::load(path)?,
+        None if config.balls > 0 => config.random_scene(&mut rng, config.balls, width, height),
+        None => Demo::Pile.build(&mut rng, width, height),
+    };
+
+    let mut sim = Simulation::from_scene(scene, width, height);
+    sim.params.parallel = config.parallel;
+    if let Some(forces) = config.forces.take() {
+        sim.forces = forces;
+    }
+    Ok((sim, seed))
+}
+
+/// Run the starting scene for a number of ticks without a window, and print a summary
+pub fn run(mut config: AppConfig, ticks: u32) -> Result<(), String> {
+    let (mut sim, seed) = start(&mut config)?;
+
+    let mut log = match &config.log_csv {
+        Some(path) => Some(TrajectoryLog::create(path, config.log_every)?),
+        None => None,
+    };
+
+    let start = Instant::now();
+    let mut total = StepStats::default();
+    for tick in 0..=ticks {
+        if tick > 0 {
+            total = total.merge(sim.tick());
+        }
+        if let Some(log) = &mut log {
+            log.record(tick as u64, &sim)?;
+        }
+    }
+    if let Some(log) = &mut log {
+        log.flush()?;
+    }
+    let elapsed = start.elapsed();
+
+    println!(
+        "Simulated {} ticks ({:.1}s) of {} balls in {:.2?}",
+        ticks,
+        ticks as f32 * TICK,
+        sim.ball_count(),
+        elapsed
+    );
+    println!("Seed: {}", seed);
+    println!("Pairs tested: {}", total.pairs_tested);
+    println!("Collisions: {}", total.collisions);
+    println!("Max penetration: {:.2}", total.max_penetration);
+    println!("Sleeping at end: {}", total.sleeping);
+    println!("Kinetic energy at end: {:.1}", sim.kinetic_energy());
+    Ok(())
+}
+
+/// Run the starting scene in real time without a window, hosting it for
+/// players to join over the LAN until stopped
+#[cfg(feature = "lan")]
+pub fn serve(mut config: AppConfig, addr: &str) -> Result<(), String> {
+    let (mut sim, seed) = start(&mut config)?;
+    sim.cap = config.cap();
+    sim.lifetime = config.lifetime;
+    let mut session = Session::Host(Host::listen(addr)?);
+    println!(
+        "Hosting {} balls on {} (seed {})",
+        sim.ball_count(),
+        addr,
+        seed
+    );
+
+    let tick = Duration::from_secs_f32(TICK);
+    let mut next = Instant::now();
+    for ticks in 1.. {
+        for notice in session.receive(&mut sim)? {
+            match notice {
+                Notice::Joined(peer) => println!("Player {} joined", peer),
+                Notice::Spectating(peer) => println!("Player {} is watching", peer),
+                Notice::Left(peer) => println!("Player {} left", peer),
+                Notice::Refused | Notice::SizeDiffers { .. } => (),
+            }
+        }
+        sim.tick();
+        sim.clear_events();
+        session.send(&mut sim, ticks);
+
+        // Keep to real time, without rushing to catch up after a slow tick
+        next += tick;
+        let now = Instant::now();
+        if next > now {
+            thread::sleep(next - now);
+        } else {
+            next = now;
+        }
+    }
+    Ok(())
+}
+
+/// Time ticks of random scenes with increasing numbers of balls, and print a table.
+/// Uses the ball count from the config if set, and the default forces
+pub fn bench(config: AppConfig, ticks: u32) -> Result<(), String> {
+    let seed = config.seed_or_random();
+    let (width, height) = config.window_size;
+    let counts = match config.balls {
+        0 => BENCH_COUNTS.to_vec(),
+        count => vec![count],
+    };
+
+    println!("Seed: {}", seed);
+    if config.parallel {
+        println!("Collisions resolved in parallel");
+    }
+    println!(
+        "{:>7} {:>7} {:>12} {:>12} {:>12} {:>12}",
+        "Balls", "Ticks", "Total", "Per tick", "Pairs/tick", "Hits/tick"
+    );
+    for count in counts {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let scene = config.random_scene(&mut rng, count, width, height);
+        let mut sim = Simulation::from_scene(scene, width, height);
+        sim.params.parallel = config.parallel;
+
+        let start = Instant::now();
+        let mut total = StepStats::default();
+        for _ in 0..ticks {
+            total = total.merge(sim.tick());
+        }
+        let elapsed = start.elapsed();
+
+        let ticks_f = ticks.max(1) as f64;
+        println!(
+            "{:>7} {:>7} {:>12} {:>12} {:>12.0} {:>12.1}",
+            count,
+            ticks,
+            format!("{:.2?}", elapsed),
+            format!("{:.2?}", elapsed / ticks.max(1)),
+            total.pairs_tested as f64 / ticks_f,
+            total.collisions as f64 / ticks_f,
+        );
+    }
+    Ok(())
+}
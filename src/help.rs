@@ -0,0 +1,58 @@
+use ggez::graphics::{Canvas, DrawParam};
+
+use crate::keys::{Action, Keybindings};
+use crate::menu;
+use crate::text::TextStyle;
+
+const TEXT_SIZE: f32 = 20.0;
+const LINE_HEIGHT: f32 = 26.0;
+const KEY_COLUMN_WIDTH: f32 = 180.0;
+
+/// Translucent overlay listing keybindings, tools, and mouse controls
+pub fn draw(
+    canvas: &mut Canvas,
+    keys: &Keybindings,
+    tools: impl IntoIterator<Item = (&'static str, &'static str)>,
+    width: f32,
+    height: f32,
+    style: TextStyle,
+) {
+    menu::draw_shade(canvas, width, height, color!(0, 0, 0, 200));
+
+    let mut rows = Vec::new();
+    rows.push(("Controls".to_string(), String::new()));
+    for action in Action::ALL {
+        let keys = keys.labels(action);
+        if keys.is_empty() {
+            continue;
+        }
+        rows.push((keys.join(" / "), action.description().to_string()));
+    }
+    rows.push((
+        "Ctrl+1-9 / 1-9".to_string(),
+        "Store the scene in a slot / go back to it".to_string(),
+    ));
+
+    rows.push((String::new(), String::new()));
+    rows.push(("Tools".to_string(), String::new()));
+    for (name, description) in tools {
+        rows.push((name.to_string(), description.to_string()));
+    }
+
+    let (x, mut y) = (style.px(60.0), style.px(60.0));
+    for (key, description) in rows {
+        let header = description.is_empty();
+        let text = style.text(key, TEXT_SIZE);
+        let color = if header {
+            color!(YELLOW)
+        } else {
+            color!(WHITE)
+        };
+        canvas.draw(&text, DrawParam::from([x, y]).color(color));
+
+        let text = style.text(description, TEXT_SIZE);
+        canvas.draw(&text, DrawParam::from([x + style.px(KEY_COLUMN_WIDTH), y]));
+
+        y += style.px(LINE_HEIGHT);
+    }
+}
@@ -0,0 +1,103 @@
+use std::collections::VecDeque;
+
+use crate::ball::Ball;
+use crate::events::Event;
+use crate::simulation::Simulation;
+
+/// Seconds of history kept to replay from
+const HISTORY: f32 = 5.0;
+/// Seconds shown in a replay
+const LENGTH: f32 = 3.0;
+/// Seconds shown after the collision a replay is centered on
+const AFTER: f32 = 1.0;
+/// Slowest impact worth replaying
+const BIG_IMPACT: f32 = 8.0;
+/// Playback speed of a replay
+pub const SPEED: f32 = 0.25;
+
+/// Rolling buffer of the last few seconds, to replay from
+#[derive(Default)]
+pub struct History {
+    /// Balls at each frame, with the seconds since recording started
+    frames: VecDeque<(f32, Vec<Ball>)>,
+    time: f32,
+    /// When the last big collision happened
+    big_hit: Option<f32>,
+}
+
+impl History {
+    /// Snapshot the balls after a frame of simulation
+    pub fn record(&mut self, dt: f32, sim: &Simulation) {
+        self.time += dt;
+        let big_hit = sim
+            .events()
+            .iter()
+            .any(|event| matches!(*event, Event::BallHitBall { speed, .. } if speed >= BIG_IMPACT));
+        if big_hit {
+            self.big_hit = Some(self.time);
+        }
+        // Reuse the oldest frame dropped, so a full buffer stops allocating
+        let mut balls = Vec::new();
+        while self
+            .frames
+            .front()
+            .is_some_and(|(time, _)| *time < self.time - HISTORY)
+        {
+            if let Some((_, old)) = self.frames.pop_front() {
+                balls = old;
+            }
+        }
+        balls.clear();
+        balls.extend(sim.balls().map(|(_, ball)| ball));
+        self.frames.push_back((self.time, balls));
+    }
+
+    pub fn clear(&mut self) {
+        self.frames.clear();
+        self.big_hit = None;
+    }
+
+    /// Replay of the last big collision, or the last few seconds if there
+    /// hasn't been one recently
+    pub fn replay(&self) -> Option<Highlight> {
+        let end = match self.big_hit {
+            Some(hit) if hit > self.time - HISTORY + LENGTH - AFTER => (hit + AFTER).min(self.time),
+            _ => self.time,
+        };
+        let start = end - LENGTH;
+        let frames: Vec<_> = self
+            .frames
+            .iter()
+            .filter(|(time, _)| (start..=end).contains(time))
+            .map(|(time, balls)| (time - start, balls.clone()))
+            .collect();
+        if frames.len() < 2 {
+            return None;
+        }
+        Some(Highlight { frames, time: 0.0 })
+    }
+}
+
+/// Slow-motion playback of recorded frames
+pub struct Highlight {
+    frames: Vec<(f32, Vec<Ball>)>,
+    /// Seconds into the recording
+    time: f32,
+}
+
+impl Highlight {
+    /// Play on by some seconds of real time, returning whether the replay has finished
+    pub fn update(&mut self, dt: f32) -> bool {
+        self.time += dt * SPEED;
+        self.frames.last().is_none_or(|(time, _)| self.time > *time)
+    }
+
+    /// Balls at the frame being shown
+    pub fn balls(&self) -> &[Ball] {
+        let i = self
+            .frames
+            .partition_point(|(time, _)| *time <= self.time)
+            .max(1);
+        &self.frames[i - 1].1
+    }
+}
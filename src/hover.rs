@@ -0,0 +1,77 @@
+use ggez::graphics::{Canvas, DrawParam, Quad, Rect};
+use ggez::mint::Vector2;
+use ggez::{Context, GameResult};
+
+use crate::ball::{Ball, BallId};
+use crate::text::TextStyle;
+
+const TEXT_SIZE: f32 = 16.0;
+const MARGIN: f32 = 6.0;
+
+/// Live readout of the motion of the ball under the cursor
+pub struct Hover {
+    pub ball: BallId,
+    /// Velocity as of the last update, to find the acceleration from
+    velocity: Vector2<f32>,
+    /// Change in velocity per tick
+    acceleration: Vector2<f32>,
+}
+
+impl Hover {
+    pub fn new(ball: BallId, velocity: Vector2<f32>) -> Self {
+        Self {
+            ball,
+            velocity,
+            acceleration: Vector2 { x: 0.0, y: 0.0 },
+        }
+    }
+
+    /// Take the acceleration from the change in velocity over some ticks
+    pub fn update(&mut self, velocity: Vector2<f32>, ticks: u32) {
+        if ticks == 0 {
+            return;
+        }
+        self.acceleration = Vector2 {
+            x: (velocity.x - self.velocity.x) / ticks as f32,
+            y: (velocity.y - self.velocity.y) / ticks as f32,
+        };
+        self.velocity = velocity;
+    }
+
+    /// Tooltip beside the ball
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        ball: &Ball,
+        style: TextStyle,
+    ) -> GameResult {
+        let text = style.text(
+            format!(
+                "Speed: {:.2}\nVelocity: ({:.2}, {:.2})\nAcceleration: ({:.3}, {:.3})",
+                ball.speed(),
+                ball.velocity.x,
+                ball.velocity.y,
+                self.acceleration.x,
+                self.acceleration.y,
+            ),
+            TEXT_SIZE,
+        );
+        let size = text.measure(ctx)?;
+        let margin = style.px(MARGIN);
+        let rect = Rect::new(
+            ball.point.x + ball.radius + margin,
+            ball.point.y - size.y / 2.0 - margin,
+            size.x + margin * 2.0,
+            size.y + margin * 2.0,
+        );
+        canvas.draw(
+            &Quad,
+            DrawParam::default()
+                .dest_rect(rect)
+                .color(color!(30, 30, 30, 220)),
+        );
+        canvas.draw(&text, DrawParam::from([rect.x + margin, rect.y + margin]));
+        Ok(())
+    }
+}
@@ -0,0 +1,156 @@
+use ggez::graphics::Canvas;
+
+use crate::ball::{self, Ball, BallId, ALL_LAYERS, LAYERS};
+use crate::panel::Panel;
+use crate::text::TextStyle;
+use crate::units::Units;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Radius,
+    Red,
+    Green,
+    Blue,
+    VelocityX,
+    VelocityY,
+    Restitution,
+    Pinned,
+    Kind,
+    Layer,
+    Mask,
+    Pattern,
+}
+
+const FIELDS: [Field; 12] = [
+    Field::Radius,
+    Field::Red,
+    Field::Green,
+    Field::Blue,
+    Field::VelocityX,
+    Field::VelocityY,
+    Field::Restitution,
+    Field::Pinned,
+    Field::Kind,
+    Field::Layer,
+    Field::Mask,
+    Field::Pattern,
+];
+
+impl Field {
+    fn label(self, ball: &Ball, units: Units) -> String {
+        match self {
+            Field::Radius => format!(
+                "Radius: {:.1} ({:.1} cm)",
+                ball.radius,
+                units.centimeters(ball.radius)
+            ),
+            Field::Red => format!("Red: {:.0}", ball.color.r * 255.0),
+            Field::Green => format!("Green: {:.0}", ball.color.g * 255.0),
+            Field::Blue => format!("Blue: {:.0}", ball.color.b * 255.0),
+            Field::VelocityX => format!("Velocity x: {:.2}", ball.velocity.x),
+            Field::VelocityY => format!("Velocity y: {:.2}", ball.velocity.y),
+            Field::Restitution => format!("Restitution: {:.2}", ball.restitution),
+            Field::Pinned => format!("Pinned: {}", if ball.pinned { "yes" } else { "no" }),
+            Field::Kind => format!("Kind: {}", ball.kind.name()),
+            Field::Layer => format!("Layer: {}", ball::layer_names(ball.layer)),
+            Field::Mask => format!("Collides with: {}", ball::layer_names(ball.mask)),
+            Field::Pattern => format!("Pattern: {}", ball.pattern.name()),
+        }
+    }
+
+    /// Nudge value up (positive) or down (negative)
+    fn adjust(self, ball: &mut Ball, direction: f32) {
+        match self {
+            Field::Radius => ball.radius = (ball.radius + direction).max(1.0),
+            Field::Red => ball.color.r = (ball.color.r + direction * 0.05).clamp(0.0, 1.0),
+            Field::Green => ball.color.g = (ball.color.g + direction * 0.05).clamp(0.0, 1.0),
+            Field::Blue => ball.color.b = (ball.color.b + direction * 0.05).clamp(0.0, 1.0),
+            Field::VelocityX => ball.velocity.x += direction * 0.5,
+            Field::VelocityY => ball.velocity.y += direction * 0.5,
+            Field::Restitution => {
+                ball.restitution = (ball.restitution + direction * 0.05).clamp(0.0, 1.5)
+            }
+            Field::Pinned => ball.pinned = !ball.pinned,
+            Field::Kind => ball.set_kind(if direction > 0.0 {
+                ball.kind.next()
+            } else {
+                ball.kind.previous()
+            }),
+            Field::Layer => {
+                // One layer at a time, wrapping round
+                let layer = ball.layer.trailing_zeros().min(LAYERS - 1) as i32;
+                let next = (layer + direction.signum() as i32).rem_euclid(LAYERS as i32);
+                ball.layer = 1 << next;
+            }
+            Field::Mask => {
+                // Everything, only its own layers, every other layer, then nothing
+                let masks = [ALL_LAYERS, ball.layer, !ball.layer, 0];
+                let i = masks.iter().position(|&mask| mask == ball.mask);
+                let next = match i {
+                    Some(i) => {
+                        (i as i32 + direction.signum() as i32).rem_euclid(masks.len() as i32)
+                    }
+                    None => 0,
+                };
+                ball.mask = masks[next as usize];
+            }
+            Field::Pattern => {
+                ball.pattern = if direction > 0.0 {
+                    ball.pattern.next()
+                } else {
+                    ball.pattern.previous()
+                }
+            }
+        }
+    }
+}
+
+/// Panel for viewing and editing a single ball
+pub struct Inspector {
+    /// Handle of the inspected ball
+    pub ball: BallId,
+    panel: Panel,
+}
+
+impl Inspector {
+    pub fn new(ball: BallId, screen_width: f32) -> Self {
+        Self {
+            ball,
+            panel: Panel::top_right("Inspector", FIELDS.len(), screen_width),
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.panel.up();
+    }
+
+    pub fn down(&mut self) {
+        self.panel.down();
+    }
+
+    /// Adjust selected field of the ball
+    pub fn adjust(&self, ball: &mut Ball, direction: f32) {
+        FIELDS[self.panel.selected()].adjust(ball, direction);
+    }
+
+    pub fn contains(&self, x: f32, y: f32, style: TextStyle) -> bool {
+        self.panel.contains(x, y, style)
+    }
+
+    /// Handle a click inside the panel, adjusting the ball if a button was hit
+    pub fn click(&mut self, ball: &mut Ball, x: f32, y: f32, style: TextStyle) {
+        if let Some((_, direction)) = self.panel.click(x, y, style) {
+            if direction != 0.0 {
+                self.adjust(ball, direction);
+            }
+        }
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, ball: &Ball, units: Units, style: TextStyle) {
+        let rows: Vec<_> = FIELDS
+            .iter()
+            .map(|field| (field.label(ball, units), true))
+            .collect();
+        self.panel.draw(canvas, &rows, ball.color, style);
+    }
+}
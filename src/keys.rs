@@ -0,0 +1,227 @@
+use ggez::winit::event::VirtualKeyCode;
+use serde::Deserialize;
+
+/// Something a key can be bound to while a scene is running
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Pause,
+    Reset,
+    SpawnBall,
+    SpawnDialog,
+    DeleteHeld,
+    ToggleTool,
+    ToggleGraph,
+    ToggleStats,
+    ToggleMusic,
+    MusicVolumeDown,
+    MusicVolumeUp,
+    ToggleAudioReactive,
+    Help,
+    SaveScene,
+    LoadScene,
+    Screenshot,
+    CleanScreenshot,
+    ToggleClip,
+    ExportSvg,
+    ToggleLog,
+    SelectMode,
+    CycleKind,
+    ToggleHeat,
+    CyclePrediction,
+    InstantReplay,
+    ToggleProfiler,
+    StressTest,
+    SelectBall,
+    TogglePinSelected,
+    ThrowSelected,
+    BallMenu,
+    UiScaleUp,
+    UiScaleDown,
+    ToggleFullscreen,
+    Earthquake,
+    Rewind,
+    ToggleArt,
+}
+
+impl Action {
+    pub const ALL: [Action; 37] = [
+        Action::Pause,
+        Action::Reset,
+        Action::SpawnBall,
+        Action::SpawnDialog,
+        Action::DeleteHeld,
+        Action::ToggleTool,
+        Action::ToggleGraph,
+        Action::ToggleStats,
+        Action::ToggleMusic,
+        Action::MusicVolumeDown,
+        Action::MusicVolumeUp,
+        Action::ToggleAudioReactive,
+        Action::Help,
+        Action::SaveScene,
+        Action::LoadScene,
+        Action::Screenshot,
+        Action::CleanScreenshot,
+        Action::ToggleClip,
+        Action::ExportSvg,
+        Action::ToggleLog,
+        Action::SelectMode,
+        Action::CycleKind,
+        Action::ToggleHeat,
+        Action::CyclePrediction,
+        Action::InstantReplay,
+        Action::ToggleProfiler,
+        Action::StressTest,
+        Action::SelectBall,
+        Action::TogglePinSelected,
+        Action::ThrowSelected,
+        Action::BallMenu,
+        Action::UiScaleUp,
+        Action::UiScaleDown,
+        Action::ToggleFullscreen,
+        Action::Earthquake,
+        Action::Rewind,
+        Action::ToggleArt,
+    ];
+
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Pause => "Pause menu",
+            Action::Reset => "Reset scene",
+            Action::SpawnBall => "Spawn random ball",
+            Action::SpawnDialog => "Spawn dialog",
+            Action::DeleteHeld => "Delete held or selected ball",
+            Action::ToggleTool => "Switch tool",
+            Action::ToggleGraph => "Toggle energy graph",
+            Action::ToggleStats => "Toggle physics statistics",
+            Action::ToggleMusic => "Play/pause music",
+            Action::MusicVolumeDown => "Music volume down",
+            Action::MusicVolumeUp => "Music volume up",
+            Action::ToggleAudioReactive => "Bounce balls to audio input",
+            Action::Help => "Toggle this help",
+            Action::SaveScene => "Save scene",
+            Action::LoadScene => "Load scene",
+            Action::Screenshot => "Screenshot",
+            Action::CleanScreenshot => "Screenshot without overlays",
+            Action::ToggleClip => "Start/stop recording a GIF clip",
+            Action::ExportSvg => "Export scene as SVG",
+            Action::ToggleLog => "Start/stop logging trajectories to CSV",
+            Action::SelectMode => "Choose a game mode",
+            Action::CycleKind => "Change the held or selected ball's kind",
+            Action::ToggleHeat => "Toggle temperature, heating balls that collide",
+            Action::CyclePrediction => "Change what the path of an aimed ball is predicted with",
+            Action::InstantReplay => "Replay the last big collision in slow motion",
+            Action::ToggleProfiler => "Toggle profiler, timing each part of the frame",
+            Action::StressTest => "Start/stop adding balls until the frame rate drops",
+            Action::SelectBall => {
+                "Select the next ball, or the previous with Shift, to nudge with the arrow keys"
+            }
+            Action::TogglePinSelected => "Pin or unpin the selected ball",
+            Action::ThrowSelected => {
+                "Hold to charge a throw of the selected ball, aiming with the arrow keys"
+            }
+            Action::BallMenu => "Menu for the selected ball",
+            Action::UiScaleUp => "Larger text and menus",
+            Action::UiScaleDown => "Smaller text and menus",
+            Action::ToggleFullscreen => "Toggle fullscreen",
+            Action::Earthquake => "Hold to shake every ball, harder the longer it is held",
+            Action::Rewind => "Hold to rewind time, carrying on from wherever it is let go",
+            Action::ToggleArt => "Start/stop a long-exposure picture of the balls, saved as a PNG",
+        }
+    }
+}
+
+/// Map of keys to actions
+#[derive(Clone)]
+pub struct Keybindings {
+    bindings: Vec<(VirtualKeyCode, Action)>,
+    /// Keys pressed while holding Ctrl
+    ctrl_bindings: Vec<(VirtualKeyCode, Action)>,
+}
+
+impl Default for Keybindings {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (VirtualKeyCode::Escape, Action::Pause),
+                (VirtualKeyCode::P, Action::Pause),
+                (VirtualKeyCode::R, Action::Reset),
+                (VirtualKeyCode::Space, Action::SpawnBall),
+                (VirtualKeyCode::N, Action::SpawnDialog),
+                (VirtualKeyCode::X, Action::DeleteHeld),
+                (VirtualKeyCode::K, Action::CycleKind),
+                (VirtualKeyCode::T, Action::ToggleHeat),
+                (VirtualKeyCode::A, Action::CyclePrediction),
+                (VirtualKeyCode::Q, Action::InstantReplay),
+                (VirtualKeyCode::F4, Action::ToggleProfiler),
+                (VirtualKeyCode::I, Action::ToggleTool),
+                (VirtualKeyCode::G, Action::ToggleGraph),
+                (VirtualKeyCode::F3, Action::ToggleStats),
+                (VirtualKeyCode::M, Action::ToggleMusic),
+                (VirtualKeyCode::LBracket, Action::MusicVolumeDown),
+                (VirtualKeyCode::RBracket, Action::MusicVolumeUp),
+                (VirtualKeyCode::B, Action::ToggleAudioReactive),
+                (VirtualKeyCode::H, Action::Help),
+                (VirtualKeyCode::F1, Action::Help),
+                (VirtualKeyCode::F12, Action::Screenshot),
+                (VirtualKeyCode::F9, Action::ToggleClip),
+                (VirtualKeyCode::F8, Action::ToggleArt),
+                (VirtualKeyCode::F2, Action::SelectMode),
+                (VirtualKeyCode::F5, Action::StressTest),
+                (VirtualKeyCode::F11, Action::ToggleFullscreen),
+                (VirtualKeyCode::Tab, Action::SelectBall),
+                (VirtualKeyCode::Return, Action::TogglePinSelected),
+                (VirtualKeyCode::F, Action::ThrowSelected),
+                (VirtualKeyCode::C, Action::BallMenu),
+                (VirtualKeyCode::E, Action::Earthquake),
+                (VirtualKeyCode::Back, Action::Rewind),
+            ],
+            ctrl_bindings: vec![
+                (VirtualKeyCode::S, Action::SaveScene),
+                (VirtualKeyCode::O, Action::LoadScene),
+                (VirtualKeyCode::F12, Action::CleanScreenshot),
+                (VirtualKeyCode::E, Action::ExportSvg),
+                (VirtualKeyCode::L, Action::ToggleLog),
+                (VirtualKeyCode::Equals, Action::UiScaleUp),
+                (VirtualKeyCode::Minus, Action::UiScaleDown),
+            ],
+        }
+    }
+}
+
+impl Keybindings {
+    pub fn action(&self, keycode: VirtualKeyCode, ctrl: bool) -> Option<Action> {
+        let bindings = if ctrl {
+            &self.ctrl_bindings
+        } else {
+            &self.bindings
+        };
+        bindings
+            .iter()
+            .find(|(key, _)| *key == keycode)
+            .map(|(_, action)| *action)
+    }
+
+    /// Replace the keys bound to an action
+    pub fn bind(&mut self, action: Action, keys: &[VirtualKeyCode]) {
+        self.bindings.retain(|(_, bound)| *bound != action);
+        self.ctrl_bindings.retain(|(_, bound)| *bound != action);
+        self.bindings.extend(keys.iter().map(|&key| (key, action)));
+    }
+
+    /// Names of all keys bound to an action, such as `Ctrl+S`
+    pub fn labels(&self, action: Action) -> Vec<String> {
+        let keys = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| format!("{:?}", key));
+        let ctrl_keys = self
+            .ctrl_bindings
+            .iter()
+            .filter(|(_, bound)| *bound == action)
+            .map(|(key, _)| format!("Ctrl+{:?}", key));
+        keys.chain(ctrl_keys).collect()
+    }
+}
@@ -0,0 +1,772 @@
+//! Shared sandbox over a LAN. One instance hosts the simulation and others
+//! join it, sending their grabs, throws and spawns to the host and showing the
+//! balls it sends back. Messages are RON, one per line, over TCP
+//!
+//! A ball can only be held by one player at a time. The first to grab it keeps
+//! it until they let go or leave, and anyone else's grab is refused
+//!
+//! Spectators join the same way but only watch, and the host ignores any input
+//! they send
+//!
+//! The host drops a guest that sends a line longer than `MAX_GUEST_LINE`, and
+//! ignores spawns and drags with numbers that aren't finite
+//!
+//! A guest's held ball follows its cursor without waiting for the host. Once let
+//! go it keeps moving on its own until a frame shows the host has the release,
+//! then eases into where the host has it instead of jumping there
+
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use ggez::mint::{Point2, Vector2};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::{Ball, BallId};
+use crate::control::{id_from_u64, id_to_u64};
+use crate::simulation::Simulation;
+
+/// Number that identifies a player to the host, which is player 0 itself
+pub type PeerId = u32;
+
+/// Fraction of the gap to the host's position of a thrown ball left after each frame
+const EASING: f32 = 0.8;
+/// Gap to the host's position, in pixels, that is closed at once
+const SNAP_DISTANCE: f32 = 0.5;
+
+/// Message from a guest to the host. Input is numbered, so frames can say
+/// which the host had taken in
+#[derive(Clone, Serialize, Deserialize)]
+pub enum GuestMessage {
+    /// Take hold of a ball, unless someone else has it
+    Grab {
+        input: u64,
+        id: u64,
+    },
+    /// Move the held ball, with the velocity to throw it at if let go
+    Drag {
+        input: u64,
+        point: Point2<f32>,
+        velocity: Vector2<f32>,
+    },
+    /// Let go of the held ball
+    Release {
+        input: u64,
+    },
+    Spawn {
+        ball: Ball,
+    },
+    /// Send every ball again, after losing track of some
+    Resync,
+    /// Only watch, ignoring any other messages from this guest
+    Spectate,
+}
+
+/// Message from the host to a guest
+#[derive(Clone, Serialize, Deserialize)]
+pub enum HostMessage {
+    /// Sent once on joining, before the first frame
+    Welcome {
+        peer: PeerId,
+        width: f32,
+        height: f32,
+    },
+    /// Changes since the last frame, or every ball in a guest's first frame
+    Frame(Delta),
+    /// A grab was refused, as someone else holds the ball
+    Refused { id: u64 },
+}
+
+/// Balls that changed since the frame before
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Delta {
+    /// Ticks the host has run, so guests can tell how old a frame is
+    pub tick: u64,
+    /// Last input from the guest it is sent to that the frame includes
+    pub input: u64,
+    /// Balls that were added or changed in any way
+    pub changed: Vec<(u64, Ball)>,
+    pub removed: Vec<u64>,
+}
+
+/// Something that happened over the connection, for the app to tell the player
+pub enum Notice {
+    Joined(PeerId),
+    /// A guest turned out to be a spectator
+    Spectating(PeerId),
+    Left(PeerId),
+    /// Another player holds the ball that was grabbed
+    Refused,
+    /// Joined a host whose window is a different size
+    SizeDiffers {
+        width: f32,
+        height: f32,
+    },
+}
+
+/// Either side of a shared sandbox
+pub enum Session {
+    Host(Host),
+    Guest(Guest),
+}
+
+impl Session {
+    /// Whether the balls come from another instance, so physics shouldn't run here
+    pub fn is_guest(&self) -> bool {
+        matches!(self, Session::Guest(_))
+    }
+
+    /// Take in messages that arrived since the last frame, applying them to the
+    /// simulation. Fails once a guest loses its host
+    pub fn receive(&mut self, sim: &mut Simulation) -> Result<Vec<Notice>, String> {
+        match self {
+            Session::Host(host) => Ok(host.receive(sim)),
+            Session::Guest(guest) => guest.receive(sim),
+        }
+    }
+
+    /// Send what changed over the frame
+    pub fn send(&mut self, sim: &mut Simulation, tick: u64) {
+        match self {
+            Session::Host(host) => host.send(sim, tick),
+            Session::Guest(guest) => guest.send(sim),
+        }
+    }
+}
+
+/// Longest line a host reads from a guest, which is plenty for any single input
+pub const MAX_GUEST_LINE: u64 = 16 * 1024;
+/// Longest line a guest reads from the host, which sends every ball at once on joining
+pub const MAX_HOST_LINE: u64 = 256 * 1024 * 1024;
+
+/// Send messages to and receive them from a stream on background threads,
+/// one RON value per line. The connection is dropped once a line runs past
+/// `max_line` bytes
+fn connect<S, R>(stream: TcpStream, max_line: u64) -> Result<(Sender<S>, Receiver<R>), String>
+where
+    S: Serialize + Send + 'static,
+    R: DeserializeOwned + Send + 'static,
+{
+    let mut writer = stream.try_clone().map_err(|err| err.to_string())?;
+    let (sender, outgoing) = mpsc::channel::<S>();
+    thread::spawn(move || {
+        for message in outgoing {
+            let Ok(line) = ron::to_string(&message) else {
+                continue;
+            };
+            if writeln!(writer, "{}", line).is_err() {
+                break;
+            }
+        }
+    });
+
+    let (incoming, receiver) = mpsc::channel();
+    thread::spawn(move || {
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        loop {
+            line.clear();
+            match reader.by_ref().take(max_line).read_line(&mut line) {
+                Ok(0) | Err(_) => break,
+                Ok(_) if !line.ends_with('\n') => {
+                    log::warn!("Dropped a LAN peer for a line over {} bytes", max_line);
+                    break;
+                }
+                Ok(_) => (),
+            }
+            match ron::from_str(&line) {
+                Ok(message) => {
+                    if incoming.send(message).is_err() {
+                        break;
+                    }
+                }
+                Err(err) => log::warn!("Invalid LAN message: {}", err),
+            }
+        }
+    });
+    Ok((sender, receiver))
+}
+
+/// Whether a point and velocity sent by a guest can be put into the simulation
+fn finite(point: Point2<f32>, velocity: Vector2<f32>) -> bool {
+    [point.x, point.y, velocity.x, velocity.y]
+        .iter()
+        .all(|n| n.is_finite())
+}
+
+/// Event from a guest's connection
+enum PeerEvent {
+    Joined(Sender<HostMessage>),
+    Message(GuestMessage),
+    Left,
+}
+
+/// Player connected to the host
+struct Peer {
+    sender: Sender<HostMessage>,
+    /// Whether the peer has been sent every ball yet
+    welcomed: bool,
+    /// Whether the peer only watches
+    spectator: bool,
+    /// Last input taken in from the peer
+    input: u64,
+    /// Input last sent back in a frame
+    acknowledged: u64,
+}
+
+/// Ball held by a guest
+struct Hold {
+    peer: PeerId,
+    /// Whether the ball was pinned before it was grabbed, to restore on release
+    pinned: bool,
+}
+
+/// Runs the shared simulation, taking input from guests and sending them what changes
+pub struct Host {
+    events: Receiver<(PeerId, PeerEvent)>,
+    peers: HashMap<PeerId, Peer>,
+    /// Balls as of the last frame sent, which the next frame is the changes from
+    sent: HashMap<u64, Ball>,
+    /// Balls held by guests, which are pinned so physics leaves them where they are dragged
+    holds: HashMap<BallId, Hold>,
+    /// Smallest and largest radius of balls guests can spawn
+    radius: (f32, f32),
+}
+
+impl Host {
+    /// Listen for guests in the background
+    pub fn listen(addr: &str) -> Result<Self, String> {
+        let listener = TcpListener::bind(addr).map_err(|err| format!("{}: {}", addr, err))?;
+        let (events, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (peer, stream) in (1..).zip(listener.incoming().flatten()) {
+                let _ = stream.set_nodelay(true);
+                let (sender, messages) =
+                    match connect::<HostMessage, GuestMessage>(stream, MAX_GUEST_LINE) {
+                        Ok(channels) => channels,
+                        Err(err) => {
+                            log::warn!("Failed to accept guest: {}", err);
+                            continue;
+                        }
+                    };
+                if events.send((peer, PeerEvent::Joined(sender))).is_err() {
+                    return;
+                }
+                let events = events.clone();
+                thread::spawn(move || {
+                    for message in messages {
+                        if events.send((peer, PeerEvent::Message(message))).is_err() {
+                            return;
+                        }
+                    }
+                    let _ = events.send((peer, PeerEvent::Left));
+                });
+            }
+        });
+        Ok(Self {
+            events: receiver,
+            peers: HashMap::new(),
+            sent: HashMap::new(),
+            holds: HashMap::new(),
+            radius: (1.0, f32::MAX),
+        })
+    }
+
+    /// Clamp the radius of balls guests spawn to between `min` and `max`
+    pub fn limit_radius(&mut self, min: f32, max: f32) {
+        self.radius = (min, max.max(min));
+    }
+
+    fn receive(&mut self, sim: &mut Simulation) -> Vec<Notice> {
+        let mut notices = Vec::new();
+        loop {
+            let (peer, event) = match self.events.try_recv() {
+                Ok(event) => event,
+                Err(TryRecvError::Empty | TryRecvError::Disconnected) => break,
+            };
+            match event {
+                PeerEvent::Joined(sender) => {
+                    log::info!("Player {} joined", peer);
+                    let welcome = HostMessage::Welcome {
+                        peer,
+                        width: sim.params.width,
+                        height: sim.params.height,
+                    };
+                    if sender.send(welcome).is_ok() {
+                        let peer_state = Peer {
+                            sender,
+                            welcomed: false,
+                            spectator: false,
+                            input: 0,
+                            acknowledged: 0,
+                        };
+                        self.peers.insert(peer, peer_state);
+                        notices.push(Notice::Joined(peer));
+                    }
+                }
+                PeerEvent::Message(message) => notices.extend(self.handle(sim, peer, message)),
+                PeerEvent::Left => {
+                    log::info!("Player {} left", peer);
+                    self.let_go(sim, peer);
+                    self.peers.remove(&peer);
+                    notices.push(Notice::Left(peer));
+                }
+            }
+        }
+
+        // The first to grab a ball keeps it, including over the host's own player
+        if sim.held.is_some_and(|id| self.holds.contains_key(&id)) {
+            sim.held = None;
+            notices.push(Notice::Refused);
+        }
+        notices
+    }
+
+    fn handle(
+        &mut self,
+        sim: &mut Simulation,
+        peer: PeerId,
+        message: GuestMessage,
+    ) -> Option<Notice> {
+        let spectator = self.peers.get(&peer).is_some_and(|peer| peer.spectator);
+        if let GuestMessage::Grab { input, .. }
+        | GuestMessage::Drag { input, .. }
+        | GuestMessage::Release { input } = message
+        {
+            if let Some(peer) = self.peers.get_mut(&peer) {
+                peer.input = input;
+            }
+        }
+        match message {
+            GuestMessage::Resync => {
+                if let Some(peer) = self.peers.get_mut(&peer) {
+                    peer.welcomed = false;
+                }
+            }
+            GuestMessage::Spectate => {
+                self.let_go(sim, peer);
+                if let Some(peer) = self.peers.get_mut(&peer) {
+                    peer.spectator = true;
+                }
+                return Some(Notice::Spectating(peer));
+            }
+            _ if spectator => (),
+            GuestMessage::Grab { id, .. } => {
+                self.let_go(sim, peer);
+                let ball = id_from_u64(id);
+                let free = sim.held != Some(ball) && !self.holds.contains_key(&ball);
+                match sim.ball_mut(ball).filter(|_| free) {
+                    Some(mut grabbed) => {
+                        let pinned = grabbed.pinned;
+                        grabbed.pinned = true;
+                        self.holds.insert(ball, Hold { peer, pinned });
+                    }
+                    None => self.tell(peer, HostMessage::Refused { id }),
+                }
+            }
+            GuestMessage::Drag {
+                point, velocity, ..
+            } => {
+                if !finite(point, velocity) {
+                    log::warn!("Ignored a drag from player {} that isn't finite", peer);
+                } else if let Some(mut ball) = self.held_by(peer).and_then(|id| sim.ball_mut(id)) {
+                    ball.point = point;
+                    ball.velocity = velocity;
+                }
+            }
+            GuestMessage::Release { .. } => self.let_go(sim, peer),
+            GuestMessage::Spawn { mut ball } => {
+                if finite(ball.point, ball.velocity)
+                    && ball.radius.is_finite()
+                    && ball.restitution.is_finite()
+                {
+                    ball.radius = ball.radius.clamp(self.radius.0, self.radius.1);
+                    sim.add_ball(ball);
+                } else {
+                    log::warn!("Ignored a spawn from player {} that isn't finite", peer);
+                }
+            }
+        }
+        None
+    }
+
+    /// Ball a guest holds, if any
+    fn held_by(&self, peer: PeerId) -> Option<BallId> {
+        self.holds
+            .iter()
+            .find(|(_, hold)| hold.peer == peer)
+            .map(|(&id, _)| id)
+    }
+
+    /// Let go of the ball a guest holds, keeping the velocity it was dragged at
+    fn let_go(&mut self, sim: &mut Simulation, peer: PeerId) {
+        let Some(id) = self.held_by(peer) else {
+            return;
+        };
+        let hold = self.holds.remove(&id).expect("held ball has a hold");
+        if let Some(mut ball) = sim.ball_mut(id) {
+            ball.pinned = hold.pinned;
+        }
+    }
+
+    fn tell(&mut self, peer: PeerId, message: HostMessage) {
+        if let Some(Peer { sender, .. }) = self.peers.get(&peer) {
+            // A guest that has gone is removed once its reader notices
+            let _ = sender.send(message);
+        }
+    }
+
+    fn send(&mut self, sim: &Simulation, tick: u64) {
+        // Forget balls that were removed while held
+        self.holds.retain(|&id, _| sim.ball(id).is_some());
+        if self.peers.is_empty() {
+            self.sent.clear();
+            return;
+        }
+
+        let mut delta = Delta {
+            tick,
+            ..Delta::default()
+        };
+        let mut current = HashMap::with_capacity(self.sent.len());
+        for (id, ball) in sim.balls() {
+            let id = id_to_u64(id);
+            if self.sent.get(&id) != Some(&ball) {
+                delta.changed.push((id, ball));
+            }
+            current.insert(id, ball);
+        }
+        delta.removed = self
+            .sent
+            .keys()
+            .filter(|id| !current.contains_key(id))
+            .copied()
+            .collect();
+        self.sent = current;
+
+        let unchanged = delta.changed.is_empty() && delta.removed.is_empty();
+        for peer in self.peers.values_mut() {
+            let mut frame = if peer.welcomed {
+                if unchanged && peer.acknowledged == peer.input {
+                    continue;
+                }
+                delta.clone()
+            } else {
+                peer.welcomed = true;
+                Delta {
+                    tick,
+                    changed: self.sent.iter().map(|(&id, &ball)| (id, ball)).collect(),
+                    ..Delta::default()
+                }
+            };
+            frame.input = peer.input;
+            peer.acknowledged = peer.input;
+            let _ = peer.sender.send(HostMessage::Frame(frame));
+        }
+    }
+}
+
+/// Shows the host's balls, sending it this player's grabs, throws and spawns
+pub struct Guest {
+    sender: Sender<GuestMessage>,
+    messages: Receiver<HostMessage>,
+    /// Local handle of each of the host's balls
+    local: HashMap<u64, BallId>,
+    /// The host's number for each local ball
+    remote: HashMap<BallId, u64>,
+    /// Ball this player holds, as last told to the host
+    held: Option<BallId>,
+    /// Whether this player only watches
+    spectating: bool,
+    /// Number of the last input sent
+    input: u64,
+    /// Ball let go of, with the input that let go, moved here until a frame includes it
+    thrown: Option<(BallId, u64)>,
+    /// Thrown ball easing into the host's position, with that position and the
+    /// gap left to close
+    easing: Option<(BallId, Point2<f32>, Vector2<f32>)>,
+}
+
+/// Where a ball would be with a gap added
+fn offset(point: Point2<f32>, gap: Vector2<f32>) -> Point2<f32> {
+    Point2 {
+        x: point.x + gap.x,
+        y: point.y + gap.y,
+    }
+}
+
+impl Guest {
+    /// Connect to a host
+    pub fn join(addr: &str) -> Result<Self, String> {
+        let stream = TcpStream::connect(addr).map_err(|err| format!("{}: {}", addr, err))?;
+        let _ = stream.set_nodelay(true);
+        let (sender, messages) = connect(stream, MAX_HOST_LINE)?;
+        Ok(Self {
+            sender,
+            messages,
+            local: HashMap::new(),
+            remote: HashMap::new(),
+            held: None,
+            spectating: false,
+            input: 0,
+            thrown: None,
+            easing: None,
+        })
+    }
+
+    /// Connect to a host only to watch
+    pub fn spectate(addr: &str) -> Result<Self, String> {
+        let mut guest = Self::join(addr)?;
+        let _ = guest.sender.send(GuestMessage::Spectate);
+        guest.spectating = true;
+        Ok(guest)
+    }
+
+    fn receive(&mut self, sim: &mut Simulation) -> Result<Vec<Notice>, String> {
+        let mut notices = Vec::new();
+        loop {
+            let message = match self.messages.try_recv() {
+                Ok(message) => message,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return Err("the host left".to_string()),
+            };
+            match message {
+                HostMessage::Welcome {
+                    peer,
+                    width,
+                    height,
+                } => {
+                    log::info!("Joined as player {}", peer);
+                    if (width, height) != (sim.params.width, sim.params.height) {
+                        notices.push(Notice::SizeDiffers { width, height });
+                    }
+                }
+                HostMessage::Frame(delta) => self.apply(sim, delta),
+                HostMessage::Refused { id } => {
+                    if self.local.get(&id) == sim.held.as_ref() {
+                        sim.held = None;
+                        self.held = None;
+                        notices.push(Notice::Refused);
+                    }
+                }
+            }
+        }
+        Ok(notices)
+    }
+
+    fn apply(&mut self, sim: &mut Simulation, delta: Delta) {
+        // The host has the throw once it has the release, so its position takes over
+        let caught_up = self
+            .thrown
+            .filter(|&(_, input)| delta.input >= input)
+            .map(|(id, _)| id);
+        if caught_up.is_some() {
+            self.thrown = None;
+        }
+        for id in delta.removed {
+            if let Some(local) = self.local.remove(&id) {
+                self.remote.remove(&local);
+                sim.remove_ball(local);
+            }
+        }
+        for (id, ball) in delta.changed {
+            match self.local.get(&id) {
+                // The held ball follows the cursor here rather than lagging
+                // behind, and a thrown one carries on until the host catches up
+                Some(&local)
+                    if sim.held == Some(local)
+                        || self.thrown.is_some_and(|(id, _)| id == local) => {}
+                Some(&local) => {
+                    let Some(mut existing) = sim.ball_mut(local) else {
+                        continue;
+                    };
+                    if caught_up == Some(local) {
+                        let gap = Vector2 {
+                            x: existing.point.x - ball.point.x,
+                            y: existing.point.y - ball.point.y,
+                        };
+                        self.easing = Some((local, ball.point, gap));
+                    }
+                    *existing = ball;
+                    if let Some((_, host, gap)) = self.easing.as_mut().filter(|e| e.0 == local) {
+                        *host = ball.point;
+                        existing.point = offset(ball.point, *gap);
+                    }
+                }
+                None => {
+                    // Added without the cap, which is the host's to apply
+                    let cap = sim.cap.take();
+                    if let Some(local) = sim.add_ball(ball) {
+                        self.local.insert(id, local);
+                        self.remote.insert(local, id);
+                    }
+                    sim.cap = cap;
+                }
+            }
+        }
+    }
+
+    fn send(&mut self, sim: &mut Simulation) {
+        // Balls removed here, such as by loading another scene, are sent again
+        if self.remote.keys().any(|&id| sim.ball(id).is_none()) {
+            self.local.clear();
+            self.remote.clear();
+            let _ = self.sender.send(GuestMessage::Resync);
+        }
+
+        // Balls added here go to the host instead, and come back in a later frame.
+        // A spectator's never reach the host, so aren't shown either
+        let spawned: Vec<_> = sim
+            .balls()
+            .filter(|(id, _)| !self.remote.contains_key(id))
+            .map(|(id, _)| id)
+            .collect();
+        for id in spawned {
+            if let Some(ball) = sim.remove_ball(id) {
+                if !self.spectating {
+                    let _ = self.sender.send(GuestMessage::Spawn { ball });
+                }
+            }
+        }
+        if self.spectating {
+            sim.held = None;
+            return;
+        }
+
+        if sim.held != self.held {
+            if let Some(held) = self.held.take() {
+                self.input += 1;
+                let input = self.input;
+                let _ = self.sender.send(GuestMessage::Release { input });
+                self.thrown = Some((held, input));
+            }
+            if let Some(&id) = sim.held.and_then(|held| self.remote.get(&held)) {
+                self.input += 1;
+                let input = self.input;
+                let _ = self.sender.send(GuestMessage::Grab { input, id });
+                self.held = sim.held;
+                self.thrown = None;
+                self.easing = None;
+            }
+        }
+        if let Some(ball) = self.held.and_then(|held| sim.ball(held)) {
+            self.input += 1;
+            let _ = self.sender.send(GuestMessage::Drag {
+                input: self.input,
+                point: ball.point,
+                velocity: ball.velocity,
+            });
+        }
+        self.predict(sim);
+    }
+
+    /// Move a thrown ball the host hasn't caught up with yet, or ease one it has
+    /// towards where the host has it
+    fn predict(&mut self, sim: &mut Simulation) {
+        // Frames come about as often as ticks, so this moves about a tick's worth
+        if let Some(mut ball) = self.thrown.and_then(|(id, _)| sim.ball_mut(id)) {
+            let velocity = ball.velocity;
+            ball.point = offset(ball.point, velocity);
+        }
+        if let Some((id, host, gap)) = self.easing {
+            let gap = Vector2 {
+                x: gap.x * EASING,
+                y: gap.y * EASING,
+            };
+            let done = gap.x.hypot(gap.y) < SNAP_DISTANCE;
+            if let Some(mut ball) = sim.ball_mut(id) {
+                ball.point = if done { host } else { offset(host, gap) };
+            }
+            self.easing = (!done).then_some((id, host, gap));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Both ends of a connection over the loopback address
+    fn pair() -> (TcpStream, TcpStream) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+        let (server, _) = listener.accept().unwrap();
+        (client, server)
+    }
+
+    #[test]
+    fn messages_arrive_one_per_line() {
+        let (mut client, server) = pair();
+        let (sender, receiver) =
+            connect::<HostMessage, GuestMessage>(server, MAX_GUEST_LINE).unwrap();
+
+        let release = ron::to_string(&GuestMessage::Release { input: 3 }).unwrap();
+        let drag = ron::to_string(&GuestMessage::Drag {
+            input: 4,
+            point: Point2 { x: 1.0, y: 2.0 },
+            velocity: Vector2 { x: -3.0, y: 0.5 },
+        })
+        .unwrap();
+        write!(client, "{}\nnot ron\n{}\n", release, drag).unwrap();
+        assert!(matches!(
+            receiver.recv().unwrap(),
+            GuestMessage::Release { input: 3 }
+        ));
+        match receiver.recv().unwrap() {
+            GuestMessage::Drag {
+                input,
+                point,
+                velocity,
+            } => {
+                assert_eq!(input, 4);
+                assert_eq!((point.x, point.y), (1.0, 2.0));
+                assert_eq!((velocity.x, velocity.y), (-3.0, 0.5));
+            }
+            _ => panic!("expected a drag"),
+        }
+
+        sender.send(HostMessage::Refused { id: 7 }).unwrap();
+        let mut line = String::new();
+        BufReader::new(client).read_line(&mut line).unwrap();
+        assert!(matches!(
+            ron::from_str(&line).unwrap(),
+            HostMessage::Refused { id: 7 }
+        ));
+    }
+
+    #[test]
+    fn peers_sending_long_lines_are_dropped() {
+        let (mut client, server) = pair();
+        let (_sender, receiver) = connect::<HostMessage, GuestMessage>(server, 64).unwrap();
+        let release = ron::to_string(&GuestMessage::Release { input: 1 }).unwrap();
+        write!(client, "{}\n{}\n{}\n", release, " ".repeat(100), release).unwrap();
+        assert!(matches!(
+            receiver.recv().unwrap(),
+            GuestMessage::Release { input: 1 }
+        ));
+        assert!(receiver.recv().is_err());
+    }
+
+    #[test]
+    fn numbers_from_guests_must_be_finite() {
+        let point = Point2 { x: 1.0, y: 2.0 };
+        let velocity = Vector2 { x: 0.0, y: -1.0 };
+        assert!(finite(point, velocity));
+        for n in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert!(!finite(Point2 { x: n, ..point }, velocity));
+            assert!(!finite(point, Vector2 { y: n, ..velocity }));
+        }
+    }
+
+    #[test]
+    fn radius_limits_keep_min_below_max() {
+        let mut host = Host::listen("127.0.0.1:0").unwrap();
+        host.limit_radius(10.0, 50.0);
+        assert_eq!(host.radius, (10.0, 50.0));
+        host.limit_radius(20.0, 5.0);
+        assert_eq!(host.radius, (20.0, 20.0));
+    }
+}
@@ -33,5 +33,99 @@ macro_rules! color {
 }
 
 mod app;
+mod art;
+mod audio;
+mod ball;
+mod capture;
+#[cfg(feature = "chat")]
+mod chat;
+mod components;
+mod config;
+mod config_file;
+mod control;
+mod crash;
+mod events;
+mod forces;
+mod graph;
+mod headless;
+mod help;
+mod highlight;
+mod hover;
+mod inspector;
+mod keys;
+#[cfg(feature = "lan")]
+mod lan;
+mod logging;
+mod menu;
+#[cfg(feature = "midi")]
+mod midi;
+mod mode;
+mod obstacle;
+#[cfg(feature = "osc")]
+mod osc;
+mod panel;
+mod particles;
+mod physics;
+mod prediction;
+mod profiler;
+mod protractor;
+mod quality;
+mod repl;
+mod replay;
+mod rewind;
+mod ruler;
+mod scene;
+mod score;
+mod settings;
+mod simulation;
+mod spawn;
+mod spawner;
+mod stats;
+mod storage;
+mod stress;
+mod svg;
+#[cfg(test)]
+mod testing;
+mod text;
+mod toast;
+mod trajectory;
+mod units;
+mod walls;
+#[cfg(feature = "websocket")]
+mod websocket;
+mod zone;
 
 pub use app::App;
+pub use ball::{Attractor, Ball, BallId, BallKind, Pattern};
+#[cfg(feature = "chat")]
+pub use chat::{connect as connect_chat, ChatOptions};
+pub use components::{Component, Components, Growth, Health, Lifetime};
+pub use config::{AppBuilder, AppConfig, MIN_FPS_CAP};
+pub use config_file::{load as load_config, write_default as write_default_config};
+pub use control::{BallState, Command, Reply, Request, Requests};
+pub use crash::CrashGuard;
+pub use events::{Event, Wall};
+pub use forces::{Attractors, Drag, Env, Force, ForceGenerator, Gravity, Magnetism, Wind};
+#[cfg(feature = "lan")]
+pub use headless::serve as run_server;
+pub use headless::{bench as run_bench, run as run_headless};
+pub use keys::{Action, Keybindings};
+#[cfg(feature = "lan")]
+pub use lan::{Guest, Host, Session};
+pub use logging::init as init_logging;
+#[cfg(feature = "midi")]
+pub use midi::listen as listen_midi;
+pub use obstacle::Obstacle;
+#[cfg(feature = "osc")]
+pub use osc::serve as serve_osc;
+pub use repl::start as start_repl;
+pub use replay::{Input, Recording};
+pub use scene::Scene;
+pub use simulation::{Cap, CapPolicy, Params, Simulation, Snapshot, TICK};
+pub use spawner::Spawner;
+pub use stats::StepStats;
+pub use units::{Planet, Units};
+pub use walls::{WallMaterial, Walls};
+#[cfg(feature = "websocket")]
+pub use websocket::serve as serve_websocket;
+pub use zone::{Filter, Zone};
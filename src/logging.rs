@@ -0,0 +1,57 @@
+use std::fs::File;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use log::{LevelFilter, Log, Metadata, Record};
+
+/// Writes log records to stderr, or a file, with the time since start
+struct Logger {
+    start: Instant,
+    file: Option<Mutex<File>>,
+}
+
+impl Log for Logger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        let line = format!(
+            "{:>9.3} {:<5} {}: {}\n",
+            self.start.elapsed().as_secs_f32(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // Nowhere left to report a failure to log
+        let _ = match &self.file {
+            Some(file) => file.lock().unwrap().write_all(line.as_bytes()),
+            None => io::stderr().write_all(line.as_bytes()),
+        };
+    }
+
+    fn flush(&self) {
+        if let Some(file) = &self.file {
+            let _ = file.lock().unwrap().flush();
+        }
+    }
+}
+
+/// Log records up to a level, to a file if given or stderr otherwise
+pub fn init(level: LevelFilter, path: Option<&Path>) -> Result<(), String> {
+    let file = match path {
+        Some(path) => Some(Mutex::new(
+            File::create(path).map_err(|err| format!("{}: {}", path.display(), err))?,
+        )),
+        None => None,
+    };
+    let logger = Logger {
+        start: Instant::now(),
+        file,
+    };
+    log::set_boxed_logger(Box::new(logger)).map_err(|err| err.to_string())?;
+    log::set_max_level(level);
+    Ok(())
+}
@@ -1,24 +1,555 @@
-use ggez::conf::WindowMode;
+use std::env;
+use std::path::PathBuf;
+use std::process;
+use std::str::FromStr;
+use std::sync::mpsc;
+
+use clap::{value_parser, Arg, ArgAction, ArgMatches, Command};
 use ggez::event;
 use ggez::ContextBuilder;
 use ggez::GameResult;
+use log::LevelFilter;
+
+use balls::{AppBuilder, AppConfig, CapPolicy, CrashGuard, MIN_FPS_CAP};
 
-use balls::App;
+/// Ticks simulated without a window, unless set
+const DEFAULT_STEPS: u32 = 600;
 
 fn main() -> GameResult {
-    let window_mode = WindowMode::default().dimensions(800.0, 600.0);
+    let matches = command().get_matches();
 
-    // Create app context
-    let (mut ctx, event_loop) = ContextBuilder::new("balls", "darcy")
-        .window_mode(window_mode)
-        .build()?;
+    let level = matches
+        .get_one::<LevelFilter>("log-level")
+        .copied()
+        .unwrap_or(LevelFilter::Warn);
+    let log_file = matches.get_one::<PathBuf>("log-file");
+    if let Err(err) = balls::init_logging(level, log_file.map(PathBuf::as_path)) {
+        eprintln!("Failed to start logging: {}", err);
+    }
+
+    // Config files, which command line flags override
+    if let Err(err) = balls::write_default_config() {
+        log::error!("Failed to write default config: {}", err);
+    }
+    let mut config = AppConfig::default();
+    if let Err(err) = balls::load_config(&mut config) {
+        log::error!("Failed to load config: {}", err);
+    }
+    let app = app_builder(AppBuilder::from(config).watch_config(true), &matches);
+
+    if matches.contains_id("headless") || matches.get_flag("bench") {
+        let ticks = matches
+            .get_one::<u32>("steps")
+            .or(matches.get_one::<u32>("headless"))
+            .copied()
+            .unwrap_or(DEFAULT_STEPS);
+        let result = if matches.get_flag("bench") {
+            balls::run_bench(app.into_config(), ticks)
+        } else {
+            balls::run_headless(app.into_config(), ticks)
+        };
+        if let Err(err) = result {
+            eprintln!("{}", err);
+            process::exit(1);
+        }
+        return Ok(());
+    }
+
+    let app = start_servers(app, &matches);
 
-    // Change window properties
-    ctx.gfx.set_window_title("Balls");
+    // Create app context
+    let mut builder = ContextBuilder::new("balls", "darcy")
+        .window_mode(app.window_mode())
+        .window_setup(app.window_setup());
+    if let Ok(manifest_dir) = env::var("CARGO_MANIFEST_DIR") {
+        builder = builder.add_resource_path(PathBuf::from(manifest_dir).join("resources"));
+    }
+    let (mut ctx, event_loop) = builder.build()?;
 
     // Create app state
-    let app = App::new(&mut ctx);
+    let mut app = app.build(&mut ctx);
+    let music = matches.get_one::<PathBuf>("music");
+    app.load_music(&mut ctx, music.map(PathBuf::as_path));
 
     // Run game loop
-    event::run(ctx, event_loop, app);
+    event::run(ctx, event_loop, CrashGuard::new(app));
+}
+
+fn command() -> Command {
+    let command = Command::new("balls")
+        .about("Bouncy ball physics sandbox")
+        .arg(
+            Arg::new("balls")
+                .long("balls")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Start with N random balls instead of the start screen"),
+        )
+        .arg(
+            Arg::new("size")
+                .long("size")
+                .value_name("WxH")
+                .value_parser(parse_size)
+                .help("Window size in pixels, such as 1280x720"),
+        )
+        .arg(
+            Arg::new("seed")
+                .long("seed")
+                .value_name("S")
+                .value_parser(value_parser!(u64))
+                .help("Seed for random generation, to repeat a run"),
+        )
+        .arg(
+            Arg::new("scene")
+                .long("scene")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Open a saved scene file"),
+        )
+        .arg(
+            Arg::new("fullscreen")
+                .long("fullscreen")
+                .action(ArgAction::SetTrue)
+                .help("Start in fullscreen"),
+        )
+        .arg(
+            Arg::new("no-vsync")
+                .long("no-vsync")
+                .action(ArgAction::SetTrue)
+                .help("Show frames as soon as they are drawn, without waiting for the display"),
+        )
+        .arg(
+            Arg::new("fps-cap")
+                .long("fps-cap")
+                .value_name("FPS")
+                .value_parser(value_parser!(u32).range(MIN_FPS_CAP as i64..))
+                .help("Most frames per second, to save power on high refresh rate screens"),
+        )
+        .arg(
+            Arg::new("monitor")
+                .long("monitor")
+                .value_name("N|NAME")
+                .help("Monitor to open on, by number from 1 or part of its name"),
+        )
+        .arg(
+            Arg::new("borderless")
+                .long("borderless")
+                .action(ArgAction::SetTrue)
+                .help("Cover the whole monitor with a window without a frame"),
+        )
+        .arg(
+            Arg::new("title")
+                .long("title")
+                .value_name("TITLE")
+                .help("Title of the window"),
+        )
+        .arg(
+            Arg::new("icon")
+                .long("icon")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("PNG image for the window icon, instead of a drawn ball"),
+        )
+        .arg(
+            Arg::new("ui-scale")
+                .long("ui-scale")
+                .value_name("SCALE")
+                .value_parser(parse_scale)
+                .help("Size of text and menus from 0.5 to 3, such as 2 for a 4K screen or projector"),
+        )
+        .arg(
+            Arg::new("headless")
+                .long("headless")
+                .value_name("steps=N")
+                .num_args(0..=1)
+                .value_parser(parse_steps)
+                .help("Simulate ticks without a window and print a summary"),
+        )
+        .arg(
+            Arg::new("steps")
+                .long("steps")
+                .value_name("N")
+                .value_parser(value_parser!(u32))
+                .help("Ticks to simulate without a window [default: 600]"),
+        )
+        .arg(
+            Arg::new("bench")
+                .long("bench")
+                .action(ArgAction::SetTrue)
+                .help("Time ticks at several ball counts without a window, and print a table"),
+        )
+        .arg(
+            Arg::new("record")
+                .long("record")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Record input to a file on exit, to replay later"),
+        )
+        .arg(
+            Arg::new("replay")
+                .long("replay")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Play back recorded input, with its seed and starting scene"),
+        )
+        .arg(
+            Arg::new("log-csv")
+                .long("log-csv")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Log ball positions and velocities to a CSV file"),
+        )
+        .arg(
+            Arg::new("log-every")
+                .long("log-every")
+                .value_name("N")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Log trajectories every N ticks, instead of every tick"),
+        )
+        .arg(
+            Arg::new("parallel")
+                .long("parallel")
+                .action(ArgAction::SetTrue)
+                .help("Resolve collisions on every core, for large scenes"),
+        )
+        .arg(
+            Arg::new("pipeline")
+                .long("pipeline")
+                .action(ArgAction::SetTrue)
+                .help("Run physics on another thread while each frame is drawn"),
+        )
+        .arg(
+            Arg::new("rewind")
+                .long("rewind")
+                .value_name("SECONDS")
+                .value_parser(parse_finite)
+                .help("Seconds of the past kept to rewind through with Backspace, or 0 for none [default: 10]"),
+        )
+        .arg(
+            Arg::new("stress")
+                .long("stress")
+                .action(ArgAction::SetTrue)
+                .help("Add balls until the frame rate drops, then print the most that kept up"),
+        )
+        .arg(
+            Arg::new("max-balls")
+                .long("max-balls")
+                .value_name("N")
+                .value_parser(value_parser!(usize))
+                .help("Most balls at once, so spawning can't slow the app to a crawl"),
+        )
+        .arg(
+            Arg::new("cap-policy")
+                .long("cap-policy")
+                .value_name("POLICY")
+                .value_parser(CapPolicy::from_str)
+                .help("When over --max-balls: reject, remove-oldest or remove-smallest"),
+        )
+        .arg(
+            Arg::new("scale")
+                .long("scale")
+                .value_name("PX")
+                .value_parser(parse_scale)
+                .help("Pixels per meter, for gravity in m/s² and measurements in meters [default: 100]"),
+        )
+        .arg(
+            Arg::new("gravity")
+                .long("gravity")
+                .value_name("M/S²")
+                .value_parser(parse_finite)
+                .help("Gravity of random balls in meters per second squared, such as 9.81"),
+        )
+        .arg(
+            Arg::new("lifetime")
+                .long("lifetime")
+                .value_name("TICKS")
+                .value_parser(value_parser!(u32).range(1..))
+                .help("Remove balls from spawners after TICKS, fading them out first"),
+        )
+        .arg(
+            Arg::new("log-level")
+                .long("log-level")
+                .value_name("LEVEL")
+                .value_parser(value_parser!(LevelFilter))
+                .default_value("warn")
+                .help("Most detailed log messages to show: off, error, warn, info, debug or trace"),
+        )
+        .arg(
+            Arg::new("log-file")
+                .long("log-file")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Write log messages to a file instead of stderr"),
+        )
+        .arg(
+            Arg::new("repl")
+                .long("repl")
+                .action(ArgAction::SetTrue)
+                .help("Read commands from stdin, such as `spawn 100 100` or `list`"),
+        )
+        .arg(
+            Arg::new("music")
+                .long("music")
+                .value_name("FILE")
+                .value_parser(value_parser!(PathBuf))
+                .help("Background music to loop, instead of the default track"),
+        );
+    #[cfg(feature = "websocket")]
+    let command = command.arg(
+        Arg::new("websocket")
+            .long("websocket")
+            .value_name("ADDR")
+            .help("Take JSON commands over WebSocket, such as on 127.0.0.1:9001"),
+    );
+    #[cfg(feature = "osc")]
+    let command = command.arg(
+        Arg::new("osc")
+            .long("osc")
+            .value_name("ADDR")
+            .help("Take OSC messages over UDP, such as on 0.0.0.0:9000"),
+    );
+    #[cfg(feature = "midi")]
+    let command = command.arg(
+        Arg::new("midi")
+            .long("midi")
+            .value_name("DEVICE")
+            .value_parser(value_parser!(PathBuf))
+            .help("Play balls from a raw MIDI device, such as /dev/snd/midiC1D0"),
+    );
+    #[cfg(feature = "chat")]
+    let command = command
+        .arg(
+            Arg::new("chat")
+                .long("chat")
+                .value_name("CHANNEL")
+                .help("Take !ball, !boom and !gravity commands from a Twitch channel's chat"),
+        )
+        .arg(
+            Arg::new("chat-allow")
+                .long("chat-allow")
+                .value_name("USERS")
+                .value_delimiter(',')
+                .help("Only take chat commands from these users, separated by commas"),
+        )
+        .arg(
+            Arg::new("chat-cooldown")
+                .long("chat-cooldown")
+                .value_name("SECS")
+                .value_parser(parse_finite)
+                .default_value("5")
+                .help("Least time between chat commands from one user"),
+        );
+    #[cfg(feature = "lan")]
+    let command = command
+        .arg(
+            Arg::new("host")
+                .long("host")
+                .value_name("ADDR")
+                .conflicts_with_all(["join", "spectate"])
+                .help(
+                    "Share the sandbox with players joining over the LAN, such as on 0.0.0.0:7878",
+                ),
+        )
+        .arg(
+            Arg::new("join")
+                .long("join")
+                .value_name("ADDR")
+                .conflicts_with("spectate")
+                .help("Join a sandbox shared over the LAN, such as 192.168.1.20:7878"),
+        )
+        .arg(
+            Arg::new("spectate")
+                .long("spectate")
+                .value_name("ADDR")
+                .help("Watch a sandbox shared over the LAN, without changing anything in it"),
+        );
+    command
+}
+
+fn app_builder(mut app: AppBuilder, matches: &ArgMatches) -> AppBuilder {
+    if matches.get_flag("parallel") {
+        app = app.parallel(true);
+    }
+    if matches.get_flag("pipeline") {
+        app = app.pipeline(true);
+    }
+    if let Some(&seconds) = matches.get_one::<f32>("rewind") {
+        app = app.rewind_seconds(seconds);
+    }
+    if matches.get_flag("stress") {
+        app = app.stress(true);
+    }
+    if matches.get_flag("fullscreen") {
+        app = app.fullscreen(true);
+    }
+    if matches.get_flag("no-vsync") {
+        app = app.vsync(false);
+    }
+    if let Some(&fps) = matches.get_one::<u32>("fps-cap") {
+        app = app.fps_cap(fps);
+    }
+    if let Some(monitor) = matches.get_one::<String>("monitor") {
+        app = app.monitor(monitor);
+    }
+    if matches.get_flag("borderless") {
+        app = app.borderless(true);
+    }
+    if let Some(title) = matches.get_one::<String>("title") {
+        app = app.title(title);
+    }
+    if let Some(icon) = matches.get_one::<PathBuf>("icon") {
+        app = app.icon(icon);
+    }
+    if let Some(&scale) = matches.get_one::<f32>("ui-scale") {
+        app = app.ui_scale(scale);
+    }
+    if let Some(&count) = matches.get_one::<usize>("balls") {
+        app = app.balls(count);
+    }
+    if let Some(&(width, height)) = matches.get_one::<(f32, f32)>("size") {
+        app = app.window_size(width, height);
+    }
+    if let Some(&seed) = matches.get_one::<u64>("seed") {
+        app = app.seed(seed);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("scene") {
+        app = app.scene(path);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("record") {
+        app = app.record(path);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("replay") {
+        app = app.replay(path);
+    }
+    if let Some(path) = matches.get_one::<PathBuf>("log-csv") {
+        app = app.log_csv(path);
+    }
+    if let Some(&ticks) = matches.get_one::<u32>("log-every") {
+        app = app.log_every(ticks);
+    }
+    if let Some(&max) = matches.get_one::<usize>("max-balls") {
+        app = app.max_balls(max);
+    }
+    if let Some(&policy) = matches.get_one::<CapPolicy>("cap-policy") {
+        app = app.cap_policy(policy);
+    }
+    if let Some(&ticks) = matches.get_one::<u32>("lifetime") {
+        app = app.lifetime(ticks);
+    }
+    // After the scale, which gravity is converted with
+    if let Some(&scale) = matches.get_one::<f32>("scale") {
+        app = app.scale(scale);
+    }
+    if let Some(&gravity) = matches.get_one::<f32>("gravity") {
+        app = app.gravity_ms2(gravity);
+    }
+    app
+}
+
+/// Start any servers asked for, which send their commands to the app
+fn start_servers(app: AppBuilder, matches: &ArgMatches) -> AppBuilder {
+    let (sender, requests) = mpsc::channel();
+    if matches.get_flag("repl") {
+        balls::start_repl(sender.clone());
+    }
+    #[cfg(feature = "websocket")]
+    if let Some(addr) = matches.get_one::<String>("websocket") {
+        match balls::serve_websocket(addr, sender.clone()) {
+            Ok(()) => println!("Settings panel at http://{}/settings", addr),
+            Err(err) => log::error!("Failed to start WebSocket server: {}", err),
+        }
+    }
+    #[cfg(feature = "osc")]
+    if let Some(addr) = matches.get_one::<String>("osc") {
+        if let Err(err) = balls::serve_osc(addr, sender.clone()) {
+            log::error!("Failed to start OSC server: {}", err);
+        }
+    }
+    #[cfg(feature = "midi")]
+    if let Some(path) = matches.get_one::<PathBuf>("midi") {
+        if let Err(err) = balls::listen_midi(path, sender.clone()) {
+            log::error!("Failed to open MIDI device: {}", err);
+        }
+    }
+    #[cfg(feature = "chat")]
+    if let Some(channel) = matches.get_one::<String>("chat") {
+        let options = balls::ChatOptions {
+            channel: channel.trim_start_matches('#').to_string(),
+            allowlist: matches
+                .get_many::<String>("chat-allow")
+                .map(|users| users.cloned().collect())
+                .unwrap_or_default(),
+            // Too long to fit in a duration is as good as never taking commands twice
+            cooldown: std::time::Duration::try_from_secs_f32(
+                matches
+                    .get_one::<f32>("chat-cooldown")
+                    .copied()
+                    .unwrap_or(5.0)
+                    .max(0.0),
+            )
+            .unwrap_or(std::time::Duration::MAX),
+        };
+        balls::connect_chat(options, sender.clone());
+    }
+    #[cfg(feature = "lan")]
+    let app = start_lan(app, matches);
+    // The app stops listening once every server is gone, or if none started
+    drop(sender);
+    app.control(requests)
+}
+
+/// Host, join or watch a shared sandbox, if asked to
+#[cfg(feature = "lan")]
+fn start_lan(app: AppBuilder, matches: &ArgMatches) -> AppBuilder {
+    let session = if let Some(addr) = matches.get_one::<String>("host") {
+        balls::Host::listen(addr).map(balls::Session::Host)
+    } else if let Some(addr) = matches.get_one::<String>("join") {
+        balls::Guest::join(addr).map(balls::Session::Guest)
+    } else if let Some(addr) = matches.get_one::<String>("spectate") {
+        balls::Guest::spectate(addr).map(balls::Session::Guest)
+    } else {
+        return app;
+    };
+    match session {
+        Ok(session) => app.lan(session),
+        Err(err) => {
+            log::error!("Failed to share the sandbox: {}", err);
+            app
+        }
+    }
+}
+
+/// Parse window size such as `800x600`
+fn parse_size(value: &str) -> Result<(f32, f32), String> {
+    let (width, height) = value
+        .split_once('x')
+        .ok_or("expected size as WIDTHxHEIGHT")?;
+    let parse = |side: &str| match side.trim().parse::<u32>() {
+        Ok(side) if side > 0 => Ok(side as f32),
+        _ => Err(format!("invalid size `{}`", side)),
+    };
+    Ok((parse(width)?, parse(height)?))
+}
+
+fn parse_scale(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(scale) if scale > 0.0 && scale.is_finite() => Ok(scale),
+        _ => Err(format!("invalid scale `{}`", value)),
+    }
+}
+
+/// Parse a number that isn't infinite or NaN
+fn parse_finite(value: &str) -> Result<f32, String> {
+    match value.parse::<f32>() {
+        Ok(number) if number.is_finite() => Ok(number),
+        _ => Err(format!("invalid number `{}`", value)),
+    }
+}
+
+/// Parse tick count, as `steps=N` or just `N`
+fn parse_steps(value: &str) -> Result<u32, String> {
+    let count = value.strip_prefix("steps=").unwrap_or(value);
+    count
+        .parse()
+        .map_err(|_| format!("invalid step count `{}`", count))
 }
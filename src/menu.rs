@@ -0,0 +1,188 @@
+use ggez::graphics::{Canvas, Color, DrawParam, Quad, Rect};
+use ggez::mint::Point2;
+
+use crate::text::TextStyle;
+
+/// Sizes of a kind of menu, at a UI scale of 1
+struct Layout {
+    title_size: f32,
+    item_size: f32,
+    item_height: f32,
+    item_width: f32,
+    /// Whether to draw a box behind the items
+    background: bool,
+}
+
+/// Full-screen menus, such as the start screen
+const SCREEN: Layout = Layout {
+    title_size: 48.0,
+    item_size: 24.0,
+    item_height: 34.0,
+    item_width: 360.0,
+    background: false,
+};
+
+/// Small untitled menus, such as context menus
+const POPUP: Layout = Layout {
+    title_size: 0.0,
+    item_size: 16.0,
+    item_height: 22.0,
+    item_width: 150.0,
+    background: true,
+};
+
+/// Vertical list of selectable entries, navigable with keyboard and mouse
+pub struct Menu<T> {
+    title: String,
+    items: Vec<(String, T)>,
+    selected: usize,
+    position: Point2<f32>,
+    layout: &'static Layout,
+}
+
+impl<T: Clone> Menu<T> {
+    pub fn new(title: impl Into<String>, position: Point2<f32>) -> Self {
+        Self {
+            title: title.into(),
+            items: Vec::new(),
+            selected: 0,
+            position,
+            layout: &SCREEN,
+        }
+    }
+
+    /// Compact menu without a title, opened at the cursor
+    pub fn popup(position: Point2<f32>) -> Self {
+        Self {
+            title: String::new(),
+            items: Vec::new(),
+            selected: 0,
+            position,
+            layout: &POPUP,
+        }
+    }
+
+    pub fn item(mut self, label: impl Into<String>, action: T) -> Self {
+        self.items.push((label.into(), action));
+        self
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn up(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + self.items.len() - 1) % self.items.len();
+    }
+
+    pub fn down(&mut self) {
+        if self.items.is_empty() {
+            return;
+        }
+        self.selected = (self.selected + 1) % self.items.len();
+    }
+
+    /// Select an item by index, such as to keep the selection when rebuilding a menu
+    pub fn select(&mut self, index: usize) {
+        self.selected = index.min(self.items.len().saturating_sub(1));
+    }
+
+    pub fn selected_index(&self) -> usize {
+        self.selected
+    }
+
+    pub fn selected(&self) -> Option<T> {
+        self.items
+            .get(self.selected)
+            .map(|(_, action)| action.clone())
+    }
+
+    /// Select the item under the cursor, if any
+    pub fn hover(&mut self, x: f32, y: f32, style: TextStyle) {
+        if let Some(i) = self.item_at(x, y, style) {
+            self.selected = i;
+        }
+    }
+
+    /// Returns the action of the item under the cursor, if any
+    pub fn click(&mut self, x: f32, y: f32, style: TextStyle) -> Option<T> {
+        let i = self.item_at(x, y, style)?;
+        self.selected = i;
+        self.selected()
+    }
+
+    fn item_rect(&self, index: usize, style: TextStyle) -> Rect {
+        let layout = self.layout;
+        Rect::new(
+            self.position.x,
+            self.position.y + style.px(layout.title_size * 1.5 + index as f32 * layout.item_height),
+            style.px(layout.item_width),
+            style.px(layout.item_height),
+        )
+    }
+
+    fn item_at(&self, x: f32, y: f32, style: TextStyle) -> Option<usize> {
+        (0..self.items.len()).find(|&i| self.item_rect(i, style).contains(Point2 { x, y }))
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, style: TextStyle) {
+        let layout = self.layout;
+
+        if !self.title.is_empty() {
+            let title = style.text(&self.title, layout.title_size);
+            canvas.draw(&title, DrawParam::from(self.position).color(color!(WHITE)));
+        }
+
+        if layout.background {
+            let rect = Rect::new(
+                self.position.x,
+                self.position.y,
+                style.px(layout.item_width),
+                style.px(layout.item_height * self.items.len() as f32),
+            );
+            canvas.draw(
+                &Quad,
+                DrawParam::default()
+                    .dest_rect(rect)
+                    .color(color!(30, 30, 30, 230)),
+            );
+        }
+
+        for (i, (label, _)) in self.items.iter().enumerate() {
+            let rect = self.item_rect(i, style);
+            let color = if i == self.selected {
+                canvas.draw(
+                    &Quad,
+                    DrawParam::default()
+                        .dest_rect(rect)
+                        .color(color!(60, 60, 60)),
+                );
+                color!(YELLOW)
+            } else {
+                color!(WHITE)
+            };
+
+            canvas.draw(
+                &style.text(label, layout.item_size),
+                DrawParam::from([
+                    rect.x + style.px(10.0),
+                    rect.y + style.px(layout.item_height - layout.item_size) / 2.0,
+                ])
+                .color(color),
+            );
+        }
+    }
+}
+
+/// Dim everything already drawn, for overlays
+pub fn draw_shade(canvas: &mut Canvas, width: f32, height: f32, color: Color) {
+    canvas.draw(
+        &Quad,
+        DrawParam::default()
+            .dest_rect(Rect::new(0.0, 0.0, width, height))
+            .color(color),
+    );
+}
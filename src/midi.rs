@@ -0,0 +1,195 @@
+//! MIDI input read from a raw MIDI device, such as `/dev/snd/midiC1D0` on
+//! Linux. Notes launch balls from the floor, lower notes bigger and further
+//! left, harder notes faster. The mod wheel sets gravity and the sustain
+//! pedal kicks every ball
+
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::control::{Command, Request};
+
+/// Radius of balls launched by the lowest and highest notes
+const RADIUS_RANGE: (f32, f32) = (40.0, 5.0);
+/// Speed of balls launched by the hardest note
+const MAX_SPEED: f32 = 25.0;
+/// Gravity with the mod wheel all the way up
+const MAX_GRAVITY: f32 = 2.0;
+/// Impulse given by pressing the sustain pedal
+const PEDAL_IMPULSE: f32 = 6.0;
+
+const CC_MOD_WHEEL: u8 = 1;
+const CC_SUSTAIN: u8 = 64;
+
+/// Read a device in the background, sending commands to the app
+pub fn listen(path: &Path, sender: Sender<Request>) -> Result<(), String> {
+    let file = File::open(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    thread::spawn(move || {
+        let (reply, _) = mpsc::channel();
+        let mut parser = Parser::default();
+        for byte in BufReader::new(file).bytes() {
+            let Ok(byte) = byte else {
+                break;
+            };
+            let Some(command) = parser.push(byte).and_then(command) else {
+                continue;
+            };
+            let request = Request {
+                command,
+                reply: reply.clone(),
+            };
+            if sender.send(request).is_err() {
+                return;
+            }
+        }
+        log::warn!("MIDI device closed");
+    });
+    Ok(())
+}
+
+/// Channel message, ignoring the channel
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Message {
+    NoteOn { note: u8, velocity: u8 },
+    Control { number: u8, value: u8 },
+}
+
+fn command(message: Message) -> Option<Command> {
+    let fraction = |value: u8| value as f32 / 127.0;
+    match message {
+        Message::NoteOn { note, velocity } => {
+            let (largest, smallest) = RADIUS_RANGE;
+            Some(Command::Launch {
+                position: fraction(note),
+                radius: largest + (smallest - largest) * fraction(note),
+                speed: MAX_SPEED * fraction(velocity),
+                color: None,
+            })
+        }
+        Message::Control {
+            number: CC_MOD_WHEEL,
+            value,
+        } => Some(Command::Gravity {
+            value: MAX_GRAVITY * fraction(value),
+        }),
+        Message::Control {
+            number: CC_SUSTAIN,
+            value,
+        } if value >= 64 => Some(Command::Kick {
+            impulse: PEDAL_IMPULSE,
+        }),
+        Message::Control { .. } => None,
+    }
+}
+
+/// Turns a stream of MIDI bytes into messages, following running status
+#[derive(Default)]
+struct Parser {
+    status: Option<u8>,
+    data: Vec<u8>,
+}
+
+impl Parser {
+    fn push(&mut self, byte: u8) -> Option<Message> {
+        if byte >= 0xF8 {
+            // Real-time messages can appear anywhere, and don't affect status
+            return None;
+        }
+        if byte & 0x80 != 0 {
+            // System messages cancel running status
+            self.status = (byte < 0xF0).then_some(byte);
+            self.data.clear();
+            return None;
+        }
+
+        let status = self.status?;
+        self.data.push(byte);
+        let len = match status & 0xF0 {
+            0xC0 | 0xD0 => 1,
+            _ => 2,
+        };
+        if self.data.len() < len {
+            return None;
+        }
+        let data = std::mem::take(&mut self.data);
+        match (status & 0xF0, data.as_slice()) {
+            // Note-on with no velocity is a note-off
+            (0x90, &[note, velocity]) if velocity > 0 => Some(Message::NoteOn { note, velocity }),
+            (0xB0, &[number, value]) => Some(Message::Control { number, value }),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(bytes: &[u8]) -> Vec<Message> {
+        let mut parser = Parser::default();
+        bytes.iter().filter_map(|&byte| parser.push(byte)).collect()
+    }
+
+    #[test]
+    fn running_status_repeats_the_last_message_type() {
+        assert_eq!(
+            parse(&[0x91, 60, 100, 62, 90]),
+            [
+                Message::NoteOn {
+                    note: 60,
+                    velocity: 100
+                },
+                Message::NoteOn {
+                    note: 62,
+                    velocity: 90
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn note_offs_real_time_and_system_bytes_are_skipped() {
+        // Note-off, note-on with no velocity, then a clock tick in the middle of a control
+        assert_eq!(
+            parse(&[0x80, 60, 0, 0x90, 60, 0, 0xB0, 1, 0xF8, 64]),
+            [Message::Control {
+                number: 1,
+                value: 64
+            }]
+        );
+        // Data after a system message has no status to go with it
+        assert_eq!(parse(&[0xB0, 1, 0xF0, 1, 64]), []);
+        // Program changes take one data byte, so the next pair starts fresh
+        assert_eq!(parse(&[0xC0, 5, 0xB0, 64, 127]).len(), 1);
+    }
+
+    #[test]
+    fn notes_and_controls_become_commands() {
+        let loud = command(Message::NoteOn {
+            note: 127,
+            velocity: 127,
+        });
+        assert!(matches!(
+            loud,
+            Some(Command::Launch { position, radius, speed, .. })
+                if position == 1.0 && radius == RADIUS_RANGE.1 && speed == MAX_SPEED
+        ));
+        let wheel = command(Message::Control {
+            number: CC_MOD_WHEEL,
+            value: 0,
+        });
+        assert!(matches!(wheel, Some(Command::Gravity { value }) if value == 0.0));
+        let released = Message::Control {
+            number: CC_SUSTAIN,
+            value: 0,
+        };
+        assert!(command(released).is_none());
+        let other = Message::Control {
+            number: 7,
+            value: 100,
+        };
+        assert!(command(other).is_none());
+    }
+}
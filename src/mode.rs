@@ -0,0 +1,208 @@
+//! Games played over the shared simulation, each adding its own rules, input
+//! and drawing on top of the sandbox
+
+mod billiards;
+mod breakout;
+pub mod challenge;
+mod golf;
+mod infection;
+mod plinko;
+mod pong;
+mod predator;
+mod slingshot;
+mod targets;
+
+use ggez::event::MouseButton;
+use ggez::graphics::Canvas;
+use ggez::mint::{Point2, Vector2};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult};
+use rand::rngs::StdRng;
+
+use self::billiards::Billiards;
+use self::breakout::Breakout;
+use self::golf::Golf;
+use self::infection::Infection;
+use self::plinko::Plinko;
+use self::pong::Pong;
+use self::predator::Predator;
+use self::targets::Targets;
+use crate::ball::BallId;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+use crate::toast::Toasts;
+
+/// What a mode can change while it runs
+pub struct ModeContext<'a> {
+    pub sim: &'a mut Simulation,
+    pub toasts: &'a mut Toasts,
+    pub rng: &'a mut StdRng,
+}
+
+/// Rules layered over the simulation. Input hooks return whether the mode
+/// used the input, so the sandbox doesn't act on it too
+pub trait GameMode {
+    fn name(&self) -> &'static str;
+
+    /// Called once the starting scene is loaded, to add any balls the mode keeps track of
+    fn start(&mut self, _game: &mut ModeContext) {}
+
+    /// Called once per frame, after physics, with the events it raised
+    fn update(&mut self, _game: &mut ModeContext, _dt: f32) {}
+
+    /// Ball being aimed and the velocity it would be shot at, to predict its path
+    fn aim(&self) -> Option<(BallId, Vector2<f32>)> {
+        None
+    }
+
+    /// Whether what the mode draws can change from frame to frame on its own, such
+    /// as a countdown, so frames can't be skipped while nothing else moves
+    fn is_animated(&self) -> bool {
+        true
+    }
+
+    /// Draw over the balls
+    fn draw(
+        &self,
+        _ctx: &mut Context,
+        _canvas: &mut Canvas,
+        _sim: &Simulation,
+        _style: TextStyle,
+    ) -> GameResult {
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        _game: &mut ModeContext,
+        _button: MouseButton,
+        _point: Point2<f32>,
+    ) -> bool {
+        false
+    }
+
+    fn mouse_motion(&mut self, _game: &mut ModeContext, _point: Point2<f32>) -> bool {
+        false
+    }
+
+    fn mouse_up(
+        &mut self,
+        _game: &mut ModeContext,
+        _button: MouseButton,
+        _point: Point2<f32>,
+    ) -> bool {
+        false
+    }
+
+    fn key_down(&mut self, _game: &mut ModeContext, _key: VirtualKeyCode) -> bool {
+        false
+    }
+
+    /// Keys going up are never used by the sandbox, so aren't claimed
+    fn key_up(&mut self, _game: &mut ModeContext, _key: VirtualKeyCode) {}
+}
+
+/// Free play with no rules, where every input goes to the sandbox tools
+pub struct Sandbox;
+
+impl GameMode for Sandbox {
+    fn name(&self) -> &'static str {
+        "Sandbox"
+    }
+
+    fn is_animated(&self) -> bool {
+        false
+    }
+}
+
+/// Games that can be picked from the modes menu
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ModeKind {
+    Plinko,
+    Billiards,
+    Pong,
+    Breakout,
+    Golf,
+    Targets,
+    Infection,
+    Predator,
+}
+
+impl ModeKind {
+    pub const ALL: [ModeKind; 8] = [
+        ModeKind::Plinko,
+        ModeKind::Billiards,
+        ModeKind::Pong,
+        ModeKind::Breakout,
+        ModeKind::Golf,
+        ModeKind::Targets,
+        ModeKind::Infection,
+        ModeKind::Predator,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ModeKind::Plinko => "Plinko",
+            ModeKind::Billiards => "Billiards",
+            ModeKind::Pong => "Pong",
+            ModeKind::Breakout => "Breakout",
+            ModeKind::Golf => "Mini-golf",
+            ModeKind::Targets => "Targets",
+            ModeKind::Infection => "Infection",
+            ModeKind::Predator => "Predator",
+        }
+    }
+
+    /// How to play, shown when the mode starts
+    pub fn description(self) -> &'static str {
+        match self {
+            ModeKind::Plinko => "Click above the pegs to drop a ball, and aim for the edges",
+            ModeKind::Billiards => "Drag back from anywhere and let go to strike the cue ball",
+            ModeKind::Pong => "Left paddle W/S, right paddle Up/Down, first to 7 wins",
+            ModeKind::Breakout => "Move the paddle with the mouse, click to launch the ball",
+            ModeKind::Golf => "Drag back from anywhere and let go to putt",
+            ModeKind::Targets => "Grab and throw balls to pop the targets",
+            ModeKind::Infection => "The green ball turns every ball it touches green",
+            ModeKind::Predator => "Steer with the mouse, eat smaller balls and avoid bigger ones",
+        }
+    }
+
+    /// New game, with the scene it starts from
+    pub fn start(self, width: f32, height: f32) -> (Box<dyn GameMode>, Scene) {
+        match self {
+            ModeKind::Plinko => {
+                let (mode, scene) = Plinko::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Billiards => {
+                let (mode, scene) = Billiards::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Pong => {
+                let (mode, scene) = Pong::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Breakout => {
+                let (mode, scene) = Breakout::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Golf => {
+                let (mode, scene) = Golf::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Targets => {
+                let (mode, scene) = Targets::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Infection => {
+                let (mode, scene) = Infection::new(width, height);
+                (Box::new(mode), scene)
+            }
+            ModeKind::Predator => {
+                let (mode, scene) = Predator::new(width, height);
+                (Box::new(mode), scene)
+            }
+        }
+    }
+}
@@ -0,0 +1,297 @@
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+use super::slingshot::Slingshot;
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::Event;
+use crate::scene::Scene;
+use crate::simulation::{Simulation, TICK};
+use crate::text::TextStyle;
+
+/// Gap between the table and the window
+const MARGIN: f32 = 60.0;
+const CUSHION_RADIUS: f32 = 6.0;
+const POCKET_RADIUS: f32 = 24.0;
+const BALL_RADIUS: f32 = 12.0;
+/// Fraction of speed kept each tick, as balls roll on the cloth
+const FRICTION: f32 = 0.985;
+/// Speed below which a ball stops rolling
+const STOP_SPEED: f32 = 0.05;
+/// Longest drag counted towards a shot's power
+const MAX_DRAG: f32 = 200.0;
+/// Speed of the cue ball per pixel dragged
+const POWER: f32 = 0.12;
+const POINTS_PER_BALL: u32 = 100;
+
+/// Pot the racked balls by striking the cue ball, dragged back like a slingshot
+pub struct Billiards {
+    /// Left, top, right and bottom edges of the cloth
+    table: [f32; 4],
+    pockets: Vec<Point2<f32>>,
+    cue: Option<BallId>,
+    aim: Slingshot,
+    potted: u32,
+    /// Balls left to pot, not counting the cue ball
+    remaining: u32,
+    shots: u32,
+}
+
+impl Billiards {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let (left, top) = (MARGIN, MARGIN);
+        let (right, bottom) = (width - MARGIN, height - MARGIN);
+        let middle = (left + right) / 2.0;
+        let pockets = vec![
+            Point2 { x: left, y: top },
+            Point2 { x: middle, y: top },
+            Point2 { x: right, y: top },
+            Point2 { x: left, y: bottom },
+            Point2 {
+                x: middle,
+                y: bottom,
+            },
+            Point2 {
+                x: right,
+                y: bottom,
+            },
+        ];
+
+        // Cushions line the table, leaving gaps for the pockets
+        let mut balls = Vec::new();
+        let step = CUSHION_RADIUS * 2.0;
+        let edges = [
+            ((left, top - CUSHION_RADIUS), (right, top - CUSHION_RADIUS)),
+            (
+                (left, bottom + CUSHION_RADIUS),
+                (right, bottom + CUSHION_RADIUS),
+            ),
+            (
+                (left - CUSHION_RADIUS, top),
+                (left - CUSHION_RADIUS, bottom),
+            ),
+            (
+                (right + CUSHION_RADIUS, top),
+                (right + CUSHION_RADIUS, bottom),
+            ),
+        ];
+        for ((x1, y1), (x2, y2)) in edges {
+            let length = (x2 - x1).hypot(y2 - y1);
+            for i in 0..=(length / step) as usize {
+                let t = i as f32 * step / length;
+                let point = Point2 {
+                    x: x1 + (x2 - x1) * t,
+                    y: y1 + (y2 - y1) * t,
+                };
+                let near_pocket = pockets.iter().any(|pocket| {
+                    (pocket.x - point.x).hypot(pocket.y - point.y) < POCKET_RADIUS + BALL_RADIUS
+                });
+                if near_pocket {
+                    continue;
+                }
+                let mut cushion = Ball::new(point.x, point.y, CUSHION_RADIUS, color!(0x6b4226));
+                cushion.pinned = true;
+                balls.push(cushion);
+            }
+        }
+
+        // Triangle rack, with its point facing the cue ball
+        let colors = [
+            color!(0xffd60a),
+            color!(0x1d4ed8),
+            color!(0xdc2626),
+            color!(0x7c3aed),
+            color!(0xf97316),
+            color!(0x15803d),
+            color!(0x7f1d1d),
+        ];
+        let apex = Point2 {
+            x: left + (right - left) * 0.7,
+            y: (top + bottom) / 2.0,
+        };
+        let spacing = BALL_RADIUS * 2.0 + 1.0;
+        let mut racked: u32 = 0;
+        for row in 0..5 {
+            for i in 0..=row {
+                let x = apex.x + row as f32 * spacing * 0.87;
+                let y = apex.y + (i as f32 - row as f32 / 2.0) * spacing;
+                let color = match racked {
+                    4 => color!(BLACK),
+                    n => colors[n as usize % colors.len()],
+                };
+                balls.push(Ball::new(x, y, BALL_RADIUS, color));
+                racked += 1;
+            }
+        }
+
+        let mode = Self {
+            table: [left, top, right, bottom],
+            pockets,
+            cue: None,
+            aim: Slingshot::new(MAX_DRAG, POWER),
+            potted: 0,
+            remaining: racked,
+            shots: 0,
+        };
+        let scene = Scene::new(balls, 0.0);
+        (mode, scene)
+    }
+
+    fn spot(&self) -> Point2<f32> {
+        let [left, top, right, bottom] = self.table;
+        Point2 {
+            x: left + (right - left) * 0.25,
+            y: (top + bottom) / 2.0,
+        }
+    }
+
+    /// Whether a ball has dropped into a pocket or off the table
+    fn is_potted(&self, ball: &Ball) -> bool {
+        let [left, top, right, bottom] = self.table;
+        let off_table = ball.point.x < left
+            || ball.point.x > right
+            || ball.point.y < top
+            || ball.point.y > bottom;
+        off_table
+            || self.pockets.iter().any(|pocket| {
+                (pocket.x - ball.point.x).hypot(pocket.y - ball.point.y) < POCKET_RADIUS
+            })
+    }
+
+    fn place_cue(&mut self, sim: &mut Simulation) {
+        let spot = self.spot();
+        self.cue = sim.add_ball(Ball::new(spot.x, spot.y, BALL_RADIUS, color!(WHITE)));
+    }
+}
+
+impl GameMode for Billiards {
+    fn name(&self) -> &'static str {
+        "Billiards"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        self.place_cue(game.sim);
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        let friction = FRICTION.powf(dt / TICK);
+        game.sim.update_balls(|ball| {
+            ball.velocity.x *= friction;
+            ball.velocity.y *= friction;
+            if ball.speed() < STOP_SPEED {
+                ball.velocity = Vector2 { x: 0.0, y: 0.0 };
+            }
+        });
+
+        let potted: Vec<(BallId, Ball)> = game
+            .sim
+            .balls()
+            .filter(|(_, ball)| !ball.pinned && self.is_potted(ball))
+            .collect();
+        for (id, ball) in potted {
+            game.sim.remove_ball(id);
+            if Some(id) == self.cue {
+                game.toasts
+                    .push("Scratch! The cue ball is back on the spot");
+                self.place_cue(game.sim);
+                continue;
+            }
+            self.potted += 1;
+            game.sim.emit(Event::Scored {
+                points: POINTS_PER_BALL,
+                point: ball.point,
+            });
+            self.remaining = self.remaining.saturating_sub(1);
+            game.toasts
+                .push(format!("Potted! ({} left)", self.remaining));
+            if self.remaining == 0 {
+                game.toasts.push(format!(
+                    "Table cleared in {} shots, reset to play again",
+                    self.shots
+                ));
+            }
+        }
+    }
+
+    fn aim(&self) -> Option<(BallId, Vector2<f32>)> {
+        Some((self.cue?, self.aim.shot()?))
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        for pocket in &self.pockets {
+            let ring = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(2.0),
+                *pocket,
+                POCKET_RADIUS,
+                0.1,
+                color!(128, 128, 128),
+            )?;
+            canvas.draw(&ring, DrawParam::default());
+        }
+
+        if let Some(cue) = self.cue.and_then(|id| sim.ball(id)) {
+            self.aim.draw(ctx, canvas, cue.point)?;
+        }
+
+        let text = style.text(
+            format!("Potted: {}    Shots: {}", self.potted, self.shots),
+            24.0,
+        );
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        game: &mut ModeContext,
+        button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if button != MouseButton::Left {
+            return false;
+        }
+        let rolling = game
+            .sim
+            .balls()
+            .any(|(_, ball)| !ball.pinned && ball.speed() > 0.0);
+        if rolling {
+            game.toasts.push("Wait for the balls to stop");
+            return true;
+        }
+        self.aim.press(point);
+        true
+    }
+
+    fn mouse_motion(&mut self, _game: &mut ModeContext, point: Point2<f32>) -> bool {
+        self.aim.drag(point)
+    }
+
+    fn mouse_up(
+        &mut self,
+        game: &mut ModeContext,
+        _button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if !self.aim.is_aiming() {
+            return false;
+        }
+        let Some(velocity) = self.aim.release(point) else {
+            return true;
+        };
+        let Some(mut cue) = self.cue.and_then(|id| game.sim.ball_mut(id)) else {
+            return true;
+        };
+        cue.velocity = velocity;
+        self.shots += 1;
+        true
+    }
+}
@@ -0,0 +1,336 @@
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::{Event, Wall};
+use crate::scene::Scene;
+use crate::simulation::{Simulation, TICK};
+use crate::text::TextStyle;
+
+const BRICK_RADIUS: f32 = 14.0;
+const BRICK_ROWS: usize = 6;
+/// Colors of the rows, from the top, which are worth more points
+const ROW_COLORS: [Color; BRICK_ROWS] = [
+    color!(0xe63946),
+    color!(0xf4a261),
+    color!(0xffd60a),
+    color!(0x52b788),
+    color!(0x48cae4),
+    color!(0x9d4edd),
+];
+const PADDLE_SEGMENTS: usize = 7;
+const PADDLE_RADIUS: f32 = 8.0;
+/// Gap between the paddle and the floor
+const PADDLE_INSET: f32 = 40.0;
+const BALL_RADIUS: f32 = 8.0;
+const BALL_SPEED: f32 = 7.0;
+const LIVES: u32 = 3;
+/// Chance of a broken brick dropping a power-up
+const POWER_UP_CHANCE: f64 = 0.12;
+/// Pixels a power-up falls per tick
+const POWER_UP_SPEED: f32 = 3.0;
+const POWER_UP_RADIUS: f32 = 6.0;
+
+/// Break every brick by bouncing balls off a paddle, without letting them fall
+pub struct Breakout {
+    width: f32,
+    height: f32,
+    /// Pinned balls standing in for bricks, with the points each is worth
+    bricks: Vec<(BallId, u32)>,
+    paddle: Vec<BallId>,
+    paddle_x: f32,
+    /// Balls in play
+    balls: Vec<BallId>,
+    /// Whether the ball is resting on the paddle, waiting to be launched
+    serving: bool,
+    /// Falling power-ups, which add a ball when caught
+    power_ups: Vec<Point2<f32>>,
+    lives: u32,
+}
+
+impl Breakout {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mode = Self {
+            width,
+            height,
+            bricks: Vec::new(),
+            paddle: Vec::new(),
+            paddle_x: width / 2.0,
+            balls: Vec::new(),
+            serving: true,
+            power_ups: Vec::new(),
+            lives: LIVES,
+        };
+        let scene = Scene::new(Vec::new(), 0.0);
+        (mode, scene)
+    }
+
+    fn paddle_y(&self) -> f32 {
+        self.height - PADDLE_INSET
+    }
+
+    fn half_width() -> f32 {
+        PADDLE_SEGMENTS as f32 * PADDLE_RADIUS
+    }
+
+    /// Move the paddle under a point, keeping it on screen
+    fn move_paddle(&mut self, sim: &mut Simulation, x: f32) {
+        let half = Self::half_width();
+        self.paddle_x = x.clamp(half, self.width - half);
+        for (i, &id) in self.paddle.iter().enumerate() {
+            if let Some(mut segment) = sim.ball_mut(id) {
+                segment.point.x = self.paddle_x - half + PADDLE_RADIUS * (2 * i + 1) as f32;
+            }
+        }
+        if self.serving {
+            let point = self.serve_point();
+            if let Some(mut ball) = self.balls.first().and_then(|&id| sim.ball_mut(id)) {
+                ball.point = point;
+            }
+        }
+    }
+
+    /// Where a ball waits to be launched, on top of the paddle
+    fn serve_point(&self) -> Point2<f32> {
+        Point2 {
+            x: self.paddle_x,
+            y: self.paddle_y() - PADDLE_RADIUS - BALL_RADIUS - 1.0,
+        }
+    }
+
+    fn add_ball(&mut self, sim: &mut Simulation, point: Point2<f32>, velocity: Vector2<f32>) {
+        let mut ball = Ball::new(point.x, point.y, BALL_RADIUS, color!(WHITE));
+        ball.restitution = 1.0;
+        ball.velocity = velocity;
+        if let Some(id) = sim.add_ball(ball) {
+            self.balls.push(id);
+        }
+    }
+
+    fn launch(&mut self, game: &mut ModeContext) {
+        if !self.serving {
+            return;
+        }
+        self.serving = false;
+        let angle: f32 = game.rng.gen_range(-0.5..0.5);
+        if let Some(mut ball) = self.balls.first().and_then(|&id| game.sim.ball_mut(id)) {
+            ball.velocity = Vector2 {
+                x: angle.sin() * BALL_SPEED,
+                y: -angle.cos() * BALL_SPEED,
+            };
+        }
+    }
+
+    /// Break any brick a ball hit, scoring it, and return where each one was
+    fn break_bricks(&mut self, game: &mut ModeContext) -> Vec<Point2<f32>> {
+        let hit: Vec<BallId> = game
+            .sim
+            .events()
+            .iter()
+            .filter_map(|event| match *event {
+                Event::BallHitBall { a, b, .. } if self.balls.contains(&a) => Some(b),
+                _ => None,
+            })
+            .collect();
+        let mut broken = Vec::new();
+        for id in hit {
+            let Some(i) = self.bricks.iter().position(|(brick, _)| *brick == id) else {
+                continue;
+            };
+            let (_, points) = self.bricks.remove(i);
+            if let Some(brick) = game.sim.remove_ball(id) {
+                game.sim.emit(Event::Scored {
+                    points,
+                    point: brick.point,
+                });
+                broken.push(brick.point);
+            }
+        }
+        broken
+    }
+}
+
+impl GameMode for Breakout {
+    fn name(&self) -> &'static str {
+        "Breakout"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        let spacing = BRICK_RADIUS * 2.0 + 2.0;
+        let columns = ((self.width - 40.0) / spacing) as usize;
+        let left = (self.width - (columns - 1) as f32 * spacing) / 2.0;
+        for (row, &color) in ROW_COLORS.iter().enumerate() {
+            let points = 10 * (BRICK_ROWS - row) as u32;
+            for column in 0..columns {
+                let x = left + column as f32 * spacing;
+                let y = 80.0 + row as f32 * spacing;
+                let mut brick = Ball::new(x, y, BRICK_RADIUS, color);
+                brick.pinned = true;
+                if let Some(id) = game.sim.add_ball(brick) {
+                    self.bricks.push((id, points));
+                }
+            }
+        }
+
+        let y = self.paddle_y();
+        self.paddle = (0..PADDLE_SEGMENTS)
+            .filter_map(|_| {
+                let mut segment = Ball::new(0.0, y, PADDLE_RADIUS, color!(WHITE));
+                segment.pinned = true;
+                game.sim.add_ball(segment)
+            })
+            .collect();
+        let point = self.serve_point();
+        self.add_ball(game.sim, point, Vector2 { x: 0.0, y: 0.0 });
+        self.move_paddle(game.sim, self.paddle_x);
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        for point in self.break_bricks(game) {
+            if game.rng.gen_bool(POWER_UP_CHANCE) {
+                self.power_ups.push(point);
+            }
+        }
+        if self.bricks.is_empty() && !self.balls.is_empty() {
+            for id in self.balls.drain(..) {
+                game.sim.remove_ball(id);
+            }
+            game.toasts.push("Every brick broken! Reset to play again");
+        }
+
+        // Balls that reach the floor are lost
+        let lost: Vec<BallId> = game
+            .sim
+            .events()
+            .iter()
+            .filter_map(|event| match *event {
+                Event::BallHitWall {
+                    ball,
+                    wall: Wall::Floor,
+                    ..
+                } if self.balls.contains(&ball) => Some(ball),
+                _ => None,
+            })
+            .collect();
+        for id in lost {
+            game.sim.remove_ball(id);
+            self.balls.retain(|&ball| ball != id);
+        }
+        if self.balls.is_empty() && !self.bricks.is_empty() && self.lives > 0 {
+            self.lives -= 1;
+            self.power_ups.clear();
+            if self.lives == 0 {
+                game.toasts.push("Game over, reset to play again");
+            } else {
+                game.toasts.push(format!("Ball lost, {} left", self.lives));
+                let point = self.serve_point();
+                self.add_ball(game.sim, point, Vector2 { x: 0.0, y: 0.0 });
+                self.serving = true;
+            }
+        }
+
+        // Power-ups caught by the paddle add a ball, launched from it
+        let fall = POWER_UP_SPEED * dt / TICK;
+        let paddle = (self.paddle_x, self.paddle_y());
+        let half = Self::half_width();
+        let mut caught = 0;
+        self.power_ups.retain_mut(|point| {
+            point.y += fall;
+            let on_paddle = (point.x - paddle.0).abs() < half
+                && (point.y - paddle.1).abs() < PADDLE_RADIUS + POWER_UP_RADIUS;
+            if on_paddle {
+                caught += 1;
+            }
+            !on_paddle && point.y < paddle.1 + PADDLE_INSET
+        });
+        for _ in 0..caught {
+            let angle: f32 = game.rng.gen_range(-0.5..0.5);
+            let velocity = Vector2 {
+                x: angle.sin() * BALL_SPEED,
+                y: -angle.cos() * BALL_SPEED,
+            };
+            let point = self.serve_point();
+            self.add_ball(game.sim, point, velocity);
+            game.toasts.push("Extra ball!");
+        }
+
+        // Balls keep a steady speed, and bounce off the top of the window
+        if self.serving {
+            return;
+        }
+        for &id in &self.balls {
+            let Some(mut ball) = game.sim.ball_mut(id) else {
+                continue;
+            };
+            if ball.point.y < BALL_RADIUS {
+                ball.point.y = BALL_RADIUS;
+                ball.velocity.y = ball.velocity.y.abs();
+            }
+            // Never let a ball get stuck going straight across
+            let min_vertical = BALL_SPEED * 0.3;
+            if ball.velocity.y.abs() < min_vertical {
+                ball.velocity.y = min_vertical.copysign(ball.velocity.y);
+            }
+            let speed = ball.speed();
+            if speed > 0.0 {
+                ball.velocity.x *= BALL_SPEED / speed;
+                ball.velocity.y *= BALL_SPEED / speed;
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        _sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        for point in &self.power_ups {
+            let circle = Mesh::new_circle(
+                ctx,
+                DrawMode::fill(),
+                *point,
+                POWER_UP_RADIUS,
+                0.1,
+                color!(YELLOW),
+            )?;
+            canvas.draw(&circle, DrawParam::default());
+        }
+
+        let text = style.text(format!("Lives: {}", self.lives), 24.0);
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+
+    fn mouse_motion(&mut self, game: &mut ModeContext, point: Point2<f32>) -> bool {
+        self.move_paddle(game.sim, point.x);
+        true
+    }
+
+    fn mouse_down(
+        &mut self,
+        game: &mut ModeContext,
+        button: MouseButton,
+        _point: Point2<f32>,
+    ) -> bool {
+        if button != MouseButton::Left {
+            return false;
+        }
+        self.launch(game);
+        true
+    }
+
+    fn key_down(&mut self, game: &mut ModeContext, key: VirtualKeyCode) -> bool {
+        if key != VirtualKeyCode::Space {
+            return false;
+        }
+        self.launch(game);
+        true
+    }
+}
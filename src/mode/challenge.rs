@@ -0,0 +1,255 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+use serde::Deserialize;
+
+use super::{GameMode, ModeContext};
+use crate::ball::BallId;
+use crate::menu;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+
+/// Directory that challenge files are read from
+pub const CHALLENGES_DIR: &str = "challenges";
+/// Seconds left when the countdown turns red
+const HURRY: f32 = 10.0;
+
+/// What has to be done before time runs out
+#[derive(Clone, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Goal {
+    /// Get balls into a circular drain, which removes them
+    Drain {
+        count: u32,
+        point: Point2<f32>,
+        radius: f32,
+    },
+    /// Click on balls to pop them
+    Pop { count: u32 },
+}
+
+impl Goal {
+    fn count(&self) -> u32 {
+        match *self {
+            Goal::Drain { count, .. } | Goal::Pop { count } => count,
+        }
+    }
+}
+
+/// Task to finish against the clock, from a challenge file
+#[derive(Clone, Deserialize)]
+pub struct Challenge {
+    pub name: String,
+    pub description: String,
+    /// Seconds allowed
+    pub time: f32,
+    pub goal: Goal,
+    pub scene: Scene,
+}
+
+impl Challenge {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&file).map_err(|err| err.to_string())
+    }
+}
+
+/// Challenge files, sorted by name
+pub fn list() -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(CHALLENGES_DIR) else {
+        return Vec::new();
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Outcome {
+    /// Finished, with the seconds it took
+    Success(f32),
+    Failed,
+}
+
+/// Plays a challenge, counting down and showing the results at the end
+pub struct Timed {
+    width: f32,
+    height: f32,
+    challenge: Challenge,
+    elapsed: f32,
+    /// Balls drained or popped so far
+    progress: u32,
+    outcome: Option<Outcome>,
+}
+
+impl Timed {
+    pub fn new(challenge: Challenge, width: f32, height: f32) -> (Self, Scene) {
+        let scene = challenge.scene.clone();
+        let mode = Self {
+            width,
+            height,
+            challenge,
+            elapsed: 0.0,
+            progress: 0,
+            outcome: None,
+        };
+        (mode, scene)
+    }
+
+    fn remaining(&self) -> f32 {
+        (self.challenge.time - self.elapsed).max(0.0)
+    }
+
+    fn count(&mut self, game: &mut ModeContext, done: u32) {
+        self.progress += done;
+        if self.progress >= self.challenge.goal.count() {
+            self.outcome = Some(Outcome::Success(self.elapsed));
+            game.toasts
+                .push(format!("{} complete!", self.challenge.name));
+        }
+    }
+
+    fn draw_results(&self, canvas: &mut Canvas, outcome: Outcome, style: TextStyle) {
+        menu::draw_shade(canvas, self.width, self.height, color!(0, 0, 0, 180));
+        let (title, detail) = match outcome {
+            Outcome::Success(time) => (
+                "Challenge complete!".to_string(),
+                format!(
+                    "Finished in {:.1} of {:.0} seconds",
+                    time, self.challenge.time
+                ),
+            ),
+            Outcome::Failed => (
+                "Time's up".to_string(),
+                format!("{} of {} done", self.progress, self.challenge.goal.count()),
+            ),
+        };
+        let lines = [
+            (title, 48.0),
+            (self.challenge.name.clone(), 24.0),
+            (detail, 24.0),
+            (
+                "Reset to try again, or pause for the menu".to_string(),
+                18.0,
+            ),
+        ];
+        let mut y = self.height / 3.0;
+        for (line, size) in lines {
+            let text = style.text(line, size);
+            canvas.draw(&text, DrawParam::from([style.px(60.0), y]));
+            y += style.px(size * 1.5);
+        }
+    }
+}
+
+impl GameMode for Timed {
+    fn name(&self) -> &'static str {
+        "Challenge"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        game.toasts.push(self.challenge.description.clone());
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        if self.outcome.is_some() {
+            return;
+        }
+        self.elapsed += dt;
+
+        if let Goal::Drain { point, radius, .. } = self.challenge.goal {
+            let drained: Vec<BallId> = game
+                .sim
+                .balls()
+                .filter(|(_, ball)| {
+                    !ball.pinned && (ball.point.x - point.x).hypot(ball.point.y - point.y) < radius
+                })
+                .map(|(id, _)| id)
+                .collect();
+            for &id in &drained {
+                game.sim.remove_ball(id);
+            }
+            self.count(game, drained.len() as u32);
+        }
+
+        if self.outcome.is_none() && self.remaining() == 0.0 {
+            self.outcome = Some(Outcome::Failed);
+            game.toasts.push("Time's up");
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        _sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        if let Goal::Drain { point, radius, .. } = self.challenge.goal {
+            let drain = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(3.0),
+                point,
+                radius,
+                0.1,
+                color!(0x48cae4),
+            )?;
+            canvas.draw(&drain, DrawParam::default());
+        }
+
+        let remaining = self.remaining();
+        let text = style.text(
+            format!(
+                "{}    {}/{}    {}:{:02}",
+                self.challenge.name,
+                self.progress,
+                self.challenge.goal.count(),
+                remaining.ceil() as u32 / 60,
+                remaining.ceil() as u32 % 60
+            ),
+            24.0,
+        );
+        let color = if remaining <= HURRY {
+            color!(RED)
+        } else {
+            color!(WHITE)
+        };
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]).color(color));
+
+        if let Some(outcome) = self.outcome {
+            self.draw_results(canvas, outcome, style);
+        }
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        game: &mut ModeContext,
+        button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if self.outcome.is_some() {
+            return true;
+        }
+        if !matches!(self.challenge.goal, Goal::Pop { .. }) || button != MouseButton::Left {
+            return false;
+        }
+        let Some(id) = game.sim.ball_at(point) else {
+            return true;
+        };
+        if game.sim.ball(id).is_some_and(|ball| !ball.pinned) {
+            game.sim.remove_ball(id);
+            self.count(game, 1);
+        }
+        true
+    }
+}
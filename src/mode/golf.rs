@@ -0,0 +1,451 @@
+use std::fs;
+use std::path::Path;
+
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh, Rect};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+use serde::{Deserialize, Serialize};
+
+use super::slingshot::Slingshot;
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::scene::{Scene, SCENES_DIR};
+use crate::simulation::{Simulation, TICK};
+use crate::text::TextStyle;
+
+/// File in the scenes directory that holes are read from, if it exists
+pub const COURSE_FILE: &str = "course.ron";
+
+const BALL_RADIUS: f32 = 6.0;
+const CUP_RADIUS: f32 = 12.0;
+/// Fastest a ball can be going and still drop into the cup
+const SINK_SPEED: f32 = 4.0;
+/// Fraction of speed kept each tick, as the ball rolls on grass
+const FRICTION: f32 = 0.975;
+/// Speed below which the ball stops rolling
+const STOP_SPEED: f32 = 0.05;
+const WALL_RADIUS: f32 = 5.0;
+const MAX_DRAG: f32 = 150.0;
+const POWER: f32 = 0.1;
+/// Seconds between sinking the ball and teeing off at the next hole
+const NEXT_HOLE_DELAY: f32 = 1.5;
+
+/// Round obstacle
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Bumper {
+    pub point: Point2<f32>,
+    pub radius: f32,
+}
+
+/// Straight obstacle between two points
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Wall {
+    pub from: Point2<f32>,
+    pub to: Point2<f32>,
+}
+
+/// Area sloping one way, which speeds up balls rolling down it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Ramp {
+    /// Top left corner
+    pub min: Point2<f32>,
+    /// Bottom right corner
+    pub max: Point2<f32>,
+    /// Acceleration down the slope, in pixels per tick squared
+    pub slope: Vector2<f32>,
+}
+
+/// One level of a course. Positions are fractions of the window size, so
+/// holes fit any window
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Hole {
+    pub tee: Point2<f32>,
+    pub cup: Point2<f32>,
+    pub par: u32,
+    #[serde(default)]
+    pub bumpers: Vec<Bumper>,
+    #[serde(default)]
+    pub walls: Vec<Wall>,
+    #[serde(default)]
+    pub ramps: Vec<Ramp>,
+}
+
+/// Holes played in order
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Course {
+    pub holes: Vec<Hole>,
+}
+
+impl Course {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let course: Self = ron::from_str(&file).map_err(|err| err.to_string())?;
+        if course.holes.is_empty() {
+            return Err("Course has no holes".to_string());
+        }
+        Ok(course)
+    }
+}
+
+/// Built-in course, played when there is no course file
+impl Default for Course {
+    fn default() -> Self {
+        let point = |x, y| Point2 { x, y };
+        Self {
+            holes: vec![
+                Hole {
+                    tee: point(0.15, 0.5),
+                    cup: point(0.85, 0.5),
+                    par: 2,
+                    bumpers: vec![Bumper {
+                        point: point(0.5, 0.5),
+                        radius: 40.0,
+                    }],
+                    walls: Vec::new(),
+                    ramps: Vec::new(),
+                },
+                Hole {
+                    tee: point(0.15, 0.8),
+                    cup: point(0.85, 0.2),
+                    par: 3,
+                    bumpers: Vec::new(),
+                    walls: vec![Wall {
+                        from: point(0.5, 0.35),
+                        to: point(0.5, 1.0),
+                    }],
+                    ramps: vec![Ramp {
+                        min: point(0.6, 0.0),
+                        max: point(1.0, 0.45),
+                        slope: Vector2 { x: 0.0, y: 0.04 },
+                    }],
+                },
+                Hole {
+                    tee: point(0.1, 0.2),
+                    cup: point(0.9, 0.8),
+                    par: 4,
+                    bumpers: [(0.35, 0.35), (0.5, 0.55), (0.65, 0.35), (0.65, 0.75)]
+                        .into_iter()
+                        .map(|(x, y)| Bumper {
+                            point: point(x, y),
+                            radius: 30.0,
+                        })
+                        .collect(),
+                    walls: vec![Wall {
+                        from: point(0.8, 0.6),
+                        to: point(1.0, 0.6),
+                    }],
+                    ramps: vec![Ramp {
+                        min: point(0.75, 0.65),
+                        max: point(1.0, 1.0),
+                        slope: Vector2 { x: -0.03, y: 0.0 },
+                    }],
+                },
+            ],
+        }
+    }
+}
+
+/// Sink a ball into each hole of a course in as few strokes as possible
+pub struct Golf {
+    width: f32,
+    height: f32,
+    course: Course,
+    /// Problem reading the course file, reported once the game starts
+    load_error: Option<String>,
+    hole: usize,
+    ball: Option<BallId>,
+    /// Pinned balls making up the current hole's obstacles
+    obstacles: Vec<BallId>,
+    aim: Slingshot,
+    strokes: u32,
+    /// Strokes taken on each hole finished so far
+    card: Vec<u32>,
+    /// Seconds until the next hole, after sinking the ball
+    next_hole: Option<f32>,
+}
+
+impl Golf {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let path = Path::new(SCENES_DIR).join(COURSE_FILE);
+        let (course, load_error) = if path.exists() {
+            match Course::load(&path) {
+                Ok(course) => (course, None),
+                Err(err) => (Course::default(), Some(err)),
+            }
+        } else {
+            (Course::default(), None)
+        };
+        let mode = Self {
+            width,
+            height,
+            course,
+            load_error,
+            hole: 0,
+            ball: None,
+            obstacles: Vec::new(),
+            aim: Slingshot::new(MAX_DRAG, POWER),
+            strokes: 0,
+            card: Vec::new(),
+            next_hole: None,
+        };
+        let scene = Scene::new(Vec::new(), 0.0);
+        (mode, scene)
+    }
+
+    /// Scale a point from fractions of the window to pixels
+    fn scale(&self, point: Point2<f32>) -> Point2<f32> {
+        Point2 {
+            x: point.x * self.width,
+            y: point.y * self.height,
+        }
+    }
+
+    /// Clear the last hole and lay out the current one
+    fn set_up_hole(&mut self, sim: &mut Simulation) {
+        for id in self.obstacles.drain(..).chain(self.ball.take()) {
+            sim.remove_ball(id);
+        }
+        let hole = self.course.holes[self.hole].clone();
+
+        let mut obstacles = Vec::new();
+        for bumper in &hole.bumpers {
+            let point = self.scale(bumper.point);
+            obstacles.push(Ball::new(point.x, point.y, bumper.radius, color!(0x6b4226)));
+        }
+        for wall in &hole.walls {
+            let (from, to) = (self.scale(wall.from), self.scale(wall.to));
+            let length = (to.x - from.x).hypot(to.y - from.y);
+            let count = (length / WALL_RADIUS) as usize;
+            for i in 0..=count {
+                let t = i as f32 / count.max(1) as f32;
+                let x = from.x + (to.x - from.x) * t;
+                let y = from.y + (to.y - from.y) * t;
+                obstacles.push(Ball::new(x, y, WALL_RADIUS, color!(0x6b4226)));
+            }
+        }
+        for mut obstacle in obstacles {
+            obstacle.pinned = true;
+            self.obstacles.extend(sim.add_ball(obstacle));
+        }
+
+        let tee = self.scale(hole.tee);
+        self.ball = sim.add_ball(Ball::new(tee.x, tee.y, BALL_RADIUS, color!(WHITE)));
+        self.strokes = 0;
+    }
+
+    fn total_over_par(&self) -> i64 {
+        self.card
+            .iter()
+            .zip(&self.course.holes)
+            .map(|(&strokes, hole)| strokes as i64 - hole.par as i64)
+            .sum()
+    }
+}
+
+/// Score relative to par, such as `+2` or `E` for even
+fn relative(score: i64) -> String {
+    match score {
+        0 => "E".to_string(),
+        score if score > 0 => format!("+{}", score),
+        score => score.to_string(),
+    }
+}
+
+impl GameMode for Golf {
+    fn name(&self) -> &'static str {
+        "Mini-golf"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        if let Some(err) = self.load_error.take() {
+            game.toasts.push(format!(
+                "Failed to load {}, playing the built-in course: {}",
+                COURSE_FILE, err
+            ));
+        }
+        self.set_up_hole(game.sim);
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        if let Some(timer) = &mut self.next_hole {
+            *timer -= dt;
+            if *timer > 0.0 {
+                return;
+            }
+            self.next_hole = None;
+            self.hole += 1;
+            self.set_up_hole(game.sim);
+            return;
+        }
+
+        let Some(id) = self.ball else {
+            return;
+        };
+        let hole = &self.course.holes[self.hole];
+        let ticks = dt / TICK;
+        let ramps: Vec<(Point2<f32>, Point2<f32>, Vector2<f32>)> = hole
+            .ramps
+            .iter()
+            .map(|ramp| (self.scale(ramp.min), self.scale(ramp.max), ramp.slope))
+            .collect();
+        let cup = self.scale(hole.cup);
+        let Some(mut ball) = game.sim.ball_mut(id) else {
+            return;
+        };
+        for (min, max, slope) in ramps {
+            let on_ramp =
+                (min.x..max.x).contains(&ball.point.x) && (min.y..max.y).contains(&ball.point.y);
+            if on_ramp {
+                ball.velocity.x += slope.x * ticks;
+                ball.velocity.y += slope.y * ticks;
+            }
+        }
+        let friction = FRICTION.powf(ticks);
+        ball.velocity.x *= friction;
+        ball.velocity.y *= friction;
+        if ball.speed() < STOP_SPEED {
+            ball.velocity = Vector2 { x: 0.0, y: 0.0 };
+        }
+        // The top of the window has no wall
+        if ball.point.y < BALL_RADIUS {
+            ball.point.y = BALL_RADIUS;
+            ball.velocity.y = ball.velocity.y.abs();
+        }
+        let in_cup = (ball.point.x - cup.x).hypot(ball.point.y - cup.y) < CUP_RADIUS;
+        if !in_cup || ball.speed() > SINK_SPEED {
+            return;
+        }
+        drop(ball);
+
+        game.sim.remove_ball(id);
+        self.ball = None;
+        self.card.push(self.strokes);
+        let par = hole.par;
+        let name = match self.strokes as i64 - par as i64 {
+            _ if self.strokes == 1 => "Hole in one!".to_string(),
+            -2 => "Eagle!".to_string(),
+            -1 => "Birdie!".to_string(),
+            0 => "Par".to_string(),
+            1 => "Bogey".to_string(),
+            over => format!("{} over par", over),
+        };
+        game.toasts
+            .push(format!("{} ({} strokes)", name, self.strokes));
+        if self.hole + 1 < self.course.holes.len() {
+            self.next_hole = Some(NEXT_HOLE_DELAY);
+        } else {
+            let total: u32 = self.card.iter().sum();
+            game.toasts.push(format!(
+                "Course finished in {} strokes ({}), reset to play again",
+                total,
+                relative(self.total_over_par())
+            ));
+        }
+    }
+
+    fn aim(&self) -> Option<(BallId, Vector2<f32>)> {
+        Some((self.ball?, self.aim.shot()?))
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        let hole = &self.course.holes[self.hole];
+        for ramp in &hole.ramps {
+            let (min, max) = (self.scale(ramp.min), self.scale(ramp.max));
+            let rect = Rect::new(min.x, min.y, max.x - min.x, max.y - min.y);
+            let area = Mesh::new_rectangle(ctx, DrawMode::stroke(1.0), rect, color!(80, 80, 80))?;
+            canvas.draw(&area, DrawParam::default());
+            // Arrow down the slope
+            let center = Point2 {
+                x: (min.x + max.x) / 2.0,
+                y: (min.y + max.y) / 2.0,
+            };
+            let length = ramp.slope.x.hypot(ramp.slope.y);
+            if length > 0.0 {
+                let tip = Point2 {
+                    x: center.x + ramp.slope.x / length * 30.0,
+                    y: center.y + ramp.slope.y / length * 30.0,
+                };
+                let arrow = Mesh::new_line(ctx, &[center, tip], 2.0, color!(80, 80, 80))?;
+                canvas.draw(&arrow, DrawParam::default());
+            }
+        }
+
+        let cup = Mesh::new_circle(
+            ctx,
+            DrawMode::fill(),
+            self.scale(hole.cup),
+            CUP_RADIUS,
+            0.1,
+            color!(60, 60, 60),
+        )?;
+        canvas.draw(&cup, DrawParam::default());
+
+        if let Some(ball) = self.ball.and_then(|id| sim.ball(id)) {
+            self.aim.draw(ctx, canvas, ball.point)?;
+        }
+
+        let text = style.text(
+            format!(
+                "Hole {}/{}    Par {}    Strokes: {}    Total: {}",
+                self.hole + 1,
+                self.course.holes.len(),
+                hole.par,
+                self.strokes,
+                relative(self.total_over_par())
+            ),
+            24.0,
+        );
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        game: &mut ModeContext,
+        button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if button != MouseButton::Left {
+            return false;
+        }
+        let Some(ball) = self.ball.and_then(|id| game.sim.ball(id)) else {
+            return true;
+        };
+        if ball.speed() > 0.0 {
+            game.toasts.push("Wait for the ball to stop");
+            return true;
+        }
+        self.aim.press(point);
+        true
+    }
+
+    fn mouse_motion(&mut self, _game: &mut ModeContext, point: Point2<f32>) -> bool {
+        self.aim.drag(point)
+    }
+
+    fn mouse_up(
+        &mut self,
+        game: &mut ModeContext,
+        _button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if !self.aim.is_aiming() {
+            return false;
+        }
+        let Some(velocity) = self.aim.release(point) else {
+            return true;
+        };
+        if let Some(mut ball) = self.ball.and_then(|id| game.sim.ball_mut(id)) {
+            ball.velocity = velocity;
+            self.strokes += 1;
+        }
+        true
+    }
+}
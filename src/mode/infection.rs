@@ -0,0 +1,139 @@
+use std::collections::HashSet;
+
+use ggez::graphics::{Canvas, Color, DrawParam};
+use ggez::mint::Vector2;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::Event;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+
+const BALLS: usize = 40;
+const INFECTED_COLOR: Color = color!(0x52b788);
+/// Slowest a ball is allowed to drift, so the spread never stalls
+const MIN_SPEED: f32 = 1.5;
+
+/// One ball turns every ball it touches its color, until all of them are
+pub struct Infection {
+    width: f32,
+    height: f32,
+    infected: HashSet<BallId>,
+    elapsed: f32,
+    /// Seconds it took to infect every ball, once it has
+    finished: Option<f32>,
+}
+
+impl Infection {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mode = Self {
+            width,
+            height,
+            infected: HashSet::new(),
+            elapsed: 0.0,
+            finished: None,
+        };
+        let scene = Scene::new(Vec::new(), 0.0);
+        (mode, scene)
+    }
+}
+
+impl GameMode for Infection {
+    fn name(&self) -> &'static str {
+        "Infection"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        for i in 0..BALLS {
+            let radius = game.rng.gen_range(10.0..18.0);
+            let x = game.rng.gen_range(radius..self.width - radius);
+            let y = game.rng.gen_range(radius..self.height - radius);
+            let color = if i == 0 {
+                INFECTED_COLOR
+            } else {
+                color!(200, 200, 200)
+            };
+            let mut ball = Ball::new(x, y, radius, color);
+            ball.restitution = 1.0;
+            let angle: f32 = game.rng.gen_range(0.0..std::f32::consts::TAU);
+            ball.velocity = Vector2 {
+                x: angle.cos() * MIN_SPEED * 2.0,
+                y: angle.sin() * MIN_SPEED * 2.0,
+            };
+            if let Some(id) = game.sim.add_ball(ball) {
+                if i == 0 {
+                    self.infected.insert(id);
+                }
+            }
+        }
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        let contacts: Vec<(BallId, BallId)> = game
+            .sim
+            .events()
+            .iter()
+            .filter_map(|event| match *event {
+                Event::BallHitBall { a, b, .. } => Some((a, b)),
+                _ => None,
+            })
+            .collect();
+        for (a, b) in contacts {
+            let caught = match (self.infected.contains(&a), self.infected.contains(&b)) {
+                (true, false) => b,
+                (false, true) => a,
+                _ => continue,
+            };
+            if let Some(mut ball) = game.sim.ball_mut(caught) {
+                ball.color = INFECTED_COLOR;
+                self.infected.insert(caught);
+            }
+        }
+        self.infected.retain(|&id| game.sim.ball(id).is_some());
+
+        // Keep balls drifting, and bounce them off the top of the window
+        game.sim.update_balls(|ball| {
+            if ball.point.y < ball.radius {
+                ball.point.y = ball.radius;
+                ball.velocity.y = ball.velocity.y.abs();
+            }
+            let speed = ball.speed();
+            if speed > 0.0 && speed < MIN_SPEED {
+                ball.velocity.x *= MIN_SPEED / speed;
+                ball.velocity.y *= MIN_SPEED / speed;
+            }
+        });
+
+        if self.finished.is_some() {
+            return;
+        }
+        self.elapsed += dt;
+        let total = game.sim.ball_count();
+        if total > 0 && self.infected.len() == total {
+            self.finished = Some(self.elapsed);
+            game.toasts.push(format!(
+                "Every ball infected in {:.1} seconds",
+                self.elapsed
+            ));
+        }
+    }
+
+    fn draw(
+        &self,
+        _ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        let mut label = format!("Infected: {}/{}", self.infected.len(), sim.ball_count());
+        if let Some(time) = self.finished {
+            label.push_str(&format!("    All infected in {:.1}s", time));
+        }
+        let text = style.text(label, 24.0);
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+}
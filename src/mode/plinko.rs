@@ -0,0 +1,136 @@
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, DrawParam};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::Event;
+use crate::scene::{self, Scene};
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+
+/// Points for landing in each bin, from left to right
+const BIN_VALUES: [u32; 7] = [100, 50, 20, 10, 20, 50, 100];
+const BIN_HEIGHT: f32 = 60.0;
+const DIVIDER_RADIUS: f32 = 4.0;
+const BALL_RADIUS: f32 = 12.0;
+const BALLS_PER_GAME: u32 = 10;
+/// Speed below which a ball in a bin has settled and is scored
+const SETTLED_SPEED: f32 = 0.5;
+
+/// Drop balls through pegs into bins worth points
+pub struct Plinko {
+    width: f32,
+    height: f32,
+    balls_left: u32,
+    /// Dropped balls that haven't landed yet
+    falling: Vec<BallId>,
+}
+
+impl Plinko {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mut balls = scene::pegs(width, height);
+        let bin_width = width / BIN_VALUES.len() as f32;
+        for i in 1..BIN_VALUES.len() {
+            let mut y = height - DIVIDER_RADIUS;
+            while y > height - BIN_HEIGHT {
+                let mut divider = Ball::new(i as f32 * bin_width, y, DIVIDER_RADIUS, color!(GREEN));
+                divider.pinned = true;
+                balls.push(divider);
+                y -= DIVIDER_RADIUS * 2.0;
+            }
+        }
+        let mode = Self {
+            width,
+            height,
+            balls_left: BALLS_PER_GAME,
+            falling: Vec::new(),
+        };
+        let scene = Scene::new(balls, 0.5);
+        (mode, scene)
+    }
+
+    /// Height above which balls can be dropped, clear of the pegs
+    fn drop_line(&self) -> f32 {
+        self.height * 0.25 - 30.0
+    }
+}
+
+impl GameMode for Plinko {
+    fn name(&self) -> &'static str {
+        "Plinko"
+    }
+
+    fn update(&mut self, game: &mut ModeContext, _dt: f32) {
+        let bin_width = self.width / BIN_VALUES.len() as f32;
+        let floor = self.height - BIN_HEIGHT;
+        let mut landed = Vec::new();
+        self.falling.retain(|&id| {
+            let Some(ball) = game.sim.ball(id) else {
+                return false;
+            };
+            if ball.point.y < floor || ball.speed() > SETTLED_SPEED {
+                return true;
+            }
+            let bin = ((ball.point.x / bin_width) as usize).min(BIN_VALUES.len() - 1);
+            landed.push((id, ball.point, BIN_VALUES[bin]));
+            false
+        });
+
+        for (id, point, points) in landed {
+            game.sim.remove_ball(id);
+            game.sim.emit(Event::Scored { points, point });
+            if self.balls_left == 0 && self.falling.is_empty() {
+                game.toasts.push("All balls dropped, reset to play again");
+            }
+        }
+    }
+
+    fn draw(
+        &self,
+        _ctx: &mut Context,
+        canvas: &mut Canvas,
+        _sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        let bin_width = self.width / BIN_VALUES.len() as f32;
+        for (i, value) in BIN_VALUES.iter().enumerate() {
+            let text = style.text(value.to_string(), 18.0);
+            let x = (i as f32 + 0.5) * bin_width - style.px(14.0);
+            canvas.draw(
+                &text,
+                DrawParam::from([x, self.height - BIN_HEIGHT - style.px(24.0)])
+                    .color(color!(128, 128, 128)),
+            );
+        }
+
+        let text = style.text(format!("Balls left: {}", self.balls_left), 24.0);
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        game: &mut ModeContext,
+        button: MouseButton,
+        point: Point2<f32>,
+    ) -> bool {
+        if button != MouseButton::Left || point.y > self.drop_line() {
+            return button == MouseButton::Left;
+        }
+        if self.balls_left == 0 {
+            game.toasts.push("No balls left, reset to play again");
+            return true;
+        }
+        let x = point.x.clamp(BALL_RADIUS, self.width - BALL_RADIUS);
+        let rng = &mut game.rng;
+        let ball = Ball::new(x, BALL_RADIUS, BALL_RADIUS, color!(?rng));
+        if let Some(id) = game.sim.add_ball(ball) {
+            self.balls_left -= 1;
+            self.falling.push(id);
+        }
+        true
+    }
+}
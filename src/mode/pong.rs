@@ -0,0 +1,295 @@
+use ggez::event::MouseButton;
+use ggez::graphics::{Canvas, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::{Event, Wall};
+use crate::obstacle::Obstacle;
+use crate::scene::Scene;
+use crate::simulation::{Simulation, TICK};
+use crate::text::TextStyle;
+
+const PADDLE_WIDTH: f32 = 16.0;
+const PADDLE_HEIGHT: f32 = 100.0;
+/// Gap between each paddle and its side of the window
+const PADDLE_INSET: f32 = 30.0;
+/// Pixels a paddle moves per tick
+const PADDLE_SPEED: f32 = 7.0;
+const BALL_RADIUS: f32 = 10.0;
+const SERVE_SPEED: f32 = 6.0;
+/// Speed added to the ball every time a paddle returns it
+const SPEED_UP: f32 = 0.3;
+/// Seconds between a point and the next serve
+const SERVE_DELAY: f32 = 1.0;
+const WINNING_SCORE: u32 = 7;
+
+/// Obstacle moved by the keyboard, which the ball bounces off
+struct Paddle {
+    /// Index of the paddle's obstacle in the simulation
+    obstacle: usize,
+    /// Centre of the paddle
+    y: f32,
+    up: VirtualKeyCode,
+    down: VirtualKeyCode,
+    /// Direction the paddle is moving in, from its held keys
+    moving: f32,
+    score: u32,
+}
+
+impl Paddle {
+    fn new(up: VirtualKeyCode, down: VirtualKeyCode) -> Self {
+        Self {
+            obstacle: 0,
+            y: 0.0,
+            up,
+            down,
+            moving: 0.0,
+            score: 0,
+        }
+    }
+
+    /// Move the obstacle to where the paddle is
+    fn place(&self, sim: &mut Simulation) {
+        if let Some(obstacle) = sim.obstacles.get_mut(self.obstacle) {
+            obstacle.centre.y = self.y;
+        }
+    }
+}
+
+/// Two players return a ball with paddles, scoring when it gets past the other
+pub struct Pong {
+    width: f32,
+    height: f32,
+    /// Left and right paddles
+    paddles: [Paddle; 2],
+    ball: Option<BallId>,
+    speed: f32,
+    /// Seconds until the next serve, and which way it goes
+    serve: Option<(f32, f32)>,
+}
+
+impl Pong {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mode = Self {
+            width,
+            height,
+            paddles: [
+                Paddle::new(VirtualKeyCode::W, VirtualKeyCode::S),
+                Paddle::new(VirtualKeyCode::Up, VirtualKeyCode::Down),
+            ],
+            ball: None,
+            speed: SERVE_SPEED,
+            serve: Some((SERVE_DELAY, 1.0)),
+        };
+        let scene = Scene::new(Vec::new(), 0.0);
+        (mode, scene)
+    }
+
+    fn winner(&self) -> Option<usize> {
+        self.paddles
+            .iter()
+            .position(|paddle| paddle.score >= WINNING_SCORE)
+    }
+
+    /// Put a still ball in the middle, to serve after a delay
+    fn reset_ball(&mut self, sim: &mut Simulation, direction: f32) {
+        if let Some(id) = self.ball.take() {
+            sim.remove_ball(id);
+        }
+        let mut ball = Ball::new(
+            self.width / 2.0,
+            self.height / 2.0,
+            BALL_RADIUS,
+            color!(WHITE),
+        );
+        ball.restitution = 1.0;
+        self.ball = sim.add_ball(ball);
+        self.speed = SERVE_SPEED;
+        self.serve = Some((SERVE_DELAY, direction));
+    }
+}
+
+impl GameMode for Pong {
+    fn name(&self) -> &'static str {
+        "Pong"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        let xs = [PADDLE_INSET, self.width - PADDLE_INSET];
+        for (paddle, x) in self.paddles.iter_mut().zip(xs) {
+            paddle.y = self.height / 2.0;
+            paddle.obstacle = game.sim.obstacles.len();
+            let centre = Point2 { x, y: paddle.y };
+            game.sim
+                .obstacles
+                .push(Obstacle::new(centre, PADDLE_WIDTH, PADDLE_HEIGHT));
+        }
+        // Only balloons hit the ceiling, so the top of the court is an obstacle
+        // just out of sight
+        let above = Point2 {
+            x: self.width / 2.0,
+            y: -self.height / 2.0,
+        };
+        game.sim
+            .obstacles
+            .push(Obstacle::new(above, self.width, self.height));
+        self.reset_ball(game.sim, 1.0);
+    }
+
+    fn update(&mut self, game: &mut ModeContext, dt: f32) {
+        let half = PADDLE_HEIGHT / 2.0;
+        for paddle in &mut self.paddles {
+            paddle.y += paddle.moving * PADDLE_SPEED * dt / TICK;
+            paddle.y = paddle.y.clamp(half, self.height - half);
+            paddle.place(game.sim);
+        }
+
+        let Some(id) = self.ball else {
+            return;
+        };
+        let over = self.winner().is_some();
+        if let Some((timer, direction)) = &mut self.serve {
+            *timer -= dt;
+            if *timer > 0.0 || over {
+                return;
+            }
+            let angle: f32 = game.rng.gen_range(-0.6..0.6);
+            if let Some(mut ball) = game.sim.ball_mut(id) {
+                ball.velocity = Vector2 {
+                    x: angle.cos() * self.speed * *direction,
+                    y: angle.sin() * self.speed,
+                };
+            }
+            self.serve = None;
+            return;
+        }
+
+        // Getting past a paddle means hitting the wall behind it
+        let missed = game.sim.events().iter().find_map(|event| match event {
+            Event::BallHitWall { ball, wall, .. } if *ball == id => match wall {
+                Wall::Left => Some(1),
+                Wall::Right => Some(0),
+                Wall::Floor | Wall::Ceiling => None,
+            },
+            _ => None,
+        });
+        if let Some(scorer) = missed {
+            self.paddles[scorer].score += 1;
+            let [left, right] = [self.paddles[0].score, self.paddles[1].score];
+            if self.winner().is_some() {
+                let side = if scorer == 0 { "Left" } else { "Right" };
+                game.toasts.push(format!(
+                    "{} player wins {}-{}, reset to play again",
+                    side, left, right
+                ));
+            } else {
+                game.toasts.push(format!("{}-{}", left, right));
+            }
+            // Serve towards whoever lost the point
+            let direction = if scorer == 0 { 1.0 } else { -1.0 };
+            self.reset_ball(game.sim, direction);
+            return;
+        }
+
+        let returned = game.sim.events().iter().any(|event| match *event {
+            Event::BallHitObstacle { ball, obstacle, .. } => {
+                ball == id
+                    && self
+                        .paddles
+                        .iter()
+                        .any(|paddle| paddle.obstacle == obstacle)
+            }
+            _ => false,
+        });
+        if returned {
+            self.speed += SPEED_UP;
+        }
+
+        // Bounces lose speed, so keep the ball moving at the rally's speed
+        let speed = self.speed;
+        let Some(mut ball) = game.sim.ball_mut(id) else {
+            return;
+        };
+        // Never let the ball get stuck going straight up and down
+        let min_across = speed * 0.3;
+        if ball.velocity.x.abs() < min_across {
+            ball.velocity.x = min_across.copysign(ball.velocity.x);
+        }
+        let current = ball.speed();
+        if current > 0.0 {
+            ball.velocity.x *= speed / current;
+            ball.velocity.y *= speed / current;
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        _sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        let x = self.width / 2.0;
+        let mut y = 0.0;
+        while y < self.height {
+            let dash = Mesh::new_line(
+                ctx,
+                &[Point2 { x, y }, Point2 { x, y: y + 12.0 }],
+                2.0,
+                color!(80, 80, 80),
+            )?;
+            canvas.draw(&dash, DrawParam::default());
+            y += 24.0;
+        }
+
+        let text = style.text(
+            format!("{}    {}", self.paddles[0].score, self.paddles[1].score),
+            40.0,
+        );
+        canvas.draw(&text, DrawParam::from([x - style.px(40.0), 20.0]));
+        Ok(())
+    }
+
+    fn mouse_down(
+        &mut self,
+        _game: &mut ModeContext,
+        button: MouseButton,
+        _point: Point2<f32>,
+    ) -> bool {
+        button == MouseButton::Left
+    }
+
+    fn key_down(&mut self, _game: &mut ModeContext, key: VirtualKeyCode) -> bool {
+        for paddle in &mut self.paddles {
+            if key == paddle.up {
+                paddle.moving = -1.0;
+                return true;
+            }
+            if key == paddle.down {
+                paddle.moving = 1.0;
+                return true;
+            }
+        }
+        false
+    }
+
+    fn key_up(&mut self, _game: &mut ModeContext, key: VirtualKeyCode) {
+        for paddle in &mut self.paddles {
+            let direction = if key == paddle.up {
+                -1.0
+            } else if key == paddle.down {
+                1.0
+            } else {
+                continue;
+            };
+            // Keep moving if the other key is still held
+            if paddle.moving == direction {
+                paddle.moving = 0.0;
+            }
+        }
+    }
+}
@@ -0,0 +1,205 @@
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::Event;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+
+const BALLS: usize = 30;
+const PLAYER_RADIUS: f32 = 16.0;
+/// How much bigger a ball must be than another to eat it
+const EAT_RATIO: f32 = 1.15;
+/// Speed the player gains each tick towards the cursor
+const STEER: f32 = 0.3;
+/// Top speed of a ball the size of the player at the start, with bigger balls slower
+const TOP_SPEED: f32 = 5.0;
+/// Slowest the other balls are allowed to drift
+const DRIFT_SPEED: f32 = 1.0;
+
+/// Eat smaller balls to grow, and avoid bigger ones, until one ball is left
+pub struct Predator {
+    width: f32,
+    height: f32,
+    player: Option<BallId>,
+    /// Where the player is steering towards
+    cursor: Point2<f32>,
+    over: bool,
+}
+
+impl Predator {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mode = Self {
+            width,
+            height,
+            player: None,
+            cursor: Point2 {
+                x: width / 2.0,
+                y: height / 2.0,
+            },
+            over: false,
+        };
+        let scene = Scene::new(Vec::new(), 0.0);
+        (mode, scene)
+    }
+
+    /// Bigger balls eat smaller ones they touch, taking on their area
+    fn eat(&mut self, game: &mut ModeContext) {
+        let contacts: Vec<(BallId, BallId)> = game
+            .sim
+            .events()
+            .iter()
+            .filter_map(|event| match *event {
+                Event::BallHitBall { a, b, .. } => Some((a, b)),
+                _ => None,
+            })
+            .collect();
+        for (a, b) in contacts {
+            let (Some(ball_a), Some(ball_b)) = (game.sim.ball(a), game.sim.ball(b)) else {
+                continue;
+            };
+            let ((eater, predator), (eaten, prey)) = if ball_a.radius > ball_b.radius * EAT_RATIO {
+                ((a, ball_a), (b, ball_b))
+            } else if ball_b.radius > ball_a.radius * EAT_RATIO {
+                ((b, ball_b), (a, ball_a))
+            } else {
+                continue;
+            };
+            game.sim.remove_ball(eaten);
+            game.sim
+                .set_radius(eater, predator.radius.hypot(prey.radius));
+            if Some(eaten) == self.player {
+                self.player = None;
+                game.toasts.push("You were eaten! Reset to try again");
+            }
+        }
+    }
+}
+
+/// Fastest a ball of a radius can move
+fn top_speed(radius: f32) -> f32 {
+    TOP_SPEED * (PLAYER_RADIUS / radius).sqrt()
+}
+
+impl GameMode for Predator {
+    fn name(&self) -> &'static str {
+        "Predator"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        let mut player = Ball::new(self.cursor.x, self.cursor.y, PLAYER_RADIUS, color!(WHITE));
+        player.restitution = 1.0;
+        self.player = game.sim.add_ball(player);
+
+        for _ in 0..BALLS {
+            let radius = game.rng.gen_range(6.0..PLAYER_RADIUS * 1.8);
+            let x = game.rng.gen_range(radius..self.width - radius);
+            let y = game.rng.gen_range(radius..self.height - radius);
+            // Keep clear of the player at the start
+            if (x - self.cursor.x).hypot(y - self.cursor.y) < radius + PLAYER_RADIUS * 4.0 {
+                continue;
+            }
+            let mut ball = Ball::new(x, y, radius, color!(?game.rng));
+            ball.restitution = 1.0;
+            let angle: f32 = game.rng.gen_range(0.0..std::f32::consts::TAU);
+            ball.velocity = Vector2 {
+                x: angle.cos() * DRIFT_SPEED,
+                y: angle.sin() * DRIFT_SPEED,
+            };
+            game.sim.add_ball(ball);
+        }
+    }
+
+    fn update(&mut self, game: &mut ModeContext, _dt: f32) {
+        self.eat(game);
+
+        if let Some(mut player) = self.player.and_then(|id| game.sim.ball_mut(id)) {
+            let (dx, dy) = (
+                self.cursor.x - player.point.x,
+                self.cursor.y - player.point.y,
+            );
+            let distance = dx.hypot(dy);
+            if distance > player.radius / 2.0 {
+                player.velocity.x += dx / distance * STEER;
+                player.velocity.y += dy / distance * STEER;
+            } else {
+                player.velocity.x *= 0.9;
+                player.velocity.y *= 0.9;
+            }
+        }
+
+        // Keep everything moving no faster than its size allows, and bounce
+        // off the top of the window
+        let player = self.player;
+        let ids: Vec<BallId> = game.sim.balls().map(|(id, _)| id).collect();
+        for id in ids {
+            let Some(mut ball) = game.sim.ball_mut(id) else {
+                continue;
+            };
+            if ball.point.y < ball.radius {
+                ball.point.y = ball.radius;
+                ball.velocity.y = ball.velocity.y.abs();
+            }
+            let speed = ball.speed();
+            let limit = top_speed(ball.radius);
+            let target = if speed > limit {
+                limit
+            } else if Some(id) != player && speed < DRIFT_SPEED {
+                DRIFT_SPEED
+            } else {
+                continue;
+            };
+            if speed > 0.0 {
+                ball.velocity.x *= target / speed;
+                ball.velocity.y *= target / speed;
+            }
+        }
+
+        if !self.over && game.sim.ball_count() <= 1 {
+            self.over = true;
+            game.toasts.push(if self.player.is_some() {
+                "You ate everything!"
+            } else {
+                "One ball is left"
+            });
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        let player = self.player.and_then(|id| sim.ball(id));
+        if let Some(player) = player {
+            let ring = Mesh::new_circle(
+                ctx,
+                DrawMode::stroke(3.0),
+                player.point,
+                player.radius + 4.0,
+                0.1,
+                color!(YELLOW),
+            )?;
+            canvas.draw(&ring, DrawParam::default());
+        }
+
+        let mut label = format!("Balls: {}", sim.ball_count());
+        if let Some(player) = player {
+            label.push_str(&format!("    Size: {:.0}", player.radius));
+        }
+        let text = style.text(label, 24.0);
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+
+    fn mouse_motion(&mut self, _game: &mut ModeContext, point: Point2<f32>) -> bool {
+        self.cursor = point;
+        false
+    }
+}
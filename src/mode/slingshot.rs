@@ -0,0 +1,88 @@
+use ggez::graphics::{Canvas, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use ggez::{Context, GameResult};
+
+/// Drag back and let go to shoot, with power from the drag length
+pub struct Slingshot {
+    /// Longest drag counted towards a shot's power
+    max_drag: f32,
+    /// Speed per pixel dragged
+    power: f32,
+    /// Where the current drag started, and where the cursor is now
+    drag: Option<(Point2<f32>, Point2<f32>)>,
+}
+
+impl Slingshot {
+    pub fn new(max_drag: f32, power: f32) -> Self {
+        Self {
+            max_drag,
+            power,
+            drag: None,
+        }
+    }
+
+    pub fn press(&mut self, point: Point2<f32>) {
+        self.drag = Some((point, point));
+    }
+
+    /// Follow the cursor, returning whether a drag is underway
+    pub fn drag(&mut self, point: Point2<f32>) -> bool {
+        match &mut self.drag {
+            Some((_, end)) => {
+                *end = point;
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// End the drag, with the velocity to shoot at if it was long enough
+    pub fn release(&mut self, point: Point2<f32>) -> Option<Vector2<f32>> {
+        self.drag(point);
+        let shot = self.shot();
+        self.drag = None;
+        shot
+    }
+
+    pub fn is_aiming(&self) -> bool {
+        self.drag.is_some()
+    }
+
+    /// Velocity to shoot at if the drag ended where the cursor is now
+    pub fn shot(&self) -> Option<Vector2<f32>> {
+        let (start, end) = self.drag?;
+        let pull = self.pull(start, end)?;
+        Some(Vector2 {
+            x: pull.x * self.power,
+            y: pull.y * self.power,
+        })
+    }
+
+    /// Pull from the start of a drag, limited to the longest drag
+    fn pull(&self, start: Point2<f32>, end: Point2<f32>) -> Option<Vector2<f32>> {
+        let (x, y) = (start.x - end.x, start.y - end.y);
+        let length = x.hypot(y);
+        if length < 1.0 {
+            return None;
+        }
+        let scale = length.min(self.max_drag) / length;
+        Some(Vector2 {
+            x: x * scale,
+            y: y * scale,
+        })
+    }
+
+    /// Line from a ball in the direction and length of the shot
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, from: Point2<f32>) -> GameResult {
+        let Some(pull) = self.drag.and_then(|(start, end)| self.pull(start, end)) else {
+            return Ok(());
+        };
+        let tip = Point2 {
+            x: from.x + pull.x,
+            y: from.y + pull.y,
+        };
+        let line = Mesh::new_line(ctx, &[from, tip], 2.0, color!(WHITE))?;
+        canvas.draw(&line, DrawParam::default());
+        Ok(())
+    }
+}
@@ -0,0 +1,177 @@
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh};
+use ggez::{Context, GameResult};
+use rand::Rng;
+
+use super::{GameMode, ModeContext};
+use crate::ball::{Ball, BallId};
+use crate::events::Event;
+use crate::scene::Scene;
+use crate::simulation::Simulation;
+use crate::text::TextStyle;
+
+const TARGET_RADIUS: f32 = 30.0;
+/// Targets in the first wave, with one more each wave after
+const FIRST_WAVE: u32 = 4;
+/// Slowest hit that pops a target
+const POP_SPEED: f32 = 4.0;
+/// Size of the targets a popped target splits into, relative to it
+const SPLIT_SCALE: f32 = 0.6;
+/// Balls to throw, lined up along the floor
+const AMMO: usize = 8;
+const AMMO_RADIUS: f32 = 14.0;
+
+/// Target that pops when a thrown ball hits it hard enough
+struct Target {
+    points: u32,
+    /// Times it splits in two when popped, instead of just vanishing
+    splits: u32,
+}
+
+/// Throw balls at floating targets, which split into smaller ones worth more
+pub struct Targets {
+    width: f32,
+    height: f32,
+    targets: Vec<(BallId, Target)>,
+    wave: u32,
+}
+
+impl Targets {
+    pub fn new(width: f32, height: f32) -> (Self, Scene) {
+        let mode = Self {
+            width,
+            height,
+            targets: Vec::new(),
+            wave: 0,
+        };
+        let balls = (0..AMMO)
+            .map(|i| {
+                let x = AMMO_RADIUS * (2 * i + 1) as f32 + 10.0;
+                Ball::new(x, height - AMMO_RADIUS, AMMO_RADIUS, color!(WHITE))
+            })
+            .collect();
+        let scene = Scene::new(balls, 0.5);
+        (mode, scene)
+    }
+
+    fn add_target(&mut self, sim: &mut Simulation, x: f32, y: f32, radius: f32, target: Target) {
+        let mut ball = Ball::new(x, y, radius, color!(0xe63946));
+        ball.pinned = true;
+        if let Some(id) = sim.add_ball(ball) {
+            self.targets.push((id, target));
+        }
+    }
+
+    fn next_wave(&mut self, game: &mut ModeContext) {
+        self.wave += 1;
+        for _ in 0..FIRST_WAVE + self.wave - 1 {
+            let margin = TARGET_RADIUS * 2.0;
+            let x = game.rng.gen_range(self.width * 0.3..self.width - margin);
+            let y = game.rng.gen_range(margin..self.height * 0.6);
+            let target = Target {
+                points: 50,
+                splits: 1,
+            };
+            self.add_target(game.sim, x, y, TARGET_RADIUS, target);
+        }
+        if self.wave > 1 {
+            game.toasts.push(format!("Wave {}", self.wave));
+        }
+    }
+
+    /// Targets hit hard enough by a thrown ball
+    fn hit_targets(&self, sim: &Simulation) -> Vec<BallId> {
+        let thrown = &sim.components.thrown;
+        let mut hit: Vec<BallId> = sim
+            .events()
+            .iter()
+            .filter_map(|event| match *event {
+                Event::BallHitBall { a, b, speed } if speed >= POP_SPEED => {
+                    if thrown.contains_key(a) {
+                        Some(b)
+                    } else if thrown.contains_key(b) {
+                        Some(a)
+                    } else {
+                        None
+                    }
+                }
+                _ => None,
+            })
+            .filter(|id| self.targets.iter().any(|(target, _)| target == id))
+            .collect();
+        hit.dedup();
+        hit
+    }
+}
+
+impl GameMode for Targets {
+    fn name(&self) -> &'static str {
+        "Targets"
+    }
+
+    fn start(&mut self, game: &mut ModeContext) {
+        self.next_wave(game);
+    }
+
+    fn update(&mut self, game: &mut ModeContext, _dt: f32) {
+        for id in self.hit_targets(game.sim) {
+            let Some(i) = self.targets.iter().position(|(target, _)| *target == id) else {
+                continue;
+            };
+            let (_, target) = self.targets.remove(i);
+            let Some(ball) = game.sim.pop_ball(id) else {
+                continue;
+            };
+            game.sim.emit(Event::Scored {
+                points: target.points,
+                point: ball.point,
+            });
+            if target.splits > 0 {
+                let radius = ball.radius * SPLIT_SCALE;
+                for side in [-1.0, 1.0] {
+                    let piece = Target {
+                        points: target.points * 2,
+                        splits: target.splits - 1,
+                    };
+                    let x = ball.point.x + side * radius * 1.5;
+                    self.add_target(game.sim, x, ball.point.y, radius, piece);
+                }
+            }
+        }
+
+        if self.targets.is_empty() {
+            self.next_wave(game);
+        }
+    }
+
+    fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        sim: &Simulation,
+        style: TextStyle,
+    ) -> GameResult {
+        for (id, _) in &self.targets {
+            let Some(ball) = sim.ball(*id) else {
+                continue;
+            };
+            for scale in [0.65, 0.3] {
+                let ring = Mesh::new_circle(
+                    ctx,
+                    DrawMode::stroke(3.0),
+                    ball.point,
+                    ball.radius * scale,
+                    0.1,
+                    color!(WHITE),
+                )?;
+                canvas.draw(&ring, DrawParam::default());
+            }
+        }
+
+        let text = style.text(
+            format!("Wave: {}    Targets: {}", self.wave, self.targets.len()),
+            24.0,
+        );
+        canvas.draw(&text, DrawParam::from([10.0, 10.0]));
+        Ok(())
+    }
+}
@@ -0,0 +1,98 @@
+use ggez::graphics::Rect;
+use ggez::mint::{Point2, Vector2};
+
+/// Solid rectangle balls bounce off, such as a paddle moved by a game
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Obstacle {
+    pub centre: Point2<f32>,
+    /// Half the width and height
+    pub half_size: Vector2<f32>,
+}
+
+impl Obstacle {
+    pub fn new(centre: Point2<f32>, width: f32, height: f32) -> Self {
+        Self {
+            centre,
+            half_size: Vector2 {
+                x: width / 2.0,
+                y: height / 2.0,
+            },
+        }
+    }
+
+    pub fn rect(&self) -> Rect {
+        Rect::new(
+            self.centre.x - self.half_size.x,
+            self.centre.y - self.half_size.y,
+            self.half_size.x * 2.0,
+            self.half_size.y * 2.0,
+        )
+    }
+
+    /// Direction out of the obstacle and how far a circle overlapping it has
+    /// to move that way to only touch it, if it overlaps
+    pub fn push_out(&self, point: Point2<f32>, radius: f32) -> Option<(Vector2<f32>, f32)> {
+        let dx = point.x - self.centre.x;
+        let dy = point.y - self.centre.y;
+        // Distance past each edge, negative while inside
+        let gap_x = dx.abs() - self.half_size.x;
+        let gap_y = dy.abs() - self.half_size.y;
+        if gap_x >= radius || gap_y >= radius {
+            return None;
+        }
+        let normal = |x: f32, y: f32| Vector2 { x, y };
+        if gap_x <= 0.0 && gap_y <= 0.0 {
+            // The centre is inside, so leave by the nearest edge
+            return Some(if gap_x > gap_y {
+                (normal(1.0_f32.copysign(dx), 0.0), radius - gap_x)
+            } else {
+                (normal(0.0, 1.0_f32.copysign(dy)), radius - gap_y)
+            });
+        }
+        // Nearest point is on an edge or a corner
+        let x = gap_x.max(0.0).copysign(dx);
+        let y = gap_y.max(0.0).copysign(dy);
+        let distance = x.hypot(y);
+        if distance >= radius {
+            return None;
+        }
+        Some((normal(x / distance, y / distance), radius - distance))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ggez::graphics::Color;
+    use rand::Rng;
+
+    use super::*;
+    use crate::ball::Ball;
+    use crate::scene::Scene;
+    use crate::simulation::Simulation;
+    use crate::testing::{check, EPSILON, TICKS};
+
+    #[test]
+    fn balls_never_pass_through_obstacles() {
+        check(|seed, rng| {
+            let radius = rng.gen_range(5.0..40.0);
+            let mut ball = Ball::new(200.0, rng.gen_range(100.0..900.0), radius, Color::WHITE);
+            ball.velocity.x = rng.gen_range(1.0..30.0);
+            ball.velocity.y = rng.gen_range(-10.0..10.0);
+            ball.restitution = rng.gen_range(0.0..1.0);
+            let scene = Scene::new(vec![ball], 0.0);
+            let mut sim = Simulation::from_scene(scene, 1000.0, 1000.0);
+            // Far taller than the box, as balls can leave through the top
+            let obstacle = Obstacle::new(Point2 { x: 600.0, y: 500.0 }, 100.0, 10_000.0);
+            sim.obstacles.push(obstacle);
+            for tick in 0..TICKS {
+                sim.tick();
+                let (_, ball) = sim.balls().next().unwrap();
+                assert!(
+                    ball.point.x + ball.radius <= obstacle.rect().left() + EPSILON,
+                    "seed {seed}, tick {tick}: ball at {} went into the obstacle",
+                    ball.point.x
+                );
+            }
+        });
+    }
+}
@@ -0,0 +1,272 @@
+//! OSC messages over UDP, for driving the sandbox from controllers and
+//! performance software. Understands:
+//!
+//! - `/balls/spawn x y [radius]`
+//! - `/balls/gravity value`
+//! - `/balls/impulse strength`
+//!
+//! Numbers may be sent as ints or floats, and bundles are unpacked. Messages
+//! with numbers that aren't finite are ignored, and the rest are clamped to
+//! sensible ranges
+
+use std::net::UdpSocket;
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::control::{Command, Request, DEFAULT_RADIUS};
+
+/// Largest datagram read, which is plenty for these messages
+const MAX_PACKET: usize = 4096;
+const MIN_RADIUS: f32 = 1.0;
+const MAX_RADIUS: f32 = 200.0;
+const MAX_GRAVITY: f32 = 10.0;
+const MAX_IMPULSE: f32 = 100.0;
+
+/// Argument of a message
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Arg {
+    Number(f32),
+    /// Any type that isn't used, such as a string or blob
+    Other,
+}
+
+/// Listen for messages in the background, sending commands to the app
+pub fn serve(addr: &str, sender: Sender<Request>) -> Result<(), String> {
+    let socket = UdpSocket::bind(addr).map_err(|err| format!("{}: {}", addr, err))?;
+    thread::spawn(move || {
+        let mut buffer = [0; MAX_PACKET];
+        // Nothing is sent back over UDP, so answers are dropped
+        let (reply, _) = mpsc::channel();
+        while let Ok(len) = socket.recv(&mut buffer) {
+            let mut messages = Vec::new();
+            if let Err(err) = parse_packet(&buffer[..len], &mut messages) {
+                log::warn!("Invalid OSC packet: {}", err);
+                continue;
+            }
+            for (address, args) in messages {
+                let Some(command) = command(&address, &args) else {
+                    log::warn!("Unknown OSC message: {} {:?}", address, args);
+                    continue;
+                };
+                let request = Request {
+                    command,
+                    reply: reply.clone(),
+                };
+                if sender.send(request).is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    Ok(())
+}
+
+fn command(address: &str, args: &[Arg]) -> Option<Command> {
+    let numbers: Vec<f32> = args
+        .iter()
+        .map(|arg| match arg {
+            Arg::Number(value) if value.is_finite() => Some(*value),
+            Arg::Number(_) | Arg::Other => None,
+        })
+        .collect::<Option<_>>()?;
+    match (address, numbers.as_slice()) {
+        ("/balls/spawn", &[x, y, ref rest @ ..]) if rest.len() <= 1 => Some(Command::Spawn {
+            x,
+            y,
+            radius: rest.first().map_or(DEFAULT_RADIUS, |radius| {
+                radius.clamp(MIN_RADIUS, MAX_RADIUS)
+            }),
+            vx: 0.0,
+            vy: 0.0,
+            color: None,
+        }),
+        ("/balls/gravity", &[value]) => Some(Command::Gravity {
+            value: value.clamp(-MAX_GRAVITY, MAX_GRAVITY),
+        }),
+        ("/balls/impulse", &[impulse]) => Some(Command::Kick {
+            impulse: impulse.clamp(-MAX_IMPULSE, MAX_IMPULSE),
+        }),
+        _ => None,
+    }
+}
+
+/// Read a message or bundle, adding every message in it
+fn parse_packet(packet: &[u8], messages: &mut Vec<(String, Vec<Arg>)>) -> Result<(), String> {
+    let mut reader = Reader { bytes: packet };
+    let address = reader.string()?;
+    if address == "#bundle" {
+        // Time tag, which is ignored since messages are handled as they arrive
+        reader.take(8)?;
+        while !reader.bytes.is_empty() {
+            let size = reader.int()?;
+            let element = reader.take(usize::try_from(size).map_err(|_| "negative size")?)?;
+            parse_packet(element, messages)?;
+        }
+        return Ok(());
+    }
+
+    let tags = reader.string()?;
+    let Some(tags) = tags.strip_prefix(',') else {
+        return Err("missing type tags".to_string());
+    };
+    let mut args = Vec::new();
+    for tag in tags.chars() {
+        let arg = match tag {
+            'f' => Arg::Number(f32::from_bits(reader.int()? as u32)),
+            'i' => Arg::Number(reader.int()? as f32),
+            'd' => Arg::Number(f64::from_bits(reader.long()?) as f32),
+            'h' => Arg::Number(reader.long()? as i64 as f32),
+            's' | 'S' => {
+                reader.string()?;
+                Arg::Other
+            }
+            'b' => {
+                let size = reader.int()?;
+                reader.take(padded(usize::try_from(size).map_err(|_| "negative size")?))?;
+                Arg::Other
+            }
+            'T' | 'F' | 'N' | 'I' => Arg::Other,
+            _ => return Err(format!("unsupported type tag `{}`", tag)),
+        };
+        args.push(arg);
+    }
+    messages.push((address, args));
+    Ok(())
+}
+
+/// Length rounded up to a multiple of 4, as every OSC field is
+fn padded(len: usize) -> usize {
+    len.div_ceil(4) * 4
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], String> {
+        if len > self.bytes.len() {
+            return Err("packet ended early".to_string());
+        }
+        let (taken, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(taken)
+    }
+
+    fn int(&mut self) -> Result<i32, String> {
+        let bytes = self.take(4)?;
+        Ok(i32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    fn long(&mut self) -> Result<u64, String> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// Null-terminated string, padded to a multiple of 4 bytes
+    fn string(&mut self) -> Result<String, String> {
+        let len = self
+            .bytes
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or("unterminated string")?;
+        let bytes = self.take(padded(len + 1))?;
+        Ok(String::from_utf8_lossy(&bytes[..len]).to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Null-terminated string padded to a multiple of 4 bytes
+    fn string(text: &str) -> Vec<u8> {
+        let mut bytes = text.as_bytes().to_vec();
+        bytes.resize(padded(text.len() + 1), 0);
+        bytes
+    }
+
+    fn message(address: &str, tags: &str, args: &[[u8; 4]]) -> Vec<u8> {
+        let mut bytes = string(address);
+        bytes.extend(string(&format!(",{}", tags)));
+        bytes.extend(args.concat());
+        bytes
+    }
+
+    fn parse(packet: &[u8]) -> Result<Vec<(String, Vec<Arg>)>, String> {
+        let mut messages = Vec::new();
+        parse_packet(packet, &mut messages)?;
+        Ok(messages)
+    }
+
+    #[test]
+    fn ints_and_floats_are_both_numbers() {
+        let packet = message(
+            "/balls/spawn",
+            "fi",
+            &[1.5f32.to_be_bytes(), 2i32.to_be_bytes()],
+        );
+        let messages = parse(&packet).unwrap();
+        assert_eq!(
+            messages,
+            [(
+                "/balls/spawn".to_string(),
+                vec![Arg::Number(1.5), Arg::Number(2.0)]
+            )]
+        );
+    }
+
+    #[test]
+    fn bundles_are_unpacked() {
+        let first = message("/balls/gravity", "f", &[1.0f32.to_be_bytes()]);
+        let second = message("/balls/impulse", "i", &[3i32.to_be_bytes()]);
+        let mut packet = string("#bundle");
+        packet.extend([0; 8]);
+        for element in [&first, &second] {
+            packet.extend((element.len() as i32).to_be_bytes());
+            packet.extend(element);
+        }
+        let addresses: Vec<_> = parse(&packet)
+            .unwrap()
+            .into_iter()
+            .map(|(address, _)| address)
+            .collect();
+        assert_eq!(addresses, ["/balls/gravity", "/balls/impulse"]);
+    }
+
+    #[test]
+    fn broken_packets_are_errors() {
+        let packet = message("/balls/gravity", "f", &[1.0f32.to_be_bytes()]);
+        assert!(parse(&packet[..packet.len() - 1]).is_err());
+        assert!(parse(b"/balls").is_err());
+        assert!(parse(&string("/balls/gravity")).is_err());
+        assert!(parse(&message("/balls/gravity", "x", &[[0; 4]])).is_err());
+        let mut bundle = string("#bundle");
+        bundle.extend([0; 8]);
+        bundle.extend((-4i32).to_be_bytes());
+        assert!(parse(&bundle).is_err());
+    }
+
+    #[test]
+    fn numbers_are_finite_and_clamped() {
+        let spawn = [Arg::Number(1.0), Arg::Number(2.0), Arg::Number(1e6)];
+        assert!(matches!(
+            command("/balls/spawn", &spawn),
+            Some(Command::Spawn { radius, .. }) if radius == MAX_RADIUS
+        ));
+        assert!(matches!(
+            command("/balls/gravity", &[Arg::Number(-1e6)]),
+            Some(Command::Gravity { value }) if value == -MAX_GRAVITY
+        ));
+        assert!(command("/balls/gravity", &[Arg::Number(f32::NAN)]).is_none());
+        assert!(command(
+            "/balls/spawn",
+            &[Arg::Number(f32::INFINITY), Arg::Number(0.0)]
+        )
+        .is_none());
+        assert!(command("/balls/spawn", &[Arg::Number(0.0)]).is_none());
+        assert!(command("/balls/gravity", &[Arg::Other]).is_none());
+        assert!(command("/balls/unknown", &[]).is_none());
+    }
+}
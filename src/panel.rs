@@ -0,0 +1,156 @@
+use ggez::graphics::{Canvas, Color, DrawParam, Quad, Rect};
+use ggez::mint::Point2;
+
+use crate::text::TextStyle;
+
+const WIDTH: f32 = 260.0;
+const ROW_HEIGHT: f32 = 26.0;
+const TEXT_SIZE: f32 = 18.0;
+const MARGIN: f32 = 10.0;
+/// Width of the clickable `-` and `+` areas at each end of a row
+const BUTTON_WIDTH: f32 = 30.0;
+
+/// Titled list of adjustable rows, each with `-` and `+` buttons
+pub struct Panel {
+    title: String,
+    rows: usize,
+    selected: usize,
+    /// Width of the screen, which the panel is kept in the top-right corner of
+    screen_width: f32,
+}
+
+impl Panel {
+    /// Panel placed in the top-right corner of the screen
+    pub fn top_right(title: impl Into<String>, rows: usize, screen_width: f32) -> Self {
+        Self {
+            title: title.into(),
+            rows,
+            selected: 0,
+            screen_width,
+        }
+    }
+
+    /// Top-left corner, further left the larger the panel is drawn
+    fn position(&self, style: TextStyle) -> Point2<f32> {
+        Point2 {
+            x: self.screen_width - style.px(WIDTH + MARGIN),
+            y: style.px(MARGIN),
+        }
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    pub fn up(&mut self) {
+        self.selected = (self.selected + self.rows - 1) % self.rows;
+    }
+
+    pub fn down(&mut self) {
+        self.selected = (self.selected + 1) % self.rows;
+    }
+
+    fn rect(&self, style: TextStyle) -> Rect {
+        let position = self.position(style);
+        Rect::new(
+            position.x,
+            position.y,
+            style.px(WIDTH),
+            style.px(ROW_HEIGHT * (self.rows + 1) as f32 + MARGIN),
+        )
+    }
+
+    fn row_rect(&self, index: usize, style: TextStyle) -> Rect {
+        let position = self.position(style);
+        Rect::new(
+            position.x,
+            position.y + style.px(MARGIN / 2.0 + ROW_HEIGHT * (index + 1) as f32),
+            style.px(WIDTH),
+            style.px(ROW_HEIGHT),
+        )
+    }
+
+    pub fn contains(&self, x: f32, y: f32, style: TextStyle) -> bool {
+        self.rect(style).contains(Point2 { x, y })
+    }
+
+    /// Select the clicked row, returning it with the direction of the button hit
+    ///
+    /// Direction is `0.0` if the label itself was clicked
+    pub fn click(&mut self, x: f32, y: f32, style: TextStyle) -> Option<(usize, f32)> {
+        let point = Point2 { x, y };
+        let index = (0..self.rows).find(|&i| self.row_rect(i, style).contains(point))?;
+        self.selected = index;
+
+        let rect = self.row_rect(index, style);
+        let button = style.px(BUTTON_WIDTH);
+        let direction = if x < rect.x + button {
+            -1.0
+        } else if x > rect.x + rect.w - button {
+            1.0
+        } else {
+            0.0
+        };
+        Some((index, direction))
+    }
+
+    /// Draw rows, omitting buttons for rows that are not adjustable
+    pub fn draw(
+        &self,
+        canvas: &mut Canvas,
+        rows: &[(String, bool)],
+        title_color: Color,
+        style: TextStyle,
+    ) {
+        canvas.draw(
+            &Quad,
+            DrawParam::default()
+                .dest_rect(self.rect(style))
+                .color(color!(30, 30, 30, 220)),
+        );
+
+        let position = self.position(style);
+        canvas.draw(
+            &style.text(&self.title, TEXT_SIZE),
+            DrawParam::from([
+                position.x + style.px(MARGIN),
+                position.y + style.px(MARGIN / 2.0),
+            ])
+            .color(title_color),
+        );
+
+        for (i, (label, adjustable)) in rows.iter().enumerate() {
+            let rect = self.row_rect(i, style);
+            let color = if i == self.selected {
+                canvas.draw(
+                    &Quad,
+                    DrawParam::default()
+                        .dest_rect(rect)
+                        .color(color!(60, 60, 60)),
+                );
+                color!(YELLOW)
+            } else {
+                color!(WHITE)
+            };
+
+            let y = rect.y + style.px(ROW_HEIGHT - TEXT_SIZE) / 2.0;
+            canvas.draw(
+                &style.text(label, TEXT_SIZE),
+                DrawParam::from([rect.x + style.px(BUTTON_WIDTH + MARGIN), y]).color(color),
+            );
+
+            if !adjustable {
+                continue;
+            }
+            for (button, x) in [
+                ("-", rect.x + style.px(MARGIN)),
+                ("+", rect.x + rect.w - style.px(BUTTON_WIDTH - MARGIN)),
+            ] {
+                canvas.draw(
+                    &style.text(button, TEXT_SIZE),
+                    DrawParam::from([x, y]).color(color),
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use ggez::graphics::{Canvas, Color, DrawParam, Mesh};
+use ggez::mint::{Point2, Vector2};
+use rand::Rng;
+
+/// Seconds a particle lasts
+const LIFETIME: f32 = 0.6;
+/// Pixels per second squared that particles fall
+const GRAVITY: f32 = 300.0;
+const RADIUS: f32 = 3.0;
+
+struct Particle {
+    point: Point2<f32>,
+    /// Pixels per second
+    velocity: Vector2<f32>,
+    color: Color,
+    age: f32,
+}
+
+/// Short-lived specks thrown out by effects such as popping balls
+#[derive(Default)]
+pub struct Particles {
+    particles: Vec<Particle>,
+}
+
+impl Particles {
+    /// Scatter particles outwards from the edge of a ball
+    pub fn burst(&mut self, rng: &mut impl Rng, point: Point2<f32>, radius: f32, color: Color) {
+        let count = (radius as usize).clamp(6, 40);
+        for _ in 0..count {
+            let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+            let speed = rng.gen_range(60.0..240.0);
+            self.particles.push(Particle {
+                point: Point2 {
+                    x: point.x + angle.cos() * radius,
+                    y: point.y + angle.sin() * radius,
+                },
+                velocity: Vector2 {
+                    x: angle.cos() * speed,
+                    y: angle.sin() * speed,
+                },
+                color,
+                age: rng.gen_range(0.0..LIFETIME / 3.0),
+            });
+        }
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for particle in &mut self.particles {
+            particle.velocity.y += GRAVITY * dt;
+            particle.point.x += particle.velocity.x * dt;
+            particle.point.y += particle.velocity.y * dt;
+            particle.age += dt;
+        }
+        self.particles.retain(|particle| particle.age < LIFETIME);
+    }
+
+    pub fn clear(&mut self) {
+        self.particles.clear();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+
+    /// Draw each particle as a unit circle scaled to size
+    pub fn draw(&self, canvas: &mut Canvas, circle: &Mesh) {
+        for particle in &self.particles {
+            let fade = 1.0 - particle.age / LIFETIME;
+            let mut color = particle.color;
+            color.a = color.a.min(1.0) * fade;
+            let radius = RADIUS * fade;
+            canvas.draw(
+                circle,
+                DrawParam::from(particle.point)
+                    .scale([radius, radius])
+                    .color(color),
+            );
+        }
+    }
+}
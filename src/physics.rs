@@ -0,0 +1,658 @@
+use std::f32::consts::TAU;
+use std::time::{Duration, Instant};
+
+use ggez::mint::Point2;
+use rand::Rng;
+use rayon::prelude::*;
+
+use crate::ball::{self, Ball, BallId, BallKind, Pattern};
+use crate::components::{Components, Settled, Spin};
+use crate::events::{Event, Wall};
+use crate::forces::{Env, Force};
+use crate::obstacle::Obstacle;
+use crate::simulation::Params;
+use crate::stats::StepStats;
+use crate::storage::Balls;
+use crate::zone::Zone;
+
+/// Slowest impact reported as an event, so resting contact doesn't flood the queue
+const MIN_IMPACT_SPEED: f32 = 0.5;
+/// Temperature gained per unit of impact speed
+const HEAT_PER_SPEED: f32 = 0.05;
+/// Fraction of temperature kept each tick
+const COOLING: f32 = 0.98;
+/// Coolest a ball can be before it counts as cold again
+const MIN_TEMPERATURE: f32 = 0.01;
+/// Extra restitution of a ball at full temperature
+const HOT_BOUNCE: f32 = 0.4;
+/// Farthest a ball can be from a wall and still roll along it
+const ROLL_DISTANCE: f32 = 0.5;
+/// Farthest a ball can stray from where it stopped and still count as settled, so
+/// balls jittering on the floor under gravity can settle too
+const SETTLE_DISTANCE: f32 = 1.0;
+/// Balls resolved together when colliding in parallel
+const CHUNK: usize = 64;
+
+/// Buffers kept from one step to the next, so steps stop allocating once
+/// they have grown to fit the scene
+#[derive(Default)]
+pub struct Scratch {
+    /// Slot of each ball, in collision order
+    order: Vec<usize>,
+    /// Slots of the loose balls, and copies of them for forces to change
+    loose_slots: Vec<usize>,
+    loose: Vec<Ball>,
+    /// Copies of the balls in collision order, as they were before colliding in parallel
+    snapshot: Vec<Ball>,
+    /// Grid cell of each ball in `snapshot`, sorted so each cell's balls are together
+    cells: Vec<((i32, i32), usize)>,
+    contacts: Vec<Contacts>,
+    /// Events found in each chunk of `CHUNK` balls, which stop growing far sooner
+    /// than a buffer for each ball would
+    chunk_events: Vec<Vec<Event>>,
+}
+
+/// Advance every ball by one tick, running each system in turn
+///
+/// Balls collide in the given order. The held ball and pinned balls are not moved
+#[allow(clippy::too_many_arguments)]
+pub fn step(
+    balls: &mut Balls,
+    components: &mut Components,
+    order: &[BallId],
+    forces: &mut [Force],
+    held: Option<BallId>,
+    env: &Env,
+    events: &mut Vec<Event>,
+    scratch: &mut Scratch,
+) -> StepStats {
+    let mut stats = StepStats::default();
+    let first_event = events.len();
+    let held = held.and_then(|id| balls.slot(id));
+    scratch.order.clear();
+    scratch
+        .order
+        .extend(order.iter().filter_map(|&id| balls.slot(id)));
+
+    let mut clock = Instant::now();
+    apply_forces(balls, forces, held, env, scratch);
+    stats.times.forces = lap(&mut clock);
+    integrate(balls, held);
+    stats.times.integrate = lap(&mut clock);
+    if env.params.parallel {
+        collide_parallel(balls, held, &mut stats, events, scratch);
+    } else {
+        collide(balls, &scratch.order, held, &mut stats, events);
+    }
+    stats.times.collide = lap(&mut clock);
+    bounce_obstacles(balls, components, env.obstacles, events);
+    bounce_walls(balls, components, env.params, events);
+    update_spin(balls, components, env.params);
+    stats.times.walls = lap(&mut clock);
+    update_sleep(balls, components);
+    update_settled(balls, components);
+    if env.params.heat {
+        update_temperature(components, &events[first_event..]);
+    }
+    stats.times.rest = lap(&mut clock);
+
+    stats.sleeping = components.asleep.len();
+    stats
+}
+
+/// Time since the clock was last read, restarting it
+fn lap(clock: &mut Instant) -> Duration {
+    let now = Instant::now();
+    let elapsed = now - *clock;
+    *clock = now;
+    elapsed
+}
+
+/// Launch every loose ball upwards with some sideways jitter
+pub fn kick(balls: &mut Balls, held: Option<BallId>, impulse: f32, rng: &mut impl Rng) {
+    let held = held.and_then(|id| balls.slot(id));
+    for slot in 0..balls.len() {
+        if is_fixed(balls, slot, held) {
+            continue;
+        }
+        let bounce = balls.at(slot).get_bounce_amount();
+        balls.vxs[slot] += rng.gen_range(-0.5..0.5) * impulse * bounce;
+        balls.vys[slot] -= impulse * bounce;
+    }
+}
+
+/// Push every loose ball in a random direction, by up to an impulse
+pub fn shake(balls: &mut Balls, held: Option<BallId>, impulse: f32, rng: &mut impl Rng) {
+    let held = held.and_then(|id| balls.slot(id));
+    for slot in 0..balls.len() {
+        if is_fixed(balls, slot, held) {
+            continue;
+        }
+        let angle = rng.gen_range(0.0..TAU);
+        let push = rng.gen_range(0.0..=impulse) * balls.at(slot).get_bounce_amount();
+        balls.vxs[slot] += angle.cos() * push;
+        balls.vys[slot] += angle.sin() * push;
+    }
+}
+
+/// Slow any ball moving faster than a speed down to it, keeping its direction
+pub fn limit_speed(balls: &mut Balls, max: f32) {
+    for slot in 0..balls.len() {
+        let (vx, vy) = (balls.vxs[slot], balls.vys[slot]);
+        let speed = vx.hypot(vy);
+        if speed > max {
+            balls.vxs[slot] = vx / speed * max;
+            balls.vys[slot] = vy / speed * max;
+        }
+    }
+}
+
+/// Held and pinned balls are not moved by physics
+fn is_fixed(balls: &Balls, slot: usize, held: Option<usize>) -> bool {
+    held == Some(slot) || balls.pinned[slot]
+}
+
+/// Run forces on copies of the loose balls, then write them back
+fn apply_forces(
+    balls: &mut Balls,
+    forces: &mut [Force],
+    held: Option<usize>,
+    env: &Env,
+    scratch: &mut Scratch,
+) {
+    let Scratch {
+        loose_slots, loose, ..
+    } = scratch;
+    loose_slots.clear();
+    loose_slots.extend((0..balls.len()).filter(|&slot| !is_fixed(balls, slot, held)));
+    loose.clear();
+    loose.extend(loose_slots.iter().map(|&slot| balls.at(slot)));
+    for force in forces.iter_mut().filter(|force| force.enabled) {
+        force.generator.apply(loose, env);
+    }
+    for (&slot, &ball) in loose_slots.iter().zip(loose.iter()) {
+        balls.set(slot, ball);
+    }
+}
+
+fn integrate(balls: &mut Balls, held: Option<usize>) {
+    let held_point = held.map(|slot| (slot, balls.xs[slot], balls.ys[slot]));
+
+    // Branch-free over whole columns, so this vectorizes
+    let Balls {
+        xs,
+        ys,
+        vxs,
+        vys,
+        pinned,
+        ..
+    } = balls;
+    for ((x, vx), &pinned) in xs.iter_mut().zip(vxs.iter()).zip(pinned.iter()) {
+        *x += if pinned { 0.0 } else { *vx };
+    }
+    for ((y, vy), &pinned) in ys.iter_mut().zip(vys.iter()).zip(pinned.iter()) {
+        *y += if pinned { 0.0 } else { *vy };
+    }
+
+    if let Some((slot, x, y)) = held_point {
+        balls.xs[slot] = x;
+        balls.ys[slot] = y;
+    }
+}
+
+/// Resolve each overlapping pair once, in the given order
+fn collide(
+    balls: &mut Balls,
+    order: &[usize],
+    held: Option<usize>,
+    stats: &mut StepStats,
+    events: &mut Vec<Event>,
+) {
+    for (i, &slot) in order.iter().enumerate() {
+        let fixed = is_fixed(balls, slot, held);
+        // Later balls never come back to this one, so it stays out of storage
+        // until the end, and the others are only copied out on contact
+        let mut ball = balls.at(slot);
+        let mut moved = false;
+        for &other_slot in &order[i + 1..] {
+            let other_fixed = is_fixed(balls, other_slot, held);
+            // Pairs on layers that ignore each other are never tested
+            if (fixed && other_fixed) || !balls.layers_meet(&ball, other_slot) {
+                continue;
+            }
+            stats.pairs_tested += 1;
+            let penetration = balls.penetration(&ball, other_slot);
+            if penetration < 0.0 {
+                continue;
+            }
+            let mut other = balls.at(other_slot);
+            if !ball.collides_with(&other) {
+                continue;
+            }
+            stats.collisions += 1;
+            stats.max_penetration = stats.max_penetration.max(penetration);
+
+            let speed = ((ball.velocity.x - other.velocity.x).powi(2)
+                + (ball.velocity.y - other.velocity.y).powi(2))
+            .sqrt();
+            if speed >= MIN_IMPACT_SPEED {
+                events.push(Event::BallHitBall {
+                    a: balls.id(slot),
+                    b: balls.id(other_slot),
+                    speed,
+                });
+            }
+
+            ball.separate(&mut other, (fixed, other_fixed));
+            balls.set(other_slot, other);
+            moved = true;
+        }
+        if moved {
+            balls.set(slot, ball);
+        }
+    }
+}
+
+/// Push a ball and its velocity while resolving collisions in parallel
+#[derive(Default)]
+struct Contacts {
+    dx: f32,
+    dy: f32,
+    dvx: f32,
+    dvy: f32,
+    stats: StepStats,
+}
+
+/// Balls in collision order, and the grid cells they are in, as they were
+/// before colliding in parallel
+struct Grid<'a> {
+    snapshot: &'a [Ball],
+    /// Grid cell of each ball in `snapshot`, sorted so each cell's balls are together
+    cells: &'a [((i32, i32), usize)],
+    cell_size: f32,
+}
+
+impl Grid<'_> {
+    fn cell(&self, ball: &Ball) -> (i32, i32) {
+        (
+            (ball.point.x / self.cell_size).floor() as i32,
+            (ball.point.y / self.cell_size).floor() as i32,
+        )
+    }
+
+    /// Balls in the same or a neighbouring cell as a ball, by index into `snapshot`
+    fn neighbours(&self, ball: &Ball) -> impl Iterator<Item = usize> + '_ {
+        let (cx, cy) = self.cell(ball);
+        (cx - 1..=cx + 1)
+            .flat_map(move |x| (cy - 1..=cy + 1).map(move |y| (x, y)))
+            .flat_map(|key| {
+                let start = self.cells.partition_point(|&(other, _)| other < key);
+                self.cells[start..]
+                    .iter()
+                    .take_while(move |&&(other, _)| other == key)
+                    .map(|&(_, j)| j)
+            })
+    }
+}
+
+/// Like `collide`, but each ball is pushed away from where the others were at the
+/// start of the phase, so every ball can be resolved at once. Only balls in
+/// neighbouring grid cells are tested
+fn collide_parallel(
+    balls: &mut Balls,
+    held: Option<usize>,
+    stats: &mut StepStats,
+    events: &mut Vec<Event>,
+    scratch: &mut Scratch,
+) {
+    let Scratch {
+        order,
+        snapshot,
+        cells,
+        contacts,
+        chunk_events,
+        ..
+    } = scratch;
+    snapshot.clear();
+    snapshot.extend(order.iter().map(|&slot| balls.at(slot)));
+    // Touching balls are never more than one cell apart
+    let cell_size = snapshot
+        .iter()
+        .map(|ball| ball.radius * 2.0)
+        .fold(1.0, f32::max);
+    let mut grid = Grid {
+        snapshot,
+        cells: &[],
+        cell_size,
+    };
+    cells.clear();
+    cells.extend(
+        snapshot
+            .iter()
+            .enumerate()
+            .map(|(i, ball)| (grid.cell(ball), i)),
+    );
+    cells.sort_unstable();
+    grid.cells = cells;
+    contacts.clear();
+    contacts.resize_with(order.len(), Contacts::default);
+    chunk_events.resize_with(order.len().div_ceil(CHUNK), Vec::new);
+
+    let shared: &Balls = balls;
+    let order: &[usize] = order;
+    let chunks = contacts
+        .par_chunks_mut(CHUNK)
+        .zip(chunk_events.par_iter_mut());
+    chunks.enumerate().for_each(|(chunk, (contacts, events))| {
+        for (offset, contacts) in contacts.iter_mut().enumerate() {
+            let i = chunk * CHUNK + offset;
+            if !is_fixed(shared, order[i], held) {
+                resolve_contacts(i, contacts, events, shared, order, &grid, held);
+            }
+        }
+    });
+
+    for (&slot, contacts) in order.iter().zip(contacts.iter()) {
+        balls.xs[slot] += contacts.dx;
+        balls.ys[slot] += contacts.dy;
+        balls.vxs[slot] += contacts.dvx;
+        balls.vys[slot] += contacts.dvy;
+        stats.pairs_tested += contacts.stats.pairs_tested;
+        stats.collisions += contacts.stats.collisions;
+        stats.max_penetration = stats.max_penetration.max(contacts.stats.max_penetration);
+    }
+    for chunk in chunk_events {
+        events.append(chunk);
+    }
+}
+
+/// Push the `i`th ball in collision order away from every ball it touches
+fn resolve_contacts(
+    i: usize,
+    contacts: &mut Contacts,
+    events: &mut Vec<Event>,
+    shared: &Balls,
+    order: &[usize],
+    grid: &Grid,
+    held: Option<usize>,
+) {
+    let ball = &grid.snapshot[i];
+    for j in grid.neighbours(ball) {
+        let other = &grid.snapshot[j];
+        if i == j || !ball::layers_meet(ball.layer, ball.mask, other.layer, other.mask) {
+            continue;
+        }
+        contacts.stats.pairs_tested += 1;
+        let penetration = ball.penetration(other);
+        if penetration < 0.0 || !ball.collides_with(other) {
+            continue;
+        }
+        contacts.stats.collisions += 1;
+        contacts.stats.max_penetration = contacts.stats.max_penetration.max(penetration);
+
+        let speed = ((ball.velocity.x - other.velocity.x).powi(2)
+            + (ball.velocity.y - other.velocity.y).powi(2))
+        .sqrt();
+        if speed >= MIN_IMPACT_SPEED && (j > i || is_fixed(shared, order[j], held)) {
+            events.push(Event::BallHitBall {
+                a: shared.id(order[i]),
+                b: shared.id(order[j]),
+                speed,
+            });
+        }
+
+        let mut pushed = *ball;
+        pushed.move_from(other, (false, is_fixed(shared, order[j], held)));
+        contacts.dx += pushed.point.x - ball.point.x;
+        contacts.dy += pushed.point.y - ball.point.y;
+        contacts.dvx += pushed.velocity.x - ball.velocity.x;
+        contacts.dvy += pushed.velocity.y - ball.velocity.y;
+    }
+}
+
+/// Push balls out of any obstacle they overlap, bouncing them off it
+fn bounce_obstacles(
+    balls: &mut Balls,
+    components: &Components,
+    obstacles: &[Obstacle],
+    events: &mut Vec<Event>,
+) {
+    if obstacles.is_empty() {
+        return;
+    }
+    for slot in 0..balls.len() {
+        if balls.pinned[slot] {
+            continue;
+        }
+        let heat = components
+            .temperature
+            .get(balls.id(slot))
+            .map_or(0.0, |temperature| temperature * HOT_BOUNCE);
+        let bounce = (balls.restitutions[slot] + heat) * balls.at(slot).get_bounce_amount();
+        for (index, obstacle) in obstacles.iter().enumerate() {
+            let point = Point2 {
+                x: balls.xs[slot],
+                y: balls.ys[slot],
+            };
+            let Some((normal, depth)) = obstacle.push_out(point, balls.radii[slot]) else {
+                continue;
+            };
+            balls.xs[slot] += normal.x * depth;
+            balls.ys[slot] += normal.y * depth;
+            let into = balls.vxs[slot] * normal.x + balls.vys[slot] * normal.y;
+            if into >= 0.0 {
+                continue;
+            }
+            balls.vxs[slot] -= (1.0 + bounce) * into * normal.x;
+            balls.vys[slot] -= (1.0 + bounce) * into * normal.y;
+            if -into >= MIN_IMPACT_SPEED {
+                events.push(Event::BallHitObstacle {
+                    ball: balls.id(slot),
+                    obstacle: index,
+                    speed: -into,
+                });
+            }
+        }
+    }
+}
+
+fn bounce_walls(
+    balls: &mut Balls,
+    components: &Components,
+    params: &Params,
+    events: &mut Vec<Event>,
+) {
+    let Params { width, height, .. } = *params;
+    for slot in 0..balls.len() {
+        if balls.pinned[slot] {
+            continue;
+        }
+        let radius = balls.radii[slot];
+        let heat = components
+            .temperature
+            .get(balls.id(slot))
+            .map_or(0.0, |temperature| temperature * HOT_BOUNCE);
+        let damping = -(balls.restitutions[slot] + heat) * balls.at(slot).get_bounce_amount();
+        let mut hit = None;
+        for wall in Wall::ALL {
+            // Where the ball is pushed back to, if it went past the wall
+            let inside = match wall {
+                Wall::Left => (balls.xs[slot] - radius < 0.0).then_some(radius),
+                Wall::Right => (balls.xs[slot] + radius >= width).then_some(width - radius),
+                Wall::Floor => (balls.ys[slot] + radius >= height).then_some(height - radius),
+                // Other balls thrown out of the top are left to fall back in, so
+                // only balloons, which float up, feel the ceiling's material
+                Wall::Ceiling => (balls.kinds[slot] == BallKind::Balloon
+                    && balls.ys[slot] - radius < 0.0)
+                    .then_some(radius),
+            };
+            let Some(inside) = inside else {
+                continue;
+            };
+            let (position, across, along) = match wall {
+                Wall::Left | Wall::Right => (&mut balls.xs, &mut balls.vxs, &mut balls.vys),
+                Wall::Floor | Wall::Ceiling => (&mut balls.ys, &mut balls.vys, &mut balls.vxs),
+            };
+            let material = params.walls.get(wall);
+            // A trampoline can make the bounce elastic, but only heat adds speed
+            let bounce = (damping * material.restitution).max(damping.min(-1.0));
+            position[slot] = inside;
+            hit = Some((wall, across[slot].abs()));
+            across[slot] *= bounce;
+            along[slot] *= 1.0 - material.friction;
+        }
+
+        if let Some((wall, speed)) = hit {
+            if speed >= MIN_IMPACT_SPEED {
+                events.push(Event::BallHitWall {
+                    ball: balls.id(slot),
+                    wall,
+                    speed,
+                });
+            }
+        }
+    }
+}
+
+/// Turn patterned balls, rolling them along any wall they touch and otherwise
+/// keeping their spin. Spin never changes how balls move
+fn update_spin(balls: &Balls, components: &mut Components, params: &Params) {
+    let Params { width, height, .. } = *params;
+    for slot in 0..balls.len() {
+        if balls.patterns[slot] == Pattern::Plain {
+            continue;
+        }
+        let Some(spin) = components
+            .spin
+            .entry(balls.id(slot))
+            .map(|entry| entry.or_insert_with(Spin::default))
+        else {
+            continue;
+        };
+        let (x, y, radius) = (balls.xs[slot], balls.ys[slot], balls.radii[slot]);
+        let touches = |gap: f32| gap.abs() <= ROLL_DISTANCE;
+        // Turning clockwise on screen is positive, so a ball rolling right along the floor
+        let rolling = if touches(height - y - radius) {
+            Some(balls.vxs[slot])
+        } else if touches(y - radius) {
+            Some(-balls.vxs[slot])
+        } else if touches(x - radius) {
+            Some(balls.vys[slot])
+        } else if touches(width - x - radius) {
+            Some(-balls.vys[slot])
+        } else {
+            None
+        };
+        if let Some(speed) = rolling {
+            spin.velocity = speed / radius.max(1.0);
+        }
+        spin.angle = (spin.angle + spin.velocity).rem_euclid(TAU);
+    }
+}
+
+/// Count the balls in each zone. Zones are triggers only, so balls pass through them
+pub fn sense_zones(balls: &Balls, zones: &mut [Zone]) {
+    for zone in zones.iter_mut() {
+        zone.count = 0;
+        zone.total = 0;
+    }
+    for (_, ball) in balls.iter() {
+        for zone in zones.iter_mut().filter(|zone| zone.filter.matches(&ball)) {
+            zone.total += 1;
+            if zone.contains(&ball) {
+                zone.count += 1;
+            }
+        }
+    }
+}
+
+/// Warm up balls in collisions, by how hard they hit, and cool every ball down
+fn update_temperature(components: &mut Components, events: &[Event]) {
+    let temperature = &mut components.temperature;
+    temperature.retain(|_, heat| {
+        *heat *= COOLING;
+        *heat > MIN_TEMPERATURE
+    });
+    for event in events {
+        let (ids, speed) = match *event {
+            Event::BallHitBall { a, b, speed } => ([Some(a), Some(b)], speed),
+            Event::BallHitWall { ball, speed, .. } | Event::BallHitObstacle { ball, speed, .. } => {
+                ([Some(ball), None], speed)
+            }
+            _ => continue,
+        };
+        for id in ids.into_iter().flatten() {
+            if let Some(entry) = temperature.entry(id) {
+                let heat = entry.or_default();
+                *heat = (*heat + speed * HEAT_PER_SPEED).min(1.0);
+            }
+        }
+    }
+}
+
+/// Track how long each ball has been at rest
+fn update_sleep(balls: &Balls, components: &mut Components) {
+    for (id, ball) in balls.iter() {
+        if !ball.is_sleeping() {
+            components.asleep.remove(id);
+            continue;
+        }
+        components.thrown.remove(id);
+        if let Some(entry) = components.asleep.entry(id) {
+            *entry.or_default() += 1;
+        }
+    }
+}
+
+/// Count the ticks each ball has stayed near where it stopped, starting over
+/// from wherever it is once it strays too far
+fn update_settled(balls: &Balls, components: &mut Components) {
+    for slot in 0..balls.len() {
+        let point = Point2 {
+            x: balls.xs[slot],
+            y: balls.ys[slot],
+        };
+        let id = balls.id(slot);
+        match components.settled.get_mut(id) {
+            Some(settled)
+                if (point.x - settled.anchor.x).hypot(point.y - settled.anchor.y)
+                    <= SETTLE_DISTANCE =>
+            {
+                settled.ticks += 1;
+            }
+            _ => {
+                components.settled.insert(
+                    id,
+                    Settled {
+                        anchor: point,
+                        ticks: 0,
+                    },
+                );
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::simulation::Simulation;
+    use crate::testing::{check, random_scene, TICKS};
+
+    #[test]
+    fn temperatures_stay_between_zero_and_one() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            sim.params.heat = true;
+            for _ in 0..TICKS {
+                sim.tick();
+                for (_, &heat) in sim.components.temperature.iter() {
+                    assert!(
+                        (0.0..=1.0).contains(&heat),
+                        "seed {seed}: a ball reached a temperature of {heat}"
+                    );
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,58 @@
+use ggez::graphics::{Canvas, DrawMode, DrawParam, Mesh, MeshBuilder};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+
+/// Ticks to look ahead, two seconds
+pub const TICKS: u32 = 120;
+/// Ticks between the dots of a path
+const SPACING: usize = 4;
+const DOT_RADIUS: f32 = 2.0;
+
+/// Whether to show where an aimed ball will go, and what to include
+#[derive(Clone, Copy, PartialEq)]
+pub enum Prediction {
+    Off,
+    /// Only the aimed ball, ignoring the others
+    Alone,
+    /// The whole simulation, so the path bends around other balls
+    WithOthers,
+}
+
+impl Prediction {
+    pub fn next(self) -> Self {
+        match self {
+            Prediction::Off => Prediction::Alone,
+            Prediction::Alone => Prediction::WithOthers,
+            Prediction::WithOthers => Prediction::Off,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Prediction::Off => "off",
+            Prediction::Alone => "ball alone",
+            Prediction::WithOthers => "with other balls",
+        }
+    }
+}
+
+/// Dotted line along a predicted path, fading out towards its end
+pub fn draw_path(ctx: &mut Context, canvas: &mut Canvas, points: &[Point2<f32>]) -> GameResult {
+    if points.len() < SPACING {
+        return Ok(());
+    }
+    let mut builder = MeshBuilder::new();
+    for (i, &point) in points.iter().enumerate().step_by(SPACING) {
+        let fade = 1.0 - i as f32 / points.len() as f32;
+        builder.circle(
+            DrawMode::fill(),
+            point,
+            DOT_RADIUS,
+            0.5,
+            color!(255, 255, 255, 200.0 * fade),
+        )?;
+    }
+    let mesh = Mesh::from_data(ctx, builder.build());
+    canvas.draw(&mesh, DrawParam::default());
+    Ok(())
+}
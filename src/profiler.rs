@@ -0,0 +1,110 @@
+use std::time::Duration;
+
+use ggez::graphics::{Canvas, Color, DrawParam, Quad, Rect};
+
+use crate::text::TextStyle;
+
+pub const WIDTH: f32 = 300.0;
+const LINE_HEIGHT: f32 = 20.0;
+const TEXT_SIZE: f32 = 16.0;
+const PADDING: f32 = 6.0;
+/// Indent of each level of nested scopes
+const INDENT: f32 = 14.0;
+/// Width of the column of names, before the bars and times
+const NAME_WIDTH: f32 = 130.0;
+/// Milliseconds a full bar stands for, the length of a frame at 60 FPS
+const BUDGET: f32 = 1000.0 / 60.0;
+/// Fraction of each new time taken into the average, smoothing out the jitter
+const SMOOTHING: f32 = 0.1;
+/// Bar colors, cycled through by top-level scope
+const COLORS: [Color; 4] = [
+    color!(0x48cae4),
+    color!(0xffd166),
+    color!(0x06d6a0),
+    color!(0xef476f),
+];
+
+/// Time taken by a part of each frame, averaged over recent frames
+struct Scope {
+    name: &'static str,
+    /// Scopes nested in another have a depth of 1, and so on
+    depth: usize,
+    millis: f32,
+}
+
+/// Time spent in each part of the frame, such as physics and drawing, to see
+/// where the time goes
+#[derive(Default)]
+pub struct Profiler {
+    /// In the order first recorded
+    scopes: Vec<Scope>,
+}
+
+impl Profiler {
+    /// Record the time a scope took this frame, nested to a depth
+    pub fn record(&mut self, name: &'static str, depth: usize, elapsed: Duration) {
+        let millis = elapsed.as_secs_f32() * 1000.0;
+        match self
+            .scopes
+            .iter_mut()
+            .find(|scope| scope.name == name && scope.depth == depth)
+        {
+            Some(scope) => scope.millis += (millis - scope.millis) * SMOOTHING,
+            None => self.scopes.push(Scope {
+                name,
+                depth,
+                millis,
+            }),
+        }
+    }
+
+    /// Draw panel with its top-left corner at the given point, with a bar for
+    /// each scope against the length of a frame
+    pub fn draw(&self, canvas: &mut Canvas, x: f32, y: f32, style: TextStyle) {
+        canvas.draw(
+            &Quad,
+            DrawParam::default()
+                .dest_rect(Rect::new(
+                    x,
+                    y,
+                    style.px(WIDTH),
+                    style.px(LINE_HEIGHT * self.scopes.len() as f32 + PADDING * 2.0),
+                ))
+                .color(color!(30, 30, 30, 200)),
+        );
+
+        let mut color = COLORS[0];
+        let mut top_level = 0;
+        for (i, scope) in self.scopes.iter().enumerate() {
+            if scope.depth == 0 {
+                color = COLORS[top_level % COLORS.len()];
+                top_level += 1;
+            }
+            let line_y = y + style.px(PADDING + i as f32 * LINE_HEIGHT);
+            let bar_x = x + style.px(PADDING + NAME_WIDTH);
+            let bar_width = (WIDTH - NAME_WIDTH - PADDING * 2.0) * (scope.millis / BUDGET).min(1.0);
+            let mut bar = color;
+            bar.a = if scope.depth == 0 { 0.8 } else { 0.4 };
+            canvas.draw(
+                &Quad,
+                DrawParam::default()
+                    .dest_rect(Rect::new(
+                        bar_x,
+                        line_y + style.px(2.0),
+                        style.px(bar_width),
+                        style.px(LINE_HEIGHT - 4.0),
+                    ))
+                    .color(bar),
+            );
+
+            let name = style.text(scope.name, TEXT_SIZE);
+            let indent = style.px(PADDING + scope.depth as f32 * INDENT);
+            canvas.draw(&name, DrawParam::from([x + indent, line_y]));
+            let millis = style.text(format!("{:.2} ms", scope.millis), TEXT_SIZE);
+            canvas.draw(
+                &millis,
+                DrawParam::from([bar_x + style.px(PADDING), line_y]),
+            );
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::f32::consts::{PI, TAU};
+
+use ggez::graphics::{Canvas, DrawParam, Mesh};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+
+use crate::text::TextStyle;
+
+/// Radius of the arc drawn between the arms
+const ARC_RADIUS: f32 = 30.0;
+/// Points along the arc
+const ARC_POINTS: usize = 24;
+
+/// Where a protractor is in being laid out
+#[derive(Clone, Copy, PartialEq)]
+enum Stage {
+    /// First arm follows the cursor while the mouse is held
+    First,
+    /// Second arm follows the cursor until clicked
+    Second,
+    Done,
+}
+
+/// Two arms from a shared vertex, showing the angle between them, or the
+/// incline of the first arm while it is the only one
+pub struct Protractor {
+    vertex: Point2<f32>,
+    first: Point2<f32>,
+    second: Option<Point2<f32>>,
+    stage: Stage,
+}
+
+impl Protractor {
+    pub fn new(vertex: Point2<f32>) -> Self {
+        Self {
+            vertex,
+            first: vertex,
+            second: None,
+            stage: Stage::First,
+        }
+    }
+
+    /// Whether the next click places the second arm, rather than starting over
+    pub fn is_placing(&self) -> bool {
+        self.stage == Stage::Second
+    }
+
+    /// Move the arm being placed to the cursor
+    pub fn track(&mut self, point: Point2<f32>) {
+        match self.stage {
+            Stage::First => self.first = point,
+            Stage::Second => self.second = Some(point),
+            Stage::Done => (),
+        }
+    }
+
+    /// Finish the arm being placed, returning false if the first arm was too
+    /// short to measure with
+    pub fn place(&mut self, point: Point2<f32>) -> bool {
+        self.track(point);
+        self.stage = match self.stage {
+            Stage::First if length(self.vertex, self.first) < 1.0 => return false,
+            Stage::First => Stage::Second,
+            _ => Stage::Done,
+        };
+        true
+    }
+
+    /// Angle of an arm, clockwise from the positive x axis as drawn on screen
+    fn direction(&self, end: Point2<f32>) -> f32 {
+        (end.y - self.vertex.y).atan2(end.x - self.vertex.x)
+    }
+
+    /// Start and sweep of the arc between the arms, and the angle to show in degrees
+    fn arc(&self) -> (f32, f32, f32) {
+        let start = self.direction(self.first);
+        let Some(second) = self.second.filter(|&end| length(self.vertex, end) >= 1.0) else {
+            // Incline of the single arm, measured from the horizontal
+            let (dx, dy) = (self.first.x - self.vertex.x, self.first.y - self.vertex.y);
+            let incline = dy.abs().atan2(dx.abs());
+            let horizontal = if dx < 0.0 { PI } else { 0.0 };
+            let sweep = start - horizontal;
+            let sweep = (sweep + PI).rem_euclid(TAU) - PI;
+            return (horizontal, sweep, incline.to_degrees());
+        };
+        // The smaller of the two angles between the arms
+        let sweep = (self.direction(second) - start + PI).rem_euclid(TAU) - PI;
+        (start, sweep, sweep.abs().to_degrees())
+    }
+
+    pub fn draw(&self, ctx: &mut Context, canvas: &mut Canvas, style: TextStyle) -> GameResult {
+        if length(self.vertex, self.first) < 1.0 {
+            return Ok(());
+        }
+        let mut arms = vec![self.first, self.vertex];
+        arms.extend(self.second);
+        let lines = Mesh::new_line(ctx, &arms, 2.0, color!(0x48cae4))?;
+        canvas.draw(&lines, DrawParam::default());
+
+        let (start, sweep, degrees) = self.arc();
+        if sweep.abs() > 0.01 {
+            let points: Vec<_> = (0..=ARC_POINTS)
+                .map(|i| {
+                    let angle = start + sweep * i as f32 / ARC_POINTS as f32;
+                    Point2 {
+                        x: self.vertex.x + angle.cos() * ARC_RADIUS,
+                        y: self.vertex.y + angle.sin() * ARC_RADIUS,
+                    }
+                })
+                .collect();
+            let arc = Mesh::new_line(ctx, &points, 1.5, color!(0x48cae4))?;
+            canvas.draw(&arc, DrawParam::default());
+        }
+
+        let middle = start + sweep / 2.0;
+        let text = style.text(format!("{:.1}°", degrees), 18.0);
+        let label = [
+            self.vertex.x + middle.cos() * (ARC_RADIUS + style.px(14.0)) - style.px(16.0),
+            self.vertex.y + middle.sin() * (ARC_RADIUS + style.px(14.0)) - style.px(9.0),
+        ];
+        canvas.draw(&text, DrawParam::from(label).color(color!(0x48cae4)));
+        Ok(())
+    }
+}
+
+fn length(a: Point2<f32>, b: Point2<f32>) -> f32 {
+    (b.x - a.x).hypot(b.y - a.y)
+}
@@ -0,0 +1,103 @@
+use std::time::Duration;
+
+/// Time the app may spend on a frame, leaving some room within a 60 Hz refresh
+const BUDGET: Duration = Duration::from_millis(14);
+/// Fraction of the budget frames must stay under before quality goes back up
+const RESTORE: f32 = 0.5;
+/// Seconds over budget before quality is lowered
+const LOWER_AFTER: f32 = 1.0;
+/// Seconds well under budget before quality is raised, longer so it doesn't flip back and forth
+const RAISE_AFTER: f32 = 5.0;
+/// Weight of each new frame in the running average
+const SMOOTHING: f32 = 0.1;
+
+/// How much detail to draw, lowered while frames take too long
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Quality {
+    Low,
+    Medium,
+    #[default]
+    High,
+}
+
+impl Quality {
+    pub fn name(self) -> &'static str {
+        match self {
+            Quality::Low => "low",
+            Quality::Medium => "medium",
+            Quality::High => "high",
+        }
+    }
+
+    /// Tolerance to tessellate circles with, coarser at lower quality
+    pub fn circle_tolerance(self) -> f32 {
+        match self {
+            Quality::Low => 0.02,
+            Quality::Medium => 0.005,
+            Quality::High => 0.001,
+        }
+    }
+
+    /// Whether to throw out particles and predict paths through other balls
+    pub fn effects(self) -> bool {
+        self != Quality::Low
+    }
+
+    fn lower(self) -> Option<Self> {
+        match self {
+            Quality::Low => None,
+            Quality::Medium => Some(Quality::Low),
+            Quality::High => Some(Quality::Medium),
+        }
+    }
+
+    fn higher(self) -> Option<Self> {
+        match self {
+            Quality::Low => Some(Quality::Medium),
+            Quality::Medium => Some(Quality::High),
+            Quality::High => None,
+        }
+    }
+}
+
+/// Watches how long frames take to update and draw, picking the quality to draw at
+#[derive(Default)]
+pub struct AdaptiveQuality {
+    pub quality: Quality,
+    /// Running average of seconds spent on each frame
+    average: f32,
+    /// Seconds the average has been over budget
+    over: f32,
+    /// Seconds the average has been well under budget
+    under: f32,
+}
+
+impl AdaptiveQuality {
+    /// Count the time spent on a frame, returning the new quality if it changed
+    pub fn record(&mut self, work: Duration, dt: f32) -> Option<Quality> {
+        self.average += (work.as_secs_f32() - self.average) * SMOOTHING;
+        let budget = BUDGET.as_secs_f32();
+        if self.average > budget {
+            self.over += dt;
+            self.under = 0.0;
+        } else if self.average < budget * RESTORE {
+            self.under += dt;
+            self.over = 0.0;
+        } else {
+            self.over = 0.0;
+            self.under = 0.0;
+        }
+
+        let next = if self.over >= LOWER_AFTER {
+            self.quality.lower()
+        } else if self.under >= RAISE_AFTER {
+            self.quality.higher()
+        } else {
+            None
+        }?;
+        self.quality = next;
+        self.over = 0.0;
+        self.under = 0.0;
+        Some(next)
+    }
+}
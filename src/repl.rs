@@ -0,0 +1,158 @@
+//! Line commands read from stdin, so the app can be driven from shell scripts
+
+use std::io::{self, BufRead, Write};
+use std::sync::mpsc::{self, Sender};
+use std::thread;
+
+use crate::control::{Command, Request, DEFAULT_RADIUS};
+
+const HELP: &str = "\
+spawn X Y [RADIUS] [#RRGGBB]   add a ball
+delete ID                      remove a ball
+list                           show every ball
+set gravity VALUE              change gravity
+settings                       show forces, volumes and other settings
+kick IMPULSE                   launch every ball upwards
+save [NAME]                    save the scene to the scenes directory
+screenshot                     save a screenshot of the next frame
+help                           show this";
+
+/// Read commands from stdin in the background, printing each answer
+pub fn start(sender: Sender<Request>) {
+    thread::spawn(move || {
+        let (reply, replies) = mpsc::channel();
+        for line in io::stdin().lock().lines() {
+            let Ok(line) = line else {
+                break;
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+            let command = match parse(&line) {
+                Ok(Some(command)) => command,
+                Ok(None) => {
+                    println!("{}", HELP);
+                    continue;
+                }
+                Err(err) => {
+                    println!("error: {}", err);
+                    continue;
+                }
+            };
+            let request = Request {
+                command,
+                reply: reply.clone(),
+            };
+            if sender.send(request).is_err() {
+                break;
+            }
+            match replies.recv() {
+                Ok(reply) => println!("{}", reply),
+                Err(_) => break,
+            }
+            let _ = io::stdout().flush();
+        }
+    });
+}
+
+/// Command for a line, or none for help
+fn parse(line: &str) -> Result<Option<Command>, String> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let command = match words.as_slice() {
+        ["spawn", x, y, rest @ ..] if rest.len() <= 2 => {
+            // The radius can be left out, even when a color is given
+            let (rest, color) = match rest {
+                [rest @ .., color] if color.starts_with('#') => (rest, Some(color.to_string())),
+                _ => (rest, None),
+            };
+            let radius = match rest {
+                [] => DEFAULT_RADIUS,
+                [radius] => number(radius)?,
+                _ => return Err("expected `spawn X Y [RADIUS] [#RRGGBB]`".to_string()),
+            };
+            Command::Spawn {
+                x: number(x)?,
+                y: number(y)?,
+                radius,
+                vx: 0.0,
+                vy: 0.0,
+                color,
+            }
+        }
+        ["delete", id] => Command::Delete {
+            id: id
+                .parse()
+                .map_err(|_| format!("`{}` is not a ball id", id))?,
+        },
+        ["list"] => Command::State,
+        ["set", "gravity", value] => Command::Gravity {
+            value: number(value)?,
+        },
+        ["settings"] => Command::Settings,
+        ["kick", impulse] => Command::Kick {
+            impulse: number(impulse)?,
+        },
+        ["save"] => Command::Save { name: None },
+        ["save", name] => Command::Save {
+            name: Some(name.to_string()),
+        },
+        ["screenshot"] => Command::Screenshot,
+        ["help"] => return Ok(None),
+        [name, ..] => return Err(format!("unknown command `{}`, try `help`", name)),
+        [] => return Ok(None),
+    };
+    Ok(Some(command))
+}
+
+/// Finite number, as `nan` and `inf` would spread through the scene
+fn number(word: &str) -> Result<f32, String> {
+    word.parse()
+        .ok()
+        .filter(|value: &f32| value.is_finite())
+        .ok_or_else(|| format!("`{}` is not a number", word))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spawn(line: &str) -> (f32, f32, f32, Option<String>) {
+        match parse(line) {
+            Ok(Some(Command::Spawn {
+                x,
+                y,
+                radius,
+                color,
+                ..
+            })) => (x, y, radius, color),
+            other => panic!("`{}` parsed as {:?}", line, other.map(|_| ())),
+        }
+    }
+
+    #[test]
+    fn spawn_takes_a_radius_and_color_in_any_combination() {
+        assert_eq!(spawn("spawn 1 2"), (1.0, 2.0, DEFAULT_RADIUS, None));
+        assert_eq!(spawn("spawn 1 2 5"), (1.0, 2.0, 5.0, None));
+        let red = Some("#ff0000".to_string());
+        assert_eq!(
+            spawn("spawn 1 2 #ff0000"),
+            (1.0, 2.0, DEFAULT_RADIUS, red.clone())
+        );
+        assert_eq!(spawn("spawn 1 2 5 #ff0000"), (1.0, 2.0, 5.0, red));
+        assert!(parse("spawn 1 2 5 6").is_err());
+        assert!(parse("spawn 1 2 #ff0000 5").is_err());
+    }
+
+    #[test]
+    fn numbers_must_be_finite() {
+        for line in [
+            "set gravity nan",
+            "kick inf",
+            "spawn NaN 2",
+            "spawn 1 2 -inf",
+        ] {
+            assert!(parse(line).is_err(), "`{}` was accepted", line);
+        }
+        assert!(parse("set gravity -0.5").is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ggez::event::MouseButton;
+use ggez::winit::event::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+
+/// User input, as passed to the app's event handlers
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub enum Input {
+    MouseMotion { x: f32, y: f32, dx: f32, dy: f32 },
+    MouseDown { button: MouseButton, x: f32, y: f32 },
+    MouseUp { button: MouseButton, x: f32, y: f32 },
+    KeyDown { key: VirtualKeyCode, ctrl: bool },
+    KeyUp { key: VirtualKeyCode },
+}
+
+/// Everything needed to repeat a session, with each input tagged by the tick it happened after
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+    pub seed: u64,
+    pub window_size: (f32, f32),
+    /// Random balls the session started with
+    pub balls: usize,
+    /// Scene file the session started with
+    pub scene: Option<PathBuf>,
+    pub inputs: Vec<(u64, Input)>,
+}
+
+impl Recording {
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let file = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        ron::from_str(&file).map_err(|err| err.to_string())
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        let file = ron::to_string(self).map_err(|err| err.to_string())?;
+        fs::write(path, file).map_err(|err| err.to_string())
+    }
+}
+
+/// Recording being played back
+pub struct Replay {
+    recording: Recording,
+    /// Index of the next input to play
+    next: usize,
+}
+
+impl Replay {
+    pub fn new(recording: Recording) -> Self {
+        Self { recording, next: 0 }
+    }
+
+    /// Next input due by the given tick, if any
+    pub fn next_input(&mut self, tick: u64) -> Option<Input> {
+        let &(at, input) = self.recording.inputs.get(self.next)?;
+        if at > tick {
+            return None;
+        }
+        self.next += 1;
+        Some(input)
+    }
+
+    /// Ticks until the next input, or `None` once every input has played
+    pub fn ticks_until_next(&self, tick: u64) -> Option<u64> {
+        let (at, _) = self.recording.inputs.get(self.next)?;
+        Some(at.saturating_sub(tick))
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.next >= self.recording.inputs.len()
+    }
+}
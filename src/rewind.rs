@@ -0,0 +1,78 @@
+use std::collections::VecDeque;
+
+use crate::simulation::{Simulation, Snapshot, TICK};
+
+/// Seconds of the past gone back through for each second the rewind key is held
+const SPEED: f32 = 2.0;
+
+/// Rolling buffer of the last few seconds of the simulation, to go back through
+pub struct Rewind {
+    /// Snapshot after each frame that ran ticks, with how many it ran
+    snapshots: VecDeque<(u32, Snapshot)>,
+    /// Ticks covered by the snapshots kept
+    kept: u32,
+    /// Most ticks to keep, or none to turn rewinding off
+    limit: u32,
+    /// Ticks owed to going back, less than the frame at the end of the buffer
+    owed: f32,
+}
+
+impl Rewind {
+    pub fn new(seconds: f32) -> Self {
+        Self {
+            snapshots: VecDeque::new(),
+            kept: 0,
+            limit: (seconds.max(0.0) / TICK) as u32,
+            owed: 0.0,
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.limit > 0
+    }
+
+    /// Snapshot the simulation after a frame that ran some ticks
+    pub fn record(&mut self, ticks: u32, sim: &Simulation) {
+        if !self.is_enabled() {
+            return;
+        }
+        self.snapshots.push_back((ticks, sim.snapshot()));
+        self.kept += ticks;
+        while self.kept > self.limit && self.snapshots.len() > 1 {
+            if let Some((ticks, _)) = self.snapshots.pop_front() {
+                self.kept -= ticks;
+            }
+        }
+        self.owed = 0.0;
+    }
+
+    pub fn clear(&mut self) {
+        self.snapshots.clear();
+        self.kept = 0;
+        self.owed = 0.0;
+    }
+
+    /// Go back by the time a frame of holding the key is worth, returning the
+    /// snapshot reached. The oldest one is kept, to carry on from
+    pub fn step_back(&mut self, dt: f32) -> Option<&Snapshot> {
+        self.owed += dt * SPEED / TICK;
+        while self.snapshots.len() > 1 {
+            let Some(&(ticks, _)) = self.snapshots.back() else {
+                break;
+            };
+            if self.owed < ticks as f32 {
+                break;
+            }
+            self.owed -= ticks as f32;
+            self.kept -= ticks;
+            self.snapshots.pop_back();
+        }
+        self.snapshots.back().map(|(_, snapshot)| snapshot)
+    }
+
+    /// Seconds that can still be gone back through
+    pub fn seconds_left(&self) -> f32 {
+        let oldest = self.snapshots.front().map_or(0, |&(ticks, _)| ticks);
+        (self.kept - oldest) as f32 * TICK
+    }
+}
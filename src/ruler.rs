@@ -0,0 +1,82 @@
+use ggez::graphics::{Canvas, DrawParam, Mesh};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult};
+
+use crate::text::TextStyle;
+use crate::units::Units;
+
+/// Length of the ticks across each end of the line
+const TICK: f32 = 6.0;
+
+/// Line dragged between two points, showing the distance between them
+pub struct Ruler {
+    pub start: Point2<f32>,
+    pub end: Point2<f32>,
+    /// Whether the end still follows the cursor
+    pub dragging: bool,
+}
+
+impl Ruler {
+    pub fn new(point: Point2<f32>) -> Self {
+        Self {
+            start: point,
+            end: point,
+            dragging: true,
+        }
+    }
+
+    /// Distance between the ends, in pixels
+    pub fn length(&self) -> f32 {
+        (self.end.x - self.start.x).hypot(self.end.y - self.start.y)
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &mut Context,
+        canvas: &mut Canvas,
+        units: Units,
+        style: TextStyle,
+    ) -> GameResult {
+        let length = self.length();
+        if length < 1.0 {
+            return Ok(());
+        }
+        let line = Mesh::new_line(ctx, &[self.start, self.end], 2.0, color!(YELLOW))?;
+        canvas.draw(&line, DrawParam::default());
+
+        // Ticks square to the line
+        let (nx, ny) = (
+            -(self.end.y - self.start.y) / length * TICK,
+            (self.end.x - self.start.x) / length * TICK,
+        );
+        for point in [self.start, self.end] {
+            let tick = Mesh::new_line(
+                ctx,
+                &[
+                    Point2 {
+                        x: point.x - nx,
+                        y: point.y - ny,
+                    },
+                    Point2 {
+                        x: point.x + nx,
+                        y: point.y + ny,
+                    },
+                ],
+                2.0,
+                color!(YELLOW),
+            )?;
+            canvas.draw(&tick, DrawParam::default());
+        }
+
+        let text = style.text(
+            format!("{:.1} px ({:.2} m)", length, units.meters(length)),
+            18.0,
+        );
+        let middle = [
+            (self.start.x + self.end.x) / 2.0 + TICK,
+            (self.start.y + self.end.y) / 2.0 + TICK,
+        ];
+        canvas.draw(&text, DrawParam::from(middle).color(color!(YELLOW)));
+        Ok(())
+    }
+}
@@ -0,0 +1,525 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use ggez::graphics::Color;
+use ggez::mint::{Point2, Vector2};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::{Attractor, Ball, BallKind};
+use crate::components::Growth;
+use crate::events::Wall;
+use crate::mode::ModeKind;
+use crate::spawner::Spawner;
+use crate::walls::Walls;
+use crate::zone::{Filter, Zone};
+
+/// Directory that saved scenes are read from and written to
+pub const SCENES_DIR: &str = "scenes";
+/// File in the user data directory that the running scene is saved to
+pub const AUTOSAVE_FILE: &str = "autosave.ron";
+
+/// Everything needed to restore a running sandbox
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Scene {
+    pub balls: Vec<Ball>,
+    pub gravity: f32,
+    #[serde(default)]
+    pub attractors: Vec<Attractor>,
+    #[serde(default)]
+    pub spawners: Vec<Spawner>,
+    #[serde(default)]
+    pub zones: Vec<Zone>,
+    #[serde(default)]
+    pub walls: Walls,
+}
+
+impl Scene {
+    /// Balls falling under gravity, with nothing else in the box
+    pub fn new(balls: Vec<Ball>, gravity: f32) -> Self {
+        Self {
+            balls,
+            gravity,
+            ..Self::default()
+        }
+    }
+
+    /// Error if any number would break the simulation, such as from a hand-edited file
+    pub fn check(&self) -> Result<(), String> {
+        let point = |point: Point2<f32>| point.x.is_finite() && point.y.is_finite();
+        let vector = |vector: Vector2<f32>| vector.x.is_finite() && vector.y.is_finite();
+        let radius = |radius: f32| radius.is_finite() && radius > 0.0;
+        for (i, ball) in self.balls.iter().enumerate() {
+            if !point(ball.point) || !vector(ball.velocity) || !ball.restitution.is_finite() {
+                return Err(format!("Ball {} has a number that is not finite", i));
+            }
+            if !radius(ball.radius) {
+                return Err(format!("Ball {} has a radius of {}", i, ball.radius));
+            }
+        }
+        if !self.gravity.is_finite() {
+            return Err(format!("Gravity must be finite, not {}", self.gravity));
+        }
+        if self
+            .attractors
+            .iter()
+            .any(|attractor| !point(attractor.point) || !attractor.strength.is_finite())
+        {
+            return Err("Attractors must be at a finite point with finite strength".to_string());
+        }
+        for (i, spawner) in self.spawners.iter().enumerate() {
+            let (min, max) = spawner.radius;
+            if !point(spawner.point) || !vector(spawner.velocity) || !spawner.spread.is_finite() {
+                return Err(format!("Spawner {} has a number that is not finite", i));
+            }
+            if !radius(min) || !radius(max) || min > max {
+                return Err(format!(
+                    "Spawner {} has a radius range of {} to {}",
+                    i, min, max
+                ));
+            }
+        }
+        if self
+            .zones
+            .iter()
+            .any(|zone| !point(zone.min) || !point(zone.max))
+        {
+            return Err("Zones must have finite corners".to_string());
+        }
+        for wall in Wall::ALL {
+            let material = self.walls.get(wall);
+            if !material.restitution.is_finite() || !material.friction.is_finite() {
+                return Err(format!("{:?} material must be finite", wall));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Built-in demo scenes, shown on the start screen
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Demo {
+    Pile,
+    Plinko,
+    Orbit,
+    Blob,
+    Fountain,
+    Sort,
+    Balloons,
+    Dough,
+}
+
+impl Demo {
+    pub const ALL: [Demo; 8] = [
+        Demo::Pile,
+        Demo::Plinko,
+        Demo::Orbit,
+        Demo::Blob,
+        Demo::Fountain,
+        Demo::Sort,
+        Demo::Balloons,
+        Demo::Dough,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Demo::Pile => "Pile",
+            Demo::Plinko => "Plinko",
+            Demo::Orbit => "Orbit",
+            Demo::Blob => "Blob",
+            Demo::Fountain => "Fountain",
+            Demo::Sort => "Sort",
+            Demo::Balloons => "Balloons",
+            Demo::Dough => "Dough",
+        }
+    }
+
+    pub fn build(self, rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+        match self {
+            Demo::Pile => pile(rng, width, height),
+            Demo::Plinko => plinko(rng, width, height),
+            Demo::Orbit => orbit(rng, width, height),
+            Demo::Blob => blob(rng, width, height),
+            Demo::Fountain => fountain(rng, width, height),
+            Demo::Sort => sort(rng, width, height),
+            Demo::Balloons => balloons(rng, width, height),
+            Demo::Dough => dough(rng, width, height),
+        }
+    }
+}
+
+/// Where the current scene came from, so it can be reset
+#[derive(Clone, Debug, PartialEq)]
+pub enum SceneSource {
+    Demo(Demo),
+    File(PathBuf),
+    /// Number of random balls, generated from the app config
+    Random(usize),
+    /// Scene saved when the app last crashed
+    Crash,
+    /// Starting scene of a game
+    Mode(ModeKind),
+    /// Timed challenge file
+    Challenge(PathBuf),
+}
+
+impl SceneSource {
+    pub fn name(&self) -> String {
+        match self {
+            SceneSource::Demo(demo) => demo.name().to_string(),
+            SceneSource::File(path) | SceneSource::Challenge(path) => file_name(path),
+            SceneSource::Random(count) => format!("{} random balls", count),
+            SceneSource::Crash => "scene from crash".to_string(),
+            SceneSource::Mode(kind) => kind.name().to_string(),
+        }
+    }
+
+    /// Name used for the file when saving
+    pub fn file_stem(&self) -> String {
+        match self {
+            SceneSource::Demo(demo) => demo.name().to_lowercase(),
+            SceneSource::File(path) | SceneSource::Challenge(path) => path
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            SceneSource::Random(_) => "random".to_string(),
+            SceneSource::Crash => "crash".to_string(),
+            SceneSource::Mode(kind) => kind.name().to_lowercase(),
+        }
+    }
+}
+
+/// Any number of random balls, with radius within a range and colors from a palette
+pub fn random(
+    rng: &mut impl Rng,
+    count: usize,
+    (min_radius, max_radius): (f32, f32),
+    palette: &[Color],
+    width: f32,
+    height: f32,
+) -> Scene {
+    let balls = (0..count)
+        .map(|_| {
+            let radius = rng.gen_range(min_radius..=max_radius);
+            let x = rng.gen_range(radius..(width - radius).max(radius + 1.0));
+            let y = rng.gen_range(radius..(height - radius).max(radius + 1.0));
+            let color = match palette {
+                [] => color!(?rng),
+                _ => palette[rng.gen_range(0..palette.len())],
+            };
+            Ball::new(x, y, radius, color)
+        })
+        .collect();
+    Scene::new(balls, 0.5)
+}
+
+/// Random balls dropped into an empty box
+fn pile(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let balls = (0..10)
+        .map(|_| Ball::new_random(rng, width, height))
+        .collect();
+    Scene::new(balls, 0.5)
+}
+
+/// Staggered rows of pinned pegs with small balls falling through
+fn plinko(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let mut balls = pegs(width, height);
+    for i in 0..20 {
+        let radius = rng.gen_range(8.0..16.0);
+        let x = width / 2.0 + rng.gen_range(-60.0..60.0);
+        let y = radius + i as f32 * 4.0;
+        balls.push(Ball::new(x, y, radius, color!(?rng)));
+    }
+
+    Scene::new(balls, 0.5)
+}
+
+/// Staggered rows of pinned pegs, over the middle of the screen
+pub fn pegs(width: f32, height: f32) -> Vec<Ball> {
+    let mut balls = Vec::new();
+    let spacing = 70.0;
+    let rows = ((height * 0.6) / spacing) as usize;
+    for row in 0..rows {
+        let y = height * 0.25 + row as f32 * spacing;
+        let offset = if row % 2 == 0 { 0.0 } else { spacing / 2.0 };
+        let mut x = spacing / 2.0 + offset;
+        while x < width {
+            let mut peg = Ball::new(x, y, 6.0, color!(GREEN));
+            peg.pinned = true;
+            balls.push(peg);
+            x += spacing;
+        }
+    }
+    balls
+}
+
+/// Balls circling a pinned sun, held in place by an attractor instead of gravity
+fn orbit(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let center = Point2 {
+        x: width / 2.0,
+        y: height / 2.0,
+    };
+    let strength: f32 = 9.0;
+
+    let mut sun = Ball::new(center.x, center.y, 40.0, color!(YELLOW));
+    sun.pinned = true;
+    let mut balls = vec![sun];
+
+    let max_distance = width.min(height) / 2.0 - 30.0;
+    for _ in 0..12 {
+        let radius = rng.gen_range(6.0..14.0);
+        let distance = rng.gen_range(70.0..max_distance.max(71.0));
+        let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        let mut ball = Ball::new(
+            center.x + angle.cos() * distance,
+            center.y + angle.sin() * distance,
+            radius,
+            color!(?rng),
+        );
+        // Force falls off linearly, so every orbit has the same speed
+        let speed = strength.sqrt();
+        ball.velocity = Vector2 {
+            x: -angle.sin() * speed,
+            y: angle.cos() * speed,
+        };
+        balls.push(ball);
+    }
+
+    Scene {
+        balls,
+        gravity: 0.0,
+        attractors: vec![Attractor {
+            point: center,
+            strength,
+        }],
+        ..Scene::default()
+    }
+}
+
+/// A dense clump of small balls that collapses into a puddle
+fn blob(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let color = color!(?rng);
+    let mut balls = Vec::new();
+    let radius = 10.0;
+    for row in 0..10 {
+        for col in 0..10 {
+            let x = width / 2.0 + (col as f32 - 4.5) * radius * 2.0;
+            let y = height / 4.0 + (row as f32 - 4.5) * radius * 2.0;
+            balls.push(Ball::new(x, y, radius, color));
+        }
+    }
+    Scene::new(balls, 0.5)
+}
+
+/// A spawner near the floor spraying small balls upwards
+fn fountain(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let mut spawner = Spawner::new(
+        Point2 {
+            x: width / 2.0,
+            y: height - 30.0,
+        },
+        4,
+        6.0,
+    );
+    spawner.radius = (4.0, 9.0);
+    spawner.velocity = Vector2 { x: 0.0, y: -14.0 };
+    spawner.spread = 3.0;
+    spawner.limit = Some(300);
+    spawner.seed = rng.gen();
+    spawner.palette = vec![color!(0x457b9d), color!(0xa8dadc), color!(0xf1faee)];
+    Scene {
+        balls: Vec::new(),
+        gravity: 0.3,
+        spawners: vec![spawner],
+        ..Scene::default()
+    }
+}
+
+/// Red and blue balls mixed together, to be sorted into a zone on each side
+fn sort(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let red = color!(0xe63946);
+    let blue = color!(0x457b9d);
+    let balls = (0..24)
+        .map(|i| {
+            let radius = rng.gen_range(12.0..20.0);
+            let x = rng.gen_range(width * 0.35..width * 0.65);
+            let y = rng.gen_range(radius..(height / 2.0).max(radius + 1.0));
+            Ball::new(x, y, radius, if i % 2 == 0 { red } else { blue })
+        })
+        .collect();
+    let zone_width = width * 0.25;
+    let top = height * 0.6;
+    Scene {
+        balls,
+        gravity: 0.5,
+        zones: vec![
+            Zone::new(
+                Point2 { x: 0.0, y: top },
+                Point2 {
+                    x: zone_width,
+                    y: height,
+                },
+                "Red",
+                Filter::Color(red),
+            ),
+            Zone::new(
+                Point2 {
+                    x: width - zone_width,
+                    y: top,
+                },
+                Point2 {
+                    x: width,
+                    y: height,
+                },
+                "Blue",
+                Filter::Color(blue),
+            ),
+        ],
+        ..Scene::default()
+    }
+}
+
+/// Balloons rising from the floor past heavy balls falling from the top
+fn balloons(rng: &mut impl Rng, width: f32, height: f32) -> Scene {
+    let mut balls = Vec::new();
+    for _ in 0..16 {
+        let radius = rng.gen_range(16.0..26.0);
+        let x = rng.gen_range(radius..(width - radius).max(radius + 1.0));
+        let y = rng.gen_range(height * 0.7..(height - radius).max(height * 0.7 + 1.0));
+        let mut balloon = Ball::new(x, y, radius, color!(?rng));
+        balloon.set_kind(BallKind::Balloon);
+        balls.push(balloon);
+    }
+    for _ in 0..4 {
+        let radius = rng.gen_range(20.0..30.0);
+        let x = rng.gen_range(radius..(width - radius).max(radius + 1.0));
+        let y = rng.gen_range(radius..(height * 0.3).max(radius + 1.0));
+        let mut rock = Ball::new(x, y, radius, color!(90, 90, 90));
+        rock.set_kind(BallKind::Heavy);
+        balls.push(rock);
+    }
+    Scene::new(balls, 0.5)
+}
+
+/// Small balls dropped in, which swell to fill the box and burst when too big
+fn dough(rng: &mut impl Rng, width: f32, _height: f32) -> Scene {
+    let mut spawner = Spawner::new(
+        Point2 {
+            x: width / 2.0,
+            y: 30.0,
+        },
+        20,
+        6.0,
+    );
+    spawner.radius = (5.0, 8.0);
+    spawner.spread = 2.0;
+    spawner.limit = Some(60);
+    spawner.seed = rng.gen();
+    spawner.palette = vec![color!(0xf4e1c1), color!(0xe9c46a), color!(0xf4a261)];
+    spawner.growth = Some(Growth {
+        rate: 0.04,
+        limit: 48.0,
+    });
+    Scene {
+        balls: Vec::new(),
+        gravity: 0.5,
+        spawners: vec![spawner],
+        ..Scene::default()
+    }
+}
+
+pub fn load(path: &Path) -> Result<Scene, String> {
+    let file = fs::read_to_string(path).map_err(|err| err.to_string())?;
+    let scene: Scene = ron::from_str(&file).map_err(|err| err.to_string())?;
+    scene.check()?;
+    Ok(scene)
+}
+
+/// Write scene to the scenes directory, returning the path written
+pub fn save(scene: &Scene, name: &str) -> Result<PathBuf, String> {
+    fs::create_dir_all(SCENES_DIR).map_err(|err| err.to_string())?;
+    let path = Path::new(SCENES_DIR).join(format!("{}.ron", name));
+    write(scene, &path)?;
+    Ok(path)
+}
+
+/// Write scene to any path, creating its directory if needed
+pub fn write(scene: &Scene, path: &Path) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+    }
+    let file =
+        ron::ser::to_string_pretty(scene, Default::default()).map_err(|err| err.to_string())?;
+    fs::write(path, file).map_err(|err| err.to_string())
+}
+
+/// Saved scenes, most recently modified first
+pub fn recent(limit: usize) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(SCENES_DIR) else {
+        return Vec::new();
+    };
+
+    let mut scenes: Vec<_> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().is_some_and(|ext| ext == "ron"))
+        .filter_map(|entry| {
+            let modified = entry.metadata().ok()?.modified().ok()?;
+            Some((modified, entry.path()))
+        })
+        .collect();
+    scenes.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    scenes
+        .into_iter()
+        .take(limit)
+        .map(|(_, path)| path)
+        .collect()
+}
+
+pub fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::SeedableRng;
+
+    /// Write a one-ball scene after breaking it, and load it back
+    fn load_broken(name: &str, f: impl FnOnce(&mut Scene)) -> Result<Scene, String> {
+        let mut scene = Scene::new(vec![Ball::new(10.0, 10.0, 10.0, Color::WHITE)], 0.5);
+        f(&mut scene);
+        let path = std::env::temp_dir().join(format!("balls-{}-{}.ron", name, std::process::id()));
+        write(&scene, &path).unwrap();
+        let scene = load(&path);
+        fs::remove_file(&path).unwrap();
+        scene
+    }
+
+    #[test]
+    fn scenes_with_broken_numbers_are_rejected() {
+        assert!(load_broken("good", |_| ()).is_ok());
+        assert!(load_broken("zero-radius", |scene| scene.balls[0].radius = 0.0).is_err());
+        assert!(load_broken("negative-radius", |scene| scene.balls[0].radius = -4.0).is_err());
+        assert!(load_broken("nan-radius", |scene| scene.balls[0].radius = f32::NAN).is_err());
+        assert!(load_broken("inf-point", |scene| scene.balls[0].point.x = f32::INFINITY).is_err());
+        assert!(load_broken("nan-velocity", |scene| scene.balls[0].velocity.y = f32::NAN).is_err());
+        assert!(load_broken("nan-gravity", |scene| scene.gravity = f32::NAN).is_err());
+        assert!(load_broken("inf-wall", |scene| {
+            scene.walls.floor.restitution = f32::INFINITY
+        })
+        .is_err());
+    }
+
+    #[test]
+    fn demos_build_in_tiny_windows() {
+        let mut rng = rand::rngs::StdRng::seed_from_u64(0);
+        for demo in Demo::ALL {
+            for (width, height) in [(1.0, 1.0), (40.0, 300.0), (300.0, 40.0), (120.0, 120.0)] {
+                demo.build(&mut rng, width, height);
+            }
+        }
+    }
+}
@@ -0,0 +1,124 @@
+use ggez::graphics::{Canvas, DrawParam, Drawable};
+use ggez::mint::Point2;
+use ggez::Context;
+
+use crate::events::Event;
+use crate::text::TextStyle;
+
+/// Seconds after scoring that another score continues the combo
+const COMBO_WINDOW: f32 = 2.0;
+const MAX_MULTIPLIER: u32 = 5;
+/// Seconds a popup stays on screen
+const POPUP_LIFETIME: f32 = 1.0;
+/// Pixels a popup rises over its lifetime
+const POPUP_RISE: f32 = 40.0;
+const MARGIN: f32 = 10.0;
+
+/// Points shown rising from where they were scored
+struct Popup {
+    text: String,
+    point: Point2<f32>,
+    age: f32,
+}
+
+/// Running total of points scored by a game, fed by `Event::Scored`, which
+/// goes up faster for scores in quick succession
+#[derive(Default)]
+pub struct Score {
+    total: u64,
+    /// Scores in the current combo, the first of which is worth normal points
+    combo: u32,
+    /// Seconds left to continue the combo
+    combo_timer: f32,
+    popups: Vec<Popup>,
+    /// Whether anything has scored, so games without points show no total
+    active: bool,
+}
+
+impl Score {
+    fn multiplier(&self) -> u32 {
+        self.combo.clamp(1, MAX_MULTIPLIER)
+    }
+
+    /// Add points for each score event
+    pub fn record(&mut self, events: &[Event]) {
+        for event in events {
+            if let Event::Scored { points, point } = *event {
+                self.add(points, point);
+            }
+        }
+    }
+
+    fn add(&mut self, points: u32, point: Point2<f32>) {
+        self.active = true;
+        self.combo = if self.combo_timer > 0.0 {
+            self.combo + 1
+        } else {
+            1
+        };
+        self.combo_timer = COMBO_WINDOW;
+        let multiplier = self.multiplier();
+        let gained = points as u64 * multiplier as u64;
+        self.total += gained;
+        let text = if multiplier > 1 {
+            format!("+{} x{}", gained, multiplier)
+        } else {
+            format!("+{}", gained)
+        };
+        self.popups.push(Popup {
+            text,
+            point,
+            age: 0.0,
+        });
+    }
+
+    /// Whether any popups are still rising
+    pub fn is_animating(&self) -> bool {
+        !self.popups.is_empty()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        self.combo_timer = (self.combo_timer - dt).max(0.0);
+        if self.combo_timer == 0.0 {
+            self.combo = 0;
+        }
+        for popup in &mut self.popups {
+            popup.age += dt;
+        }
+        self.popups.retain(|popup| popup.age < POPUP_LIFETIME);
+    }
+
+    /// Draw popups, rising unless `still`, and the total in the top-right corner
+    /// once anything has scored
+    pub fn draw(
+        &self,
+        ctx: &Context,
+        canvas: &mut Canvas,
+        width: f32,
+        still: bool,
+        style: TextStyle,
+    ) {
+        for popup in &self.popups {
+            let fraction = popup.age / POPUP_LIFETIME;
+            let text = style.text(&popup.text, 20.0);
+            let color = color!(255, 214, 10, 255.0 * (1.0 - fraction));
+            let rise = if still { 0.0 } else { POPUP_RISE * fraction };
+            let point = [popup.point.x, popup.point.y - rise];
+            canvas.draw(&text, DrawParam::from(point).color(color));
+        }
+
+        if !self.active {
+            return;
+        }
+        let mut label = format!("Score: {}", self.total);
+        if self.combo > 1 {
+            label.push_str(&format!("    Combo x{}", self.multiplier()));
+        }
+        let text = style.text(label, 24.0);
+        let Some(size) = text.dimensions(ctx) else {
+            return;
+        };
+        let margin = style.px(MARGIN);
+        canvas.draw(&text, DrawParam::from([width - size.w - margin, margin]));
+    }
+}
@@ -0,0 +1,146 @@
+use std::fs;
+use std::path::Path;
+
+use ggez::conf::{FullscreenType, WindowMode};
+use serde::{Deserialize, Serialize};
+
+const FILE_NAME: &str = "settings.ron";
+const VOLUME_STEP: f32 = 0.1;
+
+/// User preferences, persisted between runs
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub audio: AudioSettings,
+    /// Leave out effects that move on their own, and slow the fastest balls
+    pub reduced_motion: bool,
+    /// Where the window was when the app last closed, to open it there again
+    pub window: Option<WindowGeometry>,
+}
+
+impl Settings {
+    /// Read settings from the directory, falling back to defaults
+    pub fn load(dir: &Path) -> Self {
+        fs::read_to_string(dir.join(FILE_NAME))
+            .ok()
+            .and_then(|file| ron::from_str(&file).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, dir: &Path) -> Result<(), String> {
+        fs::create_dir_all(dir).map_err(|err| err.to_string())?;
+        let file =
+            ron::ser::to_string_pretty(self, Default::default()).map_err(|err| err.to_string())?;
+        fs::write(dir.join(FILE_NAME), file).map_err(|err| err.to_string())
+    }
+}
+
+/// Size and place of the window
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct WindowGeometry {
+    /// Size while not fullscreen
+    pub width: f32,
+    pub height: f32,
+    /// Outer position while not fullscreen, in physical pixels
+    pub position: Option<(i32, i32)>,
+    /// Name of the monitor the window was on
+    pub monitor: Option<String>,
+    pub fullscreen: bool,
+    /// Whether the window has no title bar or frame
+    #[serde(default)]
+    pub borderless: bool,
+}
+
+impl WindowGeometry {
+    /// Window mode with this size and fullscreen state
+    pub fn window_mode(&self) -> WindowMode {
+        let fullscreen = if self.fullscreen {
+            FullscreenType::Desktop
+        } else {
+            FullscreenType::Windowed
+        };
+        WindowMode::default()
+            .dimensions(self.width, self.height)
+            .resizable(true)
+            .borderless(self.borderless)
+            .fullscreen_type(fullscreen)
+    }
+}
+
+/// Volume of each audio channel, from 0 to 1
+#[derive(Clone, Copy, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AudioSettings {
+    pub master: f32,
+    pub effects: f32,
+    pub music: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            effects: 1.0,
+            music: 0.5,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Master,
+    Effects,
+    Music,
+}
+
+impl Channel {
+    pub const ALL: [Channel; 3] = [Channel::Master, Channel::Effects, Channel::Music];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Channel::Master => "Master volume",
+            Channel::Effects => "Effects volume",
+            Channel::Music => "Music volume",
+        }
+    }
+}
+
+impl AudioSettings {
+    pub fn get(&self, channel: Channel) -> f32 {
+        match channel {
+            Channel::Master => self.master,
+            Channel::Effects => self.effects,
+            Channel::Music => self.music,
+        }
+    }
+
+    /// Change a channel by a step in a direction
+    pub fn adjust(&mut self, channel: Channel, direction: f32) {
+        let volume = self.volume_mut(channel);
+        // Round to avoid drift from repeated float steps
+        *volume = ((*volume + direction * VOLUME_STEP).clamp(0.0, 1.0) * 10.0).round() / 10.0;
+    }
+
+    pub fn set(&mut self, channel: Channel, volume: f32) {
+        *self.volume_mut(channel) = volume.clamp(0.0, 1.0);
+    }
+
+    fn volume_mut(&mut self, channel: Channel) -> &mut f32 {
+        match channel {
+            Channel::Master => &mut self.master,
+            Channel::Effects => &mut self.effects,
+            Channel::Music => &mut self.music,
+        }
+    }
+
+    /// Final volume music should be played at
+    pub fn music_volume(&self) -> f32 {
+        self.master * self.music
+    }
+
+    /// Final volume sound effects should be played at
+    pub fn effects_volume(&self) -> f32 {
+        self.master * self.effects
+    }
+}
@@ -0,0 +1,1217 @@
+use std::collections::VecDeque;
+use std::str::FromStr;
+
+use ggez::mint::{Point2, Vector2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::Deserialize;
+use slotmap::Key;
+
+use crate::ball::{Attractor, Ball, BallId, BallKind};
+use crate::components::{Components, Growth, Health, Lifetime};
+use crate::events::Event;
+use crate::forces::{Env, Force, ForceGenerator};
+use crate::obstacle::Obstacle;
+use crate::physics::{self, Scratch};
+use crate::scene::Scene;
+use crate::spawner::Spawner;
+use crate::stats::StepStats;
+use crate::storage::{BallMut, Balls};
+use crate::walls::Walls;
+use crate::zone::Zone;
+
+/// Length of one physics tick, in seconds
+pub const TICK: f32 = 1.0 / 60.0;
+/// Most ticks run by a single step, so a slow frame can't snowball
+const MAX_TICKS_PER_STEP: u32 = 5;
+/// Depth a heavy ball must press into a balloon to pop it, relative to the balloon's radius
+const SQUEEZE: f32 = 0.3;
+/// Overlap worth warning about, in pixels, as the solver should keep it far smaller
+const DEEP_PENETRATION: f32 = 10.0;
+/// Hit points lost per unit of impact energy, taken as half the squared impact speed
+const IMPACT_DAMAGE: f32 = 0.5;
+/// Pieces a broken ball shatters into
+const FRAGMENTS: usize = 3;
+/// Size of the pieces of a broken ball, relative to it
+const FRAGMENT_SCALE: f32 = 0.45;
+/// Smallest piece worth leaving behind when a ball breaks
+const MIN_FRAGMENT: f32 = 3.0;
+/// Speed pieces of a broken ball fly apart at
+const FRAGMENT_SPEED: f32 = 2.0;
+/// Slowest impact that sets off a bomb
+const DETONATE_SPEED: f32 = 5.0;
+/// Reach of a bomb's blast, relative to its radius
+const BLAST_SCALE: f32 = 6.0;
+/// Speed given to a ball at the center of a blast, falling off to nothing at its edge
+const BLAST_SPEED: f32 = 12.0;
+/// Hit points taken from a ball at the center of a blast
+const BLAST_DAMAGE: f32 = 60.0;
+/// Ticks every ball must stay settled for before the scene counts as at rest
+const REST_TICKS: u32 = 120;
+/// Ticks an earthquake takes to build up to full strength
+const FULL_QUAKE_TICKS: u32 = 120;
+/// Largest push given to a ball each tick of an earthquake at full strength
+const QUAKE_IMPULSE: f32 = 1.5;
+
+/// Values that apply to the whole simulation
+#[derive(Clone, Copy, PartialEq)]
+pub struct Params {
+    /// Downwards acceleration, in pixels per tick squared
+    pub gravity: f32,
+    pub width: f32,
+    pub height: f32,
+    /// Resolve collisions on every core, against where balls were at the start of the
+    /// phase. Faster for large scenes, but gives slightly different results
+    pub parallel: bool,
+    /// Collisions warm balls up, making them glow and bounce more until they cool down
+    pub heat: bool,
+    /// How bouncy and rough each side of the box is
+    pub walls: Walls,
+}
+
+/// What to do when adding a ball would go over the ball limit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CapPolicy {
+    /// Don't add the new ball
+    #[default]
+    Reject,
+    /// Remove the ball that was added first
+    RemoveOldest,
+    /// Remove the smallest ball
+    RemoveSmallest,
+}
+
+impl FromStr for CapPolicy {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "reject" => Ok(Self::Reject),
+            "remove-oldest" => Ok(Self::RemoveOldest),
+            "remove-smallest" => Ok(Self::RemoveSmallest),
+            _ => Err(format!(
+                "Invalid policy `{}`, expected reject, remove-oldest or remove-smallest",
+                value
+            )),
+        }
+    }
+}
+
+/// Most balls a simulation can hold, and how to make room for more
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cap {
+    pub max: usize,
+    pub policy: CapPolicy,
+}
+
+/// How the scene was when it came to rest, to tell when anything changes it
+struct Rest {
+    balls: Balls,
+    params: Params,
+    /// Which forces were on
+    forces: Vec<bool>,
+    attractors: usize,
+}
+
+impl Rest {
+    fn new(sim: &Simulation) -> Self {
+        Self {
+            balls: sim.balls.clone(),
+            params: sim.params,
+            forces: sim.forces.iter().map(|force| force.enabled).collect(),
+            attractors: sim.attractors.len(),
+        }
+    }
+
+    fn matches(&self, sim: &Simulation) -> bool {
+        self.balls.same_motion(&sim.balls)
+            && self.params == sim.params
+            && self.forces.len() == sim.forces.len()
+            && self
+                .forces
+                .iter()
+                .zip(&sim.forces)
+                .all(|(&enabled, force)| enabled == force.enabled)
+            && self.attractors == sim.attractors.len()
+    }
+}
+
+/// Balls and the forces acting on them, without any window or input
+pub struct Simulation {
+    balls: Balls,
+    pub components: Components,
+    /// Every ball, sorted largest to smallest
+    order: Vec<BallId>,
+    /// Balls in the order they were added, including some since removed
+    spawned: VecDeque<BallId>,
+    /// Limit on the number of balls, applied when adding more
+    pub cap: Option<Cap>,
+    pub attractors: Vec<Attractor>,
+    /// Emit balls every tick, before physics runs
+    pub spawners: Vec<Spawner>,
+    /// Areas counting the balls inside them
+    pub zones: Vec<Zone>,
+    /// Rectangles balls bounce off, placed and moved by games
+    pub obstacles: Vec<Obstacle>,
+    /// Ticks balls from spawners last, unless their spawner sets its own
+    pub lifetime: Option<u32>,
+    /// Fastest balls may move, in pixels per tick, or no limit if unset
+    pub max_speed: Option<f32>,
+    /// Applied in order every tick, to loose balls
+    pub forces: Vec<Force>,
+    pub params: Params,
+    /// Ball being moved from outside, which physics leaves in place
+    pub held: Option<BallId>,
+    /// Time not yet simulated, less than one tick
+    leftover: f32,
+    /// Events since the start of the last step
+    events: Vec<Event>,
+    /// Set once everything has settled, so ticks can be skipped until something changes
+    rest: Option<Rest>,
+    /// Shaking every loose ball each tick, while the earthquake key is held
+    quake: Option<Quake>,
+    scratch: Scratch,
+    /// Balls emitted this tick, with the index of their spawner
+    emitted: Vec<(usize, Ball)>,
+    /// Balls to remove this tick, found before removing any
+    doomed: Vec<BallId>,
+}
+
+/// Copy of the state ticks change, to go back to with `Simulation::restore`
+#[derive(Clone)]
+pub struct Snapshot {
+    balls: Balls,
+    components: Components,
+    order: Vec<BallId>,
+    spawned: VecDeque<BallId>,
+    attractors: Vec<Attractor>,
+    spawners: Vec<Spawner>,
+    zones: Vec<Zone>,
+}
+
+/// Earthquake growing stronger the longer it lasts
+struct Quake {
+    ticks: u32,
+    /// Seeded when the quake starts, so replays shake the same way
+    rng: StdRng,
+}
+
+impl Simulation {
+    pub fn new(params: Params) -> Self {
+        Self {
+            balls: Balls::default(),
+            components: Components::default(),
+            order: Vec::new(),
+            spawned: VecDeque::new(),
+            cap: None,
+            attractors: Vec::new(),
+            spawners: Vec::new(),
+            zones: Vec::new(),
+            obstacles: Vec::new(),
+            lifetime: None,
+            max_speed: None,
+            forces: Force::defaults(),
+            params,
+            held: None,
+            leftover: 0.0,
+            events: Vec::new(),
+            rest: None,
+            quake: None,
+            scratch: Scratch::default(),
+            emitted: Vec::new(),
+            doomed: Vec::new(),
+        }
+    }
+
+    pub fn from_scene(scene: Scene, width: f32, height: f32) -> Self {
+        let mut simulation = Self::new(Params {
+            gravity: scene.gravity,
+            width,
+            height,
+            parallel: false,
+            heat: false,
+            walls: scene.walls.clamped(),
+        });
+        simulation.attractors = scene.attractors;
+        simulation.spawners = scene.spawners;
+        simulation.zones = scene.zones;
+        simulation.add_balls(scene.balls);
+        simulation
+    }
+
+    /// Snapshot of the current state
+    pub fn scene(&self) -> Scene {
+        Scene {
+            balls: self.balls().map(|(_, ball)| ball).collect(),
+            gravity: self.params.gravity,
+            attractors: self.attractors.clone(),
+            spawners: self.spawners.clone(),
+            zones: self.zones.clone(),
+            walls: self.params.walls,
+        }
+    }
+
+    /// Independent copy to run ahead, with no events or held ball, and without
+    /// any forces that can't be copied
+    pub fn fork(&self) -> Simulation {
+        let mut sim = Simulation::new(self.params);
+        sim.fork_from(self);
+        sim
+    }
+
+    /// Become a fork of another simulation like `fork` makes, reusing this one's
+    /// buffers so it can be done every frame
+    pub fn fork_from(&mut self, source: &Simulation) {
+        self.balls.copy_from(&source.balls);
+        self.components.copy_from(&source.components);
+        self.order.clone_from(&source.order);
+        self.spawned.clone_from(&source.spawned);
+        self.cap = source.cap;
+        self.attractors.clone_from(&source.attractors);
+        self.spawners.clone_from(&source.spawners);
+        self.zones.clone_from(&source.zones);
+        self.obstacles.clone_from(&source.obstacles);
+        self.lifetime = source.lifetime;
+        self.max_speed = source.max_speed;
+        self.forces.clear();
+        self.forces
+            .extend(source.forces.iter().filter_map(Force::fork));
+        self.params = source.params;
+        self.held = None;
+        self.leftover = 0.0;
+        self.events.clear();
+        self.rest = None;
+        self.quake = None;
+    }
+
+    /// Copy of the balls and everything ticks change about the scene
+    pub fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            balls: self.balls.clone(),
+            components: self.components.clone(),
+            order: self.order.clone(),
+            spawned: self.spawned.clone(),
+            attractors: self.attractors.clone(),
+            spawners: self.spawners.clone(),
+            zones: self.zones.clone(),
+        }
+    }
+
+    /// Go back to a snapshot, keeping the forces and params as they are now.
+    /// Lets go of the held ball if it didn't exist yet
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        let snapshot = snapshot.clone();
+        self.balls = snapshot.balls;
+        self.components = snapshot.components;
+        self.order = snapshot.order;
+        self.spawned = snapshot.spawned;
+        self.attractors = snapshot.attractors;
+        self.spawners = snapshot.spawners;
+        self.zones = snapshot.zones;
+        if self.held.is_some_and(|id| self.balls.slot(id).is_none()) {
+            self.held = None;
+        }
+        self.leftover = 0.0;
+        self.events.clear();
+        self.rest = None;
+    }
+
+    /// Points a ball would pass through over a number of ticks if let go at a
+    /// velocity, either alone or among the other balls
+    pub fn predict(
+        &self,
+        id: BallId,
+        velocity: Vector2<f32>,
+        ticks: u32,
+        others: bool,
+    ) -> Vec<Point2<f32>> {
+        let Some(mut ball) = self.ball(id) else {
+            return Vec::new();
+        };
+        ball.velocity = velocity;
+        let (mut sim, id) = if others {
+            let mut sim = self.fork();
+            if let Some(mut forked) = sim.ball_mut(id) {
+                forked.velocity = velocity;
+            }
+            (sim, id)
+        } else {
+            let mut sim = Simulation::new(self.params);
+            sim.forces = self.forces.iter().filter_map(Force::fork).collect();
+            sim.attractors = self.attractors.clone();
+            let Some(id) = sim.add_ball(ball) else {
+                return Vec::new();
+            };
+            (sim, id)
+        };
+        let mut points = Vec::with_capacity(ticks as usize);
+        for _ in 0..ticks {
+            sim.tick();
+            let Some(ball) = sim.ball(id) else {
+                break;
+            };
+            points.push(ball.point);
+        }
+        points
+    }
+
+    /// Advance by a number of seconds, in whole ticks
+    ///
+    /// Returns the combined stats of the ticks run, if there were any.
+    /// Events queued before this step are cleared
+    pub fn step(&mut self, dt: f32) -> Option<StepStats> {
+        self.events.clear();
+        let ticks = self.ticks_due(dt);
+        self.run(ticks)
+    }
+
+    /// Whole ticks that fit in the time passed, keeping the remainder for later
+    pub fn ticks_due(&mut self, dt: f32) -> u32 {
+        self.leftover += dt;
+        let mut ticks = 0;
+        while self.leftover >= TICK {
+            self.leftover -= TICK;
+            ticks += 1;
+            if ticks == MAX_TICKS_PER_STEP {
+                self.leftover = 0.0;
+                break;
+            }
+        }
+        ticks
+    }
+
+    /// Advance by a number of ticks, returning their combined stats if there were any.
+    /// Events are kept until cleared
+    pub fn run(&mut self, ticks: u32) -> Option<StepStats> {
+        (0..ticks).map(|_| self.tick()).reduce(StepStats::merge)
+    }
+
+    /// Advance by exactly one tick, or do nothing while the scene is at rest
+    pub fn tick(&mut self) -> StepStats {
+        if self.is_resting() {
+            return StepStats {
+                sleeping: self.components.asleep.len(),
+                ..StepStats::default()
+            };
+        }
+        if self.rest.is_some() {
+            self.wake();
+        }
+        self.spawn();
+        if let Some(quake) = &mut self.quake {
+            quake.ticks = (quake.ticks + 1).min(FULL_QUAKE_TICKS);
+            let impulse = QUAKE_IMPULSE * quake.ticks as f32 / FULL_QUAKE_TICKS as f32;
+            physics::shake(&mut self.balls, self.held, impulse, &mut quake.rng);
+        }
+        let first_event = self.events.len();
+        let stats = physics::step(
+            &mut self.balls,
+            &mut self.components,
+            &self.order,
+            &mut self.forces,
+            self.held,
+            &Env {
+                params: &self.params,
+                attractors: &self.attractors,
+                obstacles: &self.obstacles,
+            },
+            &mut self.events,
+            &mut self.scratch,
+        );
+        if stats.max_penetration > DEEP_PENETRATION {
+            log::warn!(
+                "Deep penetration of {:.1}px among {} balls",
+                stats.max_penetration,
+                self.balls.len()
+            );
+        }
+        self.reset_non_finite();
+        if let Some(max) = self.max_speed {
+            physics::limit_speed(&mut self.balls, max);
+        }
+        self.damage(first_event);
+        self.detonate(first_event);
+        self.pop_squeezed();
+        self.grow();
+        self.expire();
+        physics::sense_zones(&self.balls, &mut self.zones);
+        if self.has_settled() {
+            self.rest = Some(Rest::new(self));
+        }
+        stats
+    }
+
+    /// Whether nothing has changed since every ball settled, so ticks do nothing.
+    /// Forces are assumed to stay the same unless turned on or off
+    pub fn is_resting(&self) -> bool {
+        self.rest
+            .as_ref()
+            .is_some_and(|rest| !self.is_busy() && rest.matches(self))
+    }
+
+    /// Start counting towards rest again, after a change that ticks wouldn't notice
+    /// such as replacing the forces
+    pub fn wake(&mut self) {
+        self.rest = None;
+        self.components.settled.clear();
+    }
+
+    /// Start shaking every loose ball each tick, unless already shaking
+    pub fn start_quake(&mut self, seed: u64) {
+        if self.quake.is_none() {
+            self.quake = Some(Quake {
+                ticks: 0,
+                rng: StdRng::seed_from_u64(seed),
+            });
+        }
+    }
+
+    pub fn stop_quake(&mut self) {
+        self.quake = None;
+    }
+
+    /// How strong the earthquake is, from 0 if there is none to 1 at full strength
+    pub fn quake_strength(&self) -> f32 {
+        self.quake
+            .as_ref()
+            .map_or(0.0, |quake| quake.ticks as f32 / FULL_QUAKE_TICKS as f32)
+    }
+
+    /// Whether something other than the balls' motion changes the scene each tick
+    fn is_busy(&self) -> bool {
+        self.held.is_some()
+            || self.quake.is_some()
+            || !self.spawners.is_empty()
+            || !self.components.lifetime.is_empty()
+            || !self.components.growth.is_empty()
+            || !self.components.temperature.is_empty()
+    }
+
+    /// Whether every ball has stayed where it stopped for long enough to rest
+    fn has_settled(&self) -> bool {
+        !self.is_busy()
+            && (0..self.balls.len()).all(|slot| {
+                self.components
+                    .settled
+                    .get(self.balls.id(slot))
+                    .is_some_and(|settled| settled.ticks >= REST_TICKS)
+            })
+    }
+
+    /// Add balls emitted by spawners, with their spawner's lifetime
+    fn spawn(&mut self) {
+        let mut emitted = std::mem::take(&mut self.emitted);
+        for (i, spawner) in self.spawners.iter_mut().enumerate() {
+            emitted.extend(spawner.tick().map(|ball| (i, ball)));
+        }
+        if emitted.is_empty() {
+            self.emitted = emitted;
+            return;
+        }
+        for (i, ball) in emitted.drain(..) {
+            let spawner = &self.spawners[i];
+            let lifetime = spawner.lifetime.or(self.lifetime);
+            let (growth, hp) = (spawner.growth, spawner.hp);
+            let Some(id) = self.insert(ball) else {
+                break;
+            };
+            if let Some(ticks) = lifetime {
+                self.set_lifetime(id, ticks);
+            }
+            if let Some(growth) = growth {
+                self.set_growth(id, growth);
+            }
+            if let Some(hp) = hp {
+                self.set_hp(id, hp);
+            }
+        }
+        self.emitted = emitted;
+        self.balls.sort_by_size(&mut self.order);
+    }
+
+    /// Age balls with a lifetime, removing any that have run out
+    fn expire(&mut self) {
+        let mut expired = std::mem::take(&mut self.doomed);
+        expired.extend(
+            self.components
+                .lifetime
+                .iter_mut()
+                .filter_map(|(id, lifetime)| {
+                    lifetime.age += 1;
+                    (lifetime.age >= lifetime.ticks).then_some(id)
+                }),
+        );
+        for id in expired.drain(..) {
+            // Balloons pop instead of fading away
+            if self
+                .ball(id)
+                .is_some_and(|ball| ball.kind == BallKind::Balloon)
+            {
+                self.pop_ball(id);
+            } else {
+                self.remove_ball(id);
+            }
+        }
+        self.doomed = expired;
+    }
+
+    /// Take hit points from breakable balls for impacts since an event, breaking
+    /// any that run out
+    fn damage(&mut self, first_event: usize) {
+        if self.components.health.is_empty() {
+            return;
+        }
+        let mut broken = std::mem::take(&mut self.doomed);
+        for event in &self.events[first_event..] {
+            let (ids, speed) = match *event {
+                Event::BallHitBall { a, b, speed } => ([Some(a), Some(b)], speed),
+                Event::BallHitWall { ball, speed, .. }
+                | Event::BallHitObstacle { ball, speed, .. } => ([Some(ball), None], speed),
+                _ => continue,
+            };
+            for id in ids.into_iter().flatten() {
+                let Some(health) = self.components.health.get_mut(id) else {
+                    continue;
+                };
+                if health.hp <= 0.0 {
+                    continue;
+                }
+                health.hp -= IMPACT_DAMAGE * 0.5 * speed * speed;
+                if health.hp <= 0.0 {
+                    broken.push(id);
+                }
+            }
+        }
+        for id in broken.drain(..) {
+            self.break_ball(id);
+        }
+        self.doomed = broken;
+    }
+
+    /// Set off bombs that hit something hard enough since an event, along with any
+    /// bombs caught in their blasts, one at a time in the order they were set off
+    fn detonate(&mut self, first_event: usize) {
+        let mut queue: VecDeque<BallId> = VecDeque::new();
+        for event in &self.events[first_event..] {
+            let (ids, speed) = match *event {
+                Event::BallHitBall { a, b, speed } => ([Some(a), Some(b)], speed),
+                Event::BallHitWall { ball, speed, .. }
+                | Event::BallHitObstacle { ball, speed, .. } => ([Some(ball), None], speed),
+                _ => continue,
+            };
+            if speed < DETONATE_SPEED {
+                continue;
+            }
+            for id in ids.into_iter().flatten() {
+                let is_bomb = self
+                    .balls
+                    .get(id)
+                    .is_some_and(|ball| ball.kind == BallKind::Bomb);
+                if is_bomb && !queue.contains(&id) {
+                    queue.push_back(id);
+                }
+            }
+        }
+
+        while let Some(id) = queue.pop_front() {
+            let Some(bomb) = self.pop_ball(id) else {
+                continue;
+            };
+            let radius = bomb.radius * BLAST_SCALE;
+            self.events.push(Event::Exploded {
+                point: bomb.point,
+                radius,
+            });
+            let mut broken = Vec::new();
+            for slot in 0..self.balls.len() {
+                let ball = self.balls.at(slot);
+                let (dx, dy) = (ball.point.x - bomb.point.x, ball.point.y - bomb.point.y);
+                let distance = dx.hypot(dy);
+                let reach = 1.0 - (distance - ball.radius).max(0.0) / radius;
+                if reach <= 0.0 {
+                    continue;
+                }
+                let id = self.balls.id(slot);
+                if ball.kind == BallKind::Bomb {
+                    if !queue.contains(&id) {
+                        queue.push_back(id);
+                    }
+                    continue;
+                }
+                if let Some(health) = self.components.health.get_mut(id) {
+                    health.hp -= BLAST_DAMAGE * reach;
+                    if health.hp <= 0.0 {
+                        broken.push(id);
+                    }
+                }
+                if !ball.pinned && distance > 0.0 {
+                    let speed = BLAST_SPEED * reach / ball.kind.density();
+                    self.balls.vxs[slot] += dx / distance * speed;
+                    self.balls.vys[slot] += dy / distance * speed;
+                }
+            }
+            for id in broken {
+                self.break_ball(id);
+            }
+        }
+    }
+
+    /// Pop a ball, leaving smaller pieces flying apart where it was
+    pub fn break_ball(&mut self, id: BallId) -> Option<Ball> {
+        let ball = self.pop_ball(id)?;
+        let radius = ball.radius * FRAGMENT_SCALE;
+        if radius < MIN_FRAGMENT {
+            return Some(ball);
+        }
+        for i in 0..FRAGMENTS {
+            let angle = i as f32 / FRAGMENTS as f32 * std::f32::consts::TAU;
+            let (sin, cos) = angle.sin_cos();
+            let mut piece = Ball::new(
+                ball.point.x + cos * ball.radius * 0.55,
+                ball.point.y + sin * ball.radius * 0.55,
+                radius,
+                ball.color,
+            );
+            piece.kind = ball.kind;
+            piece.restitution = ball.restitution;
+            piece.velocity.x = ball.velocity.x + cos * FRAGMENT_SPEED;
+            piece.velocity.y = ball.velocity.y + sin * FRAGMENT_SPEED;
+            if self.insert(piece).is_none() {
+                break;
+            }
+        }
+        self.balls.sort_by_size(&mut self.order);
+        Some(ball)
+    }
+
+    /// Pop balloons that heavy balls are pressing too far into
+    fn pop_squeezed(&mut self) {
+        let kinds = &self.balls.kinds;
+        if !kinds.contains(&BallKind::Heavy) || !kinds.contains(&BallKind::Balloon) {
+            return;
+        }
+        let mut squeezed = std::mem::take(&mut self.doomed);
+        squeezed.extend(
+            (0..self.balls.len())
+                .filter(|&slot| kinds[slot] == BallKind::Balloon)
+                .filter(|&slot| {
+                    let ball = self.balls.at(slot);
+                    (0..self.balls.len()).any(|other| {
+                        kinds[other] == BallKind::Heavy
+                            && self.balls.penetration(&ball, other) > ball.radius * SQUEEZE
+                    })
+                })
+                .map(|slot| self.balls.id(slot)),
+        );
+        for id in squeezed.drain(..) {
+            self.pop_ball(id);
+        }
+        self.doomed = squeezed;
+    }
+
+    /// Change the size of growing and shrinking balls, bursting or removing any
+    /// that reach their limit
+    fn grow(&mut self) {
+        if self.components.growth.is_empty() {
+            return;
+        }
+        let mut done = std::mem::take(&mut self.doomed);
+        for (id, growth) in &self.components.growth {
+            let Some(slot) = self.balls.slot(id) else {
+                continue;
+            };
+            let radius = (self.balls.radii[slot] + growth.rate).max(0.5);
+            self.balls.radii[slot] = radius;
+            if growth.is_done(radius) {
+                done.push(id);
+            }
+        }
+        for id in done.drain(..) {
+            // Grown balls burst, and shrunk ones fade away
+            if self
+                .components
+                .growth
+                .get(id)
+                .is_some_and(|growth| growth.rate >= 0.0)
+            {
+                self.pop_ball(id);
+            } else {
+                self.remove_ball(id);
+            }
+        }
+        self.doomed = done;
+        self.balls.sort_by_size(&mut self.order);
+    }
+
+    /// Resize a ball, keeping the balls sorted by size
+    pub fn set_radius(&mut self, id: BallId, radius: f32) {
+        let Some(slot) = self.balls.slot(id) else {
+            return;
+        };
+        self.balls.radii[slot] = radius;
+        self.balls.sort_by_size(&mut self.order);
+    }
+
+    /// Grow or shrink a ball every tick until it reaches a limit
+    pub fn set_growth(&mut self, id: BallId, growth: Growth) {
+        if self.balls.slot(id).is_some() {
+            self.components.growth.insert(id, growth);
+        }
+    }
+
+    /// Give a ball hit points, which hard impacts wear down until it breaks
+    pub fn set_hp(&mut self, id: BallId, hp: f32) {
+        if self.balls.slot(id).is_some() {
+            self.components.health.insert(id, Health::new(hp));
+        }
+    }
+
+    /// Remove a ball once it has lived for a number of ticks, fading out first
+    pub fn set_lifetime(&mut self, id: BallId, ticks: u32) {
+        if self.balls.slot(id).is_some() {
+            self.components.lifetime.insert(id, Lifetime::new(ticks));
+        }
+    }
+
+    /// Stop any ball whose position or velocity is no longer a number, which
+    /// would otherwise spread to every ball it touches
+    fn reset_non_finite(&mut self) {
+        for slot in 0..self.balls.len() {
+            let balls = &self.balls;
+            if [
+                balls.xs[slot],
+                balls.ys[slot],
+                balls.vxs[slot],
+                balls.vys[slot],
+            ]
+            .iter()
+            .all(|n| n.is_finite())
+            {
+                continue;
+            }
+            let mut ball = self.balls.at(slot);
+            log::warn!(
+                "Clamped non-finite position or velocity of ball {:?}",
+                self.balls.id(slot).data()
+            );
+            ball.velocity.x = 0.0;
+            ball.velocity.y = 0.0;
+            if !ball.point.x.is_finite() || !ball.point.y.is_finite() {
+                ball.point.x = self.params.width / 2.0;
+                ball.point.y = self.params.height / 2.0;
+            }
+            self.balls.set(slot, ball);
+        }
+    }
+
+    /// Register a force to apply after the existing ones
+    pub fn add_force(&mut self, generator: impl ForceGenerator + 'static) {
+        self.forces.push(Force::new(generator, true));
+    }
+
+    /// Balls from largest to smallest, with their handles
+    pub fn balls(&self) -> impl Iterator<Item = (BallId, Ball)> + '_ {
+        self.order
+            .iter()
+            .filter_map(|&id| Some((id, self.balls.get(id)?)))
+    }
+
+    pub fn ball(&self, id: BallId) -> Option<Ball> {
+        self.balls.get(id)
+    }
+
+    /// Copy of a ball, written back when dropped
+    pub fn ball_mut(&mut self, id: BallId) -> Option<BallMut<'_>> {
+        self.balls.get_mut(id)
+    }
+
+    /// Change every ball in place
+    pub fn update_balls(&mut self, mut f: impl FnMut(&mut Ball)) {
+        for slot in 0..self.balls.len() {
+            let mut ball = self.balls.at(slot);
+            f(&mut ball);
+            self.balls.set(slot, ball);
+        }
+    }
+
+    pub fn ball_count(&self) -> usize {
+        self.balls.len()
+    }
+
+    /// Add a ball, unless the cap rejects it
+    pub fn add_ball(&mut self, ball: Ball) -> Option<BallId> {
+        let id = self.insert(ball)?;
+        self.balls.sort_by_size(&mut self.order);
+        Some(id)
+    }
+
+    /// Add balls, returning how many the cap allowed
+    pub fn add_balls(&mut self, balls: Vec<Ball>) -> usize {
+        let mut added = 0;
+        for ball in balls {
+            if self.insert(ball).is_none() {
+                break;
+            }
+            added += 1;
+        }
+        self.balls.sort_by_size(&mut self.order);
+        added
+    }
+
+    /// Add a ball without sorting, making room for it first if the cap allows
+    fn insert(&mut self, ball: Ball) -> Option<BallId> {
+        if let Some(cap) = self.cap {
+            while self.balls.len() >= cap.max {
+                let victim = match cap.policy {
+                    CapPolicy::Reject => None,
+                    CapPolicy::RemoveOldest => self.oldest(),
+                    CapPolicy::RemoveSmallest => self.order.last().copied(),
+                };
+                let Some(victim) = victim else {
+                    log::debug!("Rejected ball over the cap of {}", cap.max);
+                    return None;
+                };
+                self.remove_ball(victim);
+            }
+        }
+        let id = self.balls.insert(ball);
+        self.order.push(id);
+        self.spawned.push_back(id);
+        self.events.push(Event::BallSpawned(id));
+        log::debug!("Spawned ball {:?}", id.data());
+        Some(id)
+    }
+
+    /// Ball added longest ago, forgetting any removed before it
+    fn oldest(&mut self) -> Option<BallId> {
+        while let Some(&id) = self.spawned.front() {
+            if self.balls.slot(id).is_some() {
+                return Some(id);
+            }
+            self.spawned.pop_front();
+        }
+        None
+    }
+
+    pub fn remove_ball(&mut self, id: BallId) -> Option<Ball> {
+        if self.held == Some(id) {
+            self.held = None;
+        }
+        self.order.retain(|&other| other != id);
+        self.components.remove(id);
+        let ball = self.balls.remove(id)?;
+        self.events.push(Event::BallRemoved(id));
+        log::debug!("Removed ball {:?}", id.data());
+        // Forget removed balls once they make up most of the spawn queue
+        if self.spawned.len() > 2 * self.balls.len() + 64 {
+            let balls = &self.balls;
+            self.spawned.retain(|&id| balls.slot(id).is_some());
+        }
+        Some(ball)
+    }
+
+    /// Let go of the held ball, marking it as thrown by the player
+    pub fn release(&mut self) {
+        if let Some(id) = self.held.take() {
+            self.components.thrown.insert(id, ());
+        }
+    }
+
+    /// Remove a ball with a burst, for effects to show
+    pub fn pop_ball(&mut self, id: BallId) -> Option<Ball> {
+        let ball = self.remove_ball(id)?;
+        self.events.push(Event::BallPopped {
+            point: ball.point,
+            radius: ball.radius,
+            color: ball.color,
+        });
+        Some(ball)
+    }
+
+    /// Smallest ball under the point
+    pub fn ball_at(&self, point: Point2<f32>) -> Option<BallId> {
+        // Reverse to be sorted smallest to largest
+        self.order.iter().rev().copied().find(|&id| {
+            self.balls
+                .get(id)
+                .is_some_and(|ball| ball.collides_point(point))
+        })
+    }
+
+    pub fn clear_events(&mut self) {
+        self.events.clear();
+    }
+
+    /// Everything that happened during the last step, and since it
+    pub fn events(&self) -> &[Event] {
+        &self.events
+    }
+
+    /// Raise an event from outside physics, such as a game scoring points
+    pub fn emit(&mut self, event: Event) {
+        self.events.push(event);
+    }
+
+    /// Launch every loose ball upwards by an impulse
+    pub fn kick(&mut self, impulse: f32, rng: &mut impl Rng) {
+        physics::kick(&mut self.balls, self.held, impulse, rng);
+    }
+
+    pub fn kinetic_energy(&self) -> f32 {
+        self.balls
+            .iter()
+            .map(|(_, ball)| ball.kinetic_energy())
+            .sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{check, random_scene, EPSILON, TICKS};
+
+    #[test]
+    fn population_never_exceeds_the_cap() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let max = rng.gen_range(1..20);
+            for policy in [
+                CapPolicy::Reject,
+                CapPolicy::RemoveOldest,
+                CapPolicy::RemoveSmallest,
+            ] {
+                let mut sim = Simulation::new(Params {
+                    gravity: scene.gravity,
+                    width,
+                    height,
+                    parallel: false,
+                    heat: false,
+                    walls: Walls::default(),
+                });
+                sim.cap = Some(Cap { max, policy });
+                let added = sim.add_balls(scene.balls.clone());
+                for ball in &scene.balls {
+                    sim.add_ball(*ball);
+                }
+
+                assert!(
+                    sim.ball_count() <= max,
+                    "seed {seed}: {policy:?} let {} balls past a cap of {max}",
+                    sim.ball_count()
+                );
+                assert_eq!(
+                    added,
+                    match policy {
+                        CapPolicy::Reject => scene.balls.len().min(max),
+                        _ => scene.balls.len(),
+                    },
+                    "seed {seed}: {policy:?} added the wrong number of balls"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn balls_are_removed_when_their_lifetime_runs_out() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            let ids: Vec<_> = sim.balls().map(|(id, _)| id).collect();
+            let lifetime = rng.gen_range(1..TICKS);
+            for &id in &ids {
+                sim.set_lifetime(id, lifetime);
+            }
+            sim.run(lifetime - 1);
+            assert_eq!(
+                sim.ball_count(),
+                ids.len(),
+                "seed {seed}: balls were removed before their lifetime ran out"
+            );
+            sim.tick();
+            assert_eq!(
+                sim.ball_count(),
+                0,
+                "seed {seed}: balls outlived their lifetime of {lifetime} ticks"
+            );
+        });
+    }
+
+    #[test]
+    fn no_ball_outlives_its_hit_points() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            let ids: Vec<_> = sim.balls().map(|(id, _)| id).collect();
+            let hp = rng.gen_range(1.0..50.0);
+            for &id in &ids {
+                sim.set_hp(id, hp);
+            }
+            for _ in 0..TICKS {
+                sim.tick();
+                for (id, health) in sim.components.health.iter() {
+                    assert!(
+                        health.hp > 0.0 && sim.ball(id).is_some(),
+                        "seed {seed}: a ball was left with {} of {hp} hit points",
+                        health.hp
+                    );
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn bomb_chains_play_out_the_same_every_time() {
+        check(|seed, rng| {
+            let (width, height, mut scene) = random_scene(rng);
+            for ball in scene.balls.iter_mut().step_by(2) {
+                ball.set_kind(BallKind::Bomb);
+            }
+            let run = || {
+                let mut sim = Simulation::from_scene(scene.clone(), width, height);
+                sim.run(TICKS);
+                let blasts = sim
+                    .events()
+                    .iter()
+                    .filter(|event| matches!(event, Event::Exploded { .. }))
+                    .count();
+                let points: Vec<_> = sim.balls().map(|(_, ball)| ball.point).collect();
+                (blasts, points)
+            };
+            assert!(run() == run(), "seed {seed}: bombs went off differently");
+        });
+    }
+
+    #[test]
+    fn earthquakes_play_out_the_same_every_time() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let quake_seed = rng.gen();
+            let quake_ticks = rng.gen_range(1..TICKS);
+            let run = || {
+                let mut sim = Simulation::from_scene(scene.clone(), width, height);
+                sim.start_quake(quake_seed);
+                sim.run(quake_ticks);
+                sim.stop_quake();
+                sim.run(TICKS - quake_ticks);
+                sim.balls().map(|(_, ball)| ball.point).collect::<Vec<_>>()
+            };
+            assert!(
+                run() == run(),
+                "seed {seed}: the earthquake shook differently"
+            );
+        });
+    }
+
+    #[test]
+    fn rewinding_carries_on_as_before() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            sim.run(rng.gen_range(0..TICKS));
+            let snapshot = sim.snapshot();
+            let points = |sim: &mut Simulation| {
+                sim.run(TICKS);
+                sim.balls().map(|(_, ball)| ball.point).collect::<Vec<_>>()
+            };
+            let before = points(&mut sim);
+            sim.restore(&snapshot);
+            assert!(
+                points(&mut sim) == before,
+                "seed {seed}: the simulation went differently after rewinding"
+            );
+        });
+    }
+
+    #[test]
+    fn forking_into_a_used_simulation_matches_a_fresh_fork() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let (_, _, other) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            sim.run(rng.gen_range(0..TICKS));
+            let mut used = Simulation::from_scene(other, width, height);
+            used.run(TICKS);
+            used.fork_from(&sim);
+            let points = |mut sim: Simulation| {
+                sim.run(TICKS);
+                sim.balls().map(|(_, ball)| ball.point).collect::<Vec<_>>()
+            };
+            assert!(
+                points(used) == points(sim.fork()),
+                "seed {seed}: the reused fork went differently"
+            );
+        });
+    }
+
+    #[test]
+    fn predicted_paths_match_what_happens() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            let (id, ball) = sim.balls().next().expect("scenes have at least one ball");
+            let path = sim.predict(id, ball.velocity, TICKS, true);
+            for point in path {
+                sim.tick();
+                let actual = sim.ball(id).map(|ball| ball.point);
+                assert!(
+                    actual == Some(point),
+                    "seed {seed}: predicted {point:?} but the ball went to {actual:?}"
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn resting_scenes_wake_when_a_ball_is_pushed() {
+        check(|seed, rng| {
+            let (width, height, mut scene) = random_scene(rng);
+            scene.balls.truncate(1);
+            let ball = &mut scene.balls[0];
+            ball.point.y = height - ball.radius;
+            ball.velocity.x = 0.0;
+            ball.velocity.y = 0.0;
+            let mut sim = Simulation::from_scene(scene, width, height);
+            sim.run(4 * TICKS);
+            assert!(
+                sim.is_resting(),
+                "seed {seed}: a ball on the floor never came to rest"
+            );
+
+            let (id, before) = sim.balls().next().unwrap();
+            if let Some(mut ball) = sim.ball_mut(id) {
+                ball.velocity.x = 10.0;
+            }
+            sim.tick();
+            assert!(
+                !sim.is_resting(),
+                "seed {seed}: pushing a ball didn't wake the scene"
+            );
+            let after = sim.ball(id).unwrap();
+            assert!(
+                after.point.x != before.point.x,
+                "seed {seed}: a pushed ball stayed in place"
+            );
+        });
+    }
+
+    #[test]
+    fn no_ball_moves_faster_than_the_speed_limit() {
+        check(|seed, rng| {
+            let (width, height, scene) = random_scene(rng);
+            let max = rng.gen_range(1.0..20.0);
+            let mut sim = Simulation::from_scene(scene, width, height);
+            sim.max_speed = Some(max);
+            for tick in 0..TICKS {
+                sim.tick();
+                for (_, ball) in sim.balls() {
+                    let speed = ball.velocity.x.hypot(ball.velocity.y);
+                    assert!(
+                        speed <= max + EPSILON,
+                        "seed {seed}, tick {tick}: ball moving at {speed} over the limit of {max}"
+                    );
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,170 @@
+use ggez::graphics::{Canvas, Color};
+use ggez::mint::Vector2;
+
+use crate::ball::{Ball, BallKind};
+use crate::panel::Panel;
+use crate::text::TextStyle;
+
+#[derive(Clone, Copy, PartialEq)]
+enum Field {
+    Radius,
+    X,
+    Y,
+    Red,
+    Green,
+    Blue,
+    VelocityX,
+    VelocityY,
+    Pinned,
+    Kind,
+    Copies,
+    Spacing,
+    Spawn,
+}
+
+const FIELDS: [Field; 13] = [
+    Field::Radius,
+    Field::X,
+    Field::Y,
+    Field::Red,
+    Field::Green,
+    Field::Blue,
+    Field::VelocityX,
+    Field::VelocityY,
+    Field::Pinned,
+    Field::Kind,
+    Field::Copies,
+    Field::Spacing,
+    Field::Spawn,
+];
+
+/// Dialog for spawning balls with exact parameters, optionally as a grid of copies
+pub struct SpawnDialog {
+    panel: Panel,
+    radius: f32,
+    x: f32,
+    y: f32,
+    color: Color,
+    velocity: Vector2<f32>,
+    pinned: bool,
+    kind: BallKind,
+    copies: usize,
+    spacing: f32,
+}
+
+impl SpawnDialog {
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            panel: Panel::top_right("Spawn", FIELDS.len(), width),
+            radius: 20.0,
+            x: (width / 2.0).round(),
+            y: (height / 4.0).round(),
+            color: color!(WHITE),
+            velocity: Vector2 { x: 0.0, y: 0.0 },
+            pinned: false,
+            kind: BallKind::Normal,
+            copies: 1,
+            spacing: 0.0,
+        }
+    }
+
+    fn label(&self, field: Field) -> String {
+        match field {
+            Field::Radius => format!("Radius: {:.0}", self.radius),
+            Field::X => format!("X: {:.0}", self.x),
+            Field::Y => format!("Y: {:.0}", self.y),
+            Field::Red => format!("Red: {:.0}", self.color.r * 255.0),
+            Field::Green => format!("Green: {:.0}", self.color.g * 255.0),
+            Field::Blue => format!("Blue: {:.0}", self.color.b * 255.0),
+            Field::VelocityX => format!("Velocity x: {:.1}", self.velocity.x),
+            Field::VelocityY => format!("Velocity y: {:.1}", self.velocity.y),
+            Field::Pinned => format!("Pinned: {}", if self.pinned { "yes" } else { "no" }),
+            Field::Kind => format!("Kind: {}", self.kind.name()),
+            Field::Copies => format!("Copies: {}", self.copies),
+            Field::Spacing => format!("Grid spacing: {:.0}", self.spacing),
+            Field::Spawn => "[ Spawn ]".to_string(),
+        }
+    }
+
+    fn adjust_field(&mut self, field: Field, direction: f32) {
+        match field {
+            Field::Radius => self.radius = (self.radius + direction).max(1.0),
+            Field::X => self.x += direction * 10.0,
+            Field::Y => self.y += direction * 10.0,
+            Field::Red => self.color.r = (self.color.r + direction * 0.05).clamp(0.0, 1.0),
+            Field::Green => self.color.g = (self.color.g + direction * 0.05).clamp(0.0, 1.0),
+            Field::Blue => self.color.b = (self.color.b + direction * 0.05).clamp(0.0, 1.0),
+            Field::VelocityX => self.velocity.x += direction * 0.5,
+            Field::VelocityY => self.velocity.y += direction * 0.5,
+            Field::Pinned => self.pinned = !self.pinned,
+            Field::Kind => {
+                self.kind = if direction > 0.0 {
+                    self.kind.next()
+                } else {
+                    self.kind.previous()
+                }
+            }
+            Field::Copies => {
+                self.copies = (self.copies as isize + direction as isize).max(1) as usize
+            }
+            Field::Spacing => self.spacing = (self.spacing + direction * 2.0).max(0.0),
+            Field::Spawn => (),
+        }
+    }
+
+    pub fn up(&mut self) {
+        self.panel.up();
+    }
+
+    pub fn down(&mut self) {
+        self.panel.down();
+    }
+
+    pub fn adjust(&mut self, direction: f32) {
+        self.adjust_field(FIELDS[self.panel.selected()], direction);
+    }
+
+    pub fn contains(&self, x: f32, y: f32, style: TextStyle) -> bool {
+        self.panel.contains(x, y, style)
+    }
+
+    /// Handle a click inside the dialog, returning balls if spawn was clicked
+    pub fn click(&mut self, x: f32, y: f32, style: TextStyle) -> Option<Vec<Ball>> {
+        let (index, direction) = self.panel.click(x, y, style)?;
+        match FIELDS[index] {
+            Field::Spawn => Some(self.balls()),
+            field => {
+                if direction != 0.0 {
+                    self.adjust_field(field, direction);
+                }
+                None
+            }
+        }
+    }
+
+    /// Balls described by the dialog, laid out in a square grid
+    pub fn balls(&self) -> Vec<Ball> {
+        let columns = (self.copies as f32).sqrt().ceil() as usize;
+        let step = self.radius * 2.0 + self.spacing;
+
+        (0..self.copies)
+            .map(|i| {
+                let x = self.x + (i % columns) as f32 * step;
+                let y = self.y + (i / columns) as f32 * step;
+                let mut ball = Ball::new(x, y, self.radius, self.color);
+                ball.set_kind(self.kind);
+                ball.velocity = self.velocity;
+                ball.pinned = self.pinned;
+                ball
+            })
+            .collect()
+    }
+
+    pub fn draw(&self, canvas: &mut Canvas, style: TextStyle) {
+        let rows: Vec<_> = FIELDS
+            .iter()
+            .map(|&field| (self.label(field), field != Field::Spawn))
+            .collect();
+        self.panel.draw(canvas, &rows, self.color, style);
+    }
+}
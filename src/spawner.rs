@@ -0,0 +1,137 @@
+use ggez::graphics::Color;
+use ggez::mint::{Point2, Vector2};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::ball::{Ball, BallKind};
+use crate::components::Growth;
+
+/// Point in a scene that emits balls on a schedule
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Spawner {
+    pub point: Point2<f32>,
+    /// Ticks between emissions
+    pub interval: u32,
+    /// Balls emitted each time
+    #[serde(default = "default_count")]
+    pub count: u32,
+    /// Most balls to emit in total, or no limit if unset
+    #[serde(default)]
+    pub limit: Option<u32>,
+    /// Smallest and largest radius of emitted balls
+    pub radius: (f32, f32),
+    /// Colors emitted balls are picked from, or any color if empty
+    #[serde(default)]
+    pub palette: Vec<Color>,
+    /// Starting velocity of emitted balls
+    #[serde(default = "zero")]
+    pub velocity: Vector2<f32>,
+    /// Most each velocity component can randomly differ by, either way
+    #[serde(default)]
+    pub spread: f32,
+    /// Kind of emitted balls
+    #[serde(default)]
+    pub kind: BallKind,
+    /// How emitted balls change size, if they do
+    #[serde(default)]
+    pub growth: Option<Growth>,
+    /// Ticks each emitted ball lasts before fading away, instead of the simulation default
+    #[serde(default)]
+    pub lifetime: Option<u32>,
+    /// Hit points of emitted balls, which break when they run out, or unbreakable if unset
+    #[serde(default)]
+    pub hp: Option<f32>,
+    /// Seed of the randomness, so a scene emits the same balls every time
+    #[serde(default)]
+    pub seed: u64,
+    /// Balls emitted so far
+    #[serde(default)]
+    pub emitted: u32,
+    /// Ticks since the last emission
+    #[serde(default)]
+    pub timer: u32,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+fn zero() -> Vector2<f32> {
+    Vector2 { x: 0.0, y: 0.0 }
+}
+
+impl Spawner {
+    /// Emit one ball at a time, with no randomness
+    pub fn new(point: Point2<f32>, interval: u32, radius: f32) -> Self {
+        Self {
+            point,
+            interval,
+            count: default_count(),
+            limit: None,
+            radius: (radius, radius),
+            palette: Vec::new(),
+            velocity: zero(),
+            spread: 0.0,
+            kind: BallKind::Normal,
+            growth: None,
+            lifetime: None,
+            hp: None,
+            seed: 0,
+            emitted: 0,
+            timer: 0,
+        }
+    }
+
+    /// Whether the limit has been reached
+    pub fn is_done(&self) -> bool {
+        self.limit.is_some_and(|limit| self.emitted >= limit)
+    }
+
+    /// Advance by one tick, returning any balls emitted
+    pub fn tick(&mut self) -> impl Iterator<Item = Ball> + '_ {
+        let count = self.due();
+        // Seeded from the emitted count, so a saved scene picks up where it left off
+        let mut rng = StdRng::seed_from_u64(self.seed.wrapping_add(self.emitted as u64));
+        self.emitted += count;
+        let spawner = &*self;
+        (0..count).map(move |_| spawner.ball(&mut rng))
+    }
+
+    /// Advance the timer, returning how many balls to emit this tick
+    fn due(&mut self) -> u32 {
+        if self.is_done() {
+            return 0;
+        }
+        self.timer += 1;
+        if self.timer < self.interval.max(1) {
+            return 0;
+        }
+        self.timer = 0;
+        match self.limit {
+            Some(limit) => self.count.min(limit - self.emitted),
+            None => self.count,
+        }
+    }
+
+    fn ball(&self, rng: &mut impl Rng) -> Ball {
+        let (min, max) = self.radius;
+        let radius = if max > min {
+            rng.gen_range(min..max)
+        } else {
+            min
+        };
+        let color = match self.palette.as_slice() {
+            [] => color!(?rng),
+            palette => palette[rng.gen_range(0..palette.len())],
+        };
+        let mut ball = Ball::new(self.point.x, self.point.y, radius, color);
+        ball.set_kind(self.kind);
+        ball.velocity = self.velocity;
+        if self.spread > 0.0 {
+            ball.velocity.x += rng.gen_range(-self.spread..self.spread);
+            ball.velocity.y += rng.gen_range(-self.spread..self.spread);
+        }
+        ball
+    }
+}
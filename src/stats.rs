@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use ggez::graphics::{Canvas, DrawParam, Quad, Rect};
+
+use crate::text::TextStyle;
+
+const WIDTH: f32 = 260.0;
+const LINE_HEIGHT: f32 = 20.0;
+const TEXT_SIZE: f32 = 16.0;
+const PADDING: f32 = 6.0;
+
+/// Counters collected during a single physics step
+#[derive(Clone, Copy, Default)]
+pub struct StepStats {
+    pub pairs_tested: usize,
+    pub collisions: usize,
+    pub max_penetration: f32,
+    pub sleeping: usize,
+    pub times: PhaseTimes,
+}
+
+/// Time spent in each phase of physics steps
+#[derive(Clone, Copy, Default)]
+pub struct PhaseTimes {
+    pub forces: Duration,
+    pub integrate: Duration,
+    pub collide: Duration,
+    pub walls: Duration,
+    /// Sleep, settling and temperature
+    pub rest: Duration,
+}
+
+impl PhaseTimes {
+    /// Each phase with its name, in the order they run
+    pub fn phases(self) -> [(&'static str, Duration); 5] {
+        [
+            ("Forces", self.forces),
+            ("Integrate", self.integrate),
+            ("Collide", self.collide),
+            ("Walls", self.walls),
+            ("Rest", self.rest),
+        ]
+    }
+
+    fn merge(self, next: PhaseTimes) -> PhaseTimes {
+        PhaseTimes {
+            forces: self.forces + next.forces,
+            integrate: self.integrate + next.integrate,
+            collide: self.collide + next.collide,
+            walls: self.walls + next.walls,
+            rest: self.rest + next.rest,
+        }
+    }
+}
+
+impl StepStats {
+    /// Combine with the stats of a following step
+    pub fn merge(self, next: StepStats) -> StepStats {
+        StepStats {
+            pairs_tested: self.pairs_tested + next.pairs_tested,
+            collisions: self.collisions + next.collisions,
+            max_penetration: self.max_penetration.max(next.max_penetration),
+            sleeping: next.sleeping,
+            times: self.times.merge(next.times),
+        }
+    }
+}
+
+/// Physics statistics, with per-second rates
+#[derive(Default)]
+pub struct Stats {
+    last: StepStats,
+    /// Collisions counted since the start of the current second
+    collisions_this_second: usize,
+    elapsed: f32,
+    collisions_per_second: usize,
+}
+
+impl Stats {
+    /// Record a frame, with the stats of any physics run during it
+    pub fn record(&mut self, step: Option<StepStats>, dt: f32) {
+        if let Some(step) = step {
+            self.last = step;
+            self.collisions_this_second += step.collisions;
+        }
+        self.elapsed += dt;
+        if self.elapsed >= 1.0 {
+            self.collisions_per_second = self.collisions_this_second;
+            self.collisions_this_second = 0;
+            self.elapsed -= 1.0;
+        }
+    }
+
+    /// Draw panel with its top-left corner at the given point
+    pub fn draw(&self, canvas: &mut Canvas, x: f32, y: f32, style: TextStyle) {
+        let lines = [
+            format!("Pairs tested: {}", self.last.pairs_tested),
+            format!("Collisions: {}", self.last.collisions),
+            format!("Collisions/sec: {}", self.collisions_per_second),
+            format!("Max penetration: {:.2}", self.last.max_penetration),
+            format!("Sleeping: {}", self.last.sleeping),
+        ];
+
+        canvas.draw(
+            &Quad,
+            DrawParam::default()
+                .dest_rect(Rect::new(
+                    x,
+                    y,
+                    style.px(WIDTH),
+                    style.px(LINE_HEIGHT * lines.len() as f32 + PADDING * 2.0),
+                ))
+                .color(color!(30, 30, 30, 200)),
+        );
+
+        for (i, line) in lines.into_iter().enumerate() {
+            let text = style.text(line, TEXT_SIZE);
+            canvas.draw(
+                &text,
+                DrawParam::from([
+                    x + style.px(PADDING),
+                    y + style.px(PADDING + i as f32 * LINE_HEIGHT),
+                ]),
+            );
+        }
+    }
+}
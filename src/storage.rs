@@ -0,0 +1,221 @@
+use std::ops::{Deref, DerefMut};
+
+use ggez::graphics::Color;
+use ggez::mint::{Point2, Vector2};
+use slotmap::SlotMap;
+
+use crate::ball::{self, Ball, BallId, BallKind, Pattern};
+
+/// Every ball, stored as one column per field so loops over a single field vectorize.
+///
+/// Columns are indexed by slot. Removing a ball moves the last ball into its slot,
+/// so slots only stay valid until then, unlike handles
+#[derive(Clone, Default)]
+pub struct Balls {
+    /// Slot of each ball
+    slots: SlotMap<BallId, usize>,
+    /// Handle of the ball in each slot
+    ids: Vec<BallId>,
+    pub xs: Vec<f32>,
+    pub ys: Vec<f32>,
+    pub vxs: Vec<f32>,
+    pub vys: Vec<f32>,
+    pub radii: Vec<f32>,
+    pub colors: Vec<Color>,
+    pub restitutions: Vec<f32>,
+    pub pinned: Vec<bool>,
+    pub kinds: Vec<BallKind>,
+    pub layers: Vec<u32>,
+    pub masks: Vec<u32>,
+    pub patterns: Vec<Pattern>,
+}
+
+impl Balls {
+    pub fn len(&self) -> usize {
+        self.ids.len()
+    }
+
+    pub fn insert(&mut self, ball: Ball) -> BallId {
+        let id = self.slots.insert(self.ids.len());
+        self.ids.push(id);
+        self.xs.push(ball.point.x);
+        self.ys.push(ball.point.y);
+        self.vxs.push(ball.velocity.x);
+        self.vys.push(ball.velocity.y);
+        self.radii.push(ball.radius);
+        self.colors.push(ball.color);
+        self.restitutions.push(ball.restitution);
+        self.pinned.push(ball.pinned);
+        self.kinds.push(ball.kind);
+        self.layers.push(ball.layer);
+        self.masks.push(ball.mask);
+        self.patterns.push(ball.pattern);
+        id
+    }
+
+    /// Become a copy of other balls, reusing this one's buffers
+    pub fn copy_from(&mut self, source: &Self) {
+        self.slots.clone_from(&source.slots);
+        self.ids.clone_from(&source.ids);
+        self.xs.clone_from(&source.xs);
+        self.ys.clone_from(&source.ys);
+        self.vxs.clone_from(&source.vxs);
+        self.vys.clone_from(&source.vys);
+        self.radii.clone_from(&source.radii);
+        self.colors.clone_from(&source.colors);
+        self.restitutions.clone_from(&source.restitutions);
+        self.pinned.clone_from(&source.pinned);
+        self.kinds.clone_from(&source.kinds);
+        self.layers.clone_from(&source.layers);
+        self.masks.clone_from(&source.masks);
+        self.patterns.clone_from(&source.patterns);
+    }
+
+    pub fn remove(&mut self, id: BallId) -> Option<Ball> {
+        let slot = self.slots.remove(id)?;
+        let ball = self.at(slot);
+        self.ids.swap_remove(slot);
+        self.xs.swap_remove(slot);
+        self.ys.swap_remove(slot);
+        self.vxs.swap_remove(slot);
+        self.vys.swap_remove(slot);
+        self.radii.swap_remove(slot);
+        self.colors.swap_remove(slot);
+        self.restitutions.swap_remove(slot);
+        self.pinned.swap_remove(slot);
+        self.kinds.swap_remove(slot);
+        self.layers.swap_remove(slot);
+        self.masks.swap_remove(slot);
+        self.patterns.swap_remove(slot);
+        if let Some(&moved) = self.ids.get(slot) {
+            self.slots[moved] = slot;
+        }
+        Some(ball)
+    }
+
+    pub fn slot(&self, id: BallId) -> Option<usize> {
+        self.slots.get(id).copied()
+    }
+
+    /// Handle of the ball in a slot
+    pub fn id(&self, slot: usize) -> BallId {
+        self.ids[slot]
+    }
+
+    pub fn get(&self, id: BallId) -> Option<Ball> {
+        self.slot(id).map(|slot| self.at(slot))
+    }
+
+    /// Ball that is written back when the view is dropped
+    pub fn get_mut(&mut self, id: BallId) -> Option<BallMut<'_>> {
+        let slot = self.slot(id)?;
+        let ball = self.at(slot);
+        Some(BallMut {
+            balls: self,
+            slot,
+            ball,
+        })
+    }
+
+    /// Copy of the ball in a slot
+    pub fn at(&self, slot: usize) -> Ball {
+        Ball {
+            point: Point2 {
+                x: self.xs[slot],
+                y: self.ys[slot],
+            },
+            radius: self.radii[slot],
+            velocity: Vector2 {
+                x: self.vxs[slot],
+                y: self.vys[slot],
+            },
+            color: self.colors[slot],
+            restitution: self.restitutions[slot],
+            pinned: self.pinned[slot],
+            kind: self.kinds[slot],
+            layer: self.layers[slot],
+            mask: self.masks[slot],
+            pattern: self.patterns[slot],
+        }
+    }
+
+    /// Whether a ball's layers let it collide with the ball in a slot, read
+    /// straight from the columns like `penetration`
+    pub fn layers_meet(&self, ball: &Ball, slot: usize) -> bool {
+        ball::layers_meet(ball.layer, ball.mask, self.layers[slot], self.masks[slot])
+    }
+
+    /// Overlap of a ball with the ball in a slot, read straight from the columns
+    /// so that pairs which don't touch are never copied out
+    pub fn penetration(&self, ball: &Ball, slot: usize) -> f32 {
+        let dx = ball.point.x - self.xs[slot];
+        let dy = ball.point.y - self.ys[slot];
+        let dist = (dx.powi(2) + dy.powi(2)).sqrt();
+        ball.radius + self.radii[slot] - dist
+    }
+
+    /// Whether every ball is where it is in another copy, moving the same way
+    pub fn same_motion(&self, other: &Balls) -> bool {
+        self.xs == other.xs
+            && self.ys == other.ys
+            && self.vxs == other.vxs
+            && self.vys == other.vys
+            && self.radii == other.radii
+            && self.pinned == other.pinned
+            && self.kinds == other.kinds
+    }
+
+    /// Overwrite the ball in a slot
+    pub fn set(&mut self, slot: usize, ball: Ball) {
+        self.xs[slot] = ball.point.x;
+        self.ys[slot] = ball.point.y;
+        self.vxs[slot] = ball.velocity.x;
+        self.vys[slot] = ball.velocity.y;
+        self.radii[slot] = ball.radius;
+        self.colors[slot] = ball.color;
+        self.restitutions[slot] = ball.restitution;
+        self.pinned[slot] = ball.pinned;
+        self.kinds[slot] = ball.kind;
+        self.layers[slot] = ball.layer;
+        self.masks[slot] = ball.mask;
+        self.patterns[slot] = ball.pattern;
+    }
+
+    /// Every ball in slot order, with its handle
+    pub fn iter(&self) -> impl Iterator<Item = (BallId, Ball)> + '_ {
+        (0..self.len()).map(|slot| (self.ids[slot], self.at(slot)))
+    }
+
+    /// Sort handles by the size of their balls, largest to smallest
+    pub fn sort_by_size(&self, order: &mut [BallId]) {
+        let radius = |id: &BallId| self.slot(*id).map_or(0.0, |slot| self.radii[slot]);
+        order.sort_by(|a, b| radius(b).total_cmp(&radius(a)));
+    }
+}
+
+/// Copy of a ball which is written back to storage when dropped
+pub struct BallMut<'a> {
+    balls: &'a mut Balls,
+    slot: usize,
+    ball: Ball,
+}
+
+impl Deref for BallMut<'_> {
+    type Target = Ball;
+
+    fn deref(&self) -> &Ball {
+        &self.ball
+    }
+}
+
+impl DerefMut for BallMut<'_> {
+    fn deref_mut(&mut self) -> &mut Ball {
+        &mut self.ball
+    }
+}
+
+impl Drop for BallMut<'_> {
+    fn drop(&mut self) {
+        self.balls.set(self.slot, self.ball);
+    }
+}
@@ -0,0 +1,68 @@
+use ggez::graphics::{Canvas, DrawParam};
+
+use crate::text::TextStyle;
+
+/// Balls added after each step that keeps up
+pub const STEP_BALLS: usize = 100;
+/// Seconds the frame rate is measured over at each step
+const STEP_TIME: f32 = 1.0;
+/// Slowest frame rate that counts as keeping up, a little under 60 to allow for timer jitter
+pub const MIN_FPS: f32 = 58.0;
+
+/// How the frame rate held up over a step
+pub enum Verdict {
+    /// Still measuring the current step
+    Measuring,
+    KeptUp,
+    TooSlow,
+}
+
+/// Adds balls step by step until the frame rate drops, to find the most the
+/// current configuration can sustain
+#[derive(Default)]
+pub struct StressTest {
+    /// Seconds into the current step
+    time: f32,
+    /// Frames in the current step
+    frames: u32,
+    /// Frame rate over the last whole step
+    fps: Option<f32>,
+    /// Most balls the frame rate has kept up with
+    pub sustained: usize,
+}
+
+impl StressTest {
+    /// Count a frame with a number of balls, judging the step once it is over
+    pub fn update(&mut self, dt: f32, balls: usize) -> Verdict {
+        self.time += dt;
+        self.frames += 1;
+        if self.time < STEP_TIME {
+            return Verdict::Measuring;
+        }
+        let fps = self.frames as f32 / self.time;
+        self.fps = Some(fps);
+        self.time = 0.0;
+        self.frames = 0;
+        if fps < MIN_FPS {
+            return Verdict::TooSlow;
+        }
+        self.sustained = balls;
+        Verdict::KeptUp
+    }
+
+    /// Progress so far, shown while the test runs
+    pub fn draw(&self, canvas: &mut Canvas, balls: usize, style: TextStyle) {
+        let fps = self
+            .fps
+            .map_or("...".to_string(), |fps| format!("{:.0}", fps));
+        let text = style.text(
+            format!(
+                "STRESS TEST  {} balls  {} FPS  sustained {}",
+                balls, fps, self.sustained
+            ),
+            20.0,
+        );
+        let point = [style.px(10.0), style.px(40.0)];
+        canvas.draw(&text, DrawParam::from(point).color(color!(0xffb347)));
+    }
+}
@@ -0,0 +1,35 @@
+use std::fmt::Write;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::capture::{self, SCREENSHOTS_DIR};
+use crate::scene::Scene;
+
+/// Draw a scene as an SVG image, the same way it appears in the window
+pub fn render(scene: &Scene, width: f32, height: f32) -> String {
+    let mut svg = String::new();
+    // Writing to a string can't fail
+    let _ = writeln!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{0}" height="{1}" viewBox="0 0 {0} {1}">"#,
+        width, height
+    );
+    let _ = writeln!(svg, r#"  <rect width="100%" height="100%" fill="black"/>"#);
+    for ball in &scene.balls {
+        let (r, g, b) = ball.color.to_rgb();
+        let _ = writeln!(
+            svg,
+            r##"  <circle cx="{:.2}" cy="{:.2}" r="{:.2}" fill="#{:02x}{:02x}{:02x}"/>"##,
+            ball.point.x, ball.point.y, ball.radius, r, g, b
+        );
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// Write a scene to a new SVG file in the screenshots directory, returning its path
+pub fn export(scene: &Scene, width: f32, height: f32) -> Result<PathBuf, String> {
+    let path = capture::timestamped(Path::new(SCREENSHOTS_DIR), "scene", "svg")?;
+    fs::write(&path, render(scene, width, height)).map_err(|err| err.to_string())?;
+    Ok(path)
+}
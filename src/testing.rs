@@ -0,0 +1,46 @@
+//! Helpers for randomised checks. Each property runs against many scenes
+//! generated from fixed seeds, so any failure can be repeated from the seed in
+//! its message
+
+use ggez::graphics::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::ball::Ball;
+use crate::scene::Scene;
+
+const CASES: u64 = 64;
+pub const TICKS: u32 = 120;
+/// Allowed error, for rounding when balls are clamped to a wall
+pub const EPSILON: f32 = 1e-3;
+
+/// Run a property against scenes from many seeds
+pub fn check(property: impl Fn(u64, &mut StdRng)) {
+    for seed in 0..CASES {
+        property(seed, &mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// Box size and a scene of balls that fit in it, with any velocities
+pub fn random_scene(rng: &mut StdRng) -> (f32, f32, Scene) {
+    let width: f32 = rng.gen_range(200.0..1600.0);
+    let height = rng.gen_range(200.0..1200.0);
+    let count = rng.gen_range(1..60);
+    let balls = (0..count)
+        .map(|_| {
+            let radius = rng.gen_range(1.0..width.min(height) / 4.0);
+            let mut ball = Ball::new(
+                rng.gen_range(radius..width - radius),
+                rng.gen_range(radius..height - radius),
+                radius,
+                Color::WHITE,
+            );
+            ball.velocity.x = rng.gen_range(-30.0..30.0);
+            ball.velocity.y = rng.gen_range(-30.0..30.0);
+            ball.restitution = rng.gen_range(0.0..1.0);
+            ball
+        })
+        .collect();
+    let scene = Scene::new(balls, rng.gen_range(0.0..2.0));
+    (width, height, scene)
+}
@@ -0,0 +1,53 @@
+use ggez::graphics::{Text, TextFragment};
+
+/// Smallest UI scale, below which text gets hard to read
+pub const MIN_SCALE: f32 = 0.5;
+/// Largest UI scale, beyond which menus no longer fit on most screens
+pub const MAX_SCALE: f32 = 3.0;
+/// Change in UI scale for each press of Ctrl+= or Ctrl+-
+const SCALE_STEP: f32 = 0.25;
+
+/// How text and the layout around it is sized across the interface, scaled
+/// together so it stays readable on high-resolution screens and projectors
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle {
+    scale: f32,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        Self { scale: 1.0 }
+    }
+}
+
+impl TextStyle {
+    pub fn new(scale: f32) -> Self {
+        Self {
+            scale: scale.clamp(MIN_SCALE, MAX_SCALE),
+        }
+    }
+
+    pub fn scale(self) -> f32 {
+        self.scale
+    }
+
+    /// Text at a size given for a scale of 1
+    pub fn text(self, contents: impl Into<TextFragment>, size: f32) -> Text {
+        let mut text = Text::new(contents);
+        text.set_scale(size * self.scale);
+        text
+    }
+
+    /// Length given for a scale of 1, such as a line height or margin
+    pub fn px(self, length: f32) -> f32 {
+        length * self.scale
+    }
+
+    pub fn larger(self) -> Self {
+        Self::new(self.scale + SCALE_STEP)
+    }
+
+    pub fn smaller(self) -> Self {
+        Self::new(self.scale - SCALE_STEP)
+    }
+}
@@ -0,0 +1,88 @@
+use ggez::graphics::{Canvas, DrawParam, Drawable, Quad, Rect};
+use ggez::Context;
+
+use crate::text::TextStyle;
+
+/// Seconds a toast stays on screen
+const LIFETIME: f32 = 2.5;
+/// Seconds spent fading out at the end of its lifetime
+const FADE: f32 = 0.5;
+const TEXT_SIZE: f32 = 18.0;
+const PADDING: f32 = 8.0;
+const MARGIN: f32 = 10.0;
+const MAX_TOASTS: usize = 5;
+
+struct Toast {
+    text: String,
+    age: f32,
+}
+
+/// Short-lived notifications shown in the bottom-right corner
+#[derive(Default)]
+pub struct Toasts {
+    toasts: Vec<Toast>,
+}
+
+impl Toasts {
+    pub fn push(&mut self, text: impl Into<String>) {
+        self.toasts.push(Toast {
+            text: text.into(),
+            age: 0.0,
+        });
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.toasts.is_empty()
+    }
+
+    pub fn update(&mut self, dt: f32) {
+        for toast in &mut self.toasts {
+            toast.age += dt;
+        }
+        self.toasts.retain(|toast| toast.age < LIFETIME);
+    }
+
+    pub fn draw(
+        &self,
+        ctx: &Context,
+        canvas: &mut Canvas,
+        width: f32,
+        height: f32,
+        style: TextStyle,
+    ) {
+        let (padding, margin) = (style.px(PADDING), style.px(MARGIN));
+        let mut y = height - margin;
+        // Newest at the bottom
+        for toast in self.toasts.iter().rev() {
+            let alpha = ((LIFETIME - toast.age) / FADE).min(1.0);
+
+            let text = style.text(&toast.text, TEXT_SIZE);
+            let Some(size) = text.dimensions(ctx) else {
+                continue;
+            };
+
+            let rect = Rect::new(
+                width - margin - size.w - padding * 2.0,
+                y - size.h - padding * 2.0,
+                size.w + padding * 2.0,
+                size.h + padding * 2.0,
+            );
+            canvas.draw(
+                &Quad,
+                DrawParam::default()
+                    .dest_rect(rect)
+                    .color(color!(40, 40, 40, 220.0 * alpha)),
+            );
+            let color = color!(255, 255, 255, 255.0 * alpha);
+            canvas.draw(
+                &text,
+                DrawParam::from([rect.x + padding, rect.y + padding]).color(color),
+            );
+
+            y = rect.y - margin / 2.0;
+        }
+    }
+}
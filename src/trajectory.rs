@@ -0,0 +1,69 @@
+use std::collections::HashSet;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use slotmap::Key;
+
+use crate::ball::BallId;
+use crate::simulation::{Simulation, TICK};
+
+/// Directory that trajectory logs are written to, when not given a path
+pub const LOGS_DIR: &str = "logs";
+
+/// Positions and velocities of balls, written as CSV rows every few ticks
+pub struct TrajectoryLog {
+    writer: BufWriter<File>,
+    /// Ticks between samples
+    every: u32,
+    /// Balls to log, or every ball if empty
+    pub balls: HashSet<BallId>,
+}
+
+impl TrajectoryLog {
+    pub fn create(path: &Path, every: u32) -> Result<Self, String> {
+        let file = File::create(path).map_err(|err| err.to_string())?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "tick,time,ball,x,y,vx,vy").map_err(|err| err.to_string())?;
+        Ok(Self {
+            writer,
+            every: every.max(1),
+            balls: HashSet::new(),
+        })
+    }
+
+    /// Ticks until the next sample is due
+    pub fn ticks_until_sample(&self, tick: u64) -> u64 {
+        let every = self.every as u64;
+        every - tick % every
+    }
+
+    /// Write a row for each logged ball, if a sample is due on this tick
+    pub fn record(&mut self, tick: u64, sim: &Simulation) -> Result<(), String> {
+        if !tick.is_multiple_of(self.every as u64) {
+            return Ok(());
+        }
+        for (id, ball) in sim.balls() {
+            if !self.balls.is_empty() && !self.balls.contains(&id) {
+                continue;
+            }
+            writeln!(
+                self.writer,
+                "{},{:.4},{:?},{},{},{},{}",
+                tick,
+                tick as f32 * TICK,
+                id.data(),
+                ball.point.x,
+                ball.point.y,
+                ball.velocity.x,
+                ball.velocity.y
+            )
+            .map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> Result<(), String> {
+        self.writer.flush().map_err(|err| err.to_string())
+    }
+}
@@ -0,0 +1,85 @@
+use crate::simulation::TICK;
+
+/// Pixels per meter, unless configured otherwise
+pub const DEFAULT_SCALE: f32 = 100.0;
+
+/// Scale between the simulation's pixels and ticks and real-world units
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Units {
+    pub pixels_per_meter: f32,
+}
+
+impl Default for Units {
+    fn default() -> Self {
+        Self {
+            pixels_per_meter: DEFAULT_SCALE,
+        }
+    }
+}
+
+impl Units {
+    pub fn new(pixels_per_meter: f32) -> Self {
+        Self { pixels_per_meter }
+    }
+
+    pub fn meters(self, pixels: f32) -> f32 {
+        pixels / self.pixels_per_meter
+    }
+
+    pub fn centimeters(self, pixels: f32) -> f32 {
+        self.meters(pixels) * 100.0
+    }
+
+    pub fn pixels(self, meters: f32) -> f32 {
+        meters * self.pixels_per_meter
+    }
+
+    /// Gravity in pixels per tick squared, from meters per second squared
+    pub fn gravity(self, meters_per_second_squared: f32) -> f32 {
+        self.pixels(meters_per_second_squared) * TICK * TICK
+    }
+
+    /// Gravity in meters per second squared, from pixels per tick squared
+    pub fn gravity_in_meters(self, gravity: f32) -> f32 {
+        self.meters(gravity) / (TICK * TICK)
+    }
+}
+
+/// Real-world gravity presets
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Planet {
+    Earth,
+    Moon,
+    Mars,
+    Jupiter,
+}
+
+impl Planet {
+    pub const ALL: [Planet; 4] = [Planet::Earth, Planet::Moon, Planet::Mars, Planet::Jupiter];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Planet::Earth => "Earth",
+            Planet::Moon => "Moon",
+            Planet::Mars => "Mars",
+            Planet::Jupiter => "Jupiter",
+        }
+    }
+
+    /// Surface gravity, in meters per second squared
+    pub fn gravity(self) -> f32 {
+        match self {
+            Planet::Earth => 9.81,
+            Planet::Moon => 1.62,
+            Planet::Mars => 3.71,
+            Planet::Jupiter => 24.79,
+        }
+    }
+
+    /// Preset matching a gravity in pixels per tick squared, if any does
+    pub fn matching(units: Units, gravity: f32) -> Option<Planet> {
+        Self::ALL
+            .into_iter()
+            .find(|planet| (units.gravity(planet.gravity()) - gravity).abs() < 1e-4)
+    }
+}
@@ -0,0 +1,124 @@
+use serde::{Deserialize, Serialize};
+
+use crate::events::Wall;
+
+/// Highest restitution a wall can have, making balls bounce off it as if elastic
+pub const MAX_RESTITUTION: f32 = 2.0;
+
+/// How a side of the box treats balls bouncing off it
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WallMaterial {
+    /// Scales how much balls bounce, from 0 for a dead wall up to `MAX_RESTITUTION`
+    /// for a trampoline. Balls still can't leave faster than they hit it
+    pub restitution: f32,
+    /// Fraction of the speed along the wall that balls lose each time they hit it
+    pub friction: f32,
+}
+
+impl Default for WallMaterial {
+    fn default() -> Self {
+        Self {
+            restitution: 1.0,
+            friction: 0.0,
+        }
+    }
+}
+
+impl WallMaterial {
+    /// Named materials cycled through from the settings menu
+    pub const PRESETS: [(&'static str, WallMaterial); 4] = [
+        (
+            "Normal",
+            WallMaterial {
+                restitution: 1.0,
+                friction: 0.0,
+            },
+        ),
+        (
+            "Trampoline",
+            WallMaterial {
+                restitution: MAX_RESTITUTION,
+                friction: 0.0,
+            },
+        ),
+        (
+            "Dead",
+            WallMaterial {
+                restitution: 0.0,
+                friction: 0.5,
+            },
+        ),
+        (
+            "Rough",
+            WallMaterial {
+                restitution: 1.0,
+                friction: 0.3,
+            },
+        ),
+    ];
+
+    pub fn new(restitution: f32, friction: f32) -> Self {
+        Self {
+            restitution: restitution.clamp(0.0, MAX_RESTITUTION),
+            friction: friction.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Name of the preset this matches, if any
+    pub fn name(self) -> Option<&'static str> {
+        Self::PRESETS
+            .iter()
+            .find(|(_, material)| *material == self)
+            .map(|&(name, _)| name)
+    }
+
+    /// Next preset, starting from the first if this isn't one
+    pub fn next(self) -> Self {
+        let i = Self::PRESETS
+            .iter()
+            .position(|(_, material)| *material == self);
+        Self::PRESETS[i.map_or(0, |i| (i + 1) % Self::PRESETS.len())].1
+    }
+}
+
+/// Material of each side of the box
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Walls {
+    pub left: WallMaterial,
+    pub right: WallMaterial,
+    pub floor: WallMaterial,
+    /// Only balloons hit the ceiling, as other balls thrown out of the top fall back in
+    pub ceiling: WallMaterial,
+}
+
+impl Walls {
+    pub fn get(&self, wall: Wall) -> WallMaterial {
+        match wall {
+            Wall::Left => self.left,
+            Wall::Right => self.right,
+            Wall::Floor => self.floor,
+            Wall::Ceiling => self.ceiling,
+        }
+    }
+
+    pub fn set(&mut self, wall: Wall, material: WallMaterial) {
+        let side = match wall {
+            Wall::Left => &mut self.left,
+            Wall::Right => &mut self.right,
+            Wall::Floor => &mut self.floor,
+            Wall::Ceiling => &mut self.ceiling,
+        };
+        *side = WallMaterial::new(material.restitution, material.friction);
+    }
+
+    /// Copy with every material within its limits, such as after loading a scene
+    pub fn clamped(self) -> Self {
+        let mut walls = self;
+        for wall in Wall::ALL {
+            walls.set(wall, self.get(wall));
+        }
+        walls
+    }
+}
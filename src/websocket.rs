@@ -0,0 +1,376 @@
+//! Small WebSocket server taking JSON commands, for driving the sandbox from
+//! other programs. Each text message is one `Command`, and each answer or
+//! subscribed collision is sent back as one JSON message
+//!
+//! Plain HTTP requests are answered with a page that watches the scene, so it
+//! can be shown read-only in a browser, or at `/settings` with a settings panel
+//! that can sit in its own window, away from the simulation
+
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{Shutdown, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use base64::engine::general_purpose::STANDARD as BASE64;
+use base64::Engine;
+
+use crate::control::{Command, Reply, Request};
+
+/// Appended to the client key before hashing, as the protocol requires
+const ACCEPT_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message accepted, so a client can't exhaust memory
+const MAX_MESSAGE: usize = 1 << 20;
+/// Page drawing the balls it is sent after every frame
+const SPECTATE_PAGE: &str = include_str!("spectate.html");
+/// Page changing the settings, kept up to date with changes made in the app
+const SETTINGS_PAGE: &str = include_str!("settings.html");
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// Listen for clients in the background, sending their requests to the app
+pub fn serve(addr: &str, sender: Sender<Request>) -> Result<(), String> {
+    let listener = TcpListener::bind(addr).map_err(|err| format!("{}: {}", addr, err))?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                match handle_client(stream, sender) {
+                    // Client left without closing properly
+                    Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => (),
+                    Err(err) => log::warn!("WebSocket client error: {}", err),
+                    Ok(()) => (),
+                }
+            });
+        }
+    });
+    Ok(())
+}
+
+fn handle_client(stream: TcpStream, requests: Sender<Request>) -> io::Result<()> {
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let writer = Arc::new(Mutex::new(stream.try_clone()?));
+    if !handshake(&mut reader, &mut *writer.lock().unwrap())? {
+        return Ok(());
+    }
+
+    // Answers may come long after the command, so they are written from their own thread
+    let (reply, replies) = mpsc::channel::<Reply>();
+    let reply_writer = Arc::clone(&writer);
+    thread::spawn(move || {
+        for reply in replies {
+            let Ok(json) = serde_json::to_string(&reply) else {
+                continue;
+            };
+            let mut stream = reply_writer.lock().unwrap();
+            if write_frame(&mut *stream, OPCODE_TEXT, json.as_bytes()).is_err() {
+                break;
+            }
+        }
+    });
+
+    let result = read_messages(&mut reader, &writer, &requests, &reply);
+    // Stops the reply thread at its next write, so subscriptions end too
+    let _ = stream.shutdown(Shutdown::Both);
+    result
+}
+
+fn read_messages(
+    reader: &mut impl Read,
+    writer: &Mutex<TcpStream>,
+    requests: &Sender<Request>,
+    reply: &Sender<Reply>,
+) -> io::Result<()> {
+    let mut message = Vec::new();
+    loop {
+        let (fin, opcode, payload) = read_frame(reader)?;
+        match opcode {
+            OPCODE_TEXT | OPCODE_CONTINUATION => {
+                message.extend(payload);
+                if message.len() > MAX_MESSAGE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "message too long",
+                    ));
+                }
+                if !fin {
+                    continue;
+                }
+                let text = String::from_utf8_lossy(&message).to_string();
+                message.clear();
+                match serde_json::from_str::<Command>(&text) {
+                    Ok(command) => {
+                        let request = Request {
+                            command,
+                            reply: reply.clone(),
+                        };
+                        if requests.send(request).is_err() {
+                            return Ok(());
+                        }
+                    }
+                    Err(err) => {
+                        let _ = reply.send(Reply::error(err.to_string()));
+                    }
+                }
+            }
+            OPCODE_PING => write_frame(&mut *writer.lock().unwrap(), OPCODE_PONG, &payload)?,
+            OPCODE_CLOSE => {
+                let _ = write_frame(&mut *writer.lock().unwrap(), OPCODE_CLOSE, &[]);
+                return Ok(());
+            }
+            _ => (),
+        }
+    }
+}
+
+/// Read the HTTP upgrade request and accept it, returning false if it was a
+/// plain request for a page
+fn handshake(reader: &mut impl BufRead, writer: &mut impl Write) -> io::Result<bool> {
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line)? == 0 {
+        return Err(io::ErrorKind::UnexpectedEof.into());
+    }
+    let mut key = None;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            if name.trim().eq_ignore_ascii_case("sec-websocket-key") {
+                key = Some(value.trim().to_string());
+            }
+        }
+    }
+
+    let Some(key) = key else {
+        let page = match request_line.split_whitespace().nth(1) {
+            Some("/settings") => SETTINGS_PAGE,
+            _ => SPECTATE_PAGE,
+        };
+        write!(
+            writer,
+            "HTTP/1.1 200 OK\r\n\
+             Content-Type: text/html; charset=utf-8\r\n\
+             Content-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            page.len(),
+            page
+        )?;
+        return Ok(false);
+    };
+    let accept = BASE64.encode(sha1(format!("{}{}", key, ACCEPT_GUID).as_bytes()));
+    write!(
+        writer,
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\r\n",
+        accept
+    )?;
+    Ok(true)
+}
+
+/// Read one frame, returning whether it ends a message, its opcode and unmasked payload
+fn read_frame(reader: &mut impl Read) -> io::Result<(bool, u8, Vec<u8>)> {
+    let mut header = [0; 2];
+    reader.read_exact(&mut header)?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    let masked = header[1] & 0x80 != 0;
+    let len = match header[1] & 0x7F {
+        126 => {
+            let mut len = [0; 2];
+            reader.read_exact(&mut len)?;
+            u16::from_be_bytes(len) as usize
+        }
+        127 => {
+            let mut len = [0; 8];
+            reader.read_exact(&mut len)?;
+            u64::from_be_bytes(len) as usize
+        }
+        len => len as usize,
+    };
+    if len > MAX_MESSAGE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "frame too long"));
+    }
+
+    let mut mask = [0; 4];
+    if masked {
+        reader.read_exact(&mut mask)?;
+    }
+    let mut payload = vec![0; len];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
+
+/// Write one unmasked frame, as servers send them
+fn write_frame(writer: &mut impl Write, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = vec![0x80 | opcode];
+    match payload.len() {
+        len if len < 126 => frame.push(len as u8),
+        len if len <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend((len as u16).to_be_bytes());
+        }
+        len => {
+            frame.push(127);
+            frame.extend((len as u64).to_be_bytes());
+        }
+    }
+    frame.extend(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// SHA-1 digest, which the handshake needs and nothing else does
+fn sha1(data: &[u8]) -> [u8; 20] {
+    let mut h: [u32; 5] = [0x67452301, 0xEFCDAB89, 0x98BADCFE, 0x10325476, 0xC3D2E1F0];
+
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend((data.len() as u64 * 8).to_be_bytes());
+
+    for chunk in message.chunks(64) {
+        let mut w = [0u32; 80];
+        for (i, word) in chunk.chunks(4).enumerate() {
+            w[i] = u32::from_be_bytes([word[0], word[1], word[2], word[3]]);
+        }
+        for i in 16..80 {
+            w[i] = (w[i - 3] ^ w[i - 8] ^ w[i - 14] ^ w[i - 16]).rotate_left(1);
+        }
+
+        let [mut a, mut b, mut c, mut d, mut e] = h;
+        for (i, &word) in w.iter().enumerate() {
+            let (f, k) = match i {
+                0..=19 => ((b & c) | (!b & d), 0x5A827999),
+                20..=39 => (b ^ c ^ d, 0x6ED9EBA1),
+                40..=59 => ((b & c) | (b & d) | (c & d), 0x8F1BBCDC),
+                _ => (b ^ c ^ d, 0xCA62C1D6),
+            };
+            let temp = a
+                .rotate_left(5)
+                .wrapping_add(f)
+                .wrapping_add(e)
+                .wrapping_add(k)
+                .wrapping_add(word);
+            e = d;
+            d = c;
+            c = b.rotate_left(30);
+            b = a;
+            a = temp;
+        }
+        for (h, value) in h.iter_mut().zip([a, b, c, d, e]) {
+            *h = h.wrapping_add(value);
+        }
+    }
+
+    let mut digest = [0; 20];
+    for (bytes, word) in digest.chunks_mut(4).zip(h) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    digest
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn handshake_accepts_with_the_hashed_key() {
+        // Example from RFC 6455
+        let request = "GET /chat HTTP/1.1\r\n\
+                       Host: server.example.com\r\n\
+                       Upgrade: websocket\r\n\
+                       Sec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n";
+        let mut response = Vec::new();
+        assert!(handshake(&mut request.as_bytes(), &mut response).unwrap());
+        let response = String::from_utf8(response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 101"));
+        assert!(response.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo=\r\n"));
+    }
+
+    #[test]
+    fn plain_requests_get_a_page() {
+        let mut response = Vec::new();
+        let request = "GET /settings HTTP/1.1\r\n\r\n";
+        assert!(!handshake(&mut request.as_bytes(), &mut response).unwrap());
+        assert!(String::from_utf8(response)
+            .unwrap()
+            .ends_with(SETTINGS_PAGE));
+        assert!(handshake(&mut "GET / HTTP/1.1\r\n".as_bytes(), &mut Vec::new()).is_err());
+    }
+
+    #[test]
+    fn masked_frames_are_unmasked() {
+        // Example from RFC 6455
+        let frame = [
+            0x81, 0x85, 0x37, 0xfa, 0x21, 0x3d, 0x7f, 0x9f, 0x4d, 0x51, 0x58,
+        ];
+        let (fin, opcode, payload) = read_frame(&mut frame.as_slice()).unwrap();
+        assert!(fin);
+        assert_eq!(opcode, OPCODE_TEXT);
+        assert_eq!(payload, b"Hello");
+    }
+
+    #[test]
+    fn frames_of_every_length_read_back() {
+        for len in [0, 125, 126, 70_000] {
+            let payload = vec![7; len];
+            let mut frame = Vec::new();
+            write_frame(&mut frame, OPCODE_TEXT, &payload).unwrap();
+            let (fin, opcode, read) = read_frame(&mut frame.as_slice()).unwrap();
+            assert!(
+                fin && opcode == OPCODE_TEXT && read == payload,
+                "length {}",
+                len
+            );
+        }
+    }
+
+    #[test]
+    fn frames_over_the_limit_are_refused() {
+        let mut header = vec![0x81, 127];
+        header.extend((MAX_MESSAGE as u64 + 1).to_be_bytes());
+        let err = read_frame(&mut header.as_slice()).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(read_frame(&mut [0x81, 5, b'H'].as_slice()).is_err());
+    }
+
+    #[test]
+    fn sha1_matches_known_digests() {
+        let hex = |digest: [u8; 20]| -> String {
+            digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+        };
+        assert_eq!(hex(sha1(b"")), "da39a3ee5e6b4b0d3255bfef95601890afd80709");
+        assert_eq!(
+            hex(sha1(b"abc")),
+            "a9993e364706816aba3e25717850c26c9cd0d89d"
+        );
+    }
+
+    #[test]
+    fn messages_parse_as_commands() {
+        let spawn = r#"{"cmd": "spawn", "x": 1, "y": 2}"#;
+        assert!(matches!(
+            serde_json::from_str::<Command>(spawn),
+            Ok(Command::Spawn { radius, .. }) if radius == crate::control::DEFAULT_RADIUS
+        ));
+        assert!(serde_json::from_str::<Command>(r#"{"cmd": "spawn", "x": 1}"#).is_err());
+        assert!(serde_json::from_str::<Command>(r#"{"cmd": "explode"}"#).is_err());
+    }
+}
@@ -0,0 +1,87 @@
+use ggez::graphics::Color;
+use ggez::mint::Point2;
+use serde::{Deserialize, Serialize};
+
+use crate::ball::Ball;
+
+/// Most each color channel can differ by for a ball to match a color filter
+const COLOR_TOLERANCE: f32 = 0.02;
+
+/// Which balls a zone counts
+#[derive(Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Filter {
+    #[default]
+    Any,
+    Color(Color),
+    /// Radius within a range
+    Size {
+        min: f32,
+        max: f32,
+    },
+}
+
+impl Filter {
+    pub fn matches(&self, ball: &Ball) -> bool {
+        match *self {
+            Filter::Any => true,
+            Filter::Color(color) => [
+                (color.r, ball.color.r),
+                (color.g, ball.color.g),
+                (color.b, ball.color.b),
+            ]
+            .iter()
+            .all(|(a, b)| (a - b).abs() <= COLOR_TOLERANCE),
+            Filter::Size { min, max } => (min..=max).contains(&ball.radius),
+        }
+    }
+}
+
+/// Area that counts the balls inside it, without balls colliding with it
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Zone {
+    /// Top-left corner
+    pub min: Point2<f32>,
+    /// Bottom-right corner
+    pub max: Point2<f32>,
+    /// Shown above the tally
+    #[serde(default)]
+    pub label: String,
+    #[serde(default)]
+    pub filter: Filter,
+    /// Matching balls inside, as of the last tick
+    #[serde(skip)]
+    pub count: usize,
+    /// Matching balls anywhere, as of the last tick
+    #[serde(skip)]
+    pub total: usize,
+}
+
+impl Zone {
+    pub fn new(
+        min: Point2<f32>,
+        max: Point2<f32>,
+        label: impl Into<String>,
+        filter: Filter,
+    ) -> Self {
+        Self {
+            min,
+            max,
+            label: label.into(),
+            filter,
+            count: 0,
+            total: 0,
+        }
+    }
+
+    /// Whether the center of a ball is inside
+    pub fn contains(&self, ball: &Ball) -> bool {
+        (self.min.x..=self.max.x).contains(&ball.point.x)
+            && (self.min.y..=self.max.y).contains(&ball.point.y)
+    }
+
+    /// Whether every matching ball is inside, and there is at least one
+    pub fn is_complete(&self) -> bool {
+        self.total > 0 && self.count == self.total
+    }
+}
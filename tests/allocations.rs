@@ -0,0 +1,106 @@
+//! Checks that a running simulation stops allocating once its buffers have
+//! grown to fit the scene, counted by a global allocator. Kept in its own
+//! binary so other tests can't allocate while it counts. Drawing needs a
+//! window, so only ticks are counted
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use ggez::graphics::Color;
+use ggez::mint::Point2;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use balls::{Ball, Growth, Scene, Simulation, Spawner};
+
+/// Ticks for buffers to grow to fit before counting
+const WARM_UP: u32 = 600;
+const TICKS: u32 = 240;
+
+struct Counting;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: Counting = Counting;
+
+/// Both ways of colliding are checked in one test, as tests running at the same
+/// time would count each other's allocations. Ticks run on a rayon thread, as
+/// rayon allocates a queue block for every 63 jobs sent from outside its pool
+#[test]
+fn ticks_stop_allocating_once_warmed_up() {
+    rayon::scope(|_| {
+        for parallel in [false, true] {
+            check_ticks(parallel);
+        }
+    });
+}
+
+fn check_ticks(parallel: bool) {
+    let mut rng = StdRng::seed_from_u64(1);
+    let balls = (0..200)
+        .map(|_| {
+            let mut ball = Ball::new(
+                rng.gen_range(20.0..780.0),
+                rng.gen_range(20.0..580.0),
+                rng.gen_range(5.0..20.0),
+                Color::WHITE,
+            );
+            ball.velocity.x = rng.gen_range(-5.0..5.0);
+            ball.velocity.y = rng.gen_range(-5.0..5.0);
+            ball
+        })
+        .collect();
+    // Balls keep being emitted and removed, by running out of time or bursting
+    let mut fading = Spawner::new(Point2 { x: 200.0, y: 100.0 }, 5, 10.0);
+    fading.lifetime = Some(60);
+    let mut growing = Spawner::new(Point2 { x: 600.0, y: 100.0 }, 7, 10.0);
+    growing.growth = Some(Growth {
+        rate: 0.5,
+        limit: 30.0,
+    });
+    let scene = Scene {
+        balls,
+        gravity: 0.5,
+        spawners: vec![fading, growing],
+        ..Scene::default()
+    };
+    let mut sim = Simulation::from_scene(scene, 800.0, 600.0);
+    sim.params.parallel = parallel;
+    for _ in 0..WARM_UP {
+        sim.clear_events();
+        sim.tick();
+    }
+
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    for _ in 0..TICKS {
+        sim.clear_events();
+        sim.tick();
+    }
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    eprintln!("MEASURE parallel={parallel} allocations={allocations}");
+    assert!(
+        !sim.is_resting(),
+        "the scene came to rest, so nothing was measured"
+    );
+    assert!(
+        allocations == 0,
+        "{allocations} allocations over {TICKS} ticks, colliding in parallel: {parallel}"
+    );
+}
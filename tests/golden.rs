@@ -0,0 +1,79 @@
+//! Golden-scene regression tests. Each scene in `tests/golden` is run for a
+//! fixed number of ticks and compared against its stored snapshot, so any
+//! change to the physics shows up here. After an intended change, rewrite the
+//! snapshots with `UPDATE_GOLDEN=1 cargo test --test golden`
+
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use balls::{Scene, Simulation};
+
+const TICKS: u32 = 300;
+const WIDTH: f32 = 800.0;
+const HEIGHT: f32 = 600.0;
+/// Largest difference allowed in any position or velocity
+const TOLERANCE: f32 = 1e-3;
+
+/// Position and velocity of each ball, in simulation order
+type Snapshot = Vec<((f32, f32), (f32, f32))>;
+
+fn run(name: &str) {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let scene_file = fs::read_to_string(dir.join(format!("{}.ron", name))).unwrap();
+    let scene: Scene = ron::from_str(&scene_file).unwrap();
+
+    let mut sim = Simulation::from_scene(scene, WIDTH, HEIGHT);
+    for _ in 0..TICKS {
+        sim.tick();
+    }
+    let actual: Snapshot = sim
+        .balls()
+        .map(|(_, ball)| {
+            (
+                (ball.point.x, ball.point.y),
+                (ball.velocity.x, ball.velocity.y),
+            )
+        })
+        .collect();
+
+    let snapshot_path = dir.join(format!("{}.snapshot.ron", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        let file = ron::ser::to_string_pretty(&actual, Default::default()).unwrap();
+        fs::write(&snapshot_path, file).unwrap();
+        return;
+    }
+    let snapshot_file = fs::read_to_string(&snapshot_path).unwrap_or_else(|_| {
+        panic!("no snapshot for `{name}`, create it with UPDATE_GOLDEN=1");
+    });
+    let expected: Snapshot = ron::from_str(&snapshot_file).unwrap();
+
+    assert_eq!(actual.len(), expected.len(), "`{name}`: ball count changed");
+    for (i, (actual, expected)) in actual.iter().zip(&expected).enumerate() {
+        let values = [
+            (actual.0 .0, expected.0 .0),
+            (actual.0 .1, expected.0 .1),
+            (actual.1 .0, expected.1 .0),
+            (actual.1 .1, expected.1 .1),
+        ];
+        assert!(
+            values.iter().all(|(a, b)| (a - b).abs() <= TOLERANCE),
+            "`{name}`: ball {i} is {actual:?}, expected {expected:?}"
+        );
+    }
+}
+
+#[test]
+fn pile() {
+    run("pile");
+}
+
+#[test]
+fn pegs() {
+    run("pegs");
+}
+
+#[test]
+fn orbit() {
+    run("orbit");
+}
@@ -0,0 +1,257 @@
+//! Randomised checks of physics invariants. Each property runs against many
+//! scenes generated from fixed seeds, so any failure can be repeated from the
+//! seed in its message
+
+use ggez::graphics::Color;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use balls::{Ball, Event, Scene, Simulation, Wall, WallMaterial, Walls};
+
+const CASES: u64 = 64;
+const TICKS: u32 = 120;
+/// Allowed error, for rounding when balls are clamped to a wall
+const EPSILON: f32 = 1e-3;
+
+/// Run a property against scenes from many seeds
+fn check(property: impl Fn(u64, &mut StdRng)) {
+    for seed in 0..CASES {
+        property(seed, &mut StdRng::seed_from_u64(seed));
+    }
+}
+
+/// Box size and a scene of balls that fit in it, with any velocities
+fn random_scene(rng: &mut StdRng) -> (f32, f32, Scene) {
+    let width: f32 = rng.gen_range(200.0..1600.0);
+    let height = rng.gen_range(200.0..1200.0);
+    let count = rng.gen_range(1..60);
+    let balls = (0..count)
+        .map(|_| {
+            let radius = rng.gen_range(1.0..width.min(height) / 4.0);
+            let mut ball = Ball::new(
+                rng.gen_range(radius..width - radius),
+                rng.gen_range(radius..height - radius),
+                radius,
+                Color::WHITE,
+            );
+            ball.velocity.x = rng.gen_range(-30.0..30.0);
+            ball.velocity.y = rng.gen_range(-30.0..30.0);
+            ball.restitution = rng.gen_range(0.0..1.0);
+            ball
+        })
+        .collect();
+    let scene = Scene::new(balls, rng.gen_range(0.0..2.0));
+    (width, height, scene)
+}
+
+#[test]
+fn balls_end_every_tick_inside_the_walls() {
+    check(|seed, rng| {
+        let (width, height, scene) = random_scene(rng);
+        for parallel in [false, true] {
+            let mut sim = Simulation::from_scene(scene.clone(), width, height);
+            sim.params.parallel = parallel;
+            for tick in 0..TICKS {
+                sim.tick();
+                for (_, ball) in sim.balls() {
+                    let r = ball.radius;
+                    assert!(
+                        ball.point.x >= r - EPSILON && ball.point.x <= width - r + EPSILON,
+                        "seed {seed}, tick {tick}: ball at x {} outside 0..{width}",
+                        ball.point.x
+                    );
+                    assert!(
+                        ball.point.y <= height - r + EPSILON,
+                        "seed {seed}, tick {tick}: ball at y {} below the floor at {height}",
+                        ball.point.y
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn walls_never_speed_balls_up() {
+    check(|seed, rng| {
+        let mut walls = Walls::default();
+        for wall in Wall::ALL {
+            let material = WallMaterial::new(rng.gen_range(0.0..2.0), rng.gen_range(0.0..1.0));
+            walls.set(wall, material);
+        }
+        let radius = rng.gen_range(5.0..40.0);
+        let mut ball = Ball::new(radius + 1.0, 300.0, radius, Color::WHITE);
+        ball.velocity.x = -rng.gen_range(2.0..30.0);
+        ball.velocity.y = rng.gen_range(-30.0..30.0);
+        ball.restitution = rng.gen_range(0.0..1.0);
+        let speed = ball.velocity.x.hypot(ball.velocity.y);
+        let scene = Scene {
+            balls: vec![ball],
+            gravity: 0.0,
+            walls,
+            ..Scene::default()
+        };
+        let mut sim = Simulation::from_scene(scene, 800.0, 600.0);
+        for tick in 0..TICKS {
+            sim.tick();
+            for (_, ball) in sim.balls() {
+                let after = ball.velocity.x.hypot(ball.velocity.y);
+                assert!(
+                    after <= speed + EPSILON,
+                    "seed {seed}, tick {tick}: ball sped up from {speed} to {after}"
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn balls_on_separate_layers_never_collide() {
+    check(|seed, rng| {
+        let (width, height, mut scene) = random_scene(rng);
+        for ball in &mut scene.balls {
+            ball.layer = 1 << rng.gen_range(0..2);
+            ball.mask = ball.layer;
+        }
+        for parallel in [false, true] {
+            let mut sim = Simulation::from_scene(scene.clone(), width, height);
+            sim.params.parallel = parallel;
+            for tick in 0..TICKS {
+                sim.tick();
+                for event in sim.events() {
+                    let Event::BallHitBall { a, b, .. } = *event else {
+                        continue;
+                    };
+                    let layer = |id| sim.ball(id).map(|ball| ball.layer);
+                    assert_eq!(
+                        layer(a),
+                        layer(b),
+                        "seed {seed}, tick {tick}: balls on different layers collided"
+                    );
+                }
+            }
+        }
+    });
+}
+
+#[test]
+fn positions_and_velocities_stay_finite() {
+    check(|seed, rng| {
+        let (width, height, mut scene) = random_scene(rng);
+        // Balls on exactly the same spot have no direction to separate in
+        if let Some(ball) = scene.balls.first().cloned() {
+            scene.balls.push(ball);
+        }
+        let mut sim = Simulation::from_scene(scene, width, height);
+        sim.params.parallel = seed % 2 == 1;
+        for tick in 0..TICKS {
+            sim.tick();
+            for (_, ball) in sim.balls() {
+                let values = [ball.point.x, ball.point.y, ball.velocity.x, ball.velocity.y];
+                assert!(
+                    values.iter().all(|value| value.is_finite()),
+                    "seed {seed}, tick {tick}: ball has non-finite state {values:?}"
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn balls_placed_at_nan_are_moved_back_in() {
+    check(|seed, rng| {
+        let (width, height, mut scene) = random_scene(rng);
+        scene
+            .balls
+            .push(Ball::new(f32::NAN, height / 2.0, 10.0, Color::WHITE));
+        let mut sim = Simulation::from_scene(scene, width, height);
+        sim.params.parallel = seed % 2 == 1;
+        for tick in 0..TICKS {
+            sim.tick();
+            for (_, ball) in sim.balls() {
+                let values = [ball.point.x, ball.point.y, ball.velocity.x, ball.velocity.y];
+                assert!(
+                    values.iter().all(|value| value.is_finite()),
+                    "seed {seed}, tick {tick}: ball has non-finite state {values:?}"
+                );
+            }
+        }
+    });
+}
+
+#[test]
+fn resolving_a_pair_reduces_overlap() {
+    check(|seed, rng| {
+        let radius_a: f32 = rng.gen_range(2.0..50.0);
+        let radius_b = rng.gen_range(2.0..50.0);
+        let angle: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+        let overlap = rng.gen_range(0.1..radius_a.min(radius_b));
+        let distance = radius_a + radius_b - overlap;
+
+        let (cx, cy) = (500.0, 500.0);
+        let a = Ball::new(cx, cy, radius_a, Color::WHITE);
+        let b = Ball::new(
+            cx + angle.cos() * distance,
+            cy + angle.sin() * distance,
+            radius_b,
+            Color::WHITE,
+        );
+        let scene = Scene::new(vec![a, b], 0.0);
+        let mut sim = Simulation::from_scene(scene, 1000.0, 1000.0);
+        sim.tick();
+
+        let balls: Vec<_> = sim.balls().map(|(_, ball)| ball).collect();
+        let after = balls[0].penetration(&balls[1]);
+        assert!(
+            after < overlap,
+            "seed {seed}: overlap went from {overlap} to {after}"
+        );
+    });
+}
+
+#[test]
+fn collisions_keep_momentum() {
+    // Far from every wall, so only balls pushing each other apart changes velocities
+    const SIZE: f32 = 10000.0;
+    let momentum = |sim: &Simulation| {
+        sim.balls().fold((0.0, 0.0), |(x, y), (_, ball)| {
+            (
+                x + ball.velocity.x * ball.mass(),
+                y + ball.velocity.y * ball.mass(),
+            )
+        })
+    };
+    check(|seed, rng| {
+        let balls: Vec<_> = (0..rng.gen_range(2..12))
+            .map(|_| {
+                let mut ball = Ball::new(
+                    SIZE / 2.0 + rng.gen_range(-40.0..40.0),
+                    SIZE / 2.0 + rng.gen_range(-40.0..40.0),
+                    rng.gen_range(5.0..40.0),
+                    Color::WHITE,
+                );
+                ball.velocity.x = rng.gen_range(-10.0..10.0);
+                ball.velocity.y = rng.gen_range(-10.0..10.0);
+                ball.restitution = rng.gen_range(0.0..1.0);
+                ball
+            })
+            .collect();
+        let scene = Scene::new(balls, 0.0);
+        for parallel in [false, true] {
+            let mut sim = Simulation::from_scene(scene.clone(), SIZE, SIZE);
+            sim.params.parallel = parallel;
+            let before = momentum(&sim);
+            let mass: f32 = sim.balls().map(|(_, ball)| ball.mass()).sum();
+            for tick in 0..TICKS {
+                sim.tick();
+                let after = momentum(&sim);
+                assert!(
+                    (after.0 - before.0).abs() < EPSILON * mass
+                        && (after.1 - before.1).abs() < EPSILON * mass,
+                    "seed {seed}, tick {tick}, parallel {parallel}: momentum went from \
+                     {before:?} to {after:?}"
+                );
+            }
+        }
+    });
+}